@@ -0,0 +1,179 @@
+//! Benchmarks of parsing documents: PDF brokerage statements generated with
+//! given number of pages and transactions, XLSX gains and losses of data
+//! directory and CSV of virtual currency transactions.
+//!
+//! Run with: cargo bench --bench parser
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use etradeTaxReturnHelper::{parse_document, InMemory};
+
+// Statement rows of each kind, shown as text in the same order as E*TRADE does
+fn dividend_row(day: u32) -> Vec<String> {
+    vec![
+        format!("03/{:02}/22", day),
+        "Dividend".to_owned(),
+        "INTEL CORP".to_owned(),
+        "INTC".to_owned(),
+        "104.74".to_owned(),
+        "698.25".to_owned(),
+    ]
+}
+
+fn sold_row(day: u32) -> Vec<String> {
+    vec![
+        format!("04/{:02}/22", day),
+        format!("04/{:02}/22", day + 2),
+        "Sold".to_owned(),
+        "10".to_owned(),
+        "45.3060".to_owned(),
+        "453.04".to_owned(),
+    ]
+}
+
+fn interest_row(day: u32) -> Vec<String> {
+    vec![
+        format!("05/{:02}/22", day),
+        "Interest".to_owned(),
+        "TREASURY LIQUIDITY FUND".to_owned(),
+        "0.13".to_owned(),
+    ]
+}
+
+// Text of PDF string literal: parentheses and backslashes are escaped
+fn pdf_string(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('(', "\\(")
+        .replace(')', "\\)")
+}
+
+/// PDF statement of given number of pages, each of them with given number
+/// of transactions (dividends, sales and interest in turn)
+fn synthetic_statement(pages: usize, transactions_per_page: usize) -> Vec<u8> {
+    let mut objects: Vec<String> = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages)
+                .map(|p| format!("{} 0 R", 3 + 2 * p))
+                .collect::<Vec<String>>()
+                .join(" "),
+            pages
+        ),
+    ];
+    for p in 0..pages {
+        let mut content = String::from("BT\n/F1 8 Tf\n");
+        for t in 0..transactions_per_page {
+            let day = (t % 28) as u32 + 1;
+            let row = match t % 3 {
+                0 => dividend_row(day),
+                1 => sold_row(day.min(26)),
+                _ => interest_row(day),
+            };
+            for text in row {
+                content += &format!("[({})] TJ\n", pdf_string(&text));
+            }
+        }
+        content += "ET";
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Resources << /Font << /F1 << /Type /Font /Subtype /Type1 /BaseFont /Helvetica >> >> >> /Contents {} 0 R >>",
+            4 + 2 * p
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}\nendstream",
+            content.len(),
+            content
+        ));
+    }
+
+    let mut document = String::from("%PDF-1.4\n");
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(document.len());
+        document += &format!("{} 0 obj\n{}\nendobj\n", i + 1, object);
+    }
+    let xref = document.len();
+    document += &format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        document += &format!("{:010} 00000 n \n", offset);
+    }
+    document += &format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    document.into_bytes()
+}
+
+fn synthetic_crypto_transactions(count: usize) -> Vec<u8> {
+    let mut content = String::from("Date,Type,Amount,Currency\n");
+    for i in 0..count {
+        let kind = if i % 2 == 0 { "buy" } else { "sell" };
+        content += &format!(
+            "2023-{:02}-{:02},{},{}.5,USD\n",
+            i % 12 + 1,
+            i % 28 + 1,
+            kind,
+            i
+        );
+    }
+    content.into_bytes()
+}
+
+fn bench_pdf(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pdf statement");
+    for (pages, transactions_per_page) in [(1, 10), (10, 30), (50, 30)] {
+        let mut documents = InMemory::default();
+        let content = synthetic_statement(pages, transactions_per_page);
+        group.throughput(Throughput::Bytes(content.len() as u64));
+        documents.insert("statement.pdf", content);
+        // Measured parsing has to find all transactions, not stop at error
+        let statement = parse_document(&documents, "statement.pdf", false)
+            .expect("Error parsing synthetic statement");
+        assert_eq!(
+            statement.dividends.len() + statement.sold.len() + statement.interest.len(),
+            pages * transactions_per_page
+        );
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{} pages", pages)),
+            &documents,
+            |b, documents| b.iter(|| parse_document(documents, black_box("statement.pdf"), false)),
+        );
+    }
+    group.finish();
+}
+
+fn bench_xlsx(c: &mut Criterion) {
+    let mut group = c.benchmark_group("xlsx gains and losses");
+    for name in ["G&L_Collapsed.xlsx", "G&L_Expanded.xlsx"] {
+        let mut documents = InMemory::default();
+        let content = std::fs::read(format!("data/{}", name))
+            .unwrap_or_else(|e| panic!("Error reading data/{}: {}", name, e));
+        group.throughput(Throughput::Bytes(content.len() as u64));
+        documents.insert(name, content);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(name),
+            &documents,
+            |b, documents| b.iter(|| parse_document(documents, black_box(name), false)),
+        );
+    }
+    group.finish();
+}
+
+fn bench_csv(c: &mut Criterion) {
+    let mut group = c.benchmark_group("csv crypto transactions");
+    for count in [100, 10_000] {
+        let mut documents = InMemory::default();
+        let content = synthetic_crypto_transactions(count);
+        group.throughput(Throughput::Bytes(content.len() as u64));
+        documents.insert("crypto.csv", content);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{} transactions", count)),
+            &documents,
+            |b, documents| b.iter(|| parse_document(documents, black_box("crypto.csv"), false)),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_pdf, bench_xlsx, bench_csv);
+criterion_main!(benches);
@@ -0,0 +1,85 @@
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Cleared by --no-color
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+pub fn set_color_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Colors are used only on terminal, unless disabled with --no-color or
+/// NO_COLOR environment variable (https://no-color.org)
+fn color_enabled(terminal: bool) -> bool {
+    ENABLED.load(Ordering::Relaxed)
+        && terminal
+        && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+}
+
+/// Windows console prints escape codes of colors as text unless virtual
+/// terminal processing is enabled (supported since Windows 10)
+#[cfg(windows)]
+fn virtual_terminal(handle: windows_sys::Win32::System::Console::STD_HANDLE) -> bool {
+    use windows_sys::Win32::System::Console::{
+        GetConsoleMode, GetStdHandle, SetConsoleMode, ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+    };
+    unsafe {
+        let console = GetStdHandle(handle);
+        let mut mode = 0;
+        GetConsoleMode(console, &mut mode) != 0
+            && (mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+                || SetConsoleMode(console, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0)
+    }
+}
+
+fn stdout_terminal() -> bool {
+    let terminal = std::io::stdout().is_terminal();
+    #[cfg(windows)]
+    let terminal =
+        terminal && virtual_terminal(windows_sys::Win32::System::Console::STD_OUTPUT_HANDLE);
+    terminal
+}
+
+fn stderr_terminal() -> bool {
+    let terminal = std::io::stderr().is_terminal();
+    #[cfg(windows)]
+    let terminal =
+        terminal && virtual_terminal(windows_sys::Win32::System::Console::STD_ERROR_HANDLE);
+    terminal
+}
+
+fn paint(code: &str, text: &str, enabled: bool) -> String {
+    match enabled {
+        true => format!("\x1b[{}m{}\x1b[0m", code, text),
+        false => text.to_owned(),
+    }
+}
+
+/// Text printed to standard output made bold green e.g. final tax to be paid
+pub fn highlight(text: &str) -> String {
+    paint("1;32", text, color_enabled(stdout_terminal()))
+}
+
+/// Text printed to standard error made yellow
+pub fn warning(text: &str) -> String {
+    paint("33", text, color_enabled(stderr_terminal()))
+}
+
+/// Text printed to standard error made red
+pub fn error(text: &str) -> String {
+    paint("31", text, color_enabled(stderr_terminal()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paint() -> Result<(), String> {
+        assert_eq!(paint("33", "UWAGA", true), "\x1b[33mUWAGA\x1b[0m");
+        assert_eq!(paint("33", "UWAGA", false), "UWAGA");
+        // Tests output is not a terminal
+        assert!(!color_enabled(false));
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use chrono::{Datelike, NaiveDate};
+
+use crate::{HoldingTerm, SoldTransaction, Transaction};
+
+/// Single income event. Amounts are in currency of income (USD for E*TRADE
+/// documents) and exchange rate converts them to target currency (PLN, EUR etc.)
+#[derive(Debug, Clone, PartialEq)]
+pub enum Income {
+    /// Dividend paid on shares of symbol, tax withheld at source
+    Dividend {
+        date: NaiveDate,
+        symbol: String,
+        currency: String,
+        gross: f32,
+        tax: f32,
+        exchange_rate: f32,
+    },
+    /// Interest e.g. paid by money market fund, tax withheld at source
+    Interest {
+        date: NaiveDate,
+        currency: String,
+        gross: f32,
+        tax: f32,
+        exchange_rate: f32,
+    },
+    /// Sold stock: proceeds are converted at settlement and cost basis at acquisition
+    SaleGain {
+        settlement_date: NaiveDate,
+        acquisition_date: NaiveDate,
+        currency: String,
+        proceeds: f32,
+        cost_basis: f32,
+        fees: f32,
+        term: HoldingTerm,
+        settlement_exchange_rate: f32,
+        acquisition_exchange_rate: f32,
+    },
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%m/%d/%y")
+        .map_err(|_| format!("Error parsing transaction date: {}", date))
+}
+
+impl Income {
+    pub fn dividend(t: &Transaction) -> Result<Income, String> {
+        Ok(Income::Dividend {
+            date: parse_date(&t.transaction_date)?,
+            symbol: t.symbol.clone(),
+            currency: "USD".to_owned(),
+            gross: t.gross_us,
+            tax: t.tax_us,
+            exchange_rate: t.exchange_rate,
+        })
+    }
+
+    pub fn interest(t: &Transaction) -> Result<Income, String> {
+        Ok(Income::Interest {
+            date: parse_date(&t.transaction_date)?,
+            currency: "USD".to_owned(),
+            gross: t.gross_us,
+            tax: t.tax_us,
+            exchange_rate: t.exchange_rate,
+        })
+    }
+
+    pub fn sale(t: &SoldTransaction) -> Result<Income, String> {
+        Ok(Income::SaleGain {
+            settlement_date: parse_date(&t.settlement_date)?,
+            acquisition_date: parse_date(&t.acquisition_date)?,
+            currency: "USD".to_owned(),
+            proceeds: t.income_us,
+            cost_basis: t.cost_basis,
+            fees: t.fees_us,
+            term: t.term,
+            settlement_exchange_rate: t.exchange_rate_settlement,
+            acquisition_exchange_rate: t.exchange_rate_acquisition,
+        })
+    }
+
+    /// Date income is taxed at: payment of dividends and interest, settlement of sold stock
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            Income::Dividend { date, .. } | Income::Interest { date, .. } => *date,
+            Income::SaleGain {
+                settlement_date, ..
+            } => *settlement_date,
+        }
+    }
+
+    pub fn tax_year(&self) -> i32 {
+        self.date().year()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_income() -> Result<(), String> {
+        let sold = SoldTransaction {
+            settlement_date: "01/03/23".to_string(),
+            acquisition_date: "12/01/21".to_string(),
+            income_us: 100.0,
+            cost_basis: 70.0,
+            fees_us: 0.5,
+            term: HoldingTerm::Long,
+            exchange_rate_settlement_date: "2023-01-02".to_string(),
+            exchange_rate_settlement: 4.4,
+            exchange_rate_acquisition_date: "2021-11-30".to_string(),
+            exchange_rate_acquisition: 4.1,
+        };
+        let income = Income::sale(&sold)?;
+        assert_eq!(income.date(), NaiveDate::from_ymd_opt(2023, 1, 3).unwrap());
+        assert_eq!(income.tax_year(), 2023);
+
+        let interest = Transaction {
+            transaction_date: "N/A".to_string(),
+            gross_us: 2.0,
+            tax_us: 0.0,
+            symbol: "".to_string(),
+            exchange_rate_date: "N/A".to_string(),
+            exchange_rate: 4.0,
+        };
+        assert_eq!(
+            Income::interest(&interest),
+            Err("Error parsing transaction date: N/A".to_owned())
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+//! Tax logic of etradeTaxReturnHelper: transactions with exchange rates
+//! resolved are verified, assigned to tax years and taxation figures of each
+//! year are computed according to bundled tax rules
+
+mod color;
+mod income;
+pub mod tax;
+pub mod transactions;
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+pub use color::{error, highlight, set_color_enabled, warning};
+pub use income::Income;
+pub use tax::cashledger::compute_fx_differences;
+pub use tax::latepayment::late_payment_interest;
+pub use tax::rules::{load_tax_rules, tax_rules_for_year, Pit38Schema, TaxRules, YearlyTaxRules};
+pub use tax::{capital_gains, compute_tax_due, split_with_spouse};
+
+// Let's extend Result with logging
+pub trait ResultExt<T> {
+    fn expect_and_log(self, msg: &str) -> T;
+}
+
+impl<T, E: fmt::Debug> ResultExt<T> for Result<T, E> {
+    fn expect_and_log(self, err_msg: &str) -> T {
+        self.inspect_err(|_| {
+            log::error!("{}", err_msg);
+        })
+        .expect(err_msg)
+    }
+}
+
+impl<T> ResultExt<T> for Option<T> {
+    fn expect_and_log(self, err_msg: &str) -> T {
+        self.or_else(|| {
+            log::error!("{}", err_msg);
+            None
+        })
+        .expect(err_msg)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize)]
+pub struct Transaction {
+    pub transaction_date: String,
+    pub gross_us: f32,
+    pub tax_us: f32,
+    pub symbol: String,
+    pub exchange_rate_date: String,
+    pub exchange_rate: f32,
+}
+
+/// US holding period classification of sold stock (as in 1099-B)
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Serialize, serde::Deserialize)]
+pub enum HoldingTerm {
+    Short,
+    Long,
+}
+
+// 1. settlement date
+// 2. date of purchase
+// 3. gross income
+// 4. cost cost basis
+// 5. fees and commission of sale
+// 6. US holding term
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize)]
+pub struct SoldTransaction {
+    pub settlement_date: String,
+    pub acquisition_date: String,
+    pub income_us: f32,
+    pub cost_basis: f32,
+    pub fees_us: f32,
+    pub term: HoldingTerm,
+    pub exchange_rate_settlement_date: String,
+    pub exchange_rate_settlement: f32,
+    pub exchange_rate_acquisition_date: String,
+    pub exchange_rate_acquisition: f32,
+}
+
+/// Income of documents of single broker in target currency, when documents
+/// of several brokers are taxed together
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct BrokerIncome {
+    pub gross_div: f32,
+    pub tax_div: f32,
+    pub gross_interest: f32,
+    pub tax_interest: f32,
+    pub gross_sold: f32,
+    pub cost_sold: f32,
+    // Virtual currency disposals and acquisitions of year. Cost carried from
+    // previous years is not assigned to brokers
+    pub crypto_income: f32,
+    pub crypto_cost: f32,
+    // Dividends per country of source: (gross, tax paid, tax creditable)
+    pub per_country: BTreeMap<String, (f32, f32, f32)>,
+}
+
+impl BrokerIncome {
+    /// Figures multiplied by ratio e.g. share of jointly owned account
+    pub fn scaled(&self, ratio: f32) -> BrokerIncome {
+        BrokerIncome {
+            gross_div: self.gross_div * ratio,
+            tax_div: self.tax_div * ratio,
+            gross_interest: self.gross_interest * ratio,
+            tax_interest: self.tax_interest * ratio,
+            gross_sold: self.gross_sold * ratio,
+            cost_sold: self.cost_sold * ratio,
+            crypto_income: self.crypto_income * ratio,
+            crypto_cost: self.crypto_cost * ratio,
+            per_country: self
+                .per_country
+                .iter()
+                .map(|(country, (gross, tax, creditable))| {
+                    (
+                        country.clone(),
+                        (gross * ratio, tax * ratio, creditable * ratio),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Figures computed for single tax year in target currency (PLN, EUR etc.)
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct TaxYearResult {
+    pub gross_div: f32,
+    pub tax_div: f32,
+    // Dividends per symbol: (gross, tax paid, tax creditable up to treaty rate)
+    pub per_symbol: BTreeMap<String, (f32, f32, f32)>,
+    // Dividends per country of source, as reported in PIT/ZG
+    pub per_country: BTreeMap<String, (f32, f32, f32)>,
+    // Dividends, interest and sold stock per broker (e.g. E*TRADE, name of
+    // parser plugin), only if documents of several brokers are given. Sums of
+    // brokers make the figures of year
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub per_broker: BTreeMap<String, BrokerIncome>,
+    // Dividends and interest per month (YYYY-MM) for comparison with monthly
+    // statements: (gross, tax withheld) in USD and (gross, tax) in target currency
+    pub per_month: BTreeMap<String, (f32, f32, f32, f32)>,
+    pub gross_sold: f32,
+    pub cost_sold: f32,
+    // Interest (e.g. of cash sweep) reported separately from dividends
+    pub gross_interest: f32,
+    pub tax_interest: f32,
+    // Realized exchange rate differences on USD cash
+    pub fx_income: f32,
+    pub fx_cost: f32,
+    // Sold stock in USD split by US holding term (for 1099-B cross-check)
+    pub proceeds_short_us: f32,
+    pub cost_short_us: f32,
+    pub proceeds_long_us: f32,
+    pub cost_long_us: f32,
+    // Sold stock reported by Polish brokers in PIT-8C (income, cost)
+    pub pit8c_income: f32,
+    pub pit8c_cost: f32,
+    // US withholding on dividends above treaty rate in USD. Potentially
+    // reclaimable from IRS via 1040-NR
+    pub excess_withholding_us: f32,
+    // Virtual currency disposals and acquisition costs of the year, and excess
+    // of costs carried from previous years
+    pub crypto_income: f32,
+    pub crypto_cost: f32,
+    pub crypto_carried_cost: f32,
+    // Tax already paid in advance (zaliczki) or withheld by Polish payer
+    pub prepaid_tax: f32,
+    // Dividends of tax-sheltered accounts in USD (not taxed, for information only)
+    pub sheltered_gross_div_us: f32,
+    pub sheltered_tax_div_us: f32,
+    // Dividends and interest in USD before conversion (gross, tax withheld),
+    // for reconciliation with broker's year-end totals
+    pub gross_div_us: f32,
+    pub tax_div_us: f32,
+    pub gross_interest_us: f32,
+    pub tax_interest_us: f32,
+}
@@ -0,0 +1,151 @@
+use chrono::Datelike;
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::transactions::exchange_rate_of;
+
+/// USD cash held at broker. Every inflow (net dividend, proceeds of sold stock)
+/// is kept as a lot valued at exchange rate of its date. Withdrawals (conversion
+/// to target currency) consume lots in FIFO order and realize exchange rate
+/// differences between withdrawal date and inflow date
+struct CashLedger {
+    lots: VecDeque<(f32, f32)>,
+}
+
+impl CashLedger {
+    fn deposit(&mut self, amount: f32, exchange_rate: f32) {
+        self.lots.push_back((amount, exchange_rate));
+    }
+
+    /// Returns realized exchange rate difference and amount that could not be
+    /// covered by recorded inflows
+    fn withdraw(&mut self, amount: f32, exchange_rate: f32) -> (f32, f32) {
+        let mut remaining = amount;
+        let mut difference = 0.0;
+        while remaining > 0.0 {
+            let (lot_amount, lot_rate) = match self.lots.front_mut() {
+                Some(lot) => lot,
+                None => break,
+            };
+            let taken = remaining.min(*lot_amount);
+            difference += taken * (exchange_rate - *lot_rate);
+            remaining -= taken;
+            *lot_amount -= taken;
+            if *lot_amount <= 0.0 {
+                self.lots.pop_front();
+            }
+        }
+        (difference, remaining)
+    }
+}
+
+/// Compute realized exchange rate differences for each tax year.
+/// inflows and withdrawals are (date, amount in USD). Returned are
+/// positive (income) and negative (cost) differences per year of withdrawal
+pub fn compute_fx_differences(
+    inflows: &[(String, f32)],
+    withdrawals: &[(String, f32)],
+    dates: &std::collections::HashMap<String, Option<(String, f32)>>,
+) -> Result<BTreeMap<i32, (f32, f32)>, String> {
+    // Order all events by date. Inflows of given day are available for
+    // withdrawal on the same day
+    let mut events: Vec<(chrono::NaiveDate, bool, f32, f32)> = vec![];
+    for (date, amount) in inflows.iter() {
+        let (_, exchange_rate) = exchange_rate_of(dates, date)?;
+        events.push((parse_date(date)?, false, *amount, exchange_rate));
+    }
+    for (date, amount) in withdrawals.iter() {
+        let (_, exchange_rate) = exchange_rate_of(dates, date)?;
+        events.push((parse_date(date)?, true, *amount, exchange_rate));
+    }
+    events.sort_by_key(|(date, is_withdrawal, _, _)| (*date, *is_withdrawal));
+
+    let mut ledger = CashLedger {
+        lots: VecDeque::new(),
+    };
+    let mut differences: BTreeMap<i32, (f32, f32)> = BTreeMap::new();
+    events
+        .iter()
+        .for_each(|(date, is_withdrawal, amount, exchange_rate)| {
+            if !*is_withdrawal {
+                ledger.deposit(*amount, *exchange_rate);
+                return;
+            }
+            let (difference, uncovered) = ledger.withdraw(*amount, *exchange_rate);
+            if uncovered > 0.0 {
+                let msg = format!(
+                    "WARNING! Withdrawal of ${} on {} exceeds recorded USD inflows by ${}. Exchange rate difference of this part is not computed",
+                    amount,
+                    date.format("%Y-%m-%d"),
+                    uncovered
+                );
+                eprintln!("{}", crate::warning(&msg));
+                log::warn!("{}", msg);
+            }
+            let msg = format!(
+                " CASH WITHDRAWAL date: {}, amount: ${}, exchange_rate: {}, exchange_rate_difference: {}",
+                date.format("%Y-%m-%d"),
+                amount,
+                exchange_rate,
+                difference
+            );
+            log::info!("{}", msg);
+            let (income, cost) = differences.entry(date.year()).or_insert((0.0, 0.0));
+            if difference > 0.0 {
+                *income += difference;
+            } else {
+                *cost -= difference;
+            }
+        });
+    Ok(differences)
+}
+
+fn parse_date(date: &str) -> Result<chrono::NaiveDate, String> {
+    chrono::NaiveDate::parse_from_str(date, "%m/%d/%y")
+        .map_err(|e| format!("Error parsing date {}: {}", date, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fx_differences_fifo() -> Result<(), String> {
+        let inflows: Vec<(String, f32)> = vec![
+            ("03/01/22".to_string(), 100.0),
+            ("06/01/22".to_string(), 50.0),
+        ];
+        let withdrawals: Vec<(String, f32)> = vec![
+            ("07/01/22".to_string(), 120.0),
+            ("01/10/23".to_string(), 30.0),
+        ];
+        let mut dates: std::collections::HashMap<String, Option<(String, f32)>> =
+            std::collections::HashMap::new();
+        dates.insert("03/01/22".to_owned(), Some(("2022-02-28".to_owned(), 4.0)));
+        dates.insert("06/01/22".to_owned(), Some(("2022-05-31".to_owned(), 4.5)));
+        dates.insert("07/01/22".to_owned(), Some(("2022-06-30".to_owned(), 4.25)));
+        dates.insert("01/10/23".to_owned(), Some(("2023-01-09".to_owned(), 4.0)));
+
+        // 2022: 100 * (4.25 - 4.0) - 20 * (4.5 - 4.25)
+        // 2023: 30 * (4.0 - 4.5)
+        assert_eq!(
+            compute_fx_differences(&inflows, &withdrawals, &dates)?,
+            BTreeMap::from([(2022, (25.0 - 5.0, 0.0)), (2023, (0.0, 15.0))])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fx_differences_uncovered_withdrawal() -> Result<(), String> {
+        let inflows: Vec<(String, f32)> = vec![("03/01/22".to_string(), 100.0)];
+        let withdrawals: Vec<(String, f32)> = vec![("03/01/22".to_string(), 150.0)];
+        let mut dates: std::collections::HashMap<String, Option<(String, f32)>> =
+            std::collections::HashMap::new();
+        dates.insert("03/01/22".to_owned(), Some(("2022-02-28".to_owned(), 4.0)));
+
+        assert_eq!(
+            compute_fx_differences(&inflows, &withdrawals, &dates)?,
+            BTreeMap::from([(2022, (0.0, 0.0))])
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+/// Polish basic annual rate (percent) of late payment interest on tax arrears
+/// (odsetki za zwłokę) with the date it applies from. Rate is 200% of NBP lombard
+/// rate plus 2 percentage points, but not less than 8%
+const LATE_PAYMENT_RATES: [(&str, f32); 14] = [
+    ("2015-03-05", 8.0),
+    ("2022-02-09", 8.5),
+    ("2022-03-09", 10.0),
+    ("2022-04-07", 12.0),
+    ("2022-05-06", 13.5),
+    ("2022-06-09", 15.0),
+    ("2022-07-08", 16.0),
+    ("2022-09-08", 16.5),
+    ("2023-09-07", 15.0),
+    ("2023-10-05", 14.5),
+    ("2025-05-08", 13.5),
+    ("2025-07-03", 13.0),
+    ("2025-09-04", 12.5),
+    ("2025-10-09", 12.0),
+];
+
+/// Compute late payment interest of tax arrears paid after deadline.
+/// Interest is charged for each day after deadline up to and including payment
+/// date at rate applicable on that day: amount * days * rate / 365.
+/// Reduced rate (50% of basic) applies e.g. to corrections filed within 6 months
+/// of deadline. Interest is rounded to full PLN
+fn compute_late_payment_interest(
+    rates: &[(&str, f32)],
+    amount: f32,
+    deadline: &chrono::NaiveDate,
+    payment_date: &chrono::NaiveDate,
+    reduced: bool,
+) -> Result<f32, String> {
+    let rates = rates
+        .iter()
+        .map(|(date, rate)| {
+            chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map(|d| (d, *rate))
+                .map_err(|_| format!("Error parsing late payment rate date: {}", date))
+        })
+        .collect::<Result<Vec<(chrono::NaiveDate, f32)>, String>>()?;
+    let multiplier = match reduced {
+        true => 0.5,
+        false => 1.0,
+    };
+
+    let first_day = *deadline + chrono::Duration::days(1);
+    if first_day > *payment_date {
+        return Ok(0.0);
+    }
+    if rates.first().is_none_or(|(since, _)| *since > first_day) {
+        return Err(format!(
+            "Error: no late payment interest rate for {}",
+            first_day.format("%Y-%m-%d")
+        ));
+    }
+    // Interest of each period of the same rate is summed in f64 and rounded
+    // once, as by calculator of Ministry of Finance, so long arrears do not
+    // accumulate rounding errors of daily amounts
+    let mut interest = 0.0f64;
+    for (i, (since, rate)) in rates.iter().enumerate() {
+        let start = (*since).max(first_day);
+        let end = match rates.get(i + 1) {
+            Some((next, _)) => (*next - chrono::Duration::days(1)).min(*payment_date),
+            None => *payment_date,
+        };
+        if start > end {
+            continue;
+        }
+        let days = (end - start).num_days() + 1;
+        interest += amount as f64 * *rate as f64 * multiplier * days as f64 / 100.0 / 365.0;
+    }
+    Ok(interest.round() as f32)
+}
+
+/// Late payment interest (PLN) of tax arrears (PLN) that were due on deadline and
+/// paid on payment_date. Dates are given as %m/%d/%y
+pub fn late_payment_interest(
+    amount: f32,
+    deadline: &str,
+    payment_date: &str,
+    reduced: bool,
+) -> Result<f32, String> {
+    let parse = |date: &str| {
+        chrono::NaiveDate::parse_from_str(date, "%m/%d/%y")
+            .map_err(|_| format!("Error parsing date: {}", date))
+    };
+    compute_late_payment_interest(
+        &LATE_PAYMENT_RATES,
+        amount,
+        &parse(deadline)?,
+        &parse(payment_date)?,
+        reduced,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_late_payment_interest() -> Result<(), String> {
+        let rates = [("2015-03-05", 8.0), ("2022-02-09", 36.5)];
+        let date = |y, m, d| chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        // 10 days at 8% and 10 days at 36.5%: 1000 * (10 * 0.08 + 10 * 0.365) / 365
+        assert_eq!(
+            compute_late_payment_interest(
+                &rates,
+                1000.0,
+                &date(2022, 1, 29),
+                &date(2022, 2, 18),
+                false
+            ),
+            Ok(12.0)
+        );
+        // 20 days at reduced 36.5%: 1000 * 20 * 0.1825 / 365
+        assert_eq!(
+            compute_late_payment_interest(
+                &rates,
+                1000.0,
+                &date(2022, 2, 10),
+                &date(2022, 3, 2),
+                true
+            ),
+            Ok(10.0)
+        );
+        // 10 years at 8%, daily amounts summed in f32 would drift from 98765
+        let rates = [("2015-03-05", 8.0)];
+        assert_eq!(
+            compute_late_payment_interest(
+                &rates,
+                123456.78,
+                &date(2015, 3, 31),
+                &date(2025, 3, 28),
+                false
+            ),
+            Ok(98765.0)
+        );
+        // Paid on time
+        assert_eq!(
+            compute_late_payment_interest(
+                &rates,
+                1000.0,
+                &date(2022, 4, 30),
+                &date(2022, 4, 30),
+                false
+            ),
+            Ok(0.0)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,815 @@
+//! Computation of taxation figures of each tax year from transactions with
+//! resolved exchange rates
+
+pub mod cashledger;
+pub mod latepayment;
+pub mod rules;
+
+use chrono::Datelike;
+use std::collections::BTreeMap;
+
+use crate::{HoldingTerm, Income, TaxRules, TaxYearResult};
+
+// (gross, tax withheld) in USD and (gross, tax) in target currency per month (YYYY-MM)
+type MonthlyIncome = BTreeMap<String, (f32, f32, f32, f32)>;
+
+/// Split figures of jointly owned account between taxpayer and spouse.
+/// share is taxpayer's part in percent. Returns (taxpayer, spouse) results.
+/// PIT-8C and prepaid tax belong to taxpayer so they are not split
+pub fn split_with_spouse(result: &TaxYearResult, share: f32) -> (TaxYearResult, TaxYearResult) {
+    let scale = |breakdown: &BTreeMap<String, (f32, f32, f32)>, ratio: f32| {
+        breakdown
+            .iter()
+            .map(|(key, (gross, tax, creditable))| {
+                (
+                    key.clone(),
+                    (gross * ratio, tax * ratio, creditable * ratio),
+                )
+            })
+            .collect()
+    };
+    let part = |ratio: f32| TaxYearResult {
+        gross_div: result.gross_div * ratio,
+        tax_div: result.tax_div * ratio,
+        per_symbol: scale(&result.per_symbol, ratio),
+        per_country: scale(&result.per_country, ratio),
+        per_broker: result
+            .per_broker
+            .iter()
+            .map(|(broker, income)| (broker.clone(), income.scaled(ratio)))
+            .collect(),
+        gross_sold: result.gross_sold * ratio,
+        cost_sold: result.cost_sold * ratio,
+        gross_interest: result.gross_interest * ratio,
+        tax_interest: result.tax_interest * ratio,
+        proceeds_short_us: result.proceeds_short_us * ratio,
+        cost_short_us: result.cost_short_us * ratio,
+        proceeds_long_us: result.proceeds_long_us * ratio,
+        cost_long_us: result.cost_long_us * ratio,
+        crypto_income: result.crypto_income * ratio,
+        crypto_cost: result.crypto_cost * ratio,
+        crypto_carried_cost: result.crypto_carried_cost * ratio,
+        excess_withholding_us: result.excess_withholding_us * ratio,
+        fx_income: result.fx_income * ratio,
+        fx_cost: result.fx_cost * ratio,
+        gross_div_us: result.gross_div_us * ratio,
+        tax_div_us: result.tax_div_us * ratio,
+        gross_interest_us: result.gross_interest_us * ratio,
+        tax_interest_us: result.tax_interest_us * ratio,
+        ..Default::default()
+    };
+    let mut own = part(share / 100.0);
+    own.pit8c_income = result.pit8c_income;
+    own.pit8c_cost = result.pit8c_cost;
+    own.prepaid_tax = result.prepaid_tax;
+    (own, part((100.0 - share) / 100.0))
+}
+
+/// (income, cost) of capital gains of PIT-38 part C: sold stock, realized
+/// exchange rate differences on USD cash and PIT-8C of Polish brokers
+pub fn capital_gains(result: &TaxYearResult) -> (f32, f32) {
+    (
+        result.gross_sold + result.fx_income + result.pit8c_income,
+        result.cost_sold + result.fx_cost + result.pit8c_cost,
+    )
+}
+
+/// Tax due for given year before subtracting prepaid tax. Capital gains
+/// (including exchange rate differences and PIT-8C) and virtual currency income are taxed separately if
+/// positive. Flat tax on dividends and interest is reduced by tax paid
+/// abroad, but not below zero. Tax withheld from dividends counts only up to
+/// treaty rate, excess can only be reclaimed from IRS
+pub fn compute_tax_due(result: &TaxYearResult, rules: &TaxRules) -> f32 {
+    if rules.epit_rounding {
+        return compute_tax_due_epit(result, rules);
+    }
+    let (income, cost) = capital_gains(result);
+    let gains = income - cost;
+    let gains_tax = rules.round(rules.tax_rate * rules.round(gains.max(0.0)));
+    let crypto_gains = result.crypto_income - result.crypto_cost - result.crypto_carried_cost;
+    let crypto_tax = rules.round(rules.tax_rate * rules.round(crypto_gains.max(0.0)));
+    let div_tax = rules.round(rules.tax_rate * (result.gross_div + result.gross_interest));
+    let creditable_tax_div = result
+        .tax_div
+        .min(rules.treaty_withholding_rate * result.gross_div);
+    let paid_abroad = rules
+        .round(creditable_tax_div + result.tax_interest)
+        .min(div_tax);
+    gains_tax + crypto_tax + div_tax - paid_abroad
+}
+
+/// Tax due computed the way Twoj e-PIT calculator rounds PIT-38 fields:
+/// bases of gains taxation to full PLN, taxes computed from them and tax paid
+/// abroad to grosz, and tax due of each part to full PLN
+fn compute_tax_due_epit(result: &TaxYearResult, rules: &TaxRules) -> f32 {
+    let to_pln = |amount: f32| amount.round();
+    let to_grosz = |amount: f32| (amount * 100.0).round() / 100.0;
+    let (income, cost) = capital_gains(result);
+    let gains = income - cost;
+    let gains_tax = to_pln(to_grosz(rules.tax_rate * to_pln(gains.max(0.0))));
+    let crypto_gains = result.crypto_income - result.crypto_cost - result.crypto_carried_cost;
+    let crypto_tax = to_pln(to_grosz(rules.tax_rate * to_pln(crypto_gains.max(0.0))));
+    let div_tax = to_grosz(rules.tax_rate * (result.gross_div + result.gross_interest));
+    let creditable_tax_div = result
+        .tax_div
+        .min(rules.treaty_withholding_rate * result.gross_div);
+    let paid_abroad = to_grosz(creditable_tax_div + result.tax_interest).min(div_tax);
+    gains_tax + crypto_tax + to_pln(div_tax - paid_abroad)
+}
+
+/// (gross, tax withheld, exchange rate) of dividend or interest
+fn withheld(income: &Income) -> Option<(f32, f32, f32)> {
+    match income {
+        Income::Dividend {
+            gross,
+            tax,
+            exchange_rate,
+            ..
+        }
+        | Income::Interest {
+            gross,
+            tax,
+            exchange_rate,
+            ..
+        } => Some((*gross, *tax, *exchange_rate)),
+        Income::SaleGain { .. } => None,
+    }
+}
+
+fn compute_div_taxation(incomes: &[Income]) -> (f32, f32) {
+    // Gross income from dividends and tax paid in US in target currency (PLN, EUR etc.)
+    incomes.iter().filter_map(withheld).fold(
+        (0.0, 0.0),
+        |(gross_pl, tax_pl), (gross, tax, exchange_rate)| {
+            (
+                gross_pl + exchange_rate * gross,
+                tax_pl + exchange_rate * tax,
+            )
+        },
+    )
+}
+
+/// Gross income and tax withheld in USD, as reported by broker
+fn compute_totals_us(incomes: &[Income]) -> (f32, f32) {
+    incomes
+        .iter()
+        .filter_map(withheld)
+        .fold((0.0, 0.0), |(gross_us, tax_us), (gross, tax, _)| {
+            (gross_us + gross, tax_us + tax)
+        })
+}
+
+/// Aggregate (gross, tax paid, creditable tax) of dividends per symbol in target
+/// currency. Without treaty rate all tax paid is creditable
+pub fn compute_div_taxation_per_symbol(
+    incomes: &[Income],
+    treaty_withholding_rate: Option<f32>,
+) -> BTreeMap<String, (f32, f32, f32)> {
+    let mut per_symbol: BTreeMap<String, (f32, f32, f32)> = BTreeMap::new();
+    incomes.iter().for_each(|income| {
+        if let Income::Dividend {
+            symbol,
+            gross: gross_us,
+            tax: tax_us,
+            exchange_rate,
+            ..
+        } = income
+        {
+            let (gross, tax, creditable) =
+                per_symbol.entry(symbol.clone()).or_insert((0.0, 0.0, 0.0));
+            *gross += exchange_rate * gross_us;
+            *tax += exchange_rate * tax_us;
+            *creditable += exchange_rate
+                * match treaty_withholding_rate {
+                    Some(rate) => tax_us.min(rate * gross_us),
+                    None => *tax_us,
+                };
+        }
+    });
+    per_symbol
+}
+
+/// Aggregate per symbol figures into (gross, tax paid, creditable tax) per
+/// country of source. countries map symbol to country code, symbols not
+/// given there are assumed to be sourced in US
+pub fn aggregate_per_country(
+    per_symbol: &BTreeMap<String, (f32, f32, f32)>,
+    countries: &std::collections::HashMap<String, String>,
+) -> BTreeMap<String, (f32, f32, f32)> {
+    let mut per_country: BTreeMap<String, (f32, f32, f32)> = BTreeMap::new();
+    per_symbol
+        .iter()
+        .for_each(|(symbol, (gross, tax, creditable))| {
+            let country = countries.get(symbol).cloned().unwrap_or("US".to_string());
+            let (country_gross, country_tax, country_creditable) =
+                per_country.entry(country).or_insert((0.0, 0.0, 0.0));
+            *country_gross += gross;
+            *country_tax += tax;
+            *country_creditable += creditable;
+        });
+    per_country
+}
+
+/// Sum dividends and interest per tax year and month (YYYY-MM) into
+/// (gross, tax withheld) in USD and (gross, tax) in target currency
+pub fn compute_income_per_month(incomes: &[Income]) -> BTreeMap<i32, MonthlyIncome> {
+    let mut per_month: BTreeMap<i32, MonthlyIncome> = BTreeMap::new();
+    incomes.iter().for_each(|income| {
+        if let Some((income_gross_us, income_tax_us, exchange_rate)) = withheld(income) {
+            let (gross_us, tax_us, gross, tax) = per_month
+                .entry(income.tax_year())
+                .or_default()
+                .entry(income.date().format("%Y-%m").to_string())
+                .or_insert((0.0, 0.0, 0.0, 0.0));
+            *gross_us += income_gross_us;
+            *tax_us += income_tax_us;
+            *gross += income_gross_us * exchange_rate;
+            *tax += income_tax_us * exchange_rate;
+        }
+    });
+    per_month
+}
+
+/// (proceeds, cost basis, fees, term, settlement and acquisition exchange rates) of sold stock
+fn sold(income: &Income) -> Option<(f32, f32, f32, HoldingTerm, f32, f32)> {
+    match income {
+        Income::SaleGain {
+            proceeds,
+            cost_basis,
+            fees,
+            term,
+            settlement_exchange_rate,
+            acquisition_exchange_rate,
+            ..
+        } => Some((
+            *proceeds,
+            *cost_basis,
+            *fees,
+            *term,
+            *settlement_exchange_rate,
+            *acquisition_exchange_rate,
+        )),
+        _ => None,
+    }
+}
+
+fn compute_sold_taxation(incomes: &[Income]) -> (f32, f32) {
+    // Net income from sold stock and its cost in target currency (PLN, EUR etc.)
+    // Fees and commission are charged at settlement so the same exchange rate applies
+    incomes.iter().filter_map(sold).fold(
+        (0.0, 0.0),
+        |(gross_pl, cost_pl),
+         (proceeds, cost_basis, fees, _, settlement_rate, acquisition_rate)| {
+            (
+                gross_pl + settlement_rate * (proceeds - fees),
+                cost_pl + acquisition_rate * cost_basis,
+            )
+        },
+    )
+}
+
+/// USD (proceeds, cost basis) of sold stock of given holding term
+fn compute_sold_per_term(incomes: &[Income], term: HoldingTerm) -> (f32, f32) {
+    incomes
+        .iter()
+        .filter_map(sold)
+        .filter(|(_, _, _, sold_term, _, _)| *sold_term == term)
+        .fold(
+            (0.0, 0.0),
+            |(total_proceeds, total_cost), (proceeds, cost_basis, fees, ..)| {
+                (total_proceeds + proceeds - fees, total_cost + cost_basis)
+            },
+        )
+}
+
+/// USD withheld above treaty rate from given dividend
+pub fn compute_excess_withholding(income: &Income, treaty_withholding_rate: f32) -> f32 {
+    match income {
+        Income::Dividend { gross, tax, .. } => (tax - treaty_withholding_rate * gross).max(0.0),
+        _ => 0.0,
+    }
+}
+
+pub fn tax_year(date: &str) -> Result<i32, String> {
+    Ok(crate::transactions::parse_date(date)?.year())
+}
+
+/// Virtual currency disposals are income and acquisitions are cost of year
+/// they happened (art. 30b ust. 1a). Excess of costs over income is carried to
+/// next year. transactions are (date, is_sale, amount in target currency).
+/// Returns (income, cost, cost carried from previous years) per year
+pub fn compute_crypto_per_year(
+    transactions: Vec<(String, bool, f32)>,
+    carried_cost: f32,
+) -> Result<BTreeMap<i32, (f32, f32, f32)>, String> {
+    let mut per_year: BTreeMap<i32, (f32, f32, f32)> = BTreeMap::new();
+    for (transaction_date, is_sale, amount) in transactions.iter() {
+        let (income, cost, _) = per_year
+            .entry(tax_year(transaction_date)?)
+            .or_insert((0.0, 0.0, 0.0));
+        match is_sale {
+            true => *income += amount,
+            false => *cost += amount,
+        }
+    }
+    let mut carried = carried_cost;
+    per_year
+        .iter_mut()
+        .for_each(|(_, (income, cost, carried_cost))| {
+            *carried_cost = carried;
+            carried = (*cost + carried - *income).max(0.0);
+        });
+    Ok(per_year)
+}
+
+/// Compute taxation separately for each tax year incomes belong to.
+/// Dividends and interest are assigned to year of payment and sold stock to year of settlement
+pub fn compute_taxation_per_year(incomes: Vec<Income>) -> BTreeMap<i32, TaxYearResult> {
+    // (dividends, interest, sold stock) of each year
+    type YearIncomes = (Vec<Income>, Vec<Income>, Vec<Income>);
+    let mut per_year: BTreeMap<i32, YearIncomes> = BTreeMap::new();
+    incomes.into_iter().for_each(|income| {
+        let (dividends, interest, sold) = per_year.entry(income.tax_year()).or_default();
+        match income {
+            Income::Dividend { .. } => dividends.push(income),
+            Income::Interest { .. } => interest.push(income),
+            Income::SaleGain { .. } => sold.push(income),
+        }
+    });
+
+    per_year
+        .into_iter()
+        .map(|(year, (dividends, interest, sold))| {
+            let (gross_div_us, tax_div_us) = compute_totals_us(&dividends);
+            let (gross_interest_us, tax_interest_us) = compute_totals_us(&interest);
+            let (gross_div, tax_div) = compute_div_taxation(&dividends);
+            let (proceeds_short_us, cost_short_us) =
+                compute_sold_per_term(&sold, HoldingTerm::Short);
+            let (proceeds_long_us, cost_long_us) = compute_sold_per_term(&sold, HoldingTerm::Long);
+            let (gross_sold, cost_sold) = compute_sold_taxation(&sold);
+            let (gross_interest, tax_interest) = compute_div_taxation(&interest);
+            (
+                year,
+                TaxYearResult {
+                    gross_div,
+                    tax_div,
+                    gross_sold,
+                    cost_sold,
+                    gross_interest,
+                    tax_interest,
+                    proceeds_short_us,
+                    cost_short_us,
+                    proceeds_long_us,
+                    cost_long_us,
+                    gross_div_us,
+                    tax_div_us,
+                    gross_interest_us,
+                    tax_interest_us,
+                    ..Default::default()
+                },
+            )
+        })
+        .collect::<BTreeMap<i32, TaxYearResult>>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> chrono::NaiveDate {
+        chrono::NaiveDate::parse_from_str(date, "%m/%d/%y").unwrap()
+    }
+    #[test]
+    fn test_simple_div_taxation() -> Result<(), String> {
+        // Init Transactions
+        let transactions: Vec<Income> = vec![Income::Dividend {
+            date: date("03/01/23"),
+            symbol: "INTC".to_string(),
+            currency: "USD".to_string(),
+            gross: 100.0,
+            tax: 25.0,
+            exchange_rate: 4.0,
+        }];
+        assert_eq!(compute_div_taxation(&transactions), (400.0, 100.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_div_taxation() -> Result<(), String> {
+        // Init Transactions
+        let transactions: Vec<Income> = vec![
+            Income::Dividend {
+                date: date("03/01/23"),
+                symbol: "INTC".to_string(),
+                currency: "USD".to_string(),
+                gross: 100.0,
+                tax: 25.0,
+                exchange_rate: 4.0,
+            },
+            Income::Dividend {
+                date: date("03/01/23"),
+                symbol: "INTC".to_string(),
+                currency: "USD".to_string(),
+                gross: 126.0,
+                tax: 10.0,
+                exchange_rate: 3.5,
+            },
+        ];
+        assert_eq!(
+            compute_div_taxation(&transactions),
+            (400.0 + 126.0 * 3.5, 100.0 + 10.0 * 3.5)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_taxation_per_year() -> Result<(), String> {
+        let incomes: Vec<Income> = vec![
+            Income::Dividend {
+                date: date("06/01/22"),
+                symbol: "INTC".to_string(),
+                currency: "USD".to_string(),
+                gross: 100.0,
+                tax: 15.0,
+                exchange_rate: 4.0,
+            },
+            Income::Dividend {
+                date: date("03/01/23"),
+                symbol: "INTC".to_string(),
+                currency: "USD".to_string(),
+                gross: 126.0,
+                tax: 10.0,
+                exchange_rate: 3.5,
+            },
+            Income::SaleGain {
+                settlement_date: date("01/03/23"),
+                acquisition_date: date("01/01/21"),
+                currency: "USD".to_string(),
+                proceeds: 100.0,
+                cost_basis: 70.0,
+                fees: 0.0,
+                term: HoldingTerm::Long,
+                settlement_exchange_rate: 5.0,
+                acquisition_exchange_rate: 6.0,
+            },
+            Income::Interest {
+                date: date("12/30/22"),
+                currency: "USD".to_string(),
+                gross: 2.0,
+                tax: 0.0,
+                exchange_rate: 4.0,
+            },
+        ];
+        assert_eq!(
+            compute_taxation_per_year(incomes),
+            BTreeMap::from([
+                (
+                    2022,
+                    TaxYearResult {
+                        gross_div: 400.0,
+                        tax_div: 60.0,
+                        gross_interest: 8.0,
+                        gross_div_us: 100.0,
+                        tax_div_us: 15.0,
+                        gross_interest_us: 2.0,
+                        ..Default::default()
+                    }
+                ),
+                (
+                    2023,
+                    TaxYearResult {
+                        gross_div: 126.0 * 3.5,
+                        tax_div: 35.0,
+                        gross_sold: 500.0,
+                        cost_sold: 420.0,
+                        proceeds_long_us: 100.0,
+                        cost_long_us: 70.0,
+                        gross_div_us: 126.0,
+                        tax_div_us: 10.0,
+                        ..Default::default()
+                    }
+                ),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_div_taxation_per_symbol() -> Result<(), String> {
+        let transactions: Vec<Income> = vec![
+            Income::Dividend {
+                date: date("03/01/23"),
+                symbol: "INTC".to_string(),
+                currency: "USD".to_string(),
+                gross: 100.0,
+                tax: 30.0,
+                exchange_rate: 4.0,
+            },
+            Income::Dividend {
+                date: date("06/01/23"),
+                symbol: "DLB".to_string(),
+                currency: "USD".to_string(),
+                gross: 50.0,
+                tax: 5.0,
+                exchange_rate: 2.0,
+            },
+            Income::Dividend {
+                date: date("09/01/23"),
+                symbol: "INTC".to_string(),
+                currency: "USD".to_string(),
+                gross: 100.0,
+                tax: 10.0,
+                exchange_rate: 4.0,
+            },
+        ];
+        assert_eq!(
+            compute_div_taxation_per_symbol(&transactions, Some(0.25)),
+            BTreeMap::from([
+                ("DLB".to_string(), (100.0, 10.0, 10.0)),
+                ("INTC".to_string(), (800.0, 160.0, 140.0)),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_aggregate_per_country() -> Result<(), String> {
+        let per_symbol = BTreeMap::from([
+            ("DLB".to_string(), (100.0, 15.0, 15.0)),
+            ("INTC".to_string(), (800.0, 160.0, 120.0)),
+            ("VWRL".to_string(), (50.0, 0.0, 0.0)),
+        ]);
+        let countries = std::collections::HashMap::from([("VWRL".to_string(), "IE".to_string())]);
+        assert_eq!(
+            aggregate_per_country(&per_symbol, &countries),
+            BTreeMap::from([
+                ("IE".to_string(), (50.0, 0.0, 0.0)),
+                ("US".to_string(), (900.0, 175.0, 135.0)),
+            ])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_income_per_month() -> Result<(), String> {
+        let transaction =
+            |payment_date: &str, gross_us: f32, exchange_rate: f32| Income::Dividend {
+                date: date(payment_date),
+                symbol: "INTC".to_string(),
+                currency: "USD".to_string(),
+                gross: gross_us,
+                tax: gross_us / 10.0,
+                exchange_rate,
+            };
+        let per_month = compute_income_per_month(&[
+            transaction("03/01/23", 100.0, 4.0),
+            transaction("03/31/23", 10.0, 5.0),
+            transaction("12/01/23", 200.0, 4.0),
+            transaction("01/02/24", 50.0, 4.0),
+        ]);
+        assert_eq!(
+            per_month[&2023],
+            BTreeMap::from([
+                ("2023-03".to_string(), (110.0, 11.0, 450.0, 45.0)),
+                ("2023-12".to_string(), (200.0, 20.0, 800.0, 80.0)),
+            ])
+        );
+        assert_eq!(
+            per_month[&2024],
+            BTreeMap::from([("2024-01".to_string(), (50.0, 5.0, 200.0, 20.0))])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_excess_withholding() -> Result<(), String> {
+        let transaction = Income::Dividend {
+            date: date("03/01/23"),
+            symbol: "INTC".to_string(),
+            currency: "USD".to_string(),
+            gross: 200.0,
+            tax: 60.0,
+            exchange_rate: 4.0,
+        };
+        assert_eq!(compute_excess_withholding(&transaction, 0.25), 10.0);
+        assert_eq!(compute_excess_withholding(&transaction, 0.30), 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_crypto_per_year() -> Result<(), String> {
+        let transactions: Vec<(String, bool, f32)> = vec![
+            ("03/01/22".to_string(), false, 1000.0),
+            ("06/01/22".to_string(), true, 400.0),
+            ("02/01/23".to_string(), true, 900.0),
+            ("03/01/23".to_string(), false, 100.0),
+        ];
+        // 2022: 1000 + 50 - 400 excess carried to 2023
+        assert_eq!(
+            compute_crypto_per_year(transactions, 50.0)?,
+            BTreeMap::from([(2022, (400.0, 1000.0, 50.0)), (2023, (900.0, 100.0, 650.0))])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tax_due() -> Result<(), String> {
+        let rules = TaxRules {
+            tax_rate: 0.19,
+            treaty_withholding_rate: 0.15,
+            round_to: 1.0,
+            epit_rounding: false,
+            pit38_schema: None,
+        };
+        let result = TaxYearResult {
+            gross_div: 1000.0,
+            tax_div: 150.0,
+            gross_sold: 3000.0,
+            cost_sold: 2500.0,
+            pit8c_income: 1000.0,
+            pit8c_cost: 500.0,
+            ..Default::default()
+        };
+        // gains: 19% of 1000, dividends: 190 - 150
+        assert_eq!(compute_tax_due(&result, &rules), 190.0 + 40.0);
+        // Loss is not taxed and tax withheld above treaty rate is not credited
+        let result = TaxYearResult {
+            gross_div: 100.0,
+            tax_div: 30.0,
+            gross_sold: 1000.0,
+            cost_sold: 2000.0,
+            ..Default::default()
+        };
+        assert_eq!(compute_tax_due(&result, &rules), 19.0 - 15.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tax_due_fx_differences() -> Result<(), String> {
+        let rules = TaxRules {
+            tax_rate: 0.19,
+            treaty_withholding_rate: 0.15,
+            round_to: 1.0,
+            epit_rounding: false,
+            pit38_schema: None,
+        };
+        let result = TaxYearResult {
+            gross_sold: 3000.0,
+            cost_sold: 2500.0,
+            ..Default::default()
+        };
+        assert_eq!(compute_tax_due(&result, &rules), 95.0);
+        // Exchange rate differences are capital gains income and cost
+        let result = TaxYearResult {
+            fx_income: 600.0,
+            fx_cost: 100.0,
+            ..result
+        };
+        assert_eq!(capital_gains(&result), (3600.0, 2600.0));
+        assert_eq!(compute_tax_due(&result, &rules), 190.0);
+        let epit_rules = TaxRules {
+            epit_rounding: true,
+            ..rules
+        };
+        assert_eq!(compute_tax_due(&result, &epit_rules), 190.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tax_due_epit_rounding() -> Result<(), String> {
+        let rules = TaxRules {
+            tax_rate: 0.19,
+            treaty_withholding_rate: 0.15,
+            round_to: 0.01,
+            epit_rounding: true,
+            pit38_schema: None,
+        };
+        let result = TaxYearResult {
+            gross_div: 1000.25,
+            tax_div: 150.0,
+            gross_sold: 3000.5,
+            cost_sold: 2500.0,
+            ..Default::default()
+        };
+        // gains: 19% of 501 = 95.19 -> 95, dividends: 190.05 - 150 = 40.05 -> 40
+        assert_eq!(compute_tax_due(&result, &rules), 95.0 + 40.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_with_spouse() -> Result<(), String> {
+        let result = TaxYearResult {
+            gross_div: 400.0,
+            tax_div: 60.0,
+            gross_sold: 1000.0,
+            cost_sold: 200.0,
+            fx_income: 10.0,
+            fx_cost: 0.0,
+            pit8c_income: 500.0,
+            pit8c_cost: 100.0,
+            ..Default::default()
+        };
+        assert_eq!(
+            split_with_spouse(&result, 75.0),
+            (
+                TaxYearResult {
+                    gross_div: 300.0,
+                    tax_div: 45.0,
+                    gross_sold: 750.0,
+                    cost_sold: 150.0,
+                    fx_income: 7.5,
+                    fx_cost: 0.0,
+                    pit8c_income: 500.0,
+                    pit8c_cost: 100.0,
+                    ..Default::default()
+                },
+                TaxYearResult {
+                    gross_div: 100.0,
+                    tax_div: 15.0,
+                    gross_sold: 250.0,
+                    cost_sold: 50.0,
+                    fx_income: 2.5,
+                    fx_cost: 0.0,
+                    ..Default::default()
+                }
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_simple_sold_taxation() -> Result<(), String> {
+        // Init Transactions
+        let transactions: Vec<Income> = vec![Income::SaleGain {
+            settlement_date: date("03/01/23"),
+            acquisition_date: date("01/03/22"),
+            currency: "USD".to_string(),
+            proceeds: 100.0,
+            cost_basis: 70.0,
+            fees: 0.0,
+            term: HoldingTerm::Long,
+            settlement_exchange_rate: 5.0,
+            acquisition_exchange_rate: 6.0,
+        }];
+        assert_eq!(
+            compute_sold_taxation(&transactions),
+            (100.0 * 5.0, 70.0 * 6.0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sold_taxation_with_fees() -> Result<(), String> {
+        // Init Transactions
+        let transactions: Vec<Income> = vec![Income::SaleGain {
+            settlement_date: date("03/01/23"),
+            acquisition_date: date("01/03/22"),
+            currency: "USD".to_string(),
+            proceeds: 100.0,
+            cost_basis: 70.0,
+            fees: 2.0,
+            term: HoldingTerm::Long,
+            settlement_exchange_rate: 5.0,
+            acquisition_exchange_rate: 6.0,
+        }];
+        assert_eq!(
+            compute_sold_taxation(&transactions),
+            (98.0 * 5.0, 70.0 * 6.0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sold_taxation() -> Result<(), String> {
+        // Init Transactions
+        let transactions: Vec<Income> = vec![
+            Income::SaleGain {
+                settlement_date: date("03/01/23"),
+                acquisition_date: date("01/03/22"),
+                currency: "USD".to_string(),
+                proceeds: 100.0,
+                cost_basis: 70.0,
+                fees: 0.0,
+                term: HoldingTerm::Long,
+                settlement_exchange_rate: 5.0,
+                acquisition_exchange_rate: 6.0,
+            },
+            Income::SaleGain {
+                settlement_date: date("03/01/23"),
+                acquisition_date: date("01/03/22"),
+                currency: "USD".to_string(),
+                proceeds: 10.0,
+                cost_basis: 4.0,
+                fees: 0.0,
+                term: HoldingTerm::Long,
+                settlement_exchange_rate: 2.0,
+                acquisition_exchange_rate: 3.0,
+            },
+        ];
+        assert_eq!(
+            compute_sold_taxation(&transactions),
+            (100.0 * 5.0 + 10.0 * 2.0, 70.0 * 6.0 + 4.0 * 3.0)
+        );
+        Ok(())
+    }
+}
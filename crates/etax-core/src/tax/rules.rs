@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+const BUNDLED_TAX_RULES: &str = include_str!("../../tax_rules.toml");
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct TaxRules {
+    pub tax_rate: f32,
+    pub treaty_withholding_rate: f32,
+    pub round_to: f32,
+    // Reproduce intermediate rounding of Twoj e-PIT calculator: tax bases
+    // and final tax amounts to full PLN, intermediate taxes to grosz
+    #[serde(default)]
+    pub epit_rounding: bool,
+    // e-Deklaracje schema of PIT-38 for the year (pl residency only)
+    #[serde(default)]
+    pub pit38_schema: Option<Pit38Schema>,
+}
+
+/// Version of PIT-38 e-Deklaracje XML schema as published by Ministry of Finance
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct Pit38Schema {
+    // Form variant e.g. 17 for PIT-38(17)
+    pub variant: u32,
+    // Target namespace of the form schema
+    pub namespace: String,
+    // Namespace of common types (etd prefix) the form schema imports
+    pub types_namespace: String,
+}
+
+impl TaxRules {
+    pub fn round(&self, amount: f32) -> f32 {
+        (amount / self.round_to).round() * self.round_to
+    }
+}
+
+/// Tax rules of single residency keyed by first tax year they apply to
+pub type YearlyTaxRules = BTreeMap<i32, TaxRules>;
+
+fn parse_tax_rules(content: &str) -> Result<HashMap<String, YearlyTaxRules>, String> {
+    let parsed: HashMap<String, HashMap<String, TaxRules>> =
+        toml::from_str(content).map_err(|e| format!("Error parsing tax rules: {}", e))?;
+    parsed
+        .into_iter()
+        .map(|(residency, rules)| {
+            let rules = rules
+                .into_iter()
+                .map(|(year, r)| {
+                    year.parse::<i32>()
+                        .map(|year| (year, r))
+                        .map_err(|_| format!("Error: tax rules year is not a number: {}", year))
+                })
+                .collect::<Result<YearlyTaxRules, String>>()?;
+            Ok((residency, rules))
+        })
+        .collect()
+}
+
+/// Load bundled tax rules of given residency. Entries of overrides file
+/// (if given) are added to or replace bundled ones
+pub fn load_tax_rules(residency: &str, overrides: Option<&str>) -> Result<YearlyTaxRules, String> {
+    let mut rules = parse_tax_rules(BUNDLED_TAX_RULES)?
+        .remove(residency)
+        .unwrap_or_default();
+    if let Some(path) = overrides {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Error reading tax rules file {}: {}", path, e))?;
+        if let Some(overriden) = parse_tax_rules(&content)?.remove(residency) {
+            rules.extend(overriden);
+        }
+    }
+    match rules.is_empty() {
+        true => Err(format!("Error: no tax rules for residency: {}", residency)),
+        false => Ok(rules),
+    }
+}
+
+/// Rules of the latest year not greater than given one. With no year given
+/// (or year older than all rules) the latest/oldest rules are returned
+pub fn tax_rules_for_year(rules: &YearlyTaxRules, year: Option<i32>) -> TaxRules {
+    let found = match year {
+        Some(year) => rules.range(..=year).next_back().or(rules.iter().next()),
+        None => rules.iter().next_back(),
+    };
+    found
+        .map(|(_, r)| r.clone())
+        .expect("Error: tax rules are empty")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tax_rules_for_year() -> Result<(), String> {
+        let rules = parse_tax_rules(
+            "[pl.2019]\ntax_rate = 0.19\ntreaty_withholding_rate = 0.15\nround_to = 0.01\n\
+             [pl.2025]\ntax_rate = 0.2\ntreaty_withholding_rate = 0.15\nround_to = 1.0\n",
+        )?
+        .remove("pl")
+        .unwrap();
+        assert_eq!(tax_rules_for_year(&rules, Some(2023)).tax_rate, 0.19);
+        assert_eq!(tax_rules_for_year(&rules, Some(2026)).tax_rate, 0.2);
+        assert_eq!(tax_rules_for_year(&rules, Some(2010)).tax_rate, 0.19);
+        assert_eq!(tax_rules_for_year(&rules, None).round(12.5), 13.0);
+        assert_eq!(tax_rules_for_year(&rules, None).pit38_schema, None);
+
+        let rules = parse_tax_rules(
+            "[pl.2023]\ntax_rate = 0.19\ntreaty_withholding_rate = 0.15\nround_to = 0.01\n\
+             [pl.2023.pit38_schema]\nvariant = 17\nnamespace = \"ns\"\ntypes_namespace = \"etd\"\n",
+        )?
+        .remove("pl")
+        .unwrap();
+        assert_eq!(
+            tax_rules_for_year(&rules, Some(2023))
+                .pit38_schema
+                .map(|s| s.variant),
+            Some(17)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundled_tax_rules() -> Result<(), String> {
+        let rules = load_tax_rules("pl", None)?;
+        assert_eq!(tax_rules_for_year(&rules, Some(2023)).tax_rate, 0.19);
+        assert!(load_tax_rules("xx", None).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,1578 @@
+use chrono;
+use chrono::Datelike;
+
+use crate::{HoldingTerm, SoldTransaction, Transaction};
+
+// (trade date, settlement date, quantity, price, amount sold) of brokerage statement
+type Sale = (String, String, i32, f32, f32);
+// (acquisition date, sold date, cost basis, adjusted cost basis, total proceeds, term) of G&L
+type Lot = (String, String, f32, f32, f32, HoldingTerm);
+// (trade date, settlement date, acquisition date, income, cost basis, fees, term) of sold stock
+type DetailedSale = (String, String, String, f32, f32, f32, HoldingTerm);
+
+/// Date of transaction of brokerage statement (e.g. 03/01/21)
+pub fn parse_date(date: &str) -> Result<chrono::NaiveDate, String> {
+    chrono::NaiveDate::parse_from_str(date, "%m/%d/%y")
+        .map_err(|_| format!("Error parsing transaction date: {}", date))
+}
+
+/// Check if all dividends transaction come from the same year. Outer error
+/// is invalid date, inner one warning of transactions of different years
+pub fn verify_dividends_transactions(
+    div_transactions: &[(String, f32, f32, String)],
+) -> Result<Result<(), String>, String> {
+    let mut trans = div_transactions.iter();
+    let (transaction_date, _, _, _) = match trans.next() {
+        Some((x, a, b, c)) => (x, a, b, c),
+        None => {
+            log::info!("No Dividends transactions");
+            return Ok(Ok(()));
+        }
+    };
+
+    let transaction_year = parse_date(transaction_date)?.year();
+    let mut verification: Result<(), String> = Ok(());
+    for (tr_date, _, _, _) in trans {
+        if parse_date(tr_date)?.year() != transaction_year {
+            let msg: &str =
+                "WARNING! Brokerage statements are related to different years. Was it intentional?";
+            verification = Err(msg.to_owned());
+        }
+    }
+    Ok(verification)
+}
+
+/// US withholding on dividends is 0% (e.g. IRA), 15% (W-8BEN treaty rate)
+/// or 30% (no W-8BEN). Other ratio suggests that wrong numbers were parsed
+pub fn is_expected_withholding(gross_us: f32, tax_us: f32) -> bool {
+    const EXPECTED_RATIOS: [f32; 3] = [0.0, 0.15, 0.30];
+    const TOLERANCE: f32 = 0.01;
+    let ratio = tax_us / gross_us;
+    gross_us == 0.0
+        || EXPECTED_RATIOS
+            .iter()
+            .any(|expected| (ratio - expected).abs() < TOLERANCE)
+}
+
+/// Dividends with withholding ratio other than expected one
+pub fn verify_withholding_ratios(
+    div_transactions: &[(String, f32, f32, String)],
+) -> Result<(), String> {
+    let suspicious: Vec<String> = div_transactions
+        .iter()
+        .filter(|(_, gross_us, tax_us, _)| !is_expected_withholding(*gross_us, *tax_us))
+        .map(|(transaction_date, gross_us, tax_us, symbol)| {
+            format!(
+                "WARNING! Dividend of {} from {} has unexpected withholding ratio {:.1}% (gross: ${}, tax: ${}). Please verify parsed documents",
+                symbol,
+                transaction_date,
+                100.0 * tax_us / gross_us,
+                gross_us,
+                tax_us
+            )
+        })
+        .collect();
+    match suspicious.is_empty() {
+        true => Ok(()),
+        false => Err(suspicious.join("\n")),
+    }
+}
+
+fn is_in_tax_year(date: &str, year: i32) -> Result<bool, String> {
+    Ok(parse_date(date)?.year() == year)
+}
+
+/// Reversal of dividend is posted as negative entry, often in other month (statement)
+/// than the corrected dividend. Net each reversal with the earlier posting it
+/// cancels (the same symbol, gross and tax amount), so only corrected dividend is taxed.
+/// Reversals that cannot be paired are kept as negative income and reported
+pub fn net_dividend_corrections(
+    div_transactions: Vec<(String, f32, f32, String)>,
+) -> Result<Vec<(String, f32, f32, String)>, String> {
+    let dates = div_transactions
+        .iter()
+        .map(|(date, _, _, _)| parse_date(date))
+        .collect::<Result<Vec<chrono::NaiveDate>, String>>()?;
+    let mut transactions: Vec<Option<(String, f32, f32, String)>> =
+        div_transactions.into_iter().map(Some).collect();
+    let reversals: Vec<usize> = transactions
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| matches!(t, Some((_, gross_us, _, _)) if *gross_us < 0.0))
+        .map(|(i, _)| i)
+        .collect();
+    for i in reversals {
+        let Some((reversal_date, reversal_gross, reversal_tax, reversal_symbol)) =
+            transactions[i].clone()
+        else {
+            continue;
+        };
+        // Latest posting not later than the reversal, with matching amounts
+        let original = transactions
+            .iter()
+            .enumerate()
+            .filter_map(|(j, t)| t.as_ref().map(|t| (j, t)))
+            .filter(|(j, (_, gross_us, tax_us, symbol))| {
+                *symbol == reversal_symbol
+                    && *gross_us == -reversal_gross
+                    && *tax_us == -reversal_tax
+                    && dates[*j] <= dates[i]
+            })
+            .max_by_key(|(j, _)| dates[*j])
+            .map(|(j, (date, _, _, _))| (j, date.clone()));
+        match original {
+            Some((j, original_date)) => {
+                log::info!(
+                    "Dividend of ${} paid on {} is reversed on {}",
+                    -reversal_gross,
+                    original_date,
+                    reversal_date
+                );
+                transactions[i] = None;
+                transactions[j] = None;
+            }
+            None => {
+                let msg = format!(
+                    "WARNING! Reversal of dividend on {} (gross: ${}) has no matching dividend. It is subtracted from income",
+                    reversal_date, reversal_gross
+                );
+                eprintln!("{}", crate::warning(&msg));
+                log::warn!("{}", msg);
+            }
+        }
+    }
+    Ok(transactions.into_iter().flatten().collect())
+}
+
+/// Chronological order of dates of transactions
+fn by_date(a: &str, b: &str) -> std::cmp::Ordering {
+    let parse = |date: &str| chrono::NaiveDate::parse_from_str(date, "%m/%d/%y").ok();
+    parse(a).cmp(&parse(b))
+}
+
+/// Order of dividend (or interest) transactions: by date, then symbol, then
+/// amounts
+pub fn compare_div_transactions(
+    (a_date, a_gross, a_tax, a_symbol): &(String, f32, f32, String),
+    (b_date, b_gross, b_tax, b_symbol): &(String, f32, f32, String),
+) -> std::cmp::Ordering {
+    by_date(a_date, b_date)
+        .then_with(|| a_symbol.cmp(b_symbol))
+        .then_with(|| a_gross.total_cmp(b_gross))
+        .then_with(|| a_tax.total_cmp(b_tax))
+}
+
+/// Sort dividend (or interest) transactions by date, then symbol, then
+/// amounts, so results do not depend on order documents were given in.
+/// Sums are then also the same, as floating point addition depends on order
+pub fn sort_div_transactions(transactions: &mut [(String, f32, f32, String)]) {
+    transactions.sort_by(compare_div_transactions);
+}
+
+/// Order of virtual currency transactions: by date, then currency, then amount
+pub fn compare_crypto_transactions(
+    (a_date, a_sale, a_amount, a_currency): &(String, bool, f32, String),
+    (b_date, b_sale, b_amount, b_currency): &(String, bool, f32, String),
+) -> std::cmp::Ordering {
+    by_date(a_date, b_date)
+        .then_with(|| a_currency.cmp(b_currency))
+        .then_with(|| a_amount.total_cmp(b_amount))
+        .then_with(|| a_sale.cmp(b_sale))
+}
+
+/// Sort virtual currency transactions by date, then currency, then amount
+pub fn sort_crypto_transactions(transactions: &mut [(String, bool, f32, String)]) {
+    transactions.sort_by(compare_crypto_transactions);
+}
+
+/// Sort sales of brokerage statements by trade date, then settlement date,
+/// then amounts, so each lot of Gain & Losses is matched with the same sale
+pub fn sort_sold_transactions(transactions: &mut [(String, String, i32, f32, f32)]) {
+    transactions.sort_by(|a, b| {
+        by_date(&a.0, &b.0)
+            .then_with(|| by_date(&a.1, &b.1))
+            .then_with(|| a.4.total_cmp(&b.4))
+            .then_with(|| a.2.cmp(&b.2))
+            .then_with(|| a.3.total_cmp(&b.3))
+    });
+}
+
+/// Order of sold lots: by trade date, then settlement and acquisition dates,
+/// then amounts
+pub fn compare_detailed_sold_transactions(
+    a: &DetailedSale,
+    b: &DetailedSale,
+) -> std::cmp::Ordering {
+    by_date(&a.0, &b.0)
+        .then_with(|| by_date(&a.1, &b.1))
+        .then_with(|| by_date(&a.2, &b.2))
+        .then_with(|| a.3.total_cmp(&b.3))
+        .then_with(|| a.4.total_cmp(&b.4))
+        .then_with(|| a.5.total_cmp(&b.5))
+}
+
+/// Sort sold lots by trade date, then settlement and acquisition dates, then
+/// amounts
+pub fn sort_detailed_sold_transactions(transactions: &mut [DetailedSale]) {
+    transactions.sort_by(compare_detailed_sold_transactions);
+}
+
+/// Skip dividends transactions that were not paid in given tax year
+pub fn filter_div_transactions_by_year(
+    div_transactions: Vec<(String, f32, f32, String)>,
+    year: i32,
+) -> Result<Vec<(String, f32, f32, String)>, String> {
+    let mut kept = vec![];
+    for transaction in div_transactions {
+        let (transaction_date, gross_us, _, _) = &transaction;
+        if is_in_tax_year(transaction_date, year)? {
+            kept.push(transaction);
+        } else {
+            let msg = format!(
+                "WARNING! Skipping dividend transaction from {} (gross: ${}) as it does not belong to tax year {}",
+                transaction_date, gross_us, year
+            );
+            eprintln!("{}", crate::warning(&msg));
+            log::warn!("{}", msg);
+        }
+    }
+    Ok(kept)
+}
+
+/// Skip sold transactions that were not settled in given tax year
+pub fn filter_sold_transactions_by_year(
+    sold_transactions: Vec<DetailedSale>,
+    year: i32,
+) -> Result<Vec<DetailedSale>, String> {
+    let mut kept = vec![];
+    for transaction in sold_transactions {
+        let (_, settlement_date, _, income, _, _, _) = &transaction;
+        if is_in_tax_year(settlement_date, year)? {
+            kept.push(transaction);
+        } else {
+            let msg = format!(
+                "WARNING! Skipping sold transaction settled on {} (income: ${}) as it does not belong to tax year {}",
+                settlement_date, income, year
+            );
+            eprintln!("{}", crate::warning(&msg));
+            log::warn!("{}", msg);
+        }
+    }
+    Ok(kept)
+}
+
+const MISSING_GAINS_AND_LOSSES: &str = "Sold transaction detected, but corressponding Gain&Losses document is missing. Please download Gain&Losses XLSX document at: https://us.etrade.com/etx/sp/stockplan#/myAccount/gainsLosses";
+
+/// Date of Gain & Losses document (e.g. 06/01/2021) in format of brokerage
+/// statements (06/01/21)
+fn reformat_date(date: &str) -> Result<String, String> {
+    chrono::NaiveDate::parse_from_str(date, "%m/%d/%Y")
+        .map(|d| d.format("%m/%d/%y").to_string())
+        .map_err(|e| format!("Invalid date {} of Gain & Losses: {}", date, e))
+}
+
+/// Exchange rate (and date it was published on) of transaction date
+pub fn exchange_rate_of(
+    dates: &std::collections::HashMap<String, Option<(String, f32)>>,
+    date: &str,
+) -> Result<(String, f32), String> {
+    dates
+        .get(date)
+        .cloned()
+        .flatten()
+        .ok_or_else(|| format!("No exchange rate for transaction date {}", date))
+}
+
+/// Index of sale (trade date, principal) each lot of G&L belongs to. Larger
+/// lots are matched first and each goes to sale of its sold date whose
+/// principal is not yet covered by other lots and it fits best
+fn match_lots(sales: &[(String, f32)], gains_and_losses: &[Lot]) -> Result<Vec<usize>, String> {
+    // Lots without sale of their day are reported in order of G&L
+    for (_, tr_date, _, _, inc, _) in gains_and_losses {
+        let trade_date = reformat_date(tr_date)?;
+        if !sales.iter().any(|(date, _)| *date == trade_date) {
+            return Err(format!(
+                "Lot sold on {} (proceeds: ${}) has no sale in brokerage statements. {}",
+                tr_date, inc, MISSING_GAINS_AND_LOSSES
+            ));
+        }
+    }
+    let mut uncovered: Vec<f32> = sales.iter().map(|(_, principal)| *principal).collect();
+    let mut matched: Vec<usize> = vec![0; gains_and_losses.len()];
+    let mut by_proceeds: Vec<usize> = (0..gains_and_losses.len()).collect();
+    by_proceeds.sort_by(|a, b| gains_and_losses[*b].4.total_cmp(&gains_and_losses[*a].4));
+    for i in by_proceeds {
+        let (_, tr_date, _, _, inc, _) = &gains_and_losses[i];
+        // match trade date and gross with principal and trade date of  trade confirmation
+        let trade_date = reformat_date(tr_date)?;
+        let same_day: Vec<usize> = (0..sales.len())
+            .filter(|j| sales[*j].0 == trade_date)
+            .collect();
+        let tolerance = 0.01 + 0.001 * inc;
+        let j = same_day
+            .into_iter()
+            .filter(|j| uncovered[*j] + tolerance >= *inc)
+            .min_by(|a, b| uncovered[*a].total_cmp(&uncovered[*b]))
+            .ok_or_else(|| {
+                format!(
+                    "Lot sold on {} (proceeds: ${}) exceeds amounts of sales of that day in brokerage statements",
+                    tr_date, inc
+                )
+            })?;
+        uncovered[j] -= inc;
+        matched[i] = j;
+    }
+    Ok(matched)
+}
+
+/// Net trades of the same trade date and symbol before lots of G&L are
+/// matched with sales, as some brokers report them. Sales of the day become
+/// one sale and shares bought that day (trade date, quantity, price, symbol)
+/// are offset against shares sold: as many of sold shares as were bought are
+/// taken from that purchase, as one lot acquired and sold that day, and only
+/// the rest from lots of G&L. Lots of the sale acquired last are released
+/// (their shares are still held) to make room for it, the last one in part.
+/// Remaining lots of the sale acquired on the same day with the same holding
+/// term become one lot. Symbol of each sale is in sold_symbols, empty if
+/// documents do not give it, and trades of one day without symbol are netted
+/// together
+pub fn net_same_day_trades(
+    sold_transactions: &[Sale],
+    sold_symbols: &[String],
+    bought_transactions: &[(String, i32, f32, String)],
+    gains_and_losses: &[Lot],
+) -> Result<(Vec<Sale>, Vec<Lot>), String> {
+    // (trade date, symbol) of each netted sale and the sale itself
+    let mut groups: Vec<(String, String)> = vec![];
+    let mut netted: Vec<Sale> = vec![];
+    for (i, (trade_date, settlement_date, quantity, price, amount_sold)) in
+        sold_transactions.iter().enumerate()
+    {
+        let key = (
+            trade_date.clone(),
+            sold_symbols.get(i).cloned().unwrap_or_default(),
+        );
+        match groups.iter().position(|group| *group == key) {
+            Some(j) => {
+                let (_, _, netted_quantity, netted_price, netted_amount) = &mut netted[j];
+                let principal =
+                    netted_quantity.abs() as f32 * *netted_price + quantity.abs() as f32 * price;
+                *netted_quantity += quantity;
+                if *netted_quantity != 0 {
+                    *netted_price = principal / netted_quantity.abs() as f32;
+                }
+                *netted_amount += amount_sold;
+            }
+            None => {
+                groups.push(key);
+                netted.push((
+                    trade_date.clone(),
+                    settlement_date.clone(),
+                    *quantity,
+                    *price,
+                    *amount_sold,
+                ));
+            }
+        }
+    }
+    let principals: Vec<(String, f32)> = netted
+        .iter()
+        .map(|(trade_date, _, quantity, price, _)| {
+            (trade_date.clone(), quantity.abs() as f32 * price)
+        })
+        .collect();
+    let matched = match_lots(&principals, gains_and_losses)?;
+
+    let mut lots: Vec<Lot> = vec![];
+    for (j, ((trade_date, symbol), (_, _, quantity, _, _))) in
+        groups.iter().zip(netted.iter()).enumerate()
+    {
+        let mut sale_lots: Vec<Lot> = gains_and_losses
+            .iter()
+            .zip(matched.iter())
+            .filter(|(_, sale)| **sale == j)
+            .map(|(lot, _)| lot.clone())
+            .collect();
+        let (bought, cost) = bought_transactions
+            .iter()
+            .filter(|(date, _, _, s)| date == trade_date && s == symbol)
+            .fold((0, 0.0), |(bought, cost), (_, quantity, price, _)| {
+                (
+                    bought + quantity.abs(),
+                    cost + quantity.abs() as f32 * price,
+                )
+            });
+        let offset = bought.min(quantity.abs());
+        if let (true, Some(sold_date)) = (offset > 0, sale_lots.first().map(|lot| lot.1.clone())) {
+            // Share of proceeds of the sale (gross or net of fees, as G&L
+            // gives them) that comes from shares bought that day
+            let proceeds: f32 = sale_lots.iter().map(|lot| lot.4).sum();
+            let round_trip = proceeds * offset as f32 / quantity.abs() as f32;
+            let mut dated = sale_lots
+                .into_iter()
+                .map(|lot| Ok((parse_date(&reformat_date(&lot.0)?)?, lot)))
+                .collect::<Result<Vec<_>, String>>()?;
+            dated.sort_by_key(|(date, _)| *date);
+            sale_lots = dated.into_iter().map(|(_, lot)| lot).collect();
+            let mut released = 0.0;
+            while let Some(mut lot) = sale_lots.pop() {
+                if released + lot.4 > round_trip {
+                    let kept = (released + lot.4 - round_trip) / lot.4;
+                    lot.2 *= kept;
+                    lot.3 *= kept;
+                    lot.4 *= kept;
+                    sale_lots.push(lot);
+                    break;
+                }
+                released += lot.4;
+            }
+            let cost = cost * offset as f32 / bought as f32;
+            log::info!(
+                "{} of {} shares {} sold on {} were bought the same day",
+                offset,
+                quantity.abs(),
+                symbol,
+                trade_date
+            );
+            sale_lots.push((
+                sold_date.clone(),
+                sold_date,
+                cost,
+                cost,
+                round_trip,
+                HoldingTerm::Short,
+            ));
+        }
+        let count = lots.len();
+        for lot in sale_lots {
+            match lots[count..]
+                .iter_mut()
+                .find(|(a, _, _, _, _, t)| *a == lot.0 && *t == lot.5)
+            {
+                Some((_, _, cost, adjusted_cost, proceeds, _)) => {
+                    *cost += lot.2;
+                    *adjusted_cost += lot.3;
+                    *proceeds += lot.4;
+                }
+                None => lots.push(lot),
+            }
+        }
+    }
+    log::info!(
+        "{} sales netted into {} and {} lots into {}",
+        sold_transactions.len(),
+        netted.len(),
+        gains_and_losses.len(),
+        lots.len()
+    );
+    Ok((netted, lots))
+}
+
+/// Trade date is when transaction was trigerred.
+/// fees and commission are applied at the moment of settlement date.
+/// Each lot of G&L is matched with sale of its trade date whose principal is
+/// not yet covered by other lots. Income of lot is its share of principal and
+/// sale fees (principal - net amount of brokerage statement) are split among
+/// lots of the sale by their proceeds, whether G&L proceeds are given gross or
+/// net of fees. purchase_fees are (acquisition date, cost of purchase, fees) of
+/// bought stock and are added to cost basis of sold lots in proportion to their cost
+/// Actual Tax is to be paid from settlement_date
+/// Purchase fee of each lot. Lot gets share (by cost) of fee of a purchase
+/// (acquisition date, cost, fee) made on its acquisition date whose cost not
+/// yet taken by other lots it fits in, larger lots first. Lots not fitting in
+/// any purchase (e.g. shares vested the same day) get no fee
+fn match_purchase_fees(
+    gains_and_losses: &[Lot],
+    purchase_fees: &[(String, f32, f32)],
+) -> Result<Vec<f32>, String> {
+    let mut uncovered: Vec<f32> = purchase_fees.iter().map(|(_, cost, _)| *cost).collect();
+    let mut fees: Vec<f32> = vec![0.0; gains_and_losses.len()];
+    let mut by_cost: Vec<usize> = (0..gains_and_losses.len()).collect();
+    by_cost.sort_by(|a, b| gains_and_losses[*b].2.total_cmp(&gains_and_losses[*a].2));
+    for i in by_cost {
+        let (acquisition_date, _, cost_basis, ..) = &gains_and_losses[i];
+        let acquisition_date = reformat_date(acquisition_date)?;
+        let tolerance = 0.01 + 0.001 * cost_basis;
+        let purchase = (0..purchase_fees.len())
+            .filter(|j| {
+                purchase_fees[*j].0 == acquisition_date
+                    && purchase_fees[*j].1 > 0.0
+                    && uncovered[*j] + tolerance >= *cost_basis
+            })
+            .min_by(|a, b| uncovered[*a].total_cmp(&uncovered[*b]));
+        if let Some(j) = purchase {
+            uncovered[j] -= cost_basis;
+            fees[i] = purchase_fees[j].2 * cost_basis / purchase_fees[j].1;
+        }
+    }
+    Ok(fees)
+}
+
+pub fn reconstruct_sold_transactions(
+    sold_transactions: &[Sale],
+    gains_and_losses: &[Lot],
+    purchase_fees: &[(String, f32, f32)],
+) -> Result<Vec<DetailedSale>, String> {
+    // Ok What do I need.
+    // 1. trade date
+    // 2. settlement date
+    // 3. date of purchase
+    // 4. gross income
+    // 5. cost cost basis
+    // 6. fees and commission
+    // 7. US holding term
+    let mut detailed_sold_transactions: Vec<DetailedSale> = vec![];
+
+    if !sold_transactions.is_empty() && gains_and_losses.is_empty() {
+        return Err(MISSING_GAINS_AND_LOSSES.to_owned());
+    }
+
+    let principals: Vec<(String, f32)> = sold_transactions
+        .iter()
+        .map(|(trade_date, _, quantity, price, _)| {
+            (trade_date.clone(), quantity.abs() as f32 * price)
+        })
+        .collect();
+    let matched = match_lots(&principals, gains_and_losses)?;
+    let lot_purchase_fees = match_purchase_fees(gains_and_losses, purchase_fees)?;
+
+    // iterate through all sold transactions and update it with needed info
+    for (i, (acquisition_date, tr_date, cost_basis, _, inc, term)) in
+        gains_and_losses.iter().enumerate()
+    {
+        let (_, settlement_date, quantity, price, amount_sold) = &sold_transactions[matched[i]];
+        let principal = quantity.abs() as f32 * price;
+        let sale_fees = (principal - amount_sold).max(0.0);
+        // Proceeds of all lots of the sale when above net amount (gross of
+        // sale fees), otherwise lots are shares of net amount (e.g. G&L
+        // missing some of them or summing to it up to rounding)
+        let lots_proceeds: f32 = gains_and_losses
+            .iter()
+            .enumerate()
+            .filter(|(k, _)| matched[*k] == matched[i])
+            .map(|(_, lot)| lot.4)
+            .sum();
+        let proceeds = if lots_proceeds > amount_sold + 0.005 {
+            lots_proceeds
+        } else {
+            *amount_sold
+        };
+        let (income, fees) = if proceeds > 0.0 {
+            (
+                inc + (principal - proceeds) * inc / proceeds,
+                sale_fees * inc / proceeds,
+            )
+        } else {
+            (*inc, 0.0)
+        };
+
+        let acquisition_date = reformat_date(acquisition_date)?;
+        let purchase_fee = lot_purchase_fees[i];
+
+        detailed_sold_transactions.push((
+            reformat_date(tr_date)?,
+            settlement_date.clone(),
+            acquisition_date,
+            income,
+            cost_basis + purchase_fee,
+            fees,
+            *term,
+        ));
+    }
+
+    Ok(detailed_sold_transactions)
+}
+
+/// Date of transaction as YYYY-MM-DD for log messages
+fn log_date(date: &str) -> String {
+    chrono::NaiveDate::parse_from_str(date, "%m/%d/%y")
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or(date.to_owned())
+}
+
+pub fn create_detailed_div_transactions(
+    transactions: Vec<(String, f32, f32, String)>,
+    dates: &std::collections::HashMap<String, Option<(String, f32)>>,
+) -> Result<Vec<Transaction>, String> {
+    let mut detailed_transactions: Vec<Transaction> = Vec::new();
+    for (transaction_date, gross_us, tax_us, symbol) in transactions.iter() {
+        let (exchange_rate_date, exchange_rate) = exchange_rate_of(dates, transaction_date)?;
+
+        let msg = format!(
+            " DIV TRANSACTION date: {}, symbol: {}, gross: ${}, tax_us: ${}, exchange_rate: {} , exchange_rate_date: {}",
+            log_date(transaction_date), &symbol, &gross_us, &tax_us, &exchange_rate, &exchange_rate_date
+        )
+        .to_owned();
+
+        log::info!("{}", msg);
+
+        detailed_transactions.push(Transaction {
+            transaction_date: transaction_date.clone(),
+            gross_us: *gross_us,
+            tax_us: *tax_us,
+            symbol: symbol.clone(),
+            exchange_rate_date,
+            exchange_rate,
+        })
+    }
+    Ok(detailed_transactions)
+}
+
+//    pub trade_date: String,
+//    pub settlement_date: String,
+//    pub acquisition_date: String,
+//    pub income_us: f32,
+//    pub cost_basis: f32,
+//    pub fees_us: f32,
+//    pub term: HoldingTerm,
+//    pub exchange_rate_settlement_date: String,
+//    pub exchange_rate_settlement: f32,
+//    pub exchange_rate_acquisition_date: String,
+//    pub exchange_rate_acquisition: f32,
+pub fn create_detailed_sold_transactions(
+    transactions: Vec<DetailedSale>,
+    dates: &std::collections::HashMap<String, Option<(String, f32)>>,
+) -> Result<Vec<SoldTransaction>, String> {
+    let mut detailed_transactions: Vec<SoldTransaction> = Vec::new();
+    for (trade_date, settlement_date, acquisition_date, income, cost_basis, fees, term) in
+        transactions.iter()
+    {
+        let (exchange_rate_settlement_date, exchange_rate_settlement) =
+            exchange_rate_of(dates, settlement_date)?;
+        let (exchange_rate_acquisition_date, exchange_rate_acquisition) =
+            exchange_rate_of(dates, acquisition_date)?;
+
+        let msg = format!(
+            " SOLD TRANSACTION trade_date: {}, settlement_date: {}, acquisition_date: {}, term: {:?}, income: ${}, fees: ${}, cost_basis: {}, exchange_rate_settlement: {} , exchange_rate_settlement_date: {}, exchange_rate_acquisition: {} , exchange_rate_acquisition_date: {}",
+            log_date(trade_date),
+            log_date(settlement_date),
+            log_date(acquisition_date),
+            &term, &income, &fees, &cost_basis, &exchange_rate_settlement, &exchange_rate_settlement_date, &exchange_rate_acquisition, &exchange_rate_acquisition_date,
+        )
+        .to_owned();
+
+        log::info!("{}", msg);
+
+        detailed_transactions.push(SoldTransaction {
+            settlement_date: settlement_date.clone(),
+            acquisition_date: acquisition_date.clone(),
+            income_us: *income,
+            cost_basis: *cost_basis,
+            fees_us: *fees,
+            term: *term,
+            exchange_rate_settlement_date,
+            exchange_rate_settlement,
+            exchange_rate_acquisition_date,
+            exchange_rate_acquisition,
+        })
+    }
+    Ok(detailed_transactions)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_dividends_verification_ok() -> Result<(), String> {
+        let transactions: Vec<(String, f32, f32, String)> = vec![
+            ("06/01/21".to_string(), 100.0, 25.0, "INTC".to_string()),
+            ("03/01/21".to_string(), 126.0, 10.0, "INTC".to_string()),
+        ];
+        verify_dividends_transactions(&transactions)?
+    }
+
+    #[test]
+    fn test_withholding_ratios_ok() -> Result<(), String> {
+        let transactions: Vec<(String, f32, f32, String)> = vec![
+            ("03/01/23".to_string(), 126.0, 18.9, "INTC".to_string()),
+            ("06/01/23".to_string(), 100.0, 30.0, "INTC".to_string()),
+            ("09/01/23".to_string(), 50.0, 0.0, "INTC".to_string()),
+        ];
+        verify_withholding_ratios(&transactions)
+    }
+
+    #[test]
+    fn test_withholding_ratios_suspicious() -> Result<(), String> {
+        let transactions: Vec<(String, f32, f32, String)> = vec![
+            ("03/01/23".to_string(), 126.0, 18.9, "INTC".to_string()),
+            ("06/01/23".to_string(), 18.9, 126.0, "INTC".to_string()),
+        ];
+        assert!(verify_withholding_ratios(&transactions).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_net_dividend_corrections() -> Result<(), String> {
+        let transactions: Vec<(String, f32, f32, String)> = vec![
+            ("03/01/23".to_string(), 126.0, 18.9, "INTC".to_string()),
+            ("04/15/23".to_string(), -126.0, -18.9, "INTC".to_string()),
+            ("04/15/23".to_string(), 130.0, 19.5, "INTC".to_string()),
+            ("06/01/23".to_string(), 100.0, 15.0, "INTC".to_string()),
+            ("07/03/23".to_string(), -10.0, -1.5, "INTC".to_string()),
+        ];
+        assert_eq!(
+            net_dividend_corrections(transactions)?,
+            vec![
+                ("04/15/23".to_string(), 130.0, 19.5, "INTC".to_string()),
+                ("06/01/23".to_string(), 100.0, 15.0, "INTC".to_string()),
+                ("07/03/23".to_string(), -10.0, -1.5, "INTC".to_string()),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_transactions() -> Result<(), String> {
+        let sorted: Vec<(String, f32, f32, String)> = vec![
+            ("12/01/22".to_string(), 50.0, 7.5, "INTC".to_string()),
+            ("03/01/23".to_string(), 100.0, 15.0, "AAPL".to_string()),
+            ("03/01/23".to_string(), 10.0, 1.5, "INTC".to_string()),
+            ("03/01/23".to_string(), 126.0, 18.9, "INTC".to_string()),
+        ];
+        let mut transactions = sorted.clone();
+        transactions.reverse();
+        sort_div_transactions(&mut transactions);
+        assert_eq!(transactions, sorted);
+
+        let mut transactions = vec![
+            (
+                "03/01/23".to_string(),
+                "03/03/23".to_string(),
+                10,
+                20.0,
+                199.5,
+            ),
+            (
+                "11/30/22".to_string(),
+                "12/02/22".to_string(),
+                5,
+                20.0,
+                99.5,
+            ),
+            (
+                "03/01/23".to_string(),
+                "03/03/23".to_string(),
+                5,
+                20.0,
+                99.5,
+            ),
+        ];
+        sort_sold_transactions(&mut transactions);
+        assert_eq!(
+            transactions
+                .iter()
+                .map(|(date, _, quantity, _, _)| (date.as_str(), *quantity))
+                .collect::<Vec<(&str, i32)>>(),
+            vec![("11/30/22", 5), ("03/01/23", 5), ("03/01/23", 10)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_div_transactions_by_year() -> Result<(), String> {
+        let transactions: Vec<(String, f32, f32, String)> = vec![
+            ("12/01/22".to_string(), 100.0, 15.0, "INTC".to_string()),
+            ("03/01/23".to_string(), 126.0, 18.9, "INTC".to_string()),
+            ("01/03/24".to_string(), 50.0, 7.5, "INTC".to_string()),
+        ];
+        assert_eq!(
+            filter_div_transactions_by_year(transactions, 2023)?,
+            vec![("03/01/23".to_string(), 126.0, 18.9, "INTC".to_string())]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_sold_transactions_by_year() -> Result<(), String> {
+        let transactions: Vec<(String, String, String, f32, f32, f32, HoldingTerm)> = vec![
+            (
+                "12/29/22".to_string(),
+                "01/03/23".to_string(),
+                "01/01/21".to_string(),
+                20.0,
+                10.0,
+                0.5,
+                HoldingTerm::Long,
+            ),
+            (
+                "06/01/22".to_string(),
+                "06/03/22".to_string(),
+                "01/01/19".to_string(),
+                25.0,
+                10.0,
+                0.0,
+                HoldingTerm::Long,
+            ),
+        ];
+        assert_eq!(
+            filter_sold_transactions_by_year(transactions, 2023)?,
+            vec![(
+                "12/29/22".to_string(),
+                "01/03/23".to_string(),
+                "01/01/21".to_string(),
+                20.0,
+                10.0,
+                0.5,
+                HoldingTerm::Long,
+            )]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_detailed_div_transactions() -> Result<(), String> {
+        let parsed_transactions: Vec<(String, f32, f32, String)> = vec![
+            ("04/11/21".to_string(), 100.0, 25.0, "INTC".to_string()),
+            ("03/01/21".to_string(), 126.0, 10.0, "INTC".to_string()),
+        ];
+
+        let mut dates: std::collections::HashMap<String, Option<(String, f32)>> =
+            std::collections::HashMap::new();
+        dates.insert("03/01/21".to_owned(), Some(("02/28/21".to_owned(), 2.0)));
+        dates.insert("04/11/21".to_owned(), Some(("04/10/21".to_owned(), 3.0)));
+
+        let transactions = create_detailed_div_transactions(parsed_transactions, &dates)?;
+
+        assert_eq!(
+            transactions,
+            vec![
+                Transaction {
+                    transaction_date: "04/11/21".to_string(),
+                    gross_us: 100.0,
+                    tax_us: 25.0,
+                    symbol: "INTC".to_string(),
+                    exchange_rate_date: "04/10/21".to_string(),
+                    exchange_rate: 3.0,
+                },
+                Transaction {
+                    transaction_date: "03/01/21".to_string(),
+                    gross_us: 126.0,
+                    tax_us: 10.0,
+                    symbol: "INTC".to_string(),
+                    exchange_rate_date: "02/28/21".to_string(),
+                    exchange_rate: 2.0,
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_detailed_sold_transactions() -> Result<(), String> {
+        let parsed_transactions: Vec<(String, String, String, f32, f32, f32, HoldingTerm)> = vec![
+            (
+                "03/01/21".to_string(),
+                "03/03/21".to_string(),
+                "01/01/21".to_string(),
+                20.0,
+                20.0,
+                0.0,
+                HoldingTerm::Short,
+            ),
+            (
+                "06/01/21".to_string(),
+                "06/03/21".to_string(),
+                "01/01/19".to_string(),
+                25.0,
+                10.0,
+                1.0,
+                HoldingTerm::Long,
+            ),
+        ];
+
+        let mut dates: std::collections::HashMap<String, Option<(String, f32)>> =
+            std::collections::HashMap::new();
+        dates.insert("01/01/21".to_owned(), Some(("12/30/20".to_owned(), 1.0)));
+        dates.insert("03/01/21".to_owned(), Some(("02/28/21".to_owned(), 2.0)));
+        dates.insert("03/03/21".to_owned(), Some(("03/02/21".to_owned(), 2.5)));
+        dates.insert("06/01/21".to_owned(), Some(("06/03/21".to_owned(), 3.0)));
+        dates.insert("06/03/21".to_owned(), Some(("06/05/21".to_owned(), 4.0)));
+        dates.insert("01/01/21".to_owned(), Some(("02/28/21".to_owned(), 5.0)));
+        dates.insert("01/01/19".to_owned(), Some(("12/30/18".to_owned(), 6.0)));
+        dates.insert("04/11/21".to_owned(), Some(("04/10/21".to_owned(), 7.0)));
+
+        let transactions = create_detailed_sold_transactions(parsed_transactions, &dates)?;
+
+        assert_eq!(
+            transactions,
+            vec![
+                SoldTransaction {
+                    settlement_date: "03/03/21".to_string(),
+                    acquisition_date: "01/01/21".to_string(),
+                    income_us: 20.0,
+                    cost_basis: 20.0,
+                    fees_us: 0.0,
+                    term: HoldingTerm::Short,
+                    exchange_rate_settlement_date: "03/02/21".to_string(),
+                    exchange_rate_settlement: 2.5,
+                    exchange_rate_acquisition_date: "02/28/21".to_string(),
+                    exchange_rate_acquisition: 5.0,
+                },
+                SoldTransaction {
+                    settlement_date: "06/03/21".to_string(),
+                    acquisition_date: "01/01/19".to_string(),
+                    income_us: 25.0,
+                    cost_basis: 10.0,
+                    fees_us: 1.0,
+                    term: HoldingTerm::Long,
+                    exchange_rate_settlement_date: "06/05/21".to_string(),
+                    exchange_rate_settlement: 4.0,
+                    exchange_rate_acquisition_date: "12/30/18".to_string(),
+                    exchange_rate_acquisition: 6.0,
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_dividends_verification_empty_ok() -> Result<(), String> {
+        let transactions: Vec<(String, f32, f32, String)> = vec![];
+        verify_dividends_transactions(&transactions)?
+    }
+
+    #[test]
+    fn test_dividends_verification_fail() -> Result<(), String> {
+        let transactions: Vec<(String, f32, f32, String)> = vec![
+            ("04/11/22".to_string(), 100.0, 25.0, "INTC".to_string()),
+            ("03/01/21".to_string(), 126.0, 10.0, "INTC".to_string()),
+        ];
+        assert!(verify_dividends_transactions(&transactions)?.is_err());
+        let transactions: Vec<(String, f32, f32, String)> = vec![
+            ("04/11/22".to_string(), 100.0, 25.0, "INTC".to_string()),
+            ("2021-03-01".to_string(), 126.0, 10.0, "INTC".to_string()),
+        ];
+        assert_eq!(
+            verify_dividends_transactions(&transactions),
+            Err("Error parsing transaction date: 2021-03-01".to_owned())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sold_transaction_reconstruction_dividiends_only() -> Result<(), String> {
+        let parsed_sold_transactions: Vec<(String, String, i32, f32, f32)> = vec![];
+
+        let parsed_gains_and_losses: Vec<(String, String, f32, f32, f32, HoldingTerm)> = vec![];
+
+        let detailed_sold_transactions = reconstruct_sold_transactions(
+            &parsed_sold_transactions,
+            &parsed_gains_and_losses,
+            &[],
+        )?;
+        // 1. trade date
+        // 2. settlement date
+        // 3. date of purchase
+        // 4. gross income
+        // 5. cost cost basis
+        // 6. fees and commission
+        assert_eq!(detailed_sold_transactions, vec![]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sold_transaction_reconstruction_ok() -> Result<(), String> {
+        let parsed_sold_transactions: Vec<(String, String, i32, f32, f32)> = vec![
+            (
+                "06/01/21".to_string(),
+                "06/03/21".to_string(),
+                1,
+                25.0,
+                24.8,
+            ),
+            (
+                "03/01/21".to_string(),
+                "03/03/21".to_string(),
+                2,
+                10.0,
+                19.8,
+            ),
+        ];
+
+        let parsed_gains_and_losses: Vec<(String, String, f32, f32, f32, HoldingTerm)> = vec![
+            (
+                "01/01/2019".to_string(),
+                "06/01/2021".to_string(),
+                10.0,
+                10.0,
+                24.8,
+                HoldingTerm::Long,
+            ),
+            (
+                "01/01/2021".to_string(),
+                "03/01/2021".to_string(),
+                20.0,
+                20.0,
+                19.8,
+                HoldingTerm::Short,
+            ),
+        ];
+
+        let detailed_sold_transactions = reconstruct_sold_transactions(
+            &parsed_sold_transactions,
+            &parsed_gains_and_losses,
+            &[],
+        )?;
+
+        // 1. trade date
+        // 2. settlement date
+        // 3. date of purchase
+        // 4. gross income
+        // 5. cost cost basis
+        // 6. fees and commission
+        // 7. US holding term
+        assert_eq!(
+            detailed_sold_transactions,
+            vec![
+                (
+                    "06/01/21".to_string(),
+                    "06/03/21".to_string(),
+                    "01/01/19".to_string(),
+                    25.0,
+                    10.0,
+                    0.20000076,
+                    HoldingTerm::Long
+                ),
+                (
+                    "03/01/21".to_string(),
+                    "03/03/21".to_string(),
+                    "01/01/21".to_string(),
+                    20.0,
+                    20.0,
+                    0.20000076,
+                    HoldingTerm::Short
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sold_transaction_reconstruction_fees() -> Result<(), String> {
+        // Two sales of the same day, G&L proceeds gross of sale fees
+        let parsed_sold_transactions: Vec<(String, String, i32, f32, f32)> = vec![
+            (
+                "03/01/23".to_string(),
+                "03/03/23".to_string(),
+                10,
+                20.0,
+                195.0,
+            ),
+            (
+                "03/01/23".to_string(),
+                "03/06/23".to_string(),
+                4,
+                20.0,
+                78.0,
+            ),
+        ];
+        let parsed_gains_and_losses: Vec<(String, String, f32, f32, f32, HoldingTerm)> = vec![
+            (
+                "06/01/2022".to_string(),
+                "03/01/2023".to_string(),
+                60.0,
+                60.0,
+                80.0,
+                HoldingTerm::Short,
+            ),
+            (
+                "01/10/2022".to_string(),
+                "03/01/2023".to_string(),
+                150.0,
+                150.0,
+                200.0,
+                HoldingTerm::Long,
+            ),
+        ];
+        // Commission of $6 paid for purchase of $300 of which half is sold
+        let purchase_fees = vec![("01/10/22".to_string(), 300.0, 6.0)];
+
+        let detailed_sold_transactions = reconstruct_sold_transactions(
+            &parsed_sold_transactions,
+            &parsed_gains_and_losses,
+            &purchase_fees,
+        )?;
+        // Gain of first lot is 80 - 2 - 60 and of second 200 - 5 - 153
+        // instead of 20 and 50 without fees
+        assert_eq!(
+            detailed_sold_transactions,
+            vec![
+                (
+                    "03/01/23".to_string(),
+                    "03/06/23".to_string(),
+                    "06/01/22".to_string(),
+                    80.0,
+                    60.0,
+                    2.0,
+                    HoldingTerm::Short
+                ),
+                (
+                    "03/01/23".to_string(),
+                    "03/03/23".to_string(),
+                    "01/10/22".to_string(),
+                    200.0,
+                    153.0,
+                    5.0,
+                    HoldingTerm::Long
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_purchase_fees_by_amount() -> Result<(), String> {
+        // Purchase of $300 and vest of $500 acquired the same day
+        let parsed_gains_and_losses: Vec<(String, String, f32, f32, f32, HoldingTerm)> = vec![
+            (
+                "01/10/2022".to_string(),
+                "03/01/2023".to_string(),
+                500.0,
+                500.0,
+                600.0,
+                HoldingTerm::Long,
+            ),
+            (
+                "01/10/2022".to_string(),
+                "03/01/2023".to_string(),
+                100.0,
+                100.0,
+                120.0,
+                HoldingTerm::Long,
+            ),
+            (
+                "01/10/2022".to_string(),
+                "03/02/2023".to_string(),
+                200.0,
+                200.0,
+                240.0,
+                HoldingTerm::Long,
+            ),
+        ];
+        let purchase_fees = vec![("01/10/22".to_string(), 300.0, 6.0)];
+
+        // Vested lot does not fit in purchase and gets no fee
+        assert_eq!(
+            match_purchase_fees(&parsed_gains_and_losses, &purchase_fees)?,
+            vec![0.0, 2.0, 4.0]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sold_transaction_reconstruction_second_fail() -> Result<(), String> {
+        let parsed_sold_transactions: Vec<(String, String, i32, f32, f32)> = vec![(
+            "11/07/22".to_string(), // trade date
+            "11/09/22".to_string(), // settlement date
+            173,                    // quantity
+            28.2035,                // price
+            4877.36,                // amount sold
+        )];
+
+        let parsed_gains_and_losses: Vec<(String, String, f32, f32, f32, HoldingTerm)> = vec![
+            (
+                "05/02/22".to_string(), // date when sold stock was acquired (date_acquired)
+                "07/19/22".to_string(), // date when stock was sold (date_sold)
+                0.0,                    // aqusition cost of sold stock (aquisition_cost)
+                1593.0,                 // adjusted aquisition cost of sold stock (cost_basis)
+                1415.48,                // income from sold stock (total_proceeds)
+                HoldingTerm::Short,     // US holding term (term)
+            ),
+            (
+                "02/18/22".to_string(),
+                "07/19/22".to_string(),
+                4241.16,
+                4989.6,
+                4325.1,
+                HoldingTerm::Short,
+            ),
+            (
+                "08/19/22".to_string(),
+                "11/07/22".to_string(),
+                5236.0872,
+                6160.0975,
+                4877.3555,
+                HoldingTerm::Short,
+            ),
+        ];
+
+        assert_eq!(
+            reconstruct_sold_transactions(&parsed_sold_transactions, &parsed_gains_and_losses, &[]),
+            Err(format!(
+                "Lot sold on 07/19/22 (proceeds: $1415.48) has no sale in brokerage statements. {}",
+                MISSING_GAINS_AND_LOSSES
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sold_transaction_reconstruction_multistock() -> Result<(), String> {
+        let parsed_sold_transactions: Vec<(String, String, i32, f32, f32)> = vec![
+            (
+                "12/21/22".to_string(),
+                "12/23/22".to_string(),
+                163,
+                26.5900,
+                4332.44,
+            ),
+            (
+                "12/19/22".to_string(),
+                "12/21/22".to_string(),
+                252,
+                26.5900,
+                6698.00,
+            ),
+        ];
+
+        let parsed_gains_and_losses: Vec<(String, String, f32, f32, f32, HoldingTerm)> = vec![
+            (
+                "08/19/21".to_string(),
+                "12/19/22".to_string(),
+                4336.4874,
+                4758.6971,
+                2711.0954,
+                HoldingTerm::Long,
+            ),
+            (
+                "05/03/21".to_string(),
+                "12/21/22".to_string(),
+                0.0,
+                3876.918,
+                2046.6128,
+                HoldingTerm::Long,
+            ),
+            (
+                "08/19/22".to_string(),
+                "12/19/22".to_string(),
+                5045.6257,
+                5936.0274,
+                3986.9048,
+                HoldingTerm::Short,
+            ),
+            (
+                "05/02/22".to_string(),
+                "12/21/22".to_string(),
+                0.0,
+                4013.65,
+                2285.8274,
+                HoldingTerm::Short,
+            ),
+        ];
+
+        let detailed_sold_transactions = reconstruct_sold_transactions(
+            &parsed_sold_transactions,
+            &parsed_gains_and_losses,
+            &[],
+        )?;
+
+        assert_eq!(
+            detailed_sold_transactions,
+            vec![
+                (
+                    "12/19/22".to_string(),
+                    "12/21/22".to_string(),
+                    "08/19/21".to_string(),
+                    2712.1802,
+                    4336.4874,
+                    1.0848331,
+                    HoldingTerm::Long,
+                ),
+                (
+                    "12/21/22".to_string(),
+                    "12/23/22".to_string(),
+                    "05/03/21".to_string(),
+                    2047.43,
+                    0.0,
+                    0.81723005,
+                    HoldingTerm::Long,
+                ),
+                (
+                    "12/19/22".to_string(),
+                    "12/21/22".to_string(),
+                    "08/19/22".to_string(),
+                    3988.5002,
+                    5045.6257,
+                    1.5953428,
+                    HoldingTerm::Short,
+                ),
+                (
+                    "12/21/22".to_string(),
+                    "12/23/22".to_string(),
+                    "05/02/22".to_string(),
+                    2286.7402,
+                    0.0,
+                    0.9127505,
+                    HoldingTerm::Short,
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_net_same_day_trades() -> Result<(), String> {
+        // Two sales of the same day, with lots of the same purchase
+        let parsed_sold_transactions: Vec<(String, String, i32, f32, f32)> = vec![
+            (
+                "12/19/22".to_string(),
+                "12/21/22".to_string(),
+                100,
+                26.59,
+                2658.0,
+            ),
+            (
+                "12/19/22".to_string(),
+                "12/21/22".to_string(),
+                152,
+                26.59,
+                4040.0,
+            ),
+            (
+                "12/21/22".to_string(),
+                "12/23/22".to_string(),
+                163,
+                26.59,
+                4332.44,
+            ),
+        ];
+        let parsed_gains_and_losses: Vec<(String, String, f32, f32, f32, HoldingTerm)> = vec![
+            (
+                "08/19/21".to_string(),
+                "12/19/22".to_string(),
+                2000.0,
+                2000.0,
+                2658.0,
+                HoldingTerm::Long,
+            ),
+            (
+                "08/19/21".to_string(),
+                "12/19/22".to_string(),
+                2336.4874,
+                2758.697,
+                4040.0,
+                HoldingTerm::Long,
+            ),
+            (
+                "05/03/21".to_string(),
+                "12/21/22".to_string(),
+                0.0,
+                3876.918,
+                4332.44,
+                HoldingTerm::Long,
+            ),
+        ];
+        let (netted_sold_transactions, netted_gains_and_losses) = net_same_day_trades(
+            &parsed_sold_transactions,
+            &[],
+            &[],
+            &parsed_gains_and_losses,
+        )?;
+        assert_eq!(
+            netted_sold_transactions,
+            vec![
+                (
+                    "12/19/22".to_string(),
+                    "12/21/22".to_string(),
+                    252,
+                    26.589998,
+                    6698.0,
+                ),
+                parsed_sold_transactions[2].clone(),
+            ]
+        );
+        assert_eq!(
+            netted_gains_and_losses,
+            vec![
+                (
+                    "08/19/21".to_string(),
+                    "12/19/22".to_string(),
+                    4336.4874,
+                    4758.6971,
+                    6698.0,
+                    HoldingTerm::Long,
+                ),
+                parsed_gains_and_losses[2].clone(),
+            ]
+        );
+
+        // Netted trades make fewer lots of the same income, cost and fees
+        let detailed_sold_transactions = reconstruct_sold_transactions(
+            &parsed_sold_transactions,
+            &parsed_gains_and_losses,
+            &[],
+        )?;
+        let netted_detailed_sold_transactions = reconstruct_sold_transactions(
+            &netted_sold_transactions,
+            &netted_gains_and_losses,
+            &[],
+        )?;
+        assert_eq!(netted_detailed_sold_transactions.len(), 2);
+        let totals = |lots: &Vec<(String, String, String, f32, f32, f32, HoldingTerm)>| {
+            lots.iter()
+                .fold((0.0, 0.0, 0.0), |(income, cost, fees), lot| {
+                    (income + lot.3, cost + lot.4, fees + lot.5)
+                })
+        };
+        let (income, cost, fees) = totals(&detailed_sold_transactions);
+        let (netted_income, netted_cost, netted_fees) = totals(&netted_detailed_sold_transactions);
+        assert!((income - netted_income).abs() < 0.01);
+        assert!((cost - netted_cost).abs() < 0.01);
+        assert!((fees - netted_fees).abs() < 0.01);
+        Ok(())
+    }
+
+    #[test]
+    fn test_net_same_day_round_trip() -> Result<(), String> {
+        // 100 INTC shares sold and 40 bought on the same day. G&L matches the
+        // sale with two earlier lots of 50 shares, netting takes 40 of sold
+        // shares from that purchase instead of the latest lot
+        let parsed_sold_transactions: Vec<(String, String, i32, f32, f32)> = vec![
+            (
+                "12/19/22".to_string(),
+                "12/21/22".to_string(),
+                100,
+                26.0,
+                2599.0,
+            ),
+            (
+                "12/19/22".to_string(),
+                "12/21/22".to_string(),
+                10,
+                26.0,
+                260.0,
+            ),
+        ];
+        let sold_symbols = vec!["INTC".to_string(), "DLB".to_string()];
+        let bought_transactions = vec![
+            ("12/19/22".to_string(), 40, 25.0, "INTC".to_string()),
+            ("12/19/22".to_string(), 5, 24.0, "DLB".to_string()),
+            ("12/20/22".to_string(), 10, 25.0, "INTC".to_string()),
+        ];
+        let parsed_gains_and_losses: Vec<(String, String, f32, f32, f32, HoldingTerm)> = vec![
+            (
+                "08/19/2021".to_string(),
+                "12/19/2022".to_string(),
+                1000.0,
+                1000.0,
+                1300.0,
+                HoldingTerm::Long,
+            ),
+            (
+                "05/03/2022".to_string(),
+                "12/19/2022".to_string(),
+                1500.0,
+                1500.0,
+                1300.0,
+                HoldingTerm::Short,
+            ),
+            (
+                "01/10/2022".to_string(),
+                "12/19/2022".to_string(),
+                200.0,
+                200.0,
+                260.0,
+                HoldingTerm::Short,
+            ),
+        ];
+        let (netted_sold_transactions, netted_gains_and_losses) = net_same_day_trades(
+            &parsed_sold_transactions,
+            &sold_symbols,
+            &bought_transactions,
+            &parsed_gains_and_losses,
+        )?;
+        assert_eq!(netted_sold_transactions, parsed_sold_transactions);
+        assert_eq!(
+            netted_gains_and_losses,
+            vec![
+                parsed_gains_and_losses[0].clone(),
+                (
+                    "05/03/2022".to_string(),
+                    "12/19/2022".to_string(),
+                    300.0,
+                    300.0,
+                    260.0,
+                    HoldingTerm::Short,
+                ),
+                (
+                    "12/19/2022".to_string(),
+                    "12/19/2022".to_string(),
+                    1000.0,
+                    1000.0,
+                    1040.0,
+                    HoldingTerm::Short,
+                ),
+                (
+                    "01/10/2022".to_string(),
+                    "12/19/2022".to_string(),
+                    100.0,
+                    100.0,
+                    130.0,
+                    HoldingTerm::Short,
+                ),
+                (
+                    "12/19/2022".to_string(),
+                    "12/19/2022".to_string(),
+                    120.0,
+                    120.0,
+                    130.0,
+                    HoldingTerm::Short,
+                ),
+            ]
+        );
+
+        // Cost of sold shares is the one of same-day purchase, not of
+        // released lots, while income stays the same
+        let detailed_sold_transactions = reconstruct_sold_transactions(
+            &parsed_sold_transactions,
+            &parsed_gains_and_losses,
+            &[],
+        )?;
+        let netted_detailed_sold_transactions = reconstruct_sold_transactions(
+            &netted_sold_transactions,
+            &netted_gains_and_losses,
+            &[],
+        )?;
+        let totals = |lots: &Vec<(String, String, String, f32, f32, f32, HoldingTerm)>| {
+            lots.iter().fold((0.0, 0.0), |(income, cost), lot| {
+                (income + lot.3, cost + lot.4)
+            })
+        };
+        let (income, cost) = totals(&detailed_sold_transactions);
+        let (netted_income, netted_cost) = totals(&netted_detailed_sold_transactions);
+        assert!((income - netted_income).abs() < 0.01);
+        assert!((cost - 2700.0).abs() < 0.01);
+        assert!((netted_cost - 2520.0).abs() < 0.01);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sold_transaction_reconstruction_no_gains_fail() -> Result<(), String> {
+        let parsed_sold_transactions: Vec<(String, String, i32, f32, f32)> = vec![
+            (
+                "06/01/21".to_string(),
+                "06/03/21".to_string(),
+                1,
+                25.0,
+                24.8,
+            ),
+            (
+                "03/01/21".to_string(),
+                "03/03/21".to_string(),
+                2,
+                10.0,
+                19.8,
+            ),
+        ];
+
+        let parsed_gains_and_losses: Vec<(String, String, f32, f32, f32, HoldingTerm)> = vec![];
+
+        assert_eq!(
+            reconstruct_sold_transactions(&parsed_sold_transactions, &parsed_gains_and_losses, &[]),
+            Err(MISSING_GAINS_AND_LOSSES.to_owned())
+        );
+        Ok(())
+    }
+}
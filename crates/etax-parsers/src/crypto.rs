@@ -0,0 +1,85 @@
+use super::{DocumentSource, ParseError};
+
+/// This function parses CSV export of virtual currency transactions in a form of:
+///     Date,Type,Amount,Currency
+///     2023-03-01,buy,1500.00,PLN
+/// where Type is "buy" (virtual currency acquired for fiat money) or "sell"
+/// (virtual currency disposed for fiat money). Exchanges between virtual
+/// currencies are not taxed so other types are skipped.
+/// Returned transactions are (date, is_sale, amount, currency)
+pub fn parse_crypto_transactions(
+    source: &dyn DocumentSource,
+    csvtoparse: &str,
+) -> Result<Vec<(String, bool, f32, String)>, ParseError> {
+    let content = source
+        .read(csvtoparse)
+        .map_err(|e| ParseError::open(csvtoparse, e))?;
+    let content = std::str::from_utf8(&content)
+        .map_err(|e| ParseError::content(csvtoparse, &e.to_string()))?;
+    parse_crypto_csv(content).map_err(|e| ParseError::content(csvtoparse, &e))
+}
+
+fn parse_crypto_csv(content: &str) -> Result<Vec<(String, bool, f32, String)>, String> {
+    let mut transactions: Vec<(String, bool, f32, String)> = vec![];
+    // Excel saves CSV UTF-8 with byte order mark
+    for line in content.trim_start_matches('\u{feff}').lines() {
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        // Skip header and empty lines
+        if fields.len() < 4 || fields[0] == "Date" {
+            continue;
+        }
+        let date = chrono::NaiveDate::parse_from_str(fields[0], "%Y-%m-%d")
+            .map_err(|_| format!("Error parsing date of crypto transaction: {}", line))?;
+        let is_sale = match fields[1].to_lowercase().as_str() {
+            "buy" => false,
+            "sell" => true,
+            _ => {
+                log::info!("Skipping crypto transaction not involving fiat: {}", line);
+                continue;
+            }
+        };
+        let amount = fields[2]
+            .parse::<f32>()
+            .ok()
+            .filter(|amount| amount.is_finite())
+            .ok_or_else(|| format!("Error parsing amount of crypto transaction: {}", line))?;
+        transactions.push((
+            date.format("%m/%d/%y").to_string(),
+            is_sale,
+            amount,
+            fields[3].to_uppercase(),
+        ));
+    }
+    log::info!("Crypto Transactions: {:#?}", transactions);
+    Ok(transactions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_crypto_csv() -> Result<(), String> {
+        let content = "Date,Type,Amount,Currency\n\
+                       2023-03-01,buy,1500.00,PLN\n\
+                       2023-04-01,swap,100.00,PLN\n\
+                       2023-05-10,Sell,200.5,usd\n";
+        assert_eq!(
+            parse_crypto_csv(content)?,
+            vec![
+                ("03/01/23".to_owned(), false, 1500.0, "PLN".to_owned()),
+                ("05/10/23".to_owned(), true, 200.5, "USD".to_owned()),
+            ]
+        );
+        assert!(parse_crypto_csv("2023-13-01,buy,1,PLN").is_err());
+        // Found by fuzzing: too large amount is infinity
+        assert!(parse_crypto_csv(&format!("2023-05-10,sell,{},USD", "4".repeat(60))).is_err());
+        assert!(parse_crypto_csv("2023-05-10,sell,NaN,USD").is_err());
+        // Saved by Excel on Windows
+        assert_eq!(
+            parse_crypto_csv("\u{feff}Date,Type,Amount,Currency\r\n2023-05-10,sell,200.5,USD\r\n")?,
+            vec![("05/10/23".to_owned(), true, 200.5, "USD".to_owned())]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+//! Parsers of broker documents: PDF statements, XLSX gains and losses, CSV of
+//! virtual currency transactions and JSON statements imported from broker API.
+//! Documents of other brokers can be parsed by external plugins
+
+mod crypto;
+mod pdf;
+#[cfg(not(target_arch = "wasm32"))]
+mod plugin;
+mod source;
+mod xlsx;
+
+use etax_core::HoldingTerm;
+#[cfg(not(target_arch = "wasm32"))]
+pub use plugin::{discover_plugins, set_plugins, Plugin};
+pub use source::{Document, DocumentSource, FileSystem, InMemory, WithDocument};
+
+/// Reasons document could not be parsed
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ParseError {
+    #[error("unable to open {path}: {reason}")]
+    Open { path: String, reason: String },
+    #[error("unexpected content of {path}: {reason}")]
+    Content { path: String, reason: String },
+}
+
+impl ParseError {
+    pub fn content(path: &str, reason: &str) -> Self {
+        ParseError::Content {
+            path: path.to_owned(),
+            reason: reason.to_owned(),
+        }
+    }
+
+    pub fn open(path: &str, reason: impl std::fmt::Display) -> Self {
+        ParseError::Open {
+            path: path.to_owned(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// Transactions of single document as found there: dates as in document
+/// and amounts in USD (crypto ones in given currency). Kinds of transactions
+/// missing in JSON statement are empty
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ParsedStatement {
+    // (date, gross, tax withheld, symbol)
+    pub dividends: Vec<(String, f32, f32, String)>,
+    // Account number of each dividend as in statement (e.g. XXXX-0848),
+    // empty if statement does not give it
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dividend_accounts: Vec<String>,
+    pub interest: Vec<(String, f32, f32, String)>,
+    // (trade date, settlement date, quantity, price, amount sold)
+    pub sold: Vec<(String, String, i32, f32, f32)>,
+    // Symbol of each sale, empty if document does not give it
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sold_symbols: Vec<String>,
+    // (trade date, quantity, price, symbol) of stock bought, symbol empty if
+    // document does not give it
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bought: Vec<(String, i32, f32, String)>,
+    // (acquisition date, sold date, cost basis, adjusted cost basis, total proceeds, term)
+    pub gains_and_losses: Vec<(String, String, f32, f32, f32, HoldingTerm)>,
+    // (date, is sale, amount, currency)
+    pub crypto: Vec<(String, bool, f32, String)>,
+}
+
+// Broker of documents parsed by built-in parsers
+pub const DEFAULT_BROKER: &str = "E*TRADE";
+
+/// Broker document of given name comes from: name of parser plugin matching
+/// it, E*TRADE for documents of built-in parsers
+#[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+pub fn broker_of(name: &str) -> String {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(plugin) = plugin::find(name) {
+        return plugin.name.clone();
+    }
+    DEFAULT_BROKER.to_owned()
+}
+
+/// Parse PDF brokerage statement, XLSX gains and losses, CSV of virtual
+/// currency transactions or JSON statement (written by etrade-api
+/// subcommand), depending on extension of given path. Documents matching
+/// patterns of parser plugins are parsed by them
+pub fn parse_statement(path: &str, use_record_date: bool) -> Result<ParsedStatement, ParseError> {
+    parse_document(&FileSystem, path, use_record_date)
+}
+
+/// Parse document of given name read from source, e.g. file given to web page
+pub fn parse_document(
+    source: &dyn DocumentSource,
+    name: &str,
+    use_record_date: bool,
+) -> Result<ParsedStatement, ParseError> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(plugin) = plugin::find(name) {
+        return plugin.parse(source, name, use_record_date);
+    }
+    let mut statement = ParsedStatement::default();
+    if name.contains(".pdf") {
+        let (dividends, sold, _, interest, dividend_accounts, bought) =
+            pdf::parse_brokerage_statement(source, name, use_record_date)?;
+        statement.dividends = dividends;
+        statement.dividend_accounts = dividend_accounts;
+        statement.sold = sold;
+        statement.bought = bought;
+        statement.interest = interest;
+    } else if name.contains(".csv") {
+        statement.crypto = crypto::parse_crypto_transactions(source, name)?;
+    } else if name.contains(".json") {
+        let content = source.read(name).map_err(|e| ParseError::open(name, e))?;
+        statement = serde_json::from_slice(&content)
+            .map_err(|e| ParseError::content(name, &e.to_string()))?;
+    } else {
+        statement.gains_and_losses = xlsx::parse_gains_and_losses(source, name)?;
+    }
+    Ok(statement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_statement() -> Result<(), String> {
+        let path = std::env::temp_dir().join(format!("etrade_crypto_{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(
+            path,
+            "Date,Type,Amount,Currency\n2023-05-10,sell,200.5,USD\n",
+        )
+        .map_err(|e| e.to_string())?;
+        let statement = parse_statement(path, false);
+        std::fs::remove_file(path).map_err(|e| e.to_string())?;
+        assert_eq!(
+            statement.map_err(|e| e.to_string())?,
+            ParsedStatement {
+                crypto: vec![("05/10/23".to_owned(), true, 200.5, "USD".to_owned())],
+                ..Default::default()
+            }
+        );
+        assert!(matches!(
+            parse_statement("data/missing.csv", false),
+            Err(ParseError::Open { .. })
+        ));
+        let mut documents = InMemory::default();
+        documents.insert("crypto.csv", b"2023-05-10,buy,100,PLN\n".to_vec());
+        assert_eq!(
+            parse_document(&documents, "crypto.csv", false).map_err(|e| e.to_string())?,
+            ParsedStatement {
+                crypto: vec![("05/10/23".to_owned(), false, 100.0, "PLN".to_owned())],
+                ..Default::default()
+            }
+        );
+        assert!(matches!(
+            parse_document(&documents, "other.csv", false),
+            Err(ParseError::Open { .. })
+        ));
+        let imported = ParsedStatement {
+            dividends: vec![("03/01/22".to_owned(), 104.74, 15.71, "INTC".to_owned())],
+            ..Default::default()
+        };
+        documents.insert(
+            "etrade.json",
+            serde_json::to_vec(&imported).map_err(|e| e.to_string())?,
+        );
+        assert_eq!(
+            parse_document(&documents, "etrade.json", false).map_err(|e| e.to_string())?,
+            imported
+        );
+        Ok(())
+    }
+}
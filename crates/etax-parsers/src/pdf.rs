@@ -0,0 +1,732 @@
+use pdf::file::File;
+use pdf::primitive::Primitive;
+
+use super::{Document, DocumentSource, ParseError};
+
+// (trade date, settlement date, quantity, price, principal, commission, fee, net amount)
+type Trade = (String, String, i32, f32, f32, f32, f32, f32);
+// Dividends, sold, trades, interest, account of each dividend and bought transactions
+type Statement = (
+    Vec<(String, f32, f32, String)>,
+    Vec<(String, String, i32, f32, f32)>,
+    Vec<Trade>,
+    Vec<(String, f32, f32, String)>,
+    Vec<String>,
+    Vec<(String, i32, f32, String)>,
+);
+
+enum TransactionType {
+    Dividends,
+    Interest,
+    Sold,
+    Bought,
+    Trade,
+}
+
+enum ParserState {
+    SearchingTransactionEntry,
+    ProcessingTransaction(TransactionType),
+}
+
+pub trait Entry {
+    fn parse(&mut self, pstr: &pdf::primitive::PdfString) -> Result<(), String>;
+    fn getf32(&self) -> Option<f32> {
+        None
+    }
+    fn geti32(&self) -> Option<i32> {
+        None
+    }
+
+    fn getdate(&self) -> Option<String> {
+        None
+    }
+    fn getstring(&self) -> Option<String> {
+        None
+    }
+
+    fn is_pattern(&self) -> bool {
+        false
+    }
+}
+
+struct F32Entry {
+    pub val: f32,
+}
+
+impl Entry for F32Entry {
+    fn parse(&mut self, pstr: &pdf::primitive::PdfString) -> Result<(), String> {
+        let mystr = pstr
+            .clone()
+            .into_string()
+            .map_err(|_| format!("Error parsing : {:#?} to text", pstr))?;
+        // Extracted string should have "," removed and then be parsed.
+        // Negative amounts (e.g. reversals) are given in parentheses
+        let trimmed = mystr.trim().replace(",", "");
+        let (negative, number) = match trimmed.strip_prefix('(') {
+            Some(rest) => (true, rest.trim_end_matches(')')),
+            None => (false, trimmed.as_str()),
+        };
+        // "NaN" and "inf" are numbers to Rust, but never amounts of statement
+        let val = number
+            .parse::<f32>()
+            .ok()
+            .filter(|val| val.is_finite())
+            .ok_or_else(|| format!("Error parsing : {} to f32", mystr))?;
+        self.val = if negative { -val } else { val };
+        Ok(())
+    }
+    fn getf32(&self) -> Option<f32> {
+        Some(self.val)
+    }
+}
+
+/// Amount following free text (e.g. description of transaction).
+/// Text entries are skipped until amount is found
+struct AmountEntry {
+    pub val: Option<f32>,
+    pub text: String,
+}
+
+impl Entry for AmountEntry {
+    fn parse(&mut self, pstr: &pdf::primitive::PdfString) -> Result<(), String> {
+        let mystr = pstr
+            .clone()
+            .into_string()
+            .map_err(|_| format!("Error parsing : {:#?} to text", pstr))?;
+        self.val = mystr
+            .trim()
+            .replace(",", "")
+            .parse::<f32>()
+            .ok()
+            .filter(|val| val.is_finite());
+        self.text = mystr;
+        Ok(())
+    }
+    fn getf32(&self) -> Option<f32> {
+        self.val
+    }
+    // Until amount is found this entry behaves as unmatched string
+    fn getstring(&self) -> Option<String> {
+        match self.val {
+            Some(_) => None,
+            None => Some(self.text.clone()),
+        }
+    }
+}
+
+struct I32Entry {
+    pub val: i32,
+}
+
+impl Entry for I32Entry {
+    fn parse(&mut self, pstr: &pdf::primitive::PdfString) -> Result<(), String> {
+        let mystr = pstr
+            .clone()
+            .into_string()
+            .map_err(|_| format!("Error parsing : {:#?} to text", pstr))?;
+        self.val = mystr
+            .parse::<i32>()
+            .map_err(|_| format!("Error parsing : {} to i32", mystr))?;
+        Ok(())
+    }
+    fn geti32(&self) -> Option<i32> {
+        Some(self.val)
+    }
+}
+
+struct DateEntry {
+    pub val: String,
+}
+
+impl Entry for DateEntry {
+    fn parse(&mut self, pstr: &pdf::primitive::PdfString) -> Result<(), String> {
+        let mystr = pstr
+            .clone()
+            .into_string()
+            .map_err(|_| format!("Error parsing : {:#?} to text", pstr))?;
+
+        if chrono::NaiveDate::parse_from_str(&mystr, "%m/%d/%y").is_ok() {
+            self.val = mystr;
+        }
+        Ok(())
+    }
+    fn getdate(&self) -> Option<String> {
+        Some(self.val.clone())
+    }
+}
+
+struct StringEntry {
+    pub val: String,
+    pub patterns: Vec<String>,
+}
+
+impl Entry for StringEntry {
+    fn parse(&mut self, pstr: &pdf::primitive::PdfString) -> Result<(), String> {
+        self.val = pstr
+            .clone()
+            .into_string()
+            .map_err(|_| format!("Error parsing : {:#?} to text", pstr))?;
+        Ok(())
+    }
+    fn getstring(&self) -> Option<String> {
+        Some(self.val.clone())
+    }
+    fn is_pattern(&self) -> bool {
+        self.patterns.iter().find(|&x| self.val == *x).is_some()
+    }
+}
+
+fn create_dividend_parsing_sequence(sequence: &mut std::collections::VecDeque<Box<dyn Entry>>) {
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["INTC".to_owned(), "DLB".to_owned()],
+    })); // INTC, DLB
+    sequence.push_back(Box::new(F32Entry { val: 0.0 })); // Tax Entry
+    sequence.push_back(Box::new(F32Entry { val: 0.0 })); // Income Entry
+}
+
+fn create_interest_parsing_sequence(sequence: &mut std::collections::VecDeque<Box<dyn Entry>>) {
+    sequence.push_back(Box::new(AmountEntry {
+        val: None,
+        text: String::new(),
+    })); // Income Entry
+}
+
+fn create_sold_parsing_sequence(sequence: &mut std::collections::VecDeque<Box<dyn Entry>>) {
+    sequence.push_back(Box::new(I32Entry { val: 0 })); // Quantity
+    sequence.push_back(Box::new(F32Entry { val: 0.0 })); // Price
+    sequence.push_back(Box::new(F32Entry { val: 0.0 })); // Amount Sold
+}
+
+fn create_trade_parsing_sequence(sequence: &mut std::collections::VecDeque<Box<dyn Entry>>) {
+    sequence.push_back(Box::new(DateEntry { val: String::new() })); // Trade date
+    sequence.push_back(Box::new(DateEntry { val: String::new() })); // Settlement date
+    sequence.push_back(Box::new(I32Entry { val: 0 })); // MKT /
+    sequence.push_back(Box::new(I32Entry { val: 0 })); // / CPT
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["INTC".to_owned()],
+    }));
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["SELL".to_owned()],
+    }));
+    sequence.push_back(Box::new(I32Entry { val: 0 })); // Quantity
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["$".to_owned()],
+    })); // $...
+    sequence.push_back(Box::new(F32Entry { val: 0.0 })); // ..<price>
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["Stock".to_owned()],
+    }));
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["Plan".to_owned()],
+    }));
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["PRINCIPAL".to_owned()],
+    }));
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["$".to_owned()],
+    })); // $...
+    sequence.push_back(Box::new(F32Entry { val: 0.0 })); // ..<principal>
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["INTEL".to_owned()],
+    }));
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["CORP".to_owned()],
+    }));
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["COMMISSION".to_owned()],
+    }));
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["$".to_owned()],
+    })); // $...
+    sequence.push_back(Box::new(F32Entry { val: 0.0 })); // ..<commission>
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["FEE".to_owned()],
+    }));
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["$".to_owned()],
+    })); // $...
+    sequence.push_back(Box::new(F32Entry { val: 0.0 })); // ..<fee>
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["NET".to_owned()],
+    }));
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["AMOUNT".to_owned()],
+    }));
+    sequence.push_back(Box::new(StringEntry {
+        val: String::new(),
+        patterns: vec!["$".to_owned()],
+    })); // $...
+    sequence.push_back(Box::new(F32Entry { val: 0.0 })); // ..<net amount>
+}
+///  This function parses given PDF document
+///  and returns result of parsing which is a tuple of
+///  found Dividends paid transactions (div_transactions),
+///  Sold stock transactions (sold_transactions)
+///  information on transactions in case of parsing trade document (trades)
+///  Interest paid transactions (interest_transactions)
+///  Dividends (and interest) paid transaction is:
+///        transaction date, gross_us, tax_us, symbol (empty for interest)
+///  where transaction date of dividend is its record date (if use_record_date
+///  is set and record date is found in description) or payment date
+///  Sold stock transaction is :
+///     (trade_date, settlement_date, quantity, price, amount_sold)
+///  Bought stock transaction (bought_transactions) is:
+///     (trade_date, quantity, price, symbol), symbol being empty
+///  Account number of each dividend (dividend_accounts) is the one of last
+///  "Account Number" header before it, as statement may cover several accounts
+pub fn parse_brokerage_statement(
+    source: &dyn DocumentSource,
+    pdftoparse: &str,
+    use_record_date: bool,
+) -> Result<Statement, ParseError> {
+    let content = |reason: &str| ParseError::content(pdftoparse, reason);
+    //2. parsing each pdf
+    let data = source
+        .read(pdftoparse)
+        .map_err(|e| ParseError::open(pdftoparse, e))?;
+    let mypdffile =
+        File::<Document>::from_data(data).map_err(|e| ParseError::open(pdftoparse, e))?;
+
+    let mut state = ParserState::SearchingTransactionEntry;
+    let mut sequence: std::collections::VecDeque<Box<dyn Entry>> =
+        std::collections::VecDeque::new();
+    let mut processed_sequence: Vec<Box<dyn Entry>> = vec![];
+    // Queue for transaction dates. Pop last one or last two as trade and settlement dates
+    let mut transaction_dates: Vec<String> = vec![];
+    // Record date found in description of currently processed dividend
+    let mut record_date: Option<String> = None;
+    // Last matched pattern e.g. symbol of processed dividend
+    let mut matched_pattern = String::new();
+    let mut div_transactions: Vec<(String, f32, f32, String)> = vec![];
+    let mut sold_transactions: Vec<(String, String, i32, f32, f32)> = vec![];
+    let mut bought_transactions: Vec<(String, i32, f32, String)> = vec![];
+    let mut trades: Vec<Trade> = vec![];
+    let mut interest_transactions: Vec<(String, f32, f32, String)> = vec![];
+    // Account of transactions that follow, given after "Account Number:" in
+    // the same text or in the next one
+    let mut account = String::new();
+    let mut account_follows = false;
+    let mut dividend_accounts: Vec<String> = vec![];
+
+    log::info!("Parsing: {} of {} pages", pdftoparse, mypdffile.num_pages());
+    for page in mypdffile.pages() {
+        let page = page.map_err(|e| content(&e.to_string()))?;
+        let contents = page
+            .contents
+            .as_ref()
+            .ok_or_else(|| content("page without contents"))?;
+        for op in contents.operations.iter() {
+            match op.operator.as_ref() {
+                "TJ"
+                    // Text show
+                    if !op.operands.is_empty() => {
+                        //transaction_date = op.operands[0];
+                        let a = &op.operands[0];
+                        if let Primitive::Array(c) = a {
+                            for e in c {
+                                if let Primitive::String(actual_string) = e {
+                                    match state {
+                                        ParserState::SearchingTransactionEntry => {
+                                            let rust_string = actual_string
+                                                .clone()
+                                                .into_string()
+                                                .map_err(|e| content(&e.to_string()))?;
+                                            //println!("rust_string: {}", rust_string);
+                                            if account_follows {
+                                                account = rust_string.trim().to_owned();
+                                                account_follows = false;
+                                            } else if let Some(number) =
+                                                rust_string.strip_prefix("Account Number")
+                                            {
+                                                let number =
+                                                    number.trim_start_matches(':').trim();
+                                                account = number.to_owned();
+                                                account_follows = number.is_empty();
+                                            } else if rust_string == "Dividend" {
+                                                create_dividend_parsing_sequence(&mut sequence);
+                                                state = ParserState::ProcessingTransaction(
+                                                    TransactionType::Dividends,
+                                                );
+                                            } else if rust_string == "Interest" {
+                                                create_interest_parsing_sequence(&mut sequence);
+                                                state = ParserState::ProcessingTransaction(
+                                                    TransactionType::Interest,
+                                                );
+                                            } else if rust_string == "Sold" {
+                                                create_sold_parsing_sequence(&mut sequence);
+                                                state = ParserState::ProcessingTransaction(
+                                                    TransactionType::Sold,
+                                                );
+                                            } else if rust_string == "Bought" {
+                                                create_sold_parsing_sequence(&mut sequence);
+                                                state = ParserState::ProcessingTransaction(
+                                                    TransactionType::Bought,
+                                                );
+                                            } else if rust_string == "TYPE" {
+                                                create_trade_parsing_sequence(&mut sequence);
+                                                state = ParserState::ProcessingTransaction(
+                                                    TransactionType::Trade,
+                                                );
+                                            } else {
+                                                //if this is date then store it
+                                                if chrono::NaiveDate::parse_from_str(
+                                                    &rust_string,
+                                                    "%m/%d/%y",
+                                                )
+                                                .is_ok()
+                                                {
+                                                    transaction_dates.push(rust_string.clone());
+                                                }
+                                            }
+                                        }
+                                        ParserState::ProcessingTransaction(
+                                            transaction_type,
+                                        ) => {
+                                            // So process transaction element and store it in SOLD
+                                            // or DIV
+                                            if matches!(
+                                                transaction_type,
+                                                TransactionType::Dividends
+                                            ) {
+                                                let rust_string = actual_string
+                                                    .clone()
+                                                    .into_string()
+                                                    .map_err(|e| content(&e.to_string()))?;
+                                                if chrono::NaiveDate::parse_from_str(
+                                                    &rust_string,
+                                                    "%m/%d/%y",
+                                                )
+                                                .is_ok()
+                                                {
+                                                    record_date = Some(rust_string);
+                                                }
+                                            }
+                                            let possible_obj = sequence.pop_front();
+                                            match possible_obj {
+                                                // Move executed parser objects into Vector
+                                                // attach only i32 and f32 elements to
+                                                // processed queue
+                                                Some(mut obj) => {
+                                                    obj.parse(actual_string)
+                                                        .map_err(|e| content(&e))?;
+                                                    // attach to sequence the same string parser if pattern is not met
+                                                    if let Some(string) = obj.getstring() {
+                                                        if !obj.is_pattern() {
+                                                            sequence.push_front(obj);
+                                                        } else {
+                                                            matched_pattern = string;
+                                                        }
+                                                    } else {
+                                                        processed_sequence.push(obj);
+                                                    }
+                                                    // If sequence of expected entries is
+                                                    // empty then extract data from
+                                                    // processeed elements
+                                                    if sequence.is_empty() {
+                                                        state =
+                                                        ParserState::SearchingTransactionEntry;
+                                                        let mut transaction =
+                                                            processed_sequence.iter();
+                                                        match transaction_type {
+                                                            TransactionType::Dividends => {
+                                                                let tax_us = transaction.next().ok_or_else(|| content("incomplete transaction"))?.getf32().ok_or_else(|| content("Processing of Dividend transaction went wrong"))?;
+                                                                let gross_us = transaction.next().ok_or_else(|| content("incomplete transaction"))?.getf32().ok_or_else(|| content("Processing of Dividend transaction went wrong"))?;
+                                                                let payment_date = transaction_dates.pop().ok_or_else(|| content("Error: missing transaction dates when parsing"))?;
+                                                                let transaction_date = match (
+                                                                    use_record_date,
+                                                                    record_date.take(),
+                                                                ) {
+                                                                    (
+                                                                        true,
+                                                                        Some(record_date),
+                                                                    ) => record_date,
+                                                                    (true, None) => {
+                                                                        let msg = format!("WARNING! Record date of dividend paid on {} not found. Payment date is used", payment_date);
+                                                                        eprintln!(
+                                                                            "{}",
+                                                                            etax_core::warning(
+                                                                                &msg
+                                                                            )
+                                                                        );
+                                                                        log::warn!("{}", msg);
+                                                                        payment_date
+                                                                    }
+                                                                    (false, _) => payment_date,
+                                                                };
+                                                                div_transactions.push((
+                                                                    transaction_date,
+                                                                    gross_us,
+                                                                    tax_us,
+                                                                    matched_pattern.clone(),
+                                                                ));
+                                                                dividend_accounts
+                                                                    .push(account.clone());
+                                                            }
+                                                            TransactionType::Interest => {
+                                                                let gross_us = transaction.next().ok_or_else(|| content("incomplete transaction"))?.getf32().ok_or_else(|| content("Processing of Interest transaction went wrong"))?;
+                                                                // No US tax is withheld from interest of non-residents
+                                                                interest_transactions.push((
+                                                                    transaction_dates.pop().ok_or_else(|| content("Error: missing transaction dates when parsing"))?,
+                                                                    gross_us,
+                                                                    0.0,
+                                                                    String::new(),
+                                                                ));
+                                                            }
+                                                            TransactionType::Sold => {
+                                                                let quantity =  transaction.next().ok_or_else(|| content("incomplete transaction"))?.geti32().ok_or_else(|| content("Processing of Sold transaction went wrong"))?;
+                                                                let price = transaction.next().ok_or_else(|| content("incomplete transaction"))?.getf32().ok_or_else(|| content("Processing of Sold transaction went wrong"))?;
+                                                                let amount_sold =  transaction.next().ok_or_else(|| content("incomplete transaction"))?.getf32().ok_or_else(|| content("Prasing of Sold transaction went wrong"))?;
+                                                                // Last transaction date is settlement date
+                                                                // next to last is trade date
+                                                                let settlement_date = transaction_dates.pop().ok_or_else(|| content("Error: missing trade date when parsing"))?;
+                                                                let trade_date = transaction_dates.pop().ok_or_else(|| content("Error: missing settlement_date when parsing"))?;
+
+                                                                sold_transactions.push((
+                                                                    trade_date,
+                                                                    settlement_date,
+                                                                    quantity,
+                                                                    price,
+                                                                    amount_sold, // net income
+                                                                ));
+                                                            }
+                                                            TransactionType::Bought => {
+                                                                let quantity =  transaction.next().ok_or_else(|| content("incomplete transaction"))?.geti32().ok_or_else(|| content("Processing of Bought transaction went wrong"))?;
+                                                                let price = transaction.next().ok_or_else(|| content("incomplete transaction"))?.getf32().ok_or_else(|| content("Processing of Bought transaction went wrong"))?;
+                                                                // Settlement date is not needed, trade date is next to last
+                                                                transaction_dates.pop().ok_or_else(|| content("Error: missing settlement_date when parsing"))?;
+                                                                let trade_date = transaction_dates.pop().ok_or_else(|| content("Error: missing trade date when parsing"))?;
+                                                                // Statement does not give symbol of purchase
+                                                                bought_transactions.push((
+                                                                    trade_date,
+                                                                    quantity.abs(),
+                                                                    price,
+                                                                    String::new(),
+                                                                ));
+                                                            }
+                                                            TransactionType::Trade => {
+                                                                let transaction_date = transaction.next().ok_or_else(|| content("incomplete transaction"))?.getdate().ok_or_else(|| content("Prasing of Trade confirmation went wrong"))?; // quantity
+                                                                let settlement_date = transaction.next().ok_or_else(|| content("incomplete transaction"))?.getdate().ok_or_else(|| content("Prasing of Trade confirmation went wrong"))?; // quantity
+                                                                transaction.next().ok_or_else(|| content("incomplete transaction"))?; // MKT??
+                                                                transaction.next().ok_or_else(|| content("incomplete transaction"))?; // CPT??
+                                                                let quantity =  transaction.next().ok_or_else(|| content("incomplete transaction"))?.geti32().ok_or_else(|| content("Prasing of Trade confirmation went wrong"))?; // quantity
+                                                                let price = transaction.next().ok_or_else(|| content("incomplete transaction"))?.getf32().ok_or_else(|| content("Prasing of Trade confirmation went wrong"))?; // price
+                                                                let principal = transaction.next().ok_or_else(|| content("incomplete transaction"))?.getf32().ok_or_else(|| content("Prasing of Trade confirmation went wrong"))?; // principal
+                                                                let commission = transaction.next().ok_or_else(|| content("incomplete transaction"))?.getf32().ok_or_else(|| content("Prasing of Trade confirmation went wrong"))?; // commission
+                                                                let fee = transaction.next().ok_or_else(|| content("incomplete transaction"))?.getf32().ok_or_else(|| content("Prasing of Trade confirmation went wrong"))?; // fee
+                                                                let net = transaction.next().ok_or_else(|| content("incomplete transaction"))?.getf32().ok_or_else(|| content("Prasing of Trade confirmation went wrong"))?; // net
+                                                                trades.push((
+                                                                    transaction_date,
+                                                                    settlement_date,
+                                                                    quantity,
+                                                                    price,
+                                                                    principal,
+                                                                    commission,
+                                                                    fee,
+                                                                    net,
+                                                                ));
+                                                            }
+                                                        }
+                                                        processed_sequence.clear();
+                                                    } else {
+                                                        state =
+                                                            ParserState::ProcessingTransaction(
+                                                                transaction_type,
+                                                            );
+                                                    }
+                                                }
+
+                                                // In nothing more to be done then just extract
+                                                // parsed data from paser objects
+                                                None => {
+                                                    state = ParserState::ProcessingTransaction(
+                                                        transaction_type,
+                                                    );
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                _ => {}
+            }
+        }
+    }
+    Ok((
+        div_transactions,
+        sold_transactions,
+        trades,
+        interest_transactions,
+        dividend_accounts,
+        bought_transactions,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileSystem;
+
+    #[test]
+    fn test_parser() -> Result<(), String> {
+        // quantity
+        let data: Vec<u8> = vec![b'1'];
+        let mut i = I32Entry { val: 0 };
+        i.parse(&pdf::primitive::PdfString::new(data))?;
+        assert_eq!(i.geti32(), Some(1));
+
+        // price
+        let data: Vec<u8> = vec![b'2', b'8', b'.', b'2', b'0', b'3', b'5'];
+        let mut f = F32Entry { val: 0.0 };
+        f.parse(&pdf::primitive::PdfString::new(data))?;
+        assert_eq!(f.getf32(), Some(28.2035));
+
+        // amount
+        let data: Vec<u8> = vec![b'4', b',', b'8', b'7', b'7', b'.', b'3', b'6'];
+        let mut f = F32Entry { val: 0.0 };
+        f.parse(&pdf::primitive::PdfString::new(data))?;
+        assert_eq!(f.getf32(), Some(4877.36));
+
+        // negative amount (reversal)
+        let data: Vec<u8> = "(104.74)".bytes().collect();
+        let mut f = F32Entry { val: 0.0 };
+        f.parse(&pdf::primitive::PdfString::new(data))?;
+        assert_eq!(f.getf32(), Some(-104.74));
+        assert!(f
+            .parse(&pdf::primitive::PdfString::new("NaN".bytes().collect()))
+            .is_err());
+
+        // interest amount preceded by description
+        let mut a = AmountEntry {
+            val: None,
+            text: String::new(),
+        };
+        a.parse(&pdf::primitive::PdfString::new("SWEEP".bytes().collect()))?;
+        assert_eq!(a.getstring(), Some("SWEEP".to_owned()));
+        a.parse(&pdf::primitive::PdfString::new("0.42".bytes().collect()))?;
+        assert_eq!((a.getstring(), a.getf32()), (None, Some(0.42)));
+        a.parse(&pdf::primitive::PdfString::new("inf".bytes().collect()))?;
+        assert_eq!(a.getstring(), Some("inf".to_owned()));
+
+        // company code
+        let data: Vec<u8> = vec![b'D', b'L', b'B'];
+        let mut s = StringEntry {
+            val: String::new(),
+            patterns: vec!["INTC".to_owned(), "DLB".to_owned()],
+        };
+        s.parse(&pdf::primitive::PdfString::new(data))?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_account_numbers() -> Result<(), String> {
+        // Statement of accounts XXXX-1234 and XXXX-0848, number of the latter
+        // given in text following "Account Number:"
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../data/example-accounts.pdf"
+        );
+        assert_eq!(
+            parse_brokerage_statement(&FileSystem, path, false),
+            Ok((
+                vec![
+                    ("03/01/22".to_owned(), 100.0, 15.0, "INTC".to_owned()),
+                    ("03/01/22".to_owned(), 50.0, 7.5, "INTC".to_owned()),
+                    ("06/01/22".to_owned(), 20.0, 3.0, "DLB".to_owned())
+                ],
+                vec![],
+                vec![],
+                vec![],
+                vec![
+                    "XXXX-1234".to_owned(),
+                    "XXXX-0848".to_owned(),
+                    "XXXX-1234".to_owned()
+                ],
+                vec![]
+            ))
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[ignore]
+    fn test_parse_brokerage_statement() -> Result<(), String> {
+        assert_eq!(
+            parse_brokerage_statement(&FileSystem, "data/example-divs.pdf", false),
+            Ok((
+                vec![("03/01/22".to_owned(), 698.25, 104.74, "INTC".to_owned())],
+                vec![],
+                vec![],
+                vec![],
+                vec!["XXXX-0848".to_owned()],
+                vec![]
+            ))
+        );
+        assert_eq!(
+            parse_brokerage_statement(&FileSystem, "data/example-sold-wire.pdf", false),
+            Ok((
+                vec![],
+                vec![(
+                    "05/02/22".to_owned(),
+                    "05/04/22".to_owned(),
+                    -1,
+                    43.69,
+                    43.67
+                )],
+                vec![],
+                vec![],
+                vec![],
+                vec![]
+            ))
+        );
+
+        //TODO(jczaja): Renable reinvest dividends case as soon as you get some PDFs
+        //assert_eq!(
+        //    parse_brokerage_statement("data/example3.pdf"),
+        //    (
+        //        vec![
+        //            ("06/01/21".to_owned(), 0.17, 0.03),
+        //            ("06/01/21".to_owned(), 45.87, 6.88)
+        //        ],
+        //        vec![],
+        //        vec![]
+        //    )
+        //);
+
+        //assert_eq!(
+        //    parse_brokerage_statement("data/example5.pdf"),
+        //    (
+        //        vec![],
+        //        vec![],
+        //        vec![(
+        //            "04/11/22".to_owned(),
+        //            "04/13/22".to_owned(),
+        //            1,
+        //            46.92,
+        //            46.92,
+        //            0.01,
+        //           0.01,
+        //            46.9
+        //        )]
+        //    )
+        //);
+        Ok(())
+    }
+}
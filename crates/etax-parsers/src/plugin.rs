@@ -0,0 +1,238 @@
+//! External parsers of documents of other brokers, run as separate programs
+//! speaking JSON on standard input and output:
+//!     <program> describe
+//! prints {"name": "revolut", "patterns": ["*revolut*.csv"]}, patterns of
+//! file names of documents the plugin parses, and
+//!     <program> parse
+//! reads {"name": <file name>, "content": <base64 of document>,
+//! "use_record_date": false} and prints transactions as JSON statement
+//! (see ParsedStatement). Failure is told by non-zero exit code with reason
+//! on standard error
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use super::{DocumentSource, ParseError, ParsedStatement};
+
+/// Parser program and patterns of file names of documents it parses
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plugin {
+    pub name: String,
+    pub program: PathBuf,
+    pub patterns: Vec<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct Description {
+    name: String,
+    patterns: Vec<String>,
+}
+
+static PLUGINS: OnceLock<Vec<Plugin>> = OnceLock::new();
+
+/// Parse documents matching patterns of given plugins with them instead of
+/// built-in parsers. It has to be set before the first document is parsed
+pub fn set_plugins(plugins: Vec<Plugin>) -> Result<(), String> {
+    PLUGINS
+        .set(plugins)
+        .map_err(|_| "Parser plugins are already set".to_owned())
+}
+
+/// First of set plugins matching file name of document
+pub(crate) fn find(name: &str) -> Option<&'static Plugin> {
+    PLUGINS.get()?.iter().find(|plugin| plugin.matches(name))
+}
+
+/// Reason of failed run of plugin: its standard error or exit status
+fn reason(output: &std::process::Output) -> String {
+    match String::from_utf8_lossy(&output.stderr).trim() {
+        "" => output.status.to_string(),
+        stderr => stderr.to_owned(),
+    }
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+        path.is_file() && matches!(extension.as_deref(), Some("exe" | "bat" | "cmd"))
+    }
+}
+
+/// Plugins of executable files of directory in order of their names. Plugin
+/// that can not describe itself is skipped with warning
+pub fn discover_plugins(dir: &str) -> Result<Vec<Plugin>, String> {
+    let mut programs: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| format!("Error reading plugins directory {}: {}", dir, e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| is_executable(path))
+        .collect();
+    programs.sort();
+    let mut plugins = vec![];
+    for program in programs {
+        match Plugin::describe(&program) {
+            Ok(plugin) => {
+                log::info!(
+                    "Parser plugin {} of {:?}: {}",
+                    plugin.name,
+                    plugin.patterns,
+                    program.display()
+                );
+                plugins.push(plugin);
+            }
+            Err(e) => {
+                let msg = format!(
+                    "Warning: parser plugin {} skipped: {}",
+                    program.display(),
+                    e
+                );
+                eprintln!("{}", etax_core::warning(&msg));
+                log::warn!("{}", msg);
+            }
+        }
+    }
+    Ok(plugins)
+}
+
+impl Plugin {
+    fn describe(program: &Path) -> Result<Plugin, String> {
+        let output = std::process::Command::new(program)
+            .arg("describe")
+            .stdin(std::process::Stdio::null())
+            .output()
+            .map_err(|e| e.to_string())?;
+        if !output.status.success() {
+            return Err(reason(&output));
+        }
+        let description: Description =
+            serde_json::from_slice(&output.stdout).map_err(|e| e.to_string())?;
+        // Invalid pattern would never match
+        if let Some(pattern) = description
+            .patterns
+            .iter()
+            .find(|pattern| glob::Pattern::new(pattern).is_err())
+        {
+            return Err(format!("invalid pattern {}", pattern));
+        }
+        Ok(Plugin {
+            name: description.name,
+            program: program.to_path_buf(),
+            patterns: description.patterns,
+        })
+    }
+
+    /// File name of document matches any of patterns, regardless of case
+    pub fn matches(&self, name: &str) -> bool {
+        let file_name = Path::new(name)
+            .file_name()
+            .map_or(name.into(), |n| n.to_string_lossy());
+        let options = glob::MatchOptions {
+            case_sensitive: false,
+            ..Default::default()
+        };
+        self.patterns.iter().any(|pattern| {
+            glob::Pattern::new(pattern).is_ok_and(|p| p.matches_with(&file_name, options))
+        })
+    }
+
+    pub fn parse(
+        &self,
+        source: &dyn DocumentSource,
+        name: &str,
+        use_record_date: bool,
+    ) -> Result<ParsedStatement, ParseError> {
+        let content = source.read(name).map_err(|e| ParseError::open(name, e))?;
+        let request = serde_json::json!({
+            "name": name,
+            "content": base64::encode(&content[..]),
+            "use_record_date": use_record_date,
+        })
+        .to_string();
+        let failed = |reason: String| {
+            ParseError::content(name, &format!("plugin {} failed: {}", self.name, reason))
+        };
+        let mut child = std::process::Command::new(&self.program)
+            .arg("parse")
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
+            .map_err(|e| failed(e.to_string()))?;
+        // Written from other thread, so plugin writing output before reading
+        // whole request does not block
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| failed("standard input is not available".to_owned()))?;
+        let writer = std::thread::spawn(move || stdin.write_all(request.as_bytes()));
+        let output = child
+            .wait_with_output()
+            .map_err(|e| failed(e.to_string()))?;
+        let _ = writer.join();
+        if !output.status.success() {
+            return Err(failed(reason(&output)));
+        }
+        log::info!("Document {} parsed by plugin {}", name, self.name);
+        serde_json::from_slice(&output.stdout).map_err(|e| failed(e.to_string()))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plugin() -> Result<(), String> {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("etrade_plugins_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let program = dir.join("revolut");
+        std::fs::write(
+            &program,
+            "#!/bin/sh\ncase $1 in\ndescribe) echo '{\"name\": \"revolut\", \"patterns\": [\"*revolut*.csv\"]}' ;;\nparse) grep -q '\"name\":\"Revolut-2023.csv\"' || exit 1; echo '{\"dividends\": [[\"03/01/22\", 104.74, 15.71, \"INTC\"]]}' ;;\nesac\n",
+        )
+        .map_err(|e| e.to_string())?;
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| e.to_string())?;
+        // Files that are not executable e.g. notes are not plugins
+        std::fs::write(dir.join("README"), "").map_err(|e| e.to_string())?;
+
+        let plugins = discover_plugins(&dir.to_string_lossy())?;
+        assert_eq!(
+            plugins,
+            vec![Plugin {
+                name: "revolut".to_owned(),
+                program: program.clone(),
+                patterns: vec!["*revolut*.csv".to_owned()],
+            }]
+        );
+        assert!(plugins[0].matches("statements/Revolut-2023.csv"));
+        assert!(!plugins[0].matches("crypto.csv"));
+
+        let mut documents = crate::InMemory::default();
+        documents.insert("Revolut-2023.csv", b"Date,Ticker,Type\n".to_vec());
+        documents.insert("revolut.csv", vec![]);
+        assert_eq!(
+            plugins[0]
+                .parse(&documents, "Revolut-2023.csv", false)
+                .map_err(|e| e.to_string())?,
+            ParsedStatement {
+                dividends: vec![("03/01/22".to_owned(), 104.74, 15.71, "INTC".to_owned())],
+                ..Default::default()
+            }
+        );
+        assert!(matches!(
+            plugins[0].parse(&documents, "revolut.csv", false),
+            Err(ParseError::Content { .. })
+        ));
+        std::fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Storage documents are read from by name: local file system of command
+/// line program or files given to web page in WASM build
+pub trait DocumentSource: std::fmt::Debug + Send + Sync {
+    fn read(&self, name: &str) -> std::io::Result<Document>;
+}
+
+enum Buffer {
+    Bytes(Vec<u8>),
+    #[cfg(not(target_arch = "wasm32"))]
+    Mapped(memmap2::Mmap),
+}
+
+/// Content of document. Clones share one buffer, so document is not copied
+/// for each parsing worker
+#[derive(Clone)]
+pub struct Document(Arc<Buffer>);
+
+impl std::ops::Deref for Document {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self.0.as_ref() {
+            Buffer::Bytes(bytes) => bytes,
+            #[cfg(not(target_arch = "wasm32"))]
+            Buffer::Mapped(map) => map,
+        }
+    }
+}
+
+impl AsRef<[u8]> for Document {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl From<Vec<u8>> for Document {
+    fn from(bytes: Vec<u8>) -> Self {
+        Document(Arc::new(Buffer::Bytes(bytes)))
+    }
+}
+
+impl std::fmt::Debug for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Document({} bytes)", self.len())
+    }
+}
+
+/// Documents are paths of local files
+#[derive(Debug, Default)]
+pub struct FileSystem;
+
+impl DocumentSource for FileSystem {
+    /// Files are memory mapped, so consolidated statements of hundreds of
+    /// megabytes are not loaded into memory as a whole
+    #[cfg(not(target_arch = "wasm32"))]
+    fn read(&self, name: &str) -> std::io::Result<Document> {
+        let file = std::fs::File::open(name)?;
+        // Empty file can not be mapped
+        if file.metadata()?.len() == 0 {
+            return Ok(Document::from(vec![]));
+        }
+        // SAFETY: documents are only read, and are not expected to be
+        // modified by other programs while being parsed
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(Document(Arc::new(Buffer::Mapped(map))))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn read(&self, name: &str) -> std::io::Result<Document> {
+        std::fs::read(name).map(Document::from)
+    }
+}
+
+/// Contents of documents keyed by name e.g. file name
+#[derive(Debug, Default, Clone)]
+pub struct InMemory {
+    documents: HashMap<String, Document>,
+}
+
+impl InMemory {
+    pub fn insert(&mut self, name: &str, content: Vec<u8>) {
+        self.documents.insert(name.to_owned(), content.into());
+    }
+}
+
+impl DocumentSource for InMemory {
+    fn read(&self, name: &str) -> std::io::Result<Document> {
+        self.documents
+            .get(name)
+            .cloned()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::NotFound, "no such document"))
+    }
+}
+
+/// Source with one document already read, so it is not read again by parser
+#[derive(Debug)]
+pub struct WithDocument<'a> {
+    pub source: &'a dyn DocumentSource,
+    pub name: &'a str,
+    pub document: Document,
+}
+
+impl DocumentSource for WithDocument<'_> {
+    fn read(&self, name: &str) -> std::io::Result<Document> {
+        match name == self.name {
+            true => Ok(self.document.clone()),
+            false => self.source.read(name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_system() -> Result<(), String> {
+        let path = std::env::temp_dir().join(format!("etrade_source_{}.pdf", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, b"%PDF-1.4").map_err(|e| e.to_string())?;
+        assert_eq!(
+            &FileSystem.read(path).map_err(|e| e.to_string())?[..],
+            b"%PDF-1.4"
+        );
+        std::fs::write(path, b"").map_err(|e| e.to_string())?;
+        let empty = FileSystem.read(path).map_err(|e| e.to_string())?;
+        std::fs::remove_file(path).map_err(|e| e.to_string())?;
+        assert!(empty.is_empty());
+        assert!(FileSystem.read("data/missing.pdf").is_err());
+
+        let shared = WithDocument {
+            source: &FileSystem,
+            name: "statement.pdf",
+            document: Document::from(b"%PDF-1.7".to_vec()),
+        };
+        assert_eq!(
+            &shared.read("statement.pdf").map_err(|e| e.to_string())?[..],
+            b"%PDF-1.7"
+        );
+        assert!(shared.read("data/missing.pdf").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+use calamine::{Reader, Xlsx};
+
+use super::{DocumentSource, ParseError};
+use etax_core::HoldingTerm;
+
+// (date acquired, date sold, acquisition cost, cost basis, total proceeds, term)
+type Lot = (String, String, f32, f32, f32, HoldingTerm);
+
+/// Term as given in G&L document. If missing then it is derived from holding
+/// period: stock held more than one year is long-term
+fn parse_holding_term(
+    term: Option<&str>,
+    date_acquired: &str,
+    date_sold: &str,
+) -> Result<HoldingTerm, String> {
+    match term {
+        Some("Long") => Ok(HoldingTerm::Long),
+        Some("Short") => Ok(HoldingTerm::Short),
+        _ => {
+            let acquired = chrono::NaiveDate::parse_from_str(date_acquired, "%m/%d/%Y")
+                .map_err(|_| format!("Error parsing date: {}", date_acquired))?;
+            let sold = chrono::NaiveDate::parse_from_str(date_sold, "%m/%d/%Y")
+                .map_err(|_| format!("Error parsing date: {}", date_sold))?;
+            match sold > acquired + chrono::Duration::days(365) {
+                true => Ok(HoldingTerm::Long),
+                false => Ok(HoldingTerm::Short),
+            }
+        }
+    }
+}
+
+/// This function parses G&L Collappsed and Expanded for needed transaction details
+/// and it returns found sold transactions in a form:
+/// date when sold stock was acquired (date_acquired)
+/// date when stock was sold (date_sold)
+/// aqusition cost of sold stock (aquisition_cost)
+/// adjusted aquisition cost of sold stock (cost_basis)
+/// income from sold stock (total_proceeds)
+/// US holding term of sold stock (term)
+pub fn parse_gains_and_losses(
+    source: &dyn DocumentSource,
+    xlsxtoparse: &str,
+) -> Result<Vec<Lot>, ParseError> {
+    let content = |reason: &str| ParseError::content(xlsxtoparse, reason);
+    let data = source
+        .read(xlsxtoparse)
+        .map_err(|e| ParseError::open(xlsxtoparse, e))?;
+    let mut excel: Xlsx<_> =
+        Xlsx::new(std::io::Cursor::new(data)).map_err(|e| ParseError::open(xlsxtoparse, e))?;
+    let name = excel
+        .sheet_names()
+        .first()
+        .ok_or_else(|| content("No worksheet found"))?
+        .clone();
+    log::info!("name: {}", name);
+    let mut transactions: Vec<Lot> = vec![];
+    if let Some(Ok(r)) = excel.worksheet_range(&name) {
+        let mut rows = r.rows();
+        let categories = rows
+            .next()
+            .ok_or_else(|| content("unable to get descriptive row"))?;
+        let mut date_acquired_idx = 0;
+        let mut date_sold_idx = 0;
+        let mut cost_basis_idx = 0;
+        let mut acquistion_cost_idx = 0;
+        let mut total_proceeds_idx = 0;
+        let mut term_idx: Option<usize> = None;
+
+        for (idx, c) in categories.iter().enumerate() {
+            // Find indices of interesting collumns
+            if let Some(v) = c.get_string() {
+                match v {
+                    "Date Acquired" => date_acquired_idx = idx,
+                    "Date Sold" => date_sold_idx = idx,
+                    "Acquisition Cost" => acquistion_cost_idx = idx,
+                    "Adjusted Cost Basis" => cost_basis_idx = idx,
+                    "Total Proceeds" => total_proceeds_idx = idx,
+                    "Term" => term_idx = Some(idx),
+                    _ => (),
+                }
+            }
+        }
+
+        // Rewind summary row as we are not interested in this
+        rows.next();
+
+        // Iterate through rows of actual sold transactions
+        for transakcja in rows {
+            log::info!(
+                "G&L ACQUIRED_DATE: {} SOLD_DATE: {} ACQUISTION_COST: {} COST_BASIS: {} TOTAL: {}",
+                transakcja[date_acquired_idx],
+                transakcja[date_sold_idx],
+                transakcja[acquistion_cost_idx],
+                transakcja[cost_basis_idx],
+                transakcja[total_proceeds_idx]
+            );
+            // If row is ill formed or emtpy then it means user added something and this is to be
+            // dropped
+            if transakcja[date_acquired_idx].is_empty()
+                && transakcja[date_sold_idx].is_empty()
+                && transakcja[acquistion_cost_idx].is_empty()
+                && transakcja[cost_basis_idx].is_empty()
+                && transakcja[total_proceeds_idx].is_empty()
+            {
+                log::info!(
+                    "G&L Finished parsing due to empty raw of data. Did you modified document?"
+                );
+                break;
+            }
+
+            //println!("transakcja: {:?}", transakcja);
+            let text = |idx: usize| {
+                transakcja[idx]
+                    .get_string()
+                    .map(|s| s.to_owned())
+                    .ok_or_else(|| content(&format!("expected text in row: {:?}", transakcja)))
+            };
+            let amount = |idx: usize| {
+                transakcja[idx]
+                    .get_float()
+                    .map(|f| f as f32)
+                    .ok_or_else(|| content(&format!("expected amount in row: {:?}", transakcja)))
+            };
+            let date_acquired = text(date_acquired_idx)?;
+            let date_sold = text(date_sold_idx)?;
+            let term = parse_holding_term(
+                term_idx.and_then(|idx| transakcja[idx].get_string()),
+                &date_acquired,
+                &date_sold,
+            )
+            .map_err(|e| content(&e))?;
+            transactions.push((
+                date_acquired,
+                date_sold,
+                amount(acquistion_cost_idx)?,
+                amount(cost_basis_idx)?,
+                amount(total_proceeds_idx)?,
+                term,
+            ));
+        }
+    }
+    log::info!("G&L Transactions: {:#?}", transactions);
+    Ok(transactions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileSystem;
+
+    #[test]
+    fn test_parse_gain_and_losses() -> Result<(), String> {
+        assert_eq!(
+            parse_gains_and_losses(
+                &FileSystem,
+                concat!(env!("CARGO_MANIFEST_DIR"), "/../../data/G&L_Collapsed.xlsx")
+            ),
+            Ok(vec![
+                (
+                    "04/24/2013".to_owned(),
+                    "04/11/2022".to_owned(),
+                    0.0,
+                    23.5175,
+                    46.9,
+                    HoldingTerm::Long
+                ),
+                (
+                    "08/19/2015".to_owned(),
+                    "05/02/2022".to_owned(),
+                    24.258,
+                    29.28195,
+                    43.67,
+                    HoldingTerm::Long
+                )
+            ])
+        );
+        assert_eq!(
+            parse_gains_and_losses(
+                &FileSystem,
+                concat!(env!("CARGO_MANIFEST_DIR"), "/../../data/G&L_Expanded.xlsx")
+            ),
+            Ok(vec![
+                (
+                    "04/24/2013".to_owned(),
+                    "04/11/2022".to_owned(),
+                    0.0,
+                    23.5175,
+                    46.9,
+                    HoldingTerm::Long
+                ),
+                (
+                    "08/19/2015".to_owned(),
+                    "05/02/2022".to_owned(),
+                    24.258,
+                    29.28195,
+                    43.67,
+                    HoldingTerm::Long
+                )
+            ])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_holding_term() -> Result<(), String> {
+        assert_eq!(
+            parse_holding_term(Some("Short"), "01/01/2019", "06/01/2021"),
+            Ok(HoldingTerm::Short)
+        );
+        assert_eq!(
+            parse_holding_term(None, "01/01/2019", "06/01/2021"),
+            Ok(HoldingTerm::Long)
+        );
+        assert_eq!(
+            parse_holding_term(None, "01/01/2021", "03/01/2021"),
+            Ok(HoldingTerm::Short)
+        );
+        Ok(())
+    }
+}
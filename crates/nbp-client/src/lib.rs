@@ -0,0 +1,207 @@
+//! Client of NBP (Narodowy Bank Polski) API of average exchange rates
+//! (table A). Income in foreign currency is converted for Polish tax return
+//! with average NBP rate of last business day preceding date of income
+//! (art. 11a of PIT act), as returned by get_exchange_rate.
+//!
+//! Requests are sent by HttpClient, so any HTTP client can be used e.g.
+//! browser's one in WASM build. With reqwest feature it is implemented by
+//! reqwest blocking client
+
+use serde::{Deserialize, Serialize};
+
+// Example response: {"table":"A",
+//                    "currency":"dolar amerykański",
+//                    "code":"USD",
+//                    "rates":[{"no":"039/A/NBP/2021",
+//                              "effectiveDate":"2021-02-26",
+//                              "mid":3.7247}]}
+
+// HTTPS, so it is not blocked as mixed content of web page. Other server
+// (e.g. local mock in tests) can be given with NBP_API_URL env var
+const NBP_API_URL: &str = "https://api.nbp.pl/api";
+
+// Requests refused due to rate limit or server error are retried after
+// increasing delay: 0.5s, 1s, 2s
+const MAX_RETRIES: u32 = 3;
+const RETRY_DELAY_MS: u64 = 500;
+
+// Longest period without published table (Christmas, Easter) is shorter
+const MAX_DAYS_WITHOUT_TABLE: u32 = 14;
+
+/// Reasons exchange rates could not be obtained
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum RateError {
+    #[error("unable to create HTTP client: {0}")]
+    Client(String),
+    #[error("getting exchange rate from {url} failed: {reason}")]
+    Request { url: String, reason: String },
+    #[error("unexpected exchange rate response from {url}: {reason}")]
+    Response { url: String, reason: String },
+    #[error("invalid date of transaction: {0}")]
+    Date(String),
+    /// Run interrupted before rate was requested
+    #[error("interrupted before getting exchange rate of {0}")]
+    Interrupted(String),
+}
+
+/// HTTP GET of exchange rate services, so requests can be sent by other
+/// client than reqwest e.g. browser's one in WASM build
+pub trait HttpClient: Send + Sync {
+    /// Status code and body of response to GET request of url
+    fn get(&self, url: &str) -> Result<(u16, String), RateError>;
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+impl HttpClient for reqwest::blocking::Client {
+    fn get(&self, url: &str) -> Result<(u16, String), RateError> {
+        let response = self.get(url).send().map_err(|e| RateError::Request {
+            url: url.to_owned(),
+            reason: e.to_string(),
+        })?;
+        log::info!("RESPONSE {:#?}", response);
+        let status = response.status().as_u16();
+        let body = response.text().map_err(|e| RateError::Response {
+            url: url.to_owned(),
+            reason: e.to_string(),
+        })?;
+        Ok((status, body))
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct NBPResponse<T> {
+    table: String,
+    currency: String,
+    code: String,
+    rates: Vec<T>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[allow(non_snake_case)]
+struct ExchangeRate {
+    no: String,
+    effectiveDate: String,
+    mid: f32,
+}
+
+/// Average NBP rate (table A) of currency (e.g. "usd") of last business day
+/// preceding date of income: (rate date YYYY-MM-DD, rate, number of table
+/// rate was published in)
+pub fn get_exchange_rate(
+    client: &dyn HttpClient,
+    currency: &str,
+    date: chrono::NaiveDate,
+) -> Result<(String, f32, String), RateError> {
+    let base_exchange_rate_url = format!(
+        "{}/exchangerates/rates/a/{}/",
+        std::env::var("NBP_API_URL")
+            .unwrap_or(NBP_API_URL.to_owned())
+            .trim_end_matches('/'),
+        currency.to_lowercase()
+    );
+    let preceding = |date: chrono::NaiveDate| {
+        date.checked_sub_signed(chrono::Duration::days(1))
+            .ok_or(RateError::Date(date.to_string()))
+    };
+    let mut converted_date = preceding(date)?;
+    let mut days_without_table = 0;
+    let mut retries = 0;
+
+    // Try to get exchange rate going backwards with dates till success
+    loop {
+        let exchange_rate_url: String = base_exchange_rate_url.to_string()
+            + &format!("{}", converted_date.format("%Y-%m-%d"))
+            + "/?format=json";
+
+        let (status, body) = client.get(&exchange_rate_url)?;
+        if status == 429 || status >= 500 {
+            if retries == MAX_RETRIES {
+                return Err(RateError::Request {
+                    url: exchange_rate_url,
+                    reason: format!("status {} after {} retries", status, MAX_RETRIES),
+                });
+            }
+            log::warn!(
+                "{} responded with status {}, retrying",
+                exchange_rate_url,
+                status
+            );
+            // Browser has no threads to put to sleep
+            if !cfg!(target_arch = "wasm32") {
+                std::thread::sleep(std::time::Duration::from_millis(RETRY_DELAY_MS << retries));
+            }
+            retries += 1;
+            continue;
+        }
+        if (200..300).contains(&status) {
+            let nbp_response =
+                serde_json::from_str::<NBPResponse<ExchangeRate>>(&body).map_err(|e| {
+                    RateError::Response {
+                        url: exchange_rate_url.clone(),
+                        reason: e.to_string(),
+                    }
+                })?;
+            log::info!("body of exchange_rate = {:#?}", nbp_response);
+            let rate = nbp_response.rates.first().ok_or(RateError::Response {
+                url: exchange_rate_url,
+                reason: "no rates in response".to_owned(),
+            })?;
+            let exchange_rate_date = format!("{}", converted_date.format("%Y-%m-%d"));
+            return Ok((exchange_rate_date, rate.mid, rate.no.clone()));
+        }
+        // No table is published on weekends and holidays
+        if status != 404 {
+            return Err(RateError::Request {
+                url: exchange_rate_url,
+                reason: format!("status {}: {}", status, body),
+            });
+        }
+        days_without_table += 1;
+        if days_without_table == MAX_DAYS_WITHOUT_TABLE {
+            return Err(RateError::Response {
+                url: exchange_rate_url,
+                reason: format!("no table in {} days preceding", MAX_DAYS_WITHOUT_TABLE),
+            });
+        }
+        retries = 0;
+        converted_date = preceding(converted_date)?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    // Responses keyed by URL, other requests get 404 as NBP gives for days
+    // without table
+    struct FakeClient(HashMap<String, String>);
+
+    impl HttpClient for FakeClient {
+        fn get(&self, url: &str) -> Result<(u16, String), RateError> {
+            Ok(match self.0.get(url) {
+                Some(body) => (200, body.clone()),
+                None => (404, "404 NotFound - Not Found - Brak danych".to_owned()),
+            })
+        }
+    }
+
+    #[test]
+    fn test_get_exchange_rate() -> Result<(), String> {
+        let client = FakeClient(HashMap::from([(
+            "https://api.nbp.pl/api/exchangerates/rates/a/usd/2023-03-03/?format=json".to_owned(),
+            r#"{"table":"A","currency":"dolar amerykański","code":"USD","rates":[{"no":"044/A/NBP/2023","effectiveDate":"2023-03-03","mid":4.4286}]}"#.to_owned(),
+        )]));
+        // Monday, so rate of preceding Friday is taken
+        let monday = chrono::NaiveDate::from_ymd_opt(2023, 3, 6).ok_or("invalid date")?;
+        assert_eq!(
+            get_exchange_rate(&client, "USD", monday).map_err(|e| e.to_string())?,
+            ("2023-03-03".to_owned(), 4.4286, "044/A/NBP/2023".to_owned())
+        );
+        assert!(matches!(
+            get_exchange_rate(&client, "eur", monday),
+            Err(RateError::Response { .. })
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,29 @@
+use etradeTaxReturnHelper::ParsedStatement;
+
+/// Transactions returned by parser are valid input of taxation: dates of
+/// statement format and finite amounts. Anything else has to be parse error
+pub fn check_statement(statement: &ParsedStatement) {
+    let date = |date: &str| {
+        assert!(
+            chrono::NaiveDate::parse_from_str(date, "%m/%d/%y").is_ok(),
+            "invalid date {}",
+            date
+        )
+    };
+    let amount = |amount: f32| assert!(amount.is_finite(), "invalid amount {}", amount);
+    for (d, gross, tax, _) in statement.dividends.iter().chain(statement.interest.iter()) {
+        date(d);
+        amount(*gross);
+        amount(*tax);
+    }
+    for (trade_date, settlement_date, _, price, amount_sold) in statement.sold.iter() {
+        date(trade_date);
+        date(settlement_date);
+        amount(*price);
+        amount(*amount_sold);
+    }
+    for (d, _, value, _) in statement.crypto.iter() {
+        date(d);
+        amount(*value);
+    }
+}
@@ -0,0 +1,24 @@
+//! Whole document of any kind: first byte chooses PDF statement, XLSX gains
+//! and losses or CSV of virtual currency transactions
+#![no_main]
+
+mod checks;
+
+use etradeTaxReturnHelper::{parse_document, InMemory};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((kind, content)) = data.split_first() else {
+        return;
+    };
+    let name = match kind % 3 {
+        0 => "statement.pdf",
+        1 => "G&L_Expanded.xlsx",
+        _ => "crypto.csv",
+    };
+    let mut documents = InMemory::default();
+    documents.insert(name, content.to_vec());
+    if let Ok(statement) = parse_document(&documents, name, false) {
+        checks::check_statement(&statement);
+    }
+});
@@ -0,0 +1,52 @@
+//! Content stream of single page brokerage statement, e.g. mutated
+//! `[(Dividend)] TJ [(INTC)] TJ [(104.74)] TJ` text operations
+#![no_main]
+
+mod checks;
+
+use etradeTaxReturnHelper::{parse_document, InMemory};
+use libfuzzer_sys::fuzz_target;
+
+/// PDF document of one page with given content stream
+fn statement(content: &[u8]) -> Vec<u8> {
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+        b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 612 792] /Contents 4 0 R >>".to_vec(),
+    ];
+    let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+    stream.extend_from_slice(content);
+    stream.extend_from_slice(b"\nendstream");
+    objects.push(stream);
+
+    let mut document = b"%PDF-1.4\n".to_vec();
+    let mut offsets = vec![];
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(document.len());
+        document.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        document.extend_from_slice(object);
+        document.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = document.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        table += &format!("{:010} 00000 n \n", offset);
+    }
+    table += &format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    document.extend_from_slice(table.as_bytes());
+    document
+}
+
+fuzz_target!(|content: &[u8]| {
+    let mut documents = InMemory::default();
+    documents.insert("statement.pdf", statement(content));
+    for use_record_date in [false, true] {
+        if let Ok(statement) = parse_document(&documents, "statement.pdf", use_record_date) {
+            checks::check_statement(&statement);
+        }
+    }
+});
@@ -0,0 +1,302 @@
+use serde_json::Value;
+
+use crate::oauth::{load_cached, percent_encode, store_cached, OAuth1};
+use crate::ParsedStatement;
+
+type ReqwestClient = reqwest::blocking::Client;
+
+const API_URL: &str = "https://api.etrade.com";
+const SANDBOX_API_URL: &str = "https://apisb.etrade.com";
+const AUTHORIZE_URL: &str = "https://us.etrade.com/e/t/etws/authorize";
+// Keychain service access tokens are cached under, per consumer key
+const TOKEN_SERVICE: &str = "etrade-token";
+// Largest page of transactions API returns
+const PAGE_SIZE: u32 = 50;
+
+/// Session of E*TRADE API authorized with OAuth 1.0a
+struct Session {
+    client: &'static ReqwestClient,
+    url: &'static str,
+    oauth: OAuth1,
+}
+
+impl Session {
+    /// Status and body of signed GET request of path with query parameters
+    fn get(&self, path: &str, query: &[(&str, &str)]) -> Result<(u16, String), String> {
+        self.oauth
+            .get(self.client, &format!("{}{}", self.url, path), query, &[])
+    }
+
+    /// Body of successful response, error with status otherwise
+    fn get_ok(&self, path: &str, query: &[(&str, &str)]) -> Result<String, String> {
+        match self.get(path, query)? {
+            (200..=299, body) => Ok(body),
+            (status, body) => Err(format!(
+                "Error: E*TRADE API {} returned {}: {}",
+                path, status, body
+            )),
+        }
+    }
+
+    /// Access token cached in keychain if it can still be renewed (tokens
+    /// expire at midnight US Eastern time), otherwise of OAuth 1.0a flow:
+    /// user opens authorization URL, logs in to E*TRADE and gives
+    /// verification code shown there
+    fn authorize(
+        &mut self,
+        verifier: &dyn Fn(&str) -> Result<String, String>,
+    ) -> Result<(), String> {
+        let consumer_key = self.oauth.consumer_key.clone();
+        if let Some(token) = load_cached(TOKEN_SERVICE, &consumer_key) {
+            self.oauth.token = Some(token);
+            let renew_url = format!("{}/oauth/renew_access_token", self.url);
+            match self.oauth.renew(self.client, &renew_url) {
+                Ok(true) => return Ok(()),
+                Ok(false) => log::info!("Cached E*TRADE access token expired"),
+                Err(e) => log::warn!("{}", e),
+            }
+        }
+        let urls = (
+            format!("{}/oauth/request_token", self.url),
+            format!("{}/oauth/access_token", self.url),
+        );
+        self.oauth.authorize(
+            self.client,
+            (&urls.0, &urls.1),
+            |token| {
+                format!(
+                    "{}?key={}&token={}",
+                    AUTHORIZE_URL,
+                    percent_encode(&consumer_key),
+                    percent_encode(token)
+                )
+            },
+            verifier,
+        )?;
+        if let Some(token) = &self.oauth.token {
+            store_cached(TOKEN_SERVICE, &consumer_key, token);
+        }
+        Ok(())
+    }
+
+    /// (account id, account id key) of accounts of user
+    fn accounts(&self) -> Result<Vec<(String, String)>, String> {
+        let body = self.get_ok("/v1/accounts/list.json", &[])?;
+        let value: Value = serde_json::from_str(&body)
+            .map_err(|e| format!("Error parsing E*TRADE accounts: {}", e))?;
+        Ok(value["AccountListResponse"]["Accounts"]["Account"]
+            .as_array()
+            .map(|accounts| {
+                accounts
+                    .iter()
+                    .filter_map(|account| {
+                        Some((
+                            account["accountId"].as_str()?.to_owned(),
+                            account["accountIdKey"].as_str()?.to_owned(),
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Transactions of account between dates (MMDDYYYY), all pages of them
+    fn transactions(&self, key: &str, from: &str, to: &str) -> Result<Vec<Value>, String> {
+        let path = format!("/v1/accounts/{}/transactions.json", key);
+        let count = PAGE_SIZE.to_string();
+        let mut transactions = vec![];
+        let mut marker: Option<String> = None;
+        loop {
+            let mut query = vec![("startDate", from), ("endDate", to), ("count", &count)];
+            if let Some(marker) = &marker {
+                query.push(("marker", marker));
+            }
+            let body = match self.get(&path, &query)? {
+                // No transactions in range
+                (204, _) => return Ok(transactions),
+                (200, body) => body,
+                (status, body) => {
+                    return Err(format!(
+                        "Error: E*TRADE API {} returned {}: {}",
+                        path, status, body
+                    ))
+                }
+            };
+            let value: Value = serde_json::from_str(&body)
+                .map_err(|e| format!("Error parsing E*TRADE transactions: {}", e))?;
+            let page = &value["TransactionListResponse"];
+            if let Some(page_transactions) = page["Transaction"].as_array() {
+                transactions.extend(page_transactions.iter().cloned());
+            }
+            match (page["moreTransactions"].as_bool(), page["marker"].as_str()) {
+                (Some(true), Some(next)) => marker = Some(next.to_owned()),
+                _ => return Ok(transactions),
+            }
+        }
+    }
+}
+
+/// Date (MM/DD/YY) of milliseconds since epoch E*TRADE dates are given in
+fn date_of(value: &Value) -> Option<String> {
+    chrono::NaiveDateTime::from_timestamp_opt(value.as_i64()?.div_euclid(1000), 0)
+        .map(|date| date.format("%m/%d/%y").to_string())
+}
+
+fn amount_of(value: &Value) -> Option<f32> {
+    value
+        .as_f64()
+        .map(|amount| amount as f32)
+        .filter(|amount| amount.is_finite())
+}
+
+/// Statement of transactions as given by E*TRADE API: dividends, interest
+/// and sales. Tax withheld is reported as separate transaction, so it is
+/// assigned to dividend of the same date and symbol
+fn statement_of(transactions: &[Value]) -> ParsedStatement {
+    let mut statement = ParsedStatement::default();
+    let mut withheld: Vec<(String, f32, String)> = vec![];
+    for transaction in transactions {
+        let kind = transaction["transactionType"].as_str().unwrap_or_default();
+        let brokerage = &transaction["brokerage"];
+        let symbol = brokerage["product"]["symbol"]
+            .as_str()
+            .or(brokerage["displaySymbol"].as_str())
+            .unwrap_or_default()
+            .to_owned();
+        let (Some(date), Some(amount)) = (
+            date_of(&transaction["transactionDate"]),
+            amount_of(&transaction["amount"]),
+        ) else {
+            log::warn!(
+                "Skipping E*TRADE transaction without date or amount: {}",
+                transaction
+            );
+            continue;
+        };
+        match kind {
+            "Dividend" | "Qualified Dividend" => {
+                statement.dividends.push((date, amount, 0.0, symbol))
+            }
+            kind if kind.contains("Interest") => {
+                statement.interest.push((date, amount, 0.0, String::new()))
+            }
+            kind if kind.contains("Withholding") => withheld.push((date, -amount, symbol)),
+            "Sold" => {
+                let settlement = date_of(&brokerage["settlementDate"])
+                    .or(date_of(&transaction["postDate"]))
+                    .unwrap_or(date.clone());
+                statement.sold.push((
+                    date,
+                    settlement,
+                    amount_of(&brokerage["quantity"]).unwrap_or(0.0).abs() as i32,
+                    amount_of(&brokerage["price"]).unwrap_or(0.0),
+                    amount,
+                ));
+            }
+            kind => log::info!("Skipping E*TRADE transaction of type {}", kind),
+        }
+    }
+    for (date, tax, symbol) in withheld {
+        match statement
+            .dividends
+            .iter_mut()
+            .find(|(d, _, _, s)| *d == date && *s == symbol)
+        {
+            Some(dividend) => dividend.2 += tax,
+            None => log::warn!(
+                "Tax withheld on {} of {} without dividend of the same day",
+                date,
+                symbol
+            ),
+        }
+    }
+    statement
+}
+
+/// Dividends, interest and sales of E*TRADE accounts between dates taken
+/// from E*TRADE API with API key (consumer key and secret). Authorization
+/// URL is given to verifier, which returns verification code shown there
+/// once user logs in, access token is kept in keychain for the rest of day.
+/// Accounts are given by id (all of them if none given), dividends keep id
+/// of their account
+pub fn import_etrade(
+    (consumer_key, consumer_secret): (&str, &str),
+    sandbox: bool,
+    accounts: &[String],
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+    verifier: &dyn Fn(&str) -> Result<String, String>,
+) -> Result<ParsedStatement, String> {
+    let mut session = Session {
+        client: crate::http::shared_client()?,
+        url: if sandbox { SANDBOX_API_URL } else { API_URL },
+        oauth: OAuth1 {
+            consumer_key: consumer_key.to_owned(),
+            consumer_secret: consumer_secret.to_owned(),
+            token: None,
+        },
+    };
+    session.authorize(verifier)?;
+    let (from, to) = (
+        from.format("%m%d%Y").to_string(),
+        to.format("%m%d%Y").to_string(),
+    );
+    let mut statement = ParsedStatement::default();
+    for (id, key) in session.accounts()? {
+        if accounts.is_empty() || accounts.contains(&id) {
+            log::info!("Importing transactions of E*TRADE account {}", id);
+            let mut imported = statement_of(&session.transactions(&key, &from, &to)?);
+            statement
+                .dividend_accounts
+                .extend(std::iter::repeat_n(id.clone(), imported.dividends.len()));
+            statement.dividends.append(&mut imported.dividends);
+            statement.interest.append(&mut imported.interest);
+            statement.sold.append(&mut imported.sold);
+        }
+    }
+    Ok(statement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_statement_of() -> Result<(), String> {
+        // 03/01/22 and 04/05/22 at midnight US Eastern time
+        let transactions = vec![
+            json!({"transactionDate": 1646110800000i64, "amount": 104.74,
+                   "transactionType": "Dividend", "brokerage": {"product": {"symbol": "INTC"}}}),
+            json!({"transactionDate": 1646110800000i64, "amount": -15.71,
+                   "transactionType": "Tax Withholding", "brokerage": {"product": {"symbol": "INTC"}}}),
+            json!({"transactionDate": 1649131200000i64, "amount": 453.04, "transactionType": "Sold",
+                   "brokerage": {"product": {"symbol": "INTC"}, "quantity": -10, "price": 45.306,
+                                 "settlementDate": 1649304000000i64}}),
+            json!({"transactionDate": 1649131200000i64, "amount": 0.13,
+                   "transactionType": "Interest Income", "brokerage": {}}),
+            json!({"transactionDate": 1649131200000i64, "amount": -500.0,
+                   "transactionType": "Bought", "brokerage": {}}),
+        ];
+        let statement = statement_of(&transactions);
+        assert_eq!(
+            statement.dividends,
+            vec![("03/01/22".to_owned(), 104.74, 15.71, "INTC".to_owned())]
+        );
+        assert_eq!(
+            statement.sold,
+            vec![(
+                "04/05/22".to_owned(),
+                "04/07/22".to_owned(),
+                10,
+                45.306,
+                453.04
+            )]
+        );
+        assert_eq!(
+            statement.interest,
+            vec![("04/05/22".to_owned(), 0.13, 0.0, String::new())]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,4 @@
+//! Importers of transactions straight from broker APIs, instead of parsing
+//! downloaded documents
+
+pub(crate) mod etrade;
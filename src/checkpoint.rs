@@ -0,0 +1,216 @@
+//! Checkpoint of documents parsed so far, so run of many documents that was
+//! interrupted or crashed is resumed without parsing them again
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::{parse_document, DocumentSource, ParseError, ParsedStatement};
+use etax_parsers::WithDocument;
+
+// Checkpoint is saved after this many documents are parsed since last save,
+// or when this many seconds passed
+const SAVE_EVERY_DOCUMENTS: usize = 10;
+const SAVE_EVERY_SECS: u64 = 30;
+
+#[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+struct State {
+    // Dividends are assigned by record date, statements differ then
+    use_record_date: bool,
+    // (fingerprint of content, statement) of each document keyed by its name
+    documents: BTreeMap<String, (String, ParsedStatement)>,
+}
+
+/// FNV-1a hash and size of document content, telling whether document
+/// changed since it was checkpointed
+fn fingerprint(content: &[u8]) -> String {
+    let hash = content.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}-{}", hash, content.len())
+}
+
+/// Statements of parsed documents kept in file, saved from time to time
+#[derive(Debug)]
+pub(crate) struct Checkpoint {
+    path: String,
+    // State with number of documents recorded since last save and its time
+    state: Mutex<(State, usize, std::time::Instant)>,
+    // In dry run checkpoint is only read
+    read_only: bool,
+}
+
+impl Checkpoint {
+    /// Checkpoint of given file, with statements of earlier run if it exists
+    /// and was made with the same options
+    pub(crate) fn open(path: &str, use_record_date: bool, read_only: bool) -> Self {
+        let earlier = match std::fs::read(path) {
+            Ok(content) => match serde_json::from_slice::<State>(&content) {
+                Ok(state) => Some(state),
+                Err(e) => {
+                    let msg = format!("Warning: checkpoint {} ignored: {}", path, e);
+                    eprintln!("{}", crate::warning(&msg));
+                    log::warn!("{}", msg);
+                    None
+                }
+            },
+            Err(_) => None,
+        };
+        let state = match earlier {
+            Some(state) if state.use_record_date == use_record_date => {
+                log::info!(
+                    "Resuming from checkpoint {} with {} parsed documents",
+                    path,
+                    state.documents.len()
+                );
+                state
+            }
+            _ => State {
+                use_record_date,
+                ..Default::default()
+            },
+        };
+        Checkpoint {
+            path: path.to_owned(),
+            state: Mutex::new((state, 0, std::time::Instant::now())),
+            read_only,
+        }
+    }
+
+    /// Statement of document from earlier run if it did not change since,
+    /// otherwise parsed and recorded
+    pub(crate) fn parse(
+        &self,
+        source: &dyn DocumentSource,
+        name: &str,
+        use_record_date: bool,
+    ) -> Result<ParsedStatement, ParseError> {
+        let content = source.read(name).map_err(|e| ParseError::open(name, e))?;
+        let fingerprint = fingerprint(&content);
+        if let Some(statement) = self.resumed(name, &fingerprint) {
+            log::info!("{} taken from checkpoint", name);
+            return Ok(statement);
+        }
+        // Document read for fingerprint is given to parser, not read again
+        let source = WithDocument {
+            source,
+            name,
+            document: content,
+        };
+        let statement = parse_document(&source, name, use_record_date)?;
+        self.record(name, fingerprint, &statement);
+        Ok(statement)
+    }
+
+    /// Statement of document parsed in earlier run, unless it changed since
+    fn resumed(&self, name: &str, fingerprint: &str) -> Option<ParsedStatement> {
+        let state = self.state.lock().ok()?;
+        match state.0.documents.get(name) {
+            Some((checkpointed, statement)) if checkpointed == fingerprint => {
+                Some(statement.clone())
+            }
+            _ => None,
+        }
+    }
+
+    /// Keep statement of parsed document, saving checkpoint if it is due
+    fn record(&self, name: &str, fingerprint: String, statement: &ParsedStatement) {
+        let Ok(mut state) = self.state.lock() else {
+            return;
+        };
+        state
+            .0
+            .documents
+            .insert(name.to_owned(), (fingerprint, statement.clone()));
+        state.1 += 1;
+        if state.1 >= SAVE_EVERY_DOCUMENTS || state.2.elapsed().as_secs() >= SAVE_EVERY_SECS {
+            self.write(&state.0);
+            state.1 = 0;
+            state.2 = std::time::Instant::now();
+        }
+    }
+
+    /// Save statements recorded since last save
+    pub(crate) fn save(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            if state.1 > 0 {
+                self.write(&state.0);
+                state.1 = 0;
+            }
+        }
+    }
+
+    /// Written to other file first, so crash while writing leaves previous
+    /// checkpoint intact. Failed save is not fatal, run goes on without it
+    fn write(&self, state: &State) {
+        if self.read_only {
+            return;
+        }
+        let temporary = format!("{}.tmp", self.path);
+        let written = serde_json::to_vec(state)
+            .map_err(|e| e.to_string())
+            .and_then(|content| crate::report::export::write_file(&temporary, &content))
+            .and_then(|_| {
+                std::fs::rename(&temporary, &self.path)
+                    .map_err(|e| format!("Error writing {}: {}", self.path, e))
+            });
+        match written {
+            Ok(()) => log::info!("Checkpoint saved to {}", self.path),
+            Err(e) => {
+                let msg = format!("Warning: checkpoint not saved: {}", e);
+                eprintln!("{}", crate::warning(&msg));
+                log::warn!("{}", msg);
+            }
+        }
+    }
+}
+
+/// Checkpoint of completed run is no longer needed
+pub(crate) fn remove(path: &str) {
+    match std::fs::remove_file(path) {
+        Ok(()) => log::info!("Checkpoint {} removed, run completed", path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => (),
+        Err(e) => log::warn!("Checkpoint {} not removed: {}", path, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint() -> Result<(), String> {
+        let path =
+            std::env::temp_dir().join(format!("etrade_checkpoint_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let statement = ParsedStatement {
+            dividends: vec![("03/01/22".to_owned(), 104.74, 15.71, "INTC".to_owned())],
+            ..Default::default()
+        };
+        let content = b"Brokerage statement of March";
+        assert_eq!(fingerprint(content), fingerprint(content));
+        assert_ne!(
+            fingerprint(content),
+            fingerprint(b"Brokerage statement of April")
+        );
+
+        let checkpoint = Checkpoint::open(path, false, false);
+        assert_eq!(checkpoint.resumed("march.pdf", &fingerprint(content)), None);
+        checkpoint.record("march.pdf", fingerprint(content), &statement);
+        checkpoint.save();
+
+        // Run after crash resumes with documents that did not change
+        let resumed = Checkpoint::open(path, false, false);
+        assert_eq!(
+            resumed.resumed("march.pdf", &fingerprint(content)),
+            Some(statement)
+        );
+        assert_eq!(resumed.resumed("march.pdf", &fingerprint(b"changed")), None);
+        // Statements parsed with other dates of dividends are not taken
+        let other = Checkpoint::open(path, true, false);
+        assert_eq!(other.resumed("march.pdf", &fingerprint(content)), None);
+
+        remove(path);
+        assert!(!std::path::Path::new(path).exists());
+        Ok(())
+    }
+}
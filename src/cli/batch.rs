@@ -0,0 +1,284 @@
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use etradeTaxReturnHelper::{
+    EXIT_INTERRUPTED, EXIT_NETWORK, EXIT_OK, EXIT_PARTIAL_PARSE, EXIT_VALIDATION,
+};
+
+/// Run of compute described in job file e.g.
+///     - name: kowalski-2023
+///       profile: kowalski
+///       inputs: ["kowalski/*.pdf", "kowalski/G&L_Expanded.xlsx"]
+///       year: 2023
+///       outputs: ["kowalski-2023.pdf"]
+///       options: ["--lang", "en"]
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Job {
+    pub name: Option<String>,
+    // Glob patterns of documents, relative ones are taken from directory of job file
+    pub inputs: Vec<String>,
+    pub year: Option<i32>,
+    pub residency: Option<String>,
+    pub profile: Option<String>,
+    // Reports in format given by extension, as of --output
+    #[serde(default)]
+    pub outputs: Vec<String>,
+    // Any other options of compute
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JobFile {
+    jobs: Vec<Job>,
+}
+
+impl Job {
+    /// Command line of job without program name
+    fn args(&self, documents: &[String], dry_run: bool) -> Vec<String> {
+        let option = |name: &str, value: Option<String>| value.map(|v| format!("--{}={}", name, v));
+        option("residency", self.residency.clone())
+            .into_iter()
+            .chain(option("profile", self.profile.clone()))
+            .chain(Some("compute".to_owned()))
+            .chain(option("year", self.year.map(|year| year.to_string())))
+            .chain(
+                self.outputs
+                    .iter()
+                    .map(|output| format!("--output={}", output)),
+            )
+            .chain(dry_run.then(|| "--dry-run".to_owned()))
+            .chain(self.options.iter().cloned())
+            .chain(documents.iter().cloned())
+            .collect()
+    }
+}
+
+pub fn load_jobs(path: &str) -> Result<Vec<Job>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Error reading job file {}: {}", path, e))?;
+    let file: JobFile = serde_yaml::from_str(&content)
+        .map_err(|e| format!("Error parsing job file {}: {}", path, e))?;
+    match file.jobs.is_empty() {
+        true => Err(format!("No jobs in job file {}", path)),
+        false => Ok(file.jobs),
+    }
+}
+
+/// Documents matching patterns in order of patterns, relative to given
+/// directory. Pattern matching nothing is an error, as client's statement
+/// would be missing
+fn documents(patterns: &[String], dir: &Path) -> Result<Vec<String>, String> {
+    let mut documents = vec![];
+    for pattern in patterns {
+        let paths = glob::glob(&dir.join(pattern).to_string_lossy())
+            .map_err(|e| format!("Invalid pattern {}: {}", pattern, e))?;
+        let matched: Vec<String> = paths
+            .filter_map(|path| path.ok())
+            .map(|path| {
+                path.strip_prefix(dir)
+                    .map(|relative| relative.to_path_buf())
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        if matched.is_empty() {
+            return Err(format!("No documents match {}", pattern));
+        }
+        documents.extend(matched);
+    }
+    Ok(documents)
+}
+
+// Name of job and exit code of its run, or reason it was not run
+type Outcome = (String, Result<i32, String>);
+
+fn describe(code: i32) -> &'static str {
+    match code {
+        EXIT_OK => "ok",
+        EXIT_PARTIAL_PARSE => "some documents could not be parsed and were skipped",
+        EXIT_NETWORK => "network failure",
+        EXIT_VALIDATION => "invalid options or input files",
+        EXIT_INTERRUPTED => "interrupted",
+        _ => "failed",
+    }
+}
+
+/// Names of jobs that succeeded in earlier run of job file
+fn load_checkpoint(path: &str) -> Result<BTreeSet<String>, String> {
+    match std::fs::read(path) {
+        Ok(content) => serde_json::from_slice(&content)
+            .map_err(|e| format!("Error parsing checkpoint {}: {}", path, e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeSet::new()),
+        Err(e) => Err(format!("Error reading checkpoint {}: {}", path, e)),
+    }
+}
+
+/// Run each job of job file as separate process of this program, in directory
+/// of job file, so data of one job does not leak into another. Succeeded jobs
+/// are recorded in checkpoint next to job file, so they are skipped when
+/// resumed. Ctrl-C interrupts current job and skips remaining ones. Jobs
+/// print log records in the given --log-format, as batch does
+pub fn run_jobs(
+    path: &str,
+    dry_run: bool,
+    resume: bool,
+    log_format: &str,
+) -> Result<Vec<Outcome>, String> {
+    let jobs = load_jobs(path)?;
+    let checkpoint = format!("{}.checkpoint", path);
+    let mut succeeded = match resume {
+        true => load_checkpoint(&checkpoint)?,
+        false => BTreeSet::new(),
+    };
+    let dir = match Path::new(path).parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let dir = std::fs::canonicalize(&dir)
+        .map_err(|e| format!("Error opening directory {}: {}", dir.display(), e))?;
+    let program = std::env::current_exe().map_err(|e| e.to_string())?;
+    let interrupted = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGINT, interrupted.clone())
+        .map_err(|e| e.to_string())?;
+
+    let mut outcomes = vec![];
+    for (i, job) in jobs.iter().enumerate() {
+        let name = job.name.clone().unwrap_or(format!("job {}", i + 1));
+        if interrupted.load(std::sync::atomic::Ordering::Relaxed) {
+            outcomes.push((name, Err("not run, batch interrupted".to_owned())));
+            continue;
+        }
+        if succeeded.contains(&name) {
+            println!(
+                "===> {} ({} of {}) succeeded in earlier run, skipped",
+                name,
+                i + 1,
+                jobs.len()
+            );
+            outcomes.push((name, Ok(EXIT_OK)));
+            continue;
+        }
+        println!("===> {} ({} of {})", name, i + 1, jobs.len());
+        use std::io::Write;
+        let _ = std::io::stdout().flush();
+        let started = std::time::Instant::now();
+        let outcome = documents(&job.inputs, &dir).and_then(|documents| {
+            let args = job.args(&documents, dry_run);
+            log::info!("Running job {}: {:?}", name, args);
+            std::process::Command::new(&program)
+                .arg(format!("--log-format={}", log_format))
+                .args(&args)
+                .current_dir(&dir)
+                .status()
+                .map_err(|e| format!("Error running {}: {}", program.display(), e))
+                .map(|status| status.code().unwrap_or(EXIT_INTERRUPTED))
+        });
+        let error = match &outcome {
+            Ok(EXIT_OK) => None,
+            Ok(code) => Some(format!("exit code {}", code)),
+            Err(reason) => Some(reason.clone()),
+        };
+        crate::logging::event("job", Some(&name), started, error.as_deref());
+        if outcome == Ok(EXIT_INTERRUPTED) {
+            interrupted.store(true, std::sync::atomic::Ordering::Relaxed);
+        }
+        if outcome == Ok(EXIT_OK) && !dry_run {
+            succeeded.insert(name.clone());
+            let content = serde_json::to_vec(&succeeded).map_err(|e| e.to_string())?;
+            etradeTaxReturnHelper::write_file(&checkpoint, &content)?;
+        }
+        outcomes.push((name, outcome));
+    }
+    if outcomes.iter().all(|(_, outcome)| outcome == &Ok(EXIT_OK)) && !dry_run {
+        match std::fs::remove_file(&checkpoint) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                log::warn!("Checkpoint {} not removed: {}", checkpoint, e)
+            }
+            _ => (),
+        }
+    }
+    Ok(outcomes)
+}
+
+/// Consolidated report of jobs, returns number of failed ones
+pub fn present(path: &str, outcomes: &[Outcome]) -> usize {
+    let failed = outcomes
+        .iter()
+        .filter(|(_, outcome)| outcome != &Ok(EXIT_OK))
+        .count();
+    println!(
+        "===> BATCH {}: {} of {} jobs succeeded",
+        path,
+        outcomes.len() - failed,
+        outcomes.len()
+    );
+    outcomes.iter().for_each(|(name, outcome)| match outcome {
+        Ok(EXIT_OK) => println!("     {}: ok", name),
+        Ok(code) => println!("     {}: {} (exit code {})", name, describe(*code), code),
+        Err(reason) => println!("     {}: {}", name, reason),
+    });
+    failed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jobs() -> Result<(), String> {
+        let dir = std::env::temp_dir().join(format!("etrade_batch_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("kowalski")).map_err(|e| e.to_string())?;
+        ["Brokerage Statement - XXXX0848 - 202302.pdf", "notes.txt"]
+            .iter()
+            .try_for_each(|name| std::fs::write(dir.join("kowalski").join(name), ""))
+            .map_err(|e| e.to_string())?;
+        let path = dir.join("jobs.yaml");
+        std::fs::write(
+            &path,
+            "jobs:\n  - name: kowalski-2023\n    profile: kowalski\n    inputs: [\"kowalski/*.pdf\"]\n    year: 2023\n    outputs: [kowalski-2023.pdf]\n    options: [\"--lang\", \"en\"]\n  - inputs: [nowak/*.pdf]\n",
+        )
+        .map_err(|e| e.to_string())?;
+        let jobs = load_jobs(&path.to_string_lossy())?;
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[1].name, None);
+
+        let dir = std::fs::canonicalize(&dir).map_err(|e| e.to_string())?;
+        let documents = documents(&jobs[0].inputs, &dir)?;
+        let document = Path::new("kowalski")
+            .join("Brokerage Statement - XXXX0848 - 202302.pdf")
+            .to_string_lossy()
+            .into_owned();
+        assert_eq!(documents, vec![document.clone()]);
+        assert_eq!(
+            jobs[0].args(&documents, true),
+            vec![
+                "--profile=kowalski",
+                "compute",
+                "--year=2023",
+                "--output=kowalski-2023.pdf",
+                "--dry-run",
+                "--lang",
+                "en",
+                &document
+            ]
+        );
+        assert_eq!(
+            super::documents(&jobs[1].inputs, &dir),
+            Err("No documents match nowak/*.pdf".to_owned())
+        );
+
+        std::fs::write(
+            &path,
+            "jobs:\n  - inputs: [\"*.pdf\"]\n    broker: schwab\n",
+        )
+        .map_err(|e| e.to_string())?;
+        assert!(load_jobs(&path.to_string_lossy()).is_err());
+        std::fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Clipboard programs tried in order as (program, arguments)
+#[cfg(target_os = "windows")]
+const TOOLS: &[(&str, &[&str])] = &[("clip", &[])];
+#[cfg(target_os = "macos")]
+const TOOLS: &[(&str, &[&str])] = &[("pbcopy", &[])];
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const TOOLS: &[(&str, &[&str])] = &[
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+
+fn copy_with(program: &str, args: &[&str], text: &str) -> Result<(), String> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("{}: {}", program, e))?;
+    child
+        .stdin
+        .take()
+        .ok_or(format!("{}: no standard input", program))?
+        .write_all(text.as_bytes())
+        .map_err(|e| format!("{}: {}", program, e))?;
+    match child.wait().map_err(|e| format!("{}: {}", program, e))? {
+        status if status.success() => Ok(()),
+        status => Err(format!("{}: {}", program, status)),
+    }
+}
+
+/// Place text on system clipboard using first available clipboard program
+/// (clip on Windows, pbcopy on macOS, wl-copy, xclip or xsel on Linux)
+pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    let mut errors: Vec<String> = vec![];
+    for (program, args) in TOOLS {
+        match copy_with(program, args, text) {
+            Ok(()) => {
+                log::info!("Copied to clipboard with {}", program);
+                return Ok(());
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    Err(format!(
+        "Error: unable to copy to clipboard ({})",
+        errors.join(", ")
+    ))
+}
@@ -0,0 +1,418 @@
+use serde::Deserialize;
+
+/// Defaults of command line options read from TOML file e.g.
+///     residency = "pl"
+///     year = 2023
+///     lang = "en"
+///     sheltered_accounts = ["0848"]
+///     proxy = "http://proxy.example.com:911"
+/// Options given on command line take precedence over the ones from file
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub residency: Option<String>,
+    pub year: Option<i32>,
+    pub lang: Option<String>,
+    pub locale: Option<String>,
+    pub account: Option<String>,
+    // Tax-sheltered accounts, excluded from taxation
+    #[serde(default)]
+    pub sheltered_accounts: Vec<String>,
+    // Used for exchange rate requests when http_proxy and https_proxy are not set
+    pub proxy: Option<String>,
+}
+
+/// etradetaxhelper directory under $XDG_CONFIG_HOME if set, otherwise under
+/// %APPDATA% on Windows, ~/Library/Application Support on macOS and ~/.config
+/// elsewhere
+fn config_dir() -> Option<std::path::PathBuf> {
+    let var = |name: &str| match std::env::var(name) {
+        Ok(value) if !value.is_empty() => Some(std::path::PathBuf::from(value)),
+        _ => None,
+    };
+    let config_home = match var("XDG_CONFIG_HOME") {
+        Some(config_home) => config_home,
+        None if cfg!(windows) => var("APPDATA")?,
+        None if cfg!(target_os = "macos") => {
+            var("HOME")?.join("Library").join("Application Support")
+        }
+        None => var("HOME")?.join(".config"),
+    };
+    Some(config_home.join("etradetaxhelper"))
+}
+
+fn default_config_path() -> Option<std::path::PathBuf> {
+    Some(config_dir()?.join("config.toml"))
+}
+
+/// Directory parser plugins are discovered in, unless given with --plugins
+pub fn default_plugins_dir() -> Option<std::path::PathBuf> {
+    Some(config_dir()?.join("plugins"))
+}
+
+/// File corrections of parsed documents are kept in, unless given with
+/// --corrections or profile is used
+pub fn default_corrections_path() -> Option<std::path::PathBuf> {
+    Some(config_dir()?.join("corrections.json"))
+}
+
+pub fn validate_profile(name: String) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_' || c == '.');
+    match valid {
+        true => Ok(()),
+        false => Err(format!(
+            "Profile name {} should be made of letters, digits, -, _ and . e.g. kowalski",
+            name
+        )),
+    }
+}
+
+/// Client (e.g. of accountant) with its own config.toml, ledger, database and
+/// output directory, all kept in profiles/<name> next to default config file
+#[derive(Debug, PartialEq)]
+pub struct Profile {
+    pub name: String,
+    pub dir: std::path::PathBuf,
+}
+
+impl Profile {
+    pub fn open(name: &str) -> Result<Profile, String> {
+        validate_profile(name.to_owned())?;
+        let dir = config_dir()
+            .ok_or("Directory of profiles not found, set HOME or XDG_CONFIG_HOME".to_owned())?
+            .join("profiles")
+            .join(name);
+        Ok(Profile {
+            name: name.to_owned(),
+            dir,
+        })
+    }
+
+    pub fn config_path(&self) -> std::path::PathBuf {
+        self.dir.join("config.toml")
+    }
+
+    fn resolve(&self, dir: std::path::PathBuf, path: &str) -> String {
+        match path == "-" || std::path::Path::new(path).is_absolute() {
+            true => path.to_owned(),
+            false => dir.join(path).to_string_lossy().into_owned(),
+        }
+    }
+
+    /// Report written for client: relative path is placed in output directory
+    /// of profile, standard output (-) and absolute path are kept
+    pub fn output(&self, path: &str) -> String {
+        self.resolve(self.dir.join("output"), path)
+    }
+
+    /// Ledger or database of client: relative path is placed in profile directory
+    pub fn record(&self, path: &str) -> String {
+        self.resolve(self.dir.clone(), path)
+    }
+
+    /// Ledger figures are appended to unless other one is given with --ledger
+    pub fn ledger(&self) -> String {
+        self.record("ledger.csv")
+    }
+
+    /// Create directories of profile, returns false if they already exist
+    pub fn create(&self) -> Result<bool, String> {
+        let output = self.dir.join("output");
+        let created = !output.exists();
+        std::fs::create_dir_all(&output)
+            .map_err(|e| format!("Error creating directory {}: {}", output.display(), e))?;
+        Ok(created)
+    }
+}
+
+pub fn load_config(path: &str) -> Result<Config, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Error reading config file {}: {}", path, e))?;
+    // Notepad of older Windows saves UTF-8 files with byte order mark
+    toml::from_str(content.trim_start_matches('\u{feff}'))
+        .map_err(|e| format!("Error parsing config file {}: {}", path, e))
+}
+
+/// Value of option given as --option value or --option=value, None inside if
+/// value is missing
+fn given_value(args: &[String], option: &str) -> Option<Option<String>> {
+    let flag = format!("--{}", option);
+    args.iter()
+        .enumerate()
+        .find_map(|(i, arg)| match arg == &flag {
+            true => Some(args.get(i + 1).cloned()),
+            false => arg
+                .strip_prefix(&(flag.clone() + "="))
+                .map(|value| Some(value.to_owned())),
+        })
+}
+
+fn is_given(args: &[String], option: &str) -> bool {
+    let flag = format!("--{}", option);
+    args.iter()
+        .any(|arg| arg == &flag || arg.starts_with(&(flag.clone() + "=")))
+}
+
+// Subcommands taking documents, so year and accounts apply to them
+const DOCUMENT_SUBCOMMANDS: [&str; 5] = ["compute", "import", "report", "validate", "watch"];
+const OTHER_SUBCOMMANDS: [&str; 8] = [
+    "simulate",
+    "forecast",
+    "interest",
+    "diff",
+    "rates",
+    "serve",
+    "batch",
+    "self-update",
+];
+
+impl Config {
+    /// Command line with options from config inserted, unless already given.
+    /// Global options follow program name and options of documents follow
+    /// subcommand, if any
+    pub fn apply(&self, args: Vec<String>) -> Vec<String> {
+        let option = |option: &str, value: &Option<String>| match value {
+            Some(value) if !is_given(&args, option) => {
+                vec![format!("--{}={}", option, value)]
+            }
+            _ => vec![],
+        };
+        let global: Vec<String> = [
+            option("residency", &self.residency),
+            option("lang", &self.lang),
+            option("locale", &self.locale),
+        ]
+        .concat();
+        let subcommand = args.iter().position(|arg| {
+            DOCUMENT_SUBCOMMANDS.contains(&arg.as_str())
+                || OTHER_SUBCOMMANDS.contains(&arg.as_str())
+        });
+        let name = subcommand.map(|i| args[i].as_str());
+        let mut documents: Vec<String> = vec![];
+        if name.is_none_or(|name| DOCUMENT_SUBCOMMANDS.contains(&name)) {
+            documents.extend(option("year", &self.year.map(|year| year.to_string())));
+            if name != Some("validate") {
+                documents.extend(option("account", &self.account));
+            }
+            if !is_given(&args, "sheltered-account") {
+                self.sheltered_accounts.iter().for_each(|account| {
+                    documents.push(format!("--sheltered-account={}", account));
+                });
+            }
+        }
+        let (program, rest) = args.split_at(args.len().min(1));
+        let (before, after) = rest.split_at(subcommand.map_or(0, |i| i));
+        program
+            .iter()
+            .cloned()
+            .chain(global)
+            .chain(before.iter().cloned())
+            .chain(documents)
+            .chain(after.iter().cloned())
+            .collect()
+    }
+}
+
+/// Command line with defaults from config file given with --config, config
+/// file of --profile or the one found at default location. Command line
+/// without arguments is left as is, so help is printed
+pub fn with_config_defaults(args: Vec<String>) -> Result<Vec<String>, String> {
+    if args.len() <= 1 {
+        return Ok(args);
+    }
+    let config_path = match given_value(&args, "profile") {
+        Some(Some(name)) => Some(Profile::open(&name)?.config_path()),
+        _ => default_config_path(),
+    };
+    let config = match given_value(&args, "config") {
+        Some(Some(path)) => load_config(&path)?,
+        // Missing value is reported by argument parser
+        Some(None) => return Ok(args),
+        None => match config_path {
+            Some(path) if path.exists() => load_config(&path.to_string_lossy())?,
+            _ => return Ok(args),
+        },
+    };
+    log::info!("Defaults from config file: {:?}", config);
+    if let Some(proxy) = &config.proxy {
+        ["http_proxy", "https_proxy"].iter().for_each(|variable| {
+            if std::env::var(variable).is_err() {
+                std::env::set_var(variable, proxy);
+            }
+        });
+    }
+    Ok(config.apply(args))
+}
+
+/// Language of given locale name e.g. pl_PL.UTF-8, English unless Polish
+fn lang_of_locale(locale: &str) -> etradeTaxReturnHelper::Lang {
+    match locale.starts_with("pl") {
+        true => etradeTaxReturnHelper::Lang::Pl,
+        false => etradeTaxReturnHelper::Lang::En,
+    }
+}
+
+/// Language of help and error messages: --lang (also from config file) if
+/// given, otherwise language of system locale (LC_ALL, LC_MESSAGES or LANG)
+pub fn help_lang(args: &[String]) -> etradeTaxReturnHelper::Lang {
+    if let Some(Some(name)) = given_value(args, "lang") {
+        if let Ok(lang) = etradeTaxReturnHelper::Lang::from_name(&name) {
+            return lang;
+        }
+    }
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .find_map(|variable| std::env::var(variable).ok().filter(|v| !v.is_empty()));
+    lang_of_locale(&locale.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config() -> Result<(), String> {
+        let config: Config = toml::from_str(
+            "residency = \"pl\"\nyear = 2023\nlang = \"en\"\nsheltered_accounts = [\"0848\", \"1234\"]\n",
+        )
+        .map_err(|e| e.to_string())?;
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<String>>();
+        assert_eq!(
+            config.apply(args(&[
+                "etradeTaxReturnHelper",
+                "--year=2022",
+                "data/example.pdf"
+            ])),
+            args(&[
+                "etradeTaxReturnHelper",
+                "--residency=pl",
+                "--lang=en",
+                "--sheltered-account=0848",
+                "--sheltered-account=1234",
+                "--year=2022",
+                "data/example.pdf"
+            ])
+        );
+        assert_eq!(
+            config.apply(args(&[
+                "etradeTaxReturnHelper",
+                "--residency",
+                "us",
+                "--lang=pl",
+                "--sheltered-account",
+                "0001",
+                "data/example.pdf"
+            ])),
+            args(&[
+                "etradeTaxReturnHelper",
+                "--year=2023",
+                "--residency",
+                "us",
+                "--lang=pl",
+                "--sheltered-account",
+                "0001",
+                "data/example.pdf"
+            ])
+        );
+        assert_eq!(
+            config.apply(args(&[
+                "etradeTaxReturnHelper",
+                "--lang=pl",
+                "validate",
+                "data/example.pdf"
+            ])),
+            args(&[
+                "etradeTaxReturnHelper",
+                "--residency=pl",
+                "--lang=pl",
+                "validate",
+                "--year=2023",
+                "--sheltered-account=0848",
+                "--sheltered-account=1234",
+                "data/example.pdf"
+            ])
+        );
+        // Options of documents are not accepted by other subcommands
+        assert_eq!(
+            config.apply(args(&["etradeTaxReturnHelper", "rates", "2023-03-01"])),
+            args(&[
+                "etradeTaxReturnHelper",
+                "--residency=pl",
+                "--lang=en",
+                "rates",
+                "2023-03-01"
+            ])
+        );
+        assert!(toml::from_str::<Config>("broker = \"schwab\"\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_profile() -> Result<(), String> {
+        let dir = std::path::PathBuf::from("/home/accountant/.config/etradetaxhelper")
+            .join("profiles")
+            .join("kowalski");
+        let profile = Profile {
+            name: "kowalski".to_owned(),
+            dir: dir.clone(),
+        };
+        assert_eq!(
+            profile.output("2023.pdf"),
+            dir.join("output").join("2023.pdf").to_string_lossy()
+        );
+        assert_eq!(profile.output("-"), "-");
+        assert_eq!(profile.ledger(), dir.join("ledger.csv").to_string_lossy());
+        let absolute = std::env::temp_dir().join("2023.db");
+        assert_eq!(
+            profile.record(&absolute.to_string_lossy()),
+            absolute.to_string_lossy()
+        );
+        assert_eq!(profile.config_path(), dir.join("config.toml"));
+
+        assert!(validate_profile("kowalski-2023".to_owned()).is_ok());
+        assert!(validate_profile("../kowalski".to_owned()).is_err());
+        assert!(validate_profile(".".to_owned()).is_err());
+        assert!(validate_profile("".to_owned()).is_err());
+
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<String>>();
+        assert_eq!(
+            given_value(
+                &args(&["etradeTaxReturnHelper", "--profile=kowalski"]),
+                "profile"
+            ),
+            Some(Some("kowalski".to_owned()))
+        );
+        assert_eq!(
+            given_value(&args(&["etradeTaxReturnHelper", "--profile"]), "profile"),
+            Some(None)
+        );
+        assert_eq!(
+            given_value(&args(&["etradeTaxReturnHelper"]), "profile"),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_help_lang() -> Result<(), String> {
+        use etradeTaxReturnHelper::Lang;
+        assert_eq!(lang_of_locale("pl_PL.UTF-8"), Lang::Pl);
+        assert_eq!(lang_of_locale("en_US.UTF-8"), Lang::En);
+        assert_eq!(lang_of_locale("C"), Lang::En);
+        assert_eq!(lang_of_locale(""), Lang::En);
+        let args = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<String>>();
+        assert_eq!(
+            help_lang(&args(&["etradeTaxReturnHelper", "--lang", "pl", "--help"])),
+            Lang::Pl
+        );
+        assert_eq!(
+            help_lang(&args(&["etradeTaxReturnHelper", "--lang=en", "--help"])),
+            Lang::En
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use serde::Deserialize;
+
+use etradeTaxReturnHelper::Pit38Schema;
+
+/// Identification data of taxpayer filing PIT-38, read from TOML file e.g.
+///     pesel = "80013112345"
+///     first_name = "Jan"
+///     last_name = "Kowalski"
+///     birth_date = "1980-01-31"
+///     tax_office = "1471"
+/// Either pesel or nip is to be given. tax_office is four digit code of tax office
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct Taxpayer {
+    pub nip: Option<String>,
+    pub pesel: Option<String>,
+    pub first_name: String,
+    pub last_name: String,
+    pub birth_date: String,
+    pub tax_office: String,
+}
+
+pub fn load_taxpayer(path: &str) -> Result<Taxpayer, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Error reading taxpayer file {}: {}", path, e))?;
+    let taxpayer: Taxpayer =
+        toml::from_str(&content).map_err(|e| format!("Error parsing taxpayer file: {}", e))?;
+    if taxpayer.nip.is_none() && taxpayer.pesel.is_none() {
+        return Err(format!("Error: neither pesel nor nip given in {}", path));
+    }
+    Ok(taxpayer)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// e-Deklaracje XML document of PIT-38 filled with given boxes (poz.) values.
+/// Tax bases, rate and tax due boxes are whole numbers, other amounts are in grosz
+pub fn pit38_xml(
+    year: i32,
+    taxpayer: &Taxpayer,
+    schema: &Pit38Schema,
+    fields: &[(u32, f32)],
+) -> String {
+    const WHOLE_PLN_FIELDS: [u32; 4] = [29, 30, 33, 47];
+    let identifier = match (&taxpayer.pesel, &taxpayer.nip) {
+        (Some(pesel), _) => format!("<etd:PESEL>{}</etd:PESEL>", escape(pesel)),
+        (None, Some(nip)) => format!("<etd:NIP>{}</etd:NIP>", escape(nip)),
+        (None, None) => String::new(),
+    };
+    let positions: String = fields
+        .iter()
+        .filter(|(_, value)| *value != 0.0)
+        .map(|(field, value)| match WHOLE_PLN_FIELDS.contains(field) {
+            true => format!("    <P_{}>{:.0}</P_{}>\n", field, value, field),
+            false => format!("    <P_{}>{:.2}</P_{}>\n", field, value, field),
+        })
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+<Deklaracja xmlns=\"{ns}\" xmlns:etd=\"{etd}\">\n\
+  <Naglowek>\n\
+    <KodFormularza kodSystemowy=\"PIT-38 ({v})\" kodPodatku=\"PIT\" rodzajZobowiazania=\"Z\" wersjaSchemy=\"1-0E\">PIT-38</KodFormularza>\n\
+    <WariantFormularza>{v}</WariantFormularza>\n\
+    <CelZlozenia poz=\"P_6\">1</CelZlozenia>\n\
+    <Rok>{year}</Rok>\n\
+    <KodUrzedu>{office}</KodUrzedu>\n\
+  </Naglowek>\n\
+  <Podmiot1 rola=\"Podatnik\">\n\
+    <OsobaFizyczna>\n\
+      {id}\n\
+      <etd:ImiePierwsze>{first}</etd:ImiePierwsze>\n\
+      <etd:Nazwisko>{last}</etd:Nazwisko>\n\
+      <etd:DataUrodzenia>{birth}</etd:DataUrodzenia>\n\
+    </OsobaFizyczna>\n\
+  </Podmiot1>\n\
+  <PozycjeSzczegolowe>\n\
+{positions}\
+  </PozycjeSzczegolowe>\n\
+  <Pouczenia>1</Pouczenia>\n\
+</Deklaracja>\n",
+        ns = escape(&schema.namespace),
+        etd = escape(&schema.types_namespace),
+        v = schema.variant,
+        year = year,
+        office = escape(&taxpayer.tax_office),
+        id = identifier,
+        first = escape(&taxpayer.first_name),
+        last = escape(&taxpayer.last_name),
+        birth = escape(&taxpayer.birth_date),
+        positions = positions
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pit38_xml() -> Result<(), String> {
+        let taxpayer: Taxpayer = toml::from_str(
+            "pesel = \"80013112345\"\nfirst_name = \"Jan\"\nlast_name = \"Kowalski\"\n\
+             birth_date = \"1980-01-31\"\ntax_office = \"1471\"\n",
+        )
+        .map_err(|e| e.to_string())?;
+        let schema = Pit38Schema {
+            variant: 17,
+            namespace: "http://example.com/pit38/".to_string(),
+            types_namespace: "http://example.com/etd/".to_string(),
+        };
+        let xml = pit38_xml(
+            2023,
+            &taxpayer,
+            &schema,
+            &[(20, 0.0), (22, 3000.5), (29, 501.0), (47, 40.0)],
+        );
+        assert!(xml.contains("kodSystemowy=\"PIT-38 (17)\""));
+        assert!(xml.contains("<Rok>2023</Rok>"));
+        assert!(xml.contains("<etd:PESEL>80013112345</etd:PESEL>"));
+        assert!(xml.contains("<P_22>3000.50</P_22>"));
+        assert!(xml.contains("<P_29>501</P_29>"));
+        assert!(xml.contains("<P_47>40</P_47>"));
+        assert!(!xml.contains("<P_20>"));
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+use std::io::Write;
+
+/// Consumer secret of API key: ETRADE_CONSUMER_SECRET environment variable or
+/// keychain, where it is stored with: login etrade <consumer key>
+fn consumer_secret(consumer_key: &str) -> Result<String, String> {
+    match std::env::var("ETRADE_CONSUMER_SECRET") {
+        Ok(secret) if !secret.is_empty() => Ok(secret),
+        _ => etradeTaxReturnHelper::load_secret("etrade", consumer_key)?.ok_or(format!(
+            "no consumer secret of {}, store it with: etradeTaxReturnHelper login etrade {}",
+            consumer_key, consumer_key
+        )),
+    }
+}
+
+/// Verification code user is given after logging in at authorization URL
+fn verifier(url: &str) -> Result<String, String> {
+    eprintln!(
+        "To allow reading transactions open {} , log in to E*TRADE and enter verification code:",
+        url
+    );
+    let _ = std::io::stderr().flush();
+    let mut code = String::new();
+    std::io::stdin()
+        .read_line(&mut code)
+        .map_err(|e| e.to_string())?;
+    match code.trim() {
+        "" => Err("no verification code given".to_owned()),
+        code => Ok(code.to_owned()),
+    }
+}
+
+/// Write dividends, interest and sales of E*TRADE accounts between dates to
+/// JSON statement, to be given as financial document
+pub fn import(
+    consumer_key: &str,
+    sandbox: bool,
+    accounts: &[String],
+    (from, to): (chrono::NaiveDate, chrono::NaiveDate),
+    output: &str,
+) -> Result<(), String> {
+    let secret = consumer_secret(consumer_key)?;
+    let statement = etradeTaxReturnHelper::import_etrade(
+        (consumer_key, &secret),
+        sandbox,
+        accounts,
+        from,
+        to,
+        &verifier,
+    )?;
+    let content = serde_json::to_string_pretty(&statement).map_err(|e| e.to_string())?;
+    std::fs::write(output, content).map_err(|e| format!("Error writing {}: {}", output, e))?;
+    println!(
+        "{} dividends, {} interest payments and {} sales written to {}",
+        statement.dividends.len(),
+        statement.interest.len(),
+        statement.sold.len(),
+        output
+    );
+    log::info!("E*TRADE transactions written to {}", output);
+    Ok(())
+}
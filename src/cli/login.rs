@@ -0,0 +1,46 @@
+use std::io::IsTerminal;
+
+/// Secret typed without echo on terminal, or first line of standard input
+/// e.g. when piped from password manager
+fn read_secret(prompt: &str) -> Result<String, String> {
+    let secret = match std::io::stdin().is_terminal() {
+        true => rpassword::prompt_password(prompt).map_err(|e| e.to_string())?,
+        false => {
+            let mut line = String::new();
+            std::io::stdin()
+                .read_line(&mut line)
+                .map_err(|e| e.to_string())?;
+            line.trim_end_matches(['\r', '\n']).to_owned()
+        }
+    };
+    match secret.is_empty() {
+        true => Err("no secret given".to_owned()),
+        false => Ok(secret),
+    }
+}
+
+/// Store secret of user of service in keychain, replacing earlier one
+pub fn login(service: &str, user: &str) -> Result<(), String> {
+    let secret = read_secret(&format!("Password of {} at {}: ", user, service))?;
+    etradeTaxReturnHelper::store_secret(service, user, &secret)?;
+    println!("Password of {} at {} stored in keychain", user, service);
+    log::info!("Secret of {} at {} stored in keychain", user, service);
+    Ok(())
+}
+
+/// Remove secret of user of service and its cached access token from keychain
+pub fn logout(service: &str, user: &str) -> Result<(), String> {
+    match etradeTaxReturnHelper::delete_secret(service, user)? {
+        true => println!("Password of {} at {} removed from keychain", user, service),
+        false => println!("No password of {} at {} in keychain", user, service),
+    }
+    // Access token of service cached once authorized, see oauth module
+    if etradeTaxReturnHelper::delete_secret(&format!("{}-token", service), user)? {
+        println!(
+            "Access token of {} at {} removed from keychain",
+            user, service
+        );
+    }
+    log::info!("Secret of {} at {} removed from keychain", user, service);
+    Ok(())
+}
@@ -0,0 +1,269 @@
+use clap::App;
+
+// Sections of man page not found in help of options
+const ENVIRONMENT: [(&str, &str); 6] = [
+    ("NO_COLOR", "Output is not colored when set"),
+    (
+        "RUST_LOG",
+        "Level of log messages printed to standard error e.g. info, overriding -v and -q",
+    ),
+    (
+        "http_proxy, https_proxy",
+        "Proxies of requests to exchange rate services",
+    ),
+    (
+        "NBP_API_URL",
+        "NBP API server exchange rates are requested from (default https://api.nbp.pl/api)",
+    ),
+    ("SMTP_PASSWORD", "Password of SMTP server used by --email"),
+    (
+        "XDG_DATA_HOME",
+        "Directory the log file is kept in (default ~/.local/share)",
+    ),
+];
+
+const FILES: [(&str, &str); 3] = [
+    (
+        "~/.config/etradetaxhelper/config.toml",
+        "Defaults of options, see --config",
+    ),
+    (
+        "~/.config/etradetaxhelper/profiles/NAME/",
+        "Config, ledger and output directory of client profile, see --profile",
+    ),
+    (
+        "~/.local/share/etradetaxhelper/etradeTaxHelper.log",
+        "Log of runs, rotated when larger than 1 MB",
+    ),
+];
+
+/// Text escaped for roff: hyphens of options are kept as they are typed and
+/// lines starting with dot are not taken as requests
+fn escape(text: &str) -> String {
+    let escaped = text.replace('\\', "\\e").replace('-', "\\-");
+    match escaped.starts_with('.') || escaped.starts_with('\'') {
+        true => format!("\\&{}", escaped),
+        false => escaped,
+    }
+}
+
+/// Help printed by clap for given arguments e.g. [program, "compute", "--help"],
+/// without wrapping of lines
+fn help(app: App<'static, 'static>, args: &[&str]) -> String {
+    match app.set_term_width(0).get_matches_from_safe(args) {
+        Err(e) => e.message,
+        Ok(_) => String::new(),
+    }
+}
+
+/// Lines of each section of help keyed by its title e.g. OPTIONS. Lines
+/// before the first title (name and about) are under empty title
+fn sections(help: &str) -> Vec<(String, Vec<String>)> {
+    let mut sections = vec![(String::new(), vec![])];
+    for line in help.lines() {
+        match line.strip_suffix(':') {
+            Some(title) if !line.starts_with(' ') && !title.is_empty() => {
+                sections.push((title.to_owned(), vec![]))
+            }
+            _ => {
+                if let Some((_, lines)) = sections.last_mut() {
+                    lines.push(line.to_owned());
+                }
+            }
+        }
+    }
+    sections
+}
+
+fn section<'s>(sections: &'s [(String, Vec<String>)], title: &str) -> &'s [String] {
+    sections
+        .iter()
+        .find(|(t, _)| t == title)
+        .map_or(&[], |(_, lines)| lines.as_slice())
+}
+
+/// (head e.g. "--year <YEAR>", lines of help) of each entry of section.
+/// Entries are indented by 4 or 8 spaces, lines of their help are indented
+/// more, as clap prints them
+fn entries(lines: &[String]) -> Vec<(String, Vec<String>)> {
+    let mut entries: Vec<(String, Vec<String>)> = vec![];
+    for line in lines.iter().filter(|line| !line.trim().is_empty()) {
+        let indent = line.len() - line.trim_start().len();
+        match (indent <= 8, entries.last_mut()) {
+            (false, Some((_, help))) => help.push(line.trim().to_owned()),
+            _ => {
+                let entry = line.trim();
+                let (head, help) = entry.split_once("  ").unwrap_or((entry, ""));
+                let help = match help.trim() {
+                    "" => vec![],
+                    help => vec![help.to_owned()],
+                };
+                entries.push((head.to_owned(), help));
+            }
+        }
+    }
+    entries
+}
+
+/// Entry as tagged paragraph: options in bold, their values as typed
+fn paragraph(head: &str, help: &[String]) -> String {
+    let head = head
+        .split(' ')
+        .map(|word| match word.starts_with('-') {
+            true => format!("\\fB{}\\fR", escape(word)),
+            false => escape(word),
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+    let help = help
+        .iter()
+        .map(|line| escape(line))
+        .collect::<Vec<String>>()
+        .join("\n.br\n");
+    format!(".TP\n{}\n{}\n", head, help)
+}
+
+fn usage(lines: &[String]) -> String {
+    let usage = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| escape(line.trim()))
+        .collect::<Vec<String>>()
+        .join("\n");
+    format!(".nf\n{}\n.fi\n", usage)
+}
+
+/// Man page (roff) of program of given name made of help of its options and
+/// subcommands, e.g. to be installed by packages of Linux distributions
+pub fn man_page(
+    app: impl Fn() -> App<'static, 'static>,
+    name: &str,
+    version: &str,
+    description: &str,
+) -> String {
+    let main = sections(&help(app(), &[name, "--help"]));
+    let mut page = format!(
+        ".TH {} 1 \"\" \"{} {}\" \"User Commands\"\n",
+        name.to_uppercase(),
+        name,
+        version
+    );
+    page += &format!(
+        ".SH NAME\n{} \\- {}\n",
+        name,
+        escape("tax return helper for E*TRADE documents")
+    );
+    page += &format!(".SH SYNOPSIS\n{}", usage(section(&main, "USAGE")));
+    page += &format!(".SH DESCRIPTION\n{}\n", escape(description));
+
+    let options: Vec<(String, Vec<String>)> = entries(section(&main, "FLAGS"))
+        .into_iter()
+        .chain(entries(section(&main, "OPTIONS")))
+        .collect();
+    page += ".SH OPTIONS\n";
+    options
+        .iter()
+        .for_each(|(head, help)| page += &paragraph(head, help));
+    let arguments = entries(section(&main, "ARGS"));
+    page += ".SH ARGUMENTS\n";
+    arguments
+        .iter()
+        .for_each(|(head, help)| page += &paragraph(head, help));
+
+    // Options and arguments of subcommands given at top level too are not repeated
+    page += ".SH SUBCOMMANDS\n";
+    for (subcommand, about) in entries(section(&main, "SUBCOMMANDS")) {
+        if subcommand == "help" {
+            continue;
+        }
+        let sub = sections(&help(app(), &[name, &subcommand, "--help"]));
+        page += &format!(
+            ".SS {}\n{}\n",
+            escape(&subcommand),
+            escape(&about.join(" "))
+        );
+        page += &usage(section(&sub, "USAGE"));
+        entries(section(&sub, "FLAGS"))
+            .into_iter()
+            .chain(entries(section(&sub, "OPTIONS")))
+            .chain(entries(section(&sub, "ARGS")))
+            .filter(|entry| !options.contains(entry) && !arguments.contains(entry))
+            .for_each(|(head, help)| page += &paragraph(&head, &help));
+    }
+
+    page += ".SH ENVIRONMENT\n";
+    ENVIRONMENT
+        .iter()
+        .for_each(|(variable, help)| page += &paragraph(variable, &[help.to_string()]));
+    page += ".SH FILES\n";
+    FILES
+        .iter()
+        .for_each(|(file, help)| page += &paragraph(file, &[help.to_string()]));
+    page
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_man_page() -> Result<(), String> {
+        let page = man_page(
+            || super::super::create_cmd_line_pattern(App::new("etradeTaxHelper")),
+            "etradeTaxReturnHelper",
+            "0.2.11",
+            "Parses etrade financial documents",
+        );
+        assert!(
+            page.starts_with(".TH ETRADETAXRETURNHELPER 1 \"\" \"etradeTaxReturnHelper 0.2.11\"")
+        );
+        assert!(page.contains(".TP\n\\fB\\-\\-year\\fR <YEAR>\nTax year to compute e.g. 2023."));
+        // Explicit line breaks of help are kept
+        assert!(page.contains("e.g. 2023\\-06\\-01:INTC:2:30.5\n.br\nValue of received shares"));
+        assert!(
+            page.contains(".SS simulate\nEstimate tax due of hypothetical sale of held stock\n")
+        );
+        assert!(page.contains(".TP\n\\fB\\-\\-lot\\fR <DATE:QTY:COST>..."));
+        // Options of compute are already given at top level
+        assert_eq!(page.matches("\\fB\\-\\-year\\fR").count(), 1);
+        assert_eq!(page.matches("Brokerage statement PDFs").count(), 1);
+        assert!(page.contains(".SH ENVIRONMENT\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_help_translated() -> Result<(), String> {
+        use etradeTaxReturnHelper::Lang;
+        let app = || super::super::create_cmd_line_pattern(App::new("etradeTaxHelper"));
+        // Help and version flags and help subcommand are described by clap
+        let described = |lang: Lang, args: &[&str]| {
+            super::super::set_help_lang(lang);
+            let help = sections(&help(app(), args));
+            ["FLAGS", "OPTIONS", "ARGS", "SUBCOMMANDS"]
+                .iter()
+                .flat_map(|title| entries(section(&help, title)))
+                .filter(|(head, _)| {
+                    !["help", "-h, --help", "-V, --version"].contains(&head.as_str())
+                })
+                .collect::<Vec<(String, Vec<String>)>>()
+        };
+        let main = described(Lang::En, &["etradeTaxReturnHelper", "--help"]);
+        let mut commands = vec![vec!["etradeTaxReturnHelper", "--help"]];
+        main.iter()
+            .filter(|(head, _)| !head.starts_with('-') && !head.starts_with('<'))
+            .for_each(|(subcommand, _)| {
+                commands.push(vec!["etradeTaxReturnHelper", subcommand, "--help"])
+            });
+        assert!(commands.len() > 10);
+        for args in commands {
+            let english = described(Lang::En, &args);
+            let polish = described(Lang::Pl, &args);
+            assert_eq!(english.len(), polish.len());
+            for ((head, en), (_, pl)) in english.iter().zip(polish.iter()) {
+                assert_ne!(en, pl, "help of {} in {:?} is not translated", head, args);
+            }
+        }
+        super::super::set_help_lang(Lang::En);
+        Ok(())
+    }
+}
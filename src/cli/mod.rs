@@ -0,0 +1,1694 @@
+//! Command line interface: arguments with their validation, and helpers of
+//! the program (clipboard, e-Deklaracje, E*TRADE API import, keychain login,
+//! man page, progress bars, REST API server, watch of directory)
+
+pub mod batch;
+pub mod clipboard;
+pub mod config;
+#[cfg(feature = "edeklaracje")]
+pub mod edeklaracje;
+#[cfg(feature = "broker-api")]
+pub mod etrade;
+pub mod login;
+pub mod man;
+pub mod progress;
+pub mod serve;
+pub mod watch;
+
+use chrono::Datelike;
+use clap::{App, AppSettings, Arg, ArgGroup, SubCommand};
+use etradeTaxReturnHelper::{tr, Lang};
+
+thread_local! {
+    // Language help texts of command line are created in, see set_help_lang
+    static HELP_LANG: std::cell::Cell<Lang> = const { std::cell::Cell::new(Lang::En) };
+}
+
+/// (Polish, English) texts of help and errors printed by clap itself
+const CLAP_TEXTS: [(&str, &str); 18] = [
+    ("UZYCIE:", "USAGE:"),
+    ("FLAGI:", "FLAGS:"),
+    ("OPCJE:", "OPTIONS:"),
+    ("ARGUMENTY:", "ARGS:"),
+    ("PODPOLECENIA:", "SUBCOMMANDS:"),
+    (
+        "Wypisuje te pomoc lub pomoc podanych podpolecen",
+        "Prints this message or the help of the given subcommand(s)",
+    ),
+    ("Wypisuje pomoc", "Prints help information"),
+    ("Wypisuje wersje", "Prints version information"),
+    ("[domyslnie: ", "[default: "),
+    ("[wartosci: ", "[values: "),
+    ("Wiecej informacji:", "For more information try"),
+    ("Niepoprawna wartosc", "Invalid value for"),
+    (
+        "Nie podano wymaganych argumentow:",
+        "The following required arguments were not provided:",
+    ),
+    (
+        "wymaga wartosci, ktorej nie podano",
+        "requires a value but none was supplied",
+    ),
+    ("nie jest poprawna wartoscia", "isn't a valid value for"),
+    (
+        "jest nieoczekiwany lub niepoprawny w tym miejscu",
+        "which wasn't expected, or isn't valid in this context",
+    ),
+    ("Argument", "Found argument"),
+    ("Argument", "The argument"),
+];
+
+/// Language of help texts of command line created afterwards by this thread
+pub fn set_help_lang(lang: Lang) {
+    HELP_LANG.with(|help_lang| help_lang.set(lang));
+}
+
+pub fn help_lang() -> Lang {
+    HELP_LANG.with(|help_lang| help_lang.get())
+}
+
+/// Help text in language of help
+fn h(text: &'static str) -> &'static str {
+    tr(help_lang(), text)
+}
+
+/// Help or error message printed by clap in language of help. Texts of
+/// options are already translated, headings and error prefix are replaced
+pub fn localize(message: &str) -> String {
+    match help_lang() {
+        Lang::En => message.to_owned(),
+        Lang::Pl => CLAP_TEXTS
+            .iter()
+            .fold(message.replace("error:", "blad:"), |message, (pl, en)| {
+                message.replace(en, pl)
+            }),
+    }
+}
+
+pub fn validate_year(year: String) -> Result<(), String> {
+    let current_year = chrono::Local::now().year();
+    match year.parse::<i32>() {
+        Ok(y) if (2000..=current_year).contains(&y) => Ok(()),
+        _ => Err(format!(
+            "{} {}",
+            h("Tax year should be a number between 2000 and"),
+            current_year
+        )),
+    }
+}
+
+/// Withdrawal is given as <YYYY-MM-DD>:<amount in USD> e.g. 2023-05-10:1500.25
+pub fn parse_withdrawal(withdrawal: &str) -> Result<(String, f32), String> {
+    let msg = format!(
+        "{}, {} {}",
+        h("Withdrawal should be given as <YYYY-MM-DD>:<amount in USD>"),
+        h("got:"),
+        withdrawal
+    );
+    let (date, amount) = withdrawal.split_once(':').ok_or(msg.clone())?;
+    let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| msg.clone())?;
+    let amount = amount.parse::<f32>().map_err(|_| msg.clone())?;
+    if amount <= 0.0 {
+        return Err(msg);
+    }
+    Ok((date.format("%m/%d/%y").to_string(), amount))
+}
+
+/// Fees of purchase are given as <YYYY-MM-DD>:<cost in USD>:<fees in USD>
+pub fn parse_purchase_fee(fee: &str) -> Result<(String, f32, f32), String> {
+    let msg = format!(
+        "{}, {} {}",
+        h("Purchase fee should be given as <YYYY-MM-DD>:<cost in USD>:<fees in USD>"),
+        h("got:"),
+        fee
+    );
+    let elements: Vec<&str> = fee.split(':').collect();
+    if elements.len() != 3 {
+        return Err(msg);
+    }
+    let date =
+        chrono::NaiveDate::parse_from_str(elements[0], "%Y-%m-%d").map_err(|_| msg.clone())?;
+    let cost = elements[1].parse::<f32>().map_err(|_| msg.clone())?;
+    let fees = elements[2].parse::<f32>().map_err(|_| msg.clone())?;
+    if cost <= 0.0 || fees < 0.0 {
+        return Err(msg);
+    }
+    Ok((date.format("%m/%d/%y").to_string(), cost, fees))
+}
+
+/// Lot of held stock is given as <YYYY-MM-DD>:<quantity>:<cost basis per share in USD>
+pub fn parse_lot(lot: &str) -> Result<(String, f32, f32), String> {
+    let msg = format!(
+        "{}, {} {}",
+        h("Lot should be given as <YYYY-MM-DD>:<quantity>:<cost basis per share in USD>"),
+        h("got:"),
+        lot
+    );
+    let elements: Vec<&str> = lot.split(':').collect();
+    if elements.len() != 3 {
+        return Err(msg);
+    }
+    let date =
+        chrono::NaiveDate::parse_from_str(elements[0], "%Y-%m-%d").map_err(|_| msg.clone())?;
+    let quantity = elements[1].parse::<f32>().map_err(|_| msg.clone())?;
+    let cost_per_share = elements[2].parse::<f32>().map_err(|_| msg.clone())?;
+    if quantity <= 0.0 || cost_per_share < 0.0 {
+        return Err(msg);
+    }
+    Ok((
+        date.format("%m/%d/%y").to_string(),
+        quantity,
+        cost_per_share,
+    ))
+}
+
+/// Vest is given as <YYYY-MM-DD>:<quantity>
+pub fn parse_vest(vest: &str) -> Result<(String, f32), String> {
+    let msg = format!(
+        "{}, {} {}",
+        h("Vest should be given as <YYYY-MM-DD>:<quantity>"),
+        h("got:"),
+        vest
+    );
+    let (date, quantity) = vest.split_once(':').ok_or(msg.clone())?;
+    let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| msg.clone())?;
+    let quantity = quantity.parse::<f32>().map_err(|_| msg.clone())?;
+    if quantity <= 0.0 {
+        return Err(msg);
+    }
+    Ok((date.format("%m/%d/%y").to_string(), quantity))
+}
+
+/// Dividend paid in shares is given as <YYYY-MM-DD>:<symbol>:<quantity>:<price per share in USD>
+pub fn parse_stock_dividend(dividend: &str) -> Result<(String, String, f32, f32), String> {
+    let msg = format!(
+        "{}, {} {}",
+        h("Stock dividend should be given as <YYYY-MM-DD>:<symbol>:<quantity>:<price per share in USD>"),
+        h("got:"),
+        dividend
+    );
+    let elements: Vec<&str> = dividend.split(':').collect();
+    if elements.len() != 4 || elements[1].is_empty() {
+        return Err(msg);
+    }
+    let date =
+        chrono::NaiveDate::parse_from_str(elements[0], "%Y-%m-%d").map_err(|_| msg.clone())?;
+    let quantity = elements[2].parse::<f32>().map_err(|_| msg.clone())?;
+    let price = elements[3].parse::<f32>().map_err(|_| msg.clone())?;
+    if quantity <= 0.0 || price <= 0.0 {
+        return Err(msg);
+    }
+    Ok((
+        date.format("%m/%d/%y").to_string(),
+        elements[1].to_uppercase(),
+        quantity,
+        price,
+    ))
+}
+
+/// Stock split is given as <YYYY-MM-DD>:<ratio> e.g. 2024-06-10:10 for 10-for-1 split
+pub fn parse_split(split: &str) -> Result<(String, f32), String> {
+    let msg = format!(
+        "{}, {} {}",
+        h("Stock split should be given as <YYYY-MM-DD>:<ratio>"),
+        h("got:"),
+        split
+    );
+    let (date, ratio) = split.split_once(':').ok_or(msg.clone())?;
+    let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| msg.clone())?;
+    let ratio = ratio.parse::<f32>().map_err(|_| msg.clone())?;
+    if ratio <= 0.0 {
+        return Err(msg);
+    }
+    Ok((date.format("%m/%d/%y").to_string(), ratio))
+}
+
+pub fn validate_date(date: String) -> Result<(), String> {
+    chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map(|_| ())
+        .map_err(|_| h("Date should be given as <YYYY-MM-DD>").to_owned())
+}
+
+pub fn validate_price(price: String) -> Result<(), String> {
+    match price.parse::<f32>() {
+        Ok(p) if p > 0.0 => Ok(()),
+        _ => Err(h("Price should be a positive number").to_owned()),
+    }
+}
+
+/// Interval of watch is given in whole seconds
+pub fn validate_interval(interval: String) -> Result<(), String> {
+    match interval.parse::<u64>() {
+        Ok(seconds) if seconds > 0 => Ok(()),
+        _ => Err(h("Interval should be a positive number of seconds").to_owned()),
+    }
+}
+
+/// PIT-8C figures are given as <income>:<cost> in PLN
+pub fn parse_pit8c(pit8c: &str) -> Result<(f32, f32), String> {
+    let msg = format!(
+        "{}, {} {}",
+        h("PIT-8C figures should be given as <income>:<cost> e.g. 10500.20:8200"),
+        h("got:"),
+        pit8c
+    );
+    let (income, cost) = pit8c.split_once(':').ok_or(msg.clone())?;
+    let income = income.parse::<f32>().map_err(|_| msg.clone())?;
+    let cost = cost.parse::<f32>().map_err(|_| msg.clone())?;
+    if income < 0.0 || cost < 0.0 {
+        return Err(msg);
+    }
+    Ok((income, cost))
+}
+
+/// Country of source of security is given as <symbol>:<country code> e.g. VWRL:IE
+pub fn parse_country(country: &str) -> Result<(String, String), String> {
+    let msg = format!(
+        "{}, {} {}",
+        h("Country should be given as <symbol>:<two letter country code> e.g. VWRL:IE"),
+        h("got:"),
+        country
+    );
+    let (symbol, code) = country.split_once(':').ok_or(msg.clone())?;
+    if symbol.is_empty() || code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(msg);
+    }
+    Ok((symbol.to_uppercase(), code.to_uppercase()))
+}
+
+pub fn validate_tax_rate(rate: String) -> Result<(), String> {
+    match rate.parse::<f32>() {
+        Ok(r) if r > 0.0 && r < 100.0 => Ok(()),
+        _ => Err(h("Tax rate should be a percentage between 0 and 100 (exclusive)").to_owned()),
+    }
+}
+
+pub fn validate_share(share: String) -> Result<(), String> {
+    match share.parse::<f32>() {
+        Ok(s) if s > 0.0 && s < 100.0 => Ok(()),
+        _ => Err(h("Share should be a percentage between 0 and 100 (exclusive)").to_owned()),
+    }
+}
+
+/// Options of all subcommands, given before or after subcommand name
+fn global_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("config")
+            .long("config")
+            .help(h("TOML file with defaults of options: residency, year, lang, locale, account, sheltered_accounts and proxy (default: ~/.config/etradetaxhelper/config.toml, %APPDATA%\\etradetaxhelper\\config.toml on Windows, ~/Library/Application Support/etradetaxhelper/config.toml on macOS). Options given on command line take precedence"))
+            .value_name("FILE")
+            .takes_value(true)
+            .global(true),
+        Arg::with_name("profile")
+            .long("profile")
+            .help(h("Client profile e.g. kowalski, so many clients can be processed on one machine. Its defaults of options are read from profiles/<NAME>/config.toml next to default config file, figures are appended to ledger.csv there unless --ledger is given, and relative paths of --database and --ledger are placed in that directory and of written reports in its output directory. Directory is created on first use"))
+            .value_name("NAME")
+            .takes_value(true)
+            .validator(config::validate_profile)
+            .global(true),
+        Arg::with_name("plugins")
+            .long("plugins")
+            .help(h("Directory of parser plugins: programs parsing documents of other brokers, see README (default: plugins next to default config file e.g. ~/.config/etradetaxhelper/plugins). Documents matching patterns of plugin are parsed by it"))
+            .value_name("DIR")
+            .takes_value(true)
+            .global(true),
+        Arg::with_name("residency")
+            .long("residency")
+            .help(h("Country of residence e.g. pl , us ..."))
+            .value_name("FILE")
+            .takes_value(true)
+            .default_value("pl")
+            .global(true),
+        Arg::with_name("lang")
+            .long("lang")
+            .help(h("Language of summary, transaction tables, reports and help: pl or en. Unless given, help is in language of system locale (LANG). Summary is translated for pl residency only"))
+            .value_name("LANG")
+            .takes_value(true)
+            .possible_values(&["pl", "en"])
+            .default_value("pl")
+            .global(true),
+        Arg::with_name("locale")
+            .long("locale")
+            .help(h("Number format of PLN amounts in summary: pl (12 345,68), en (12,345.68) or c (12345.68)"))
+            .value_name("LOCALE")
+            .takes_value(true)
+            .possible_values(&["pl", "en", "c"])
+            .default_value("pl")
+            .global(true),
+        Arg::with_name("form")
+            .long("form")
+            .help(h("Polish tax return form where dividends are reported (pl residency only). Sold stock is always reported in PIT-38"))
+            .value_name("FORM")
+            .takes_value(true)
+            .possible_values(&["pit38", "pit36"])
+            .default_value("pit38")
+            .global(true),
+        Arg::with_name("tax-rules")
+            .long("tax-rules")
+            .help(h("TOML file with tax rules (rates, treaty caps, rounding) overriding bundled ones. See tax_rules.toml"))
+            .value_name("FILE")
+            .takes_value(true)
+            .global(true),
+        Arg::with_name("tax-rate")
+            .long("tax-rate")
+            .help(h("Flat tax rate in percent overriding one from tax rules e.g. 19 (pl residency only)"))
+            .value_name("PERCENT")
+            .takes_value(true)
+            .validator(validate_tax_rate)
+            .global(true),
+        Arg::with_name("epit-rounding")
+            .long("epit-rounding")
+            .help(h("Round intermediate amounts the way Twoj e-PIT calculator does, so tax to be paid matches it to PLN (pl residency only)"))
+            .global(true),
+        Arg::with_name("record-http")
+            .long("record-http")
+            .help(h("Record responses of exchange rate services to FILE, so the run can be repeated later with --replay-http"))
+            .value_name("FILE")
+            .takes_value(true)
+            .conflicts_with("replay-http")
+            .global(true),
+        Arg::with_name("replay-http")
+            .long("replay-http")
+            .help(h("Take exchange rates from responses recorded with --record-http instead of network, e.g. to reproduce past run during audit"))
+            .value_name("FILE")
+            .takes_value(true)
+            .global(true),
+        Arg::with_name("no-progress")
+            .long("no-progress")
+            .help(h("Do not show progress bars of parsing documents and getting exchange rates. They are shown only when standard error is terminal"))
+            .global(true),
+        Arg::with_name("no-color")
+            .long("no-color")
+            .help(h("Do not color output. Colors are also disabled by NO_COLOR environment variable"))
+            .global(true),
+        Arg::with_name("log-format")
+            .long("log-format")
+            .help(h("Format of messages printed to standard error: text, or json for JSON line of each record with time, level, target and message. Stages of run (parse of each document, rates, export of each file, batch jobs) are info records with stage, file, duration_ms and outcome fields, printed unless --quiet is given"))
+            .value_name("FORMAT")
+            .takes_value(true)
+            .possible_values(&["text", "json"])
+            .default_value("text")
+            .global(true),
+        Arg::with_name("verbose")
+            .short("v")
+            .long("verbose")
+            .help(h("Print transaction tables before summary and info messages to standard error. Given twice (-vv) also debug messages. RUST_LOG environment variable takes precedence for messages"))
+            .multiple(true)
+            .global(true),
+    ]
+}
+
+/// Documents and selection of their transactions
+fn document_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("year")
+            .long("year")
+            .help(h("Tax year to compute e.g. 2023. Transactions from other years are skipped"))
+            .value_name("YEAR")
+            .takes_value(true)
+            .validator(validate_year),
+        Arg::with_name("income-date")
+            .long("income-date")
+            .help(h("Date of dividend used for exchange rate and tax year assignment"))
+            .value_name("DATE")
+            .takes_value(true)
+            .possible_values(&["payment", "record"])
+            .default_value("payment"),
+        Arg::with_name("sheltered-account")
+            .long("sheltered-account")
+            .help(h("Account number (as in brokerage statement e.g. 0848, last digits are enough) of tax-sheltered account (IRA, IKE, IKZE). Its dividends are not taxed and only presented for information, other accounts of the same statement are taxed"))
+            .value_name("ACCOUNT")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1),
+        Arg::with_name("stock-dividend")
+            .long("stock-dividend")
+            .help(h("Dividend paid in shares as <YYYY-MM-DD>:<symbol>:<quantity>:<price per share in USD> e.g. 2023-06-01:INTC:2:30.5\nValue of received shares is taxed as dividend income and is their cost basis when sold"))
+            .value_name("DATE:SYMBOL:QTY:PRICE")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(|d| parse_stock_dividend(&d).map(|_| ())),
+        Arg::with_name("checkpoint")
+            .long("checkpoint")
+            .help(h("Save transactions of parsed documents to checkpoint file every few documents, so run of many documents that was interrupted or crashed resumes from there when run again with the same checkpoint file. Documents changed since are parsed again. File is removed once run completes"))
+            .value_name("FILE")
+            .takes_value(true),
+        Arg::with_name("correct")
+            .long("correct")
+            .help(h("Ask to confirm or correct suspicious values of parsed documents (invalid or future dates, unexpected withholding ratio of dividends). Answers are remembered in corrections file and applied to the same documents in later runs")),
+        Arg::with_name("corrections")
+            .long("corrections")
+            .help(h("File corrections of parsed documents are remembered in (default: corrections.json next to default config file, or in profile directory)"))
+            .value_name("FILE")
+            .takes_value(true),
+    ]
+}
+
+fn documents_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("financial documents")
+        .help(h("Brokerage statement PDFs, Gain & Losses xlsx documents and CSV exports of virtual currency transactions\n\nBrokerege statements can be downloaded from:\n\thttps://edoc.etrade.com/e/t/onlinedocs/docsearch?doc_type=stmt\n\nGain&Losses documents can be downloaded from:\n\thttps://us.etrade.com/etx/sp/stockplan#/myAccount/gainsLosses\n"))
+        .multiple(true)
+        .required_unless("generate-man")
+}
+
+/// Figures of tax computation not found in documents
+fn taxation_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("withdrawal")
+            .long("withdrawal")
+            .help(h("USD cash withdrawn from broker account as <YYYY-MM-DD>:<amount> e.g. 2023-05-10:1500.25\nWhen given, exchange rate differences on USD cash are computed"))
+            .value_name("DATE:AMOUNT")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(|w| parse_withdrawal(&w).map(|_| ())),
+        Arg::with_name("purchase-fee")
+            .long("purchase-fee")
+            .help(h("Commission and fees paid for purchase of stock as <YYYY-MM-DD>:<cost>:<fees> in USD e.g. 2022-01-10:3000:6.95\nFees are added to cost basis of sold shares acquired that day, in proportion of their cost to cost of purchase"))
+            .value_name("DATE:COST:FEES")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(|f| parse_purchase_fee(&f).map(|_| ())),
+        Arg::with_name("net-same-day")
+            .long("net-same-day")
+            .help(h("Net trades of the same day and symbol before lots of G&L are matched with sales, as some brokers report them: sales become one sale and shares bought that day are taken as sold before lots of G&L, so cost of sold stock may change"))
+            .takes_value(false),
+        Arg::with_name("pit8c")
+            .long("pit8c")
+            .help(h("Income and cost of sold stock from PIT-8C issued by Polish broker as <income>:<cost> e.g. 10500.20:8200 (requires --year)"))
+            .value_name("INCOME:COST")
+            .takes_value(true)
+            .requires("year")
+            .validator(|p| parse_pit8c(&p).map(|_| ())),
+        Arg::with_name("prepaid-tax")
+            .long("prepaid-tax")
+            .help(h("Tax already paid for the tax year (advance payments or tax withheld by Polish payer) e.g. 250 (requires --year). It is subtracted from tax to be paid"))
+            .value_name("AMOUNT")
+            .takes_value(true)
+            .requires("year")
+            .validator(|p| match p.parse::<f32>() {
+                Ok(amount) if amount >= 0.0 => Ok(()),
+                _ => Err(h("Prepaid tax should be a non-negative number").to_owned()),
+            }),
+        Arg::with_name("crypto-carried-cost")
+            .long("crypto-carried-cost")
+            .help(h("Excess of virtual currency costs over income from previous years (PIT-38 part E) e.g. 1200.50"))
+            .value_name("AMOUNT")
+            .takes_value(true)
+            .validator(|c| match c.parse::<f32>() {
+                Ok(amount) if amount >= 0.0 => Ok(()),
+                _ => Err(h("Carried cost should be a non-negative number").to_owned()),
+            }),
+        Arg::with_name("country")
+            .long("country")
+            .help(h("Country of source of security other than US as <symbol>:<country code> e.g. VWRL:IE (used by --per-country and exports)"))
+            .value_name("SYMBOL:COUNTRY")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .validator(|c| parse_country(&c).map(|_| ())),
+        Arg::with_name("review")
+            .long("review")
+            .help(h("Review parsed transactions in terminal UI before computing tax: exclude or correct them, then confirm"))
+            .takes_value(false),
+    ]
+}
+
+fn database_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("database")
+        .long("database")
+        .help(h("Store transactions, applied exchange rates and results of each tax year in SQLite database file. Rerun of account and tax year replaces its stored data"))
+        .value_name("FILE")
+        .takes_value(true)
+}
+
+fn account_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("account")
+        .long("account")
+        .help(h(
+            "Name of account figures are recorded under in ledger or database e.g. etrade or joint",
+        ))
+        .value_name("NAME")
+        .takes_value(true)
+        .default_value("default")
+}
+
+fn dry_run_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("dry-run")
+        .long("dry-run")
+        .help(h("Parse documents and get exchange rates, but only report files that would be written, ledger lines appended, database records stored, sheets created and e-mails sent instead of doing it. Results are still printed"))
+        .conflicts_with("record-http")
+}
+
+/// Reports and records of results written to files or sent
+fn report_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("export")
+            .long("export")
+            .help(h("Write results to file of given format e.g. --export csv transactions.csv\n\tcsv - all processed transactions (dates, symbols, USD amounts, exchange rates and amounts in target currency)\n\tjson - transactions, figures of each tax year and tax rules applied\n\txlsx - workbook with sheets of dividends, sales, exchange rates used and summary\n\tpdf - printable report of the same for archiving\n\thtml - report of the same with sortable and filterable tables\n\tmd - report of the same as Markdown tables\n\thuman - report of the same as aligned text tables\n\tkv - key=value lines of main figures as printed by --quiet\n\tjsonl - JSON line of each transaction and tax year result\nResults are printed to standard output if path is -"))
+            .value_names(&["FORMAT", "PATH"])
+            .takes_value(true)
+            .number_of_values(2)
+            .multiple(true),
+        Arg::with_name("output")
+            .long("output")
+            .help(h("Write results to file in format given by its extension: .csv, .json, .xlsx, .pdf, .html, .md, .txt (human) or .env (kv). Missing directories are created. Can be given multiple times e.g. --output out/2023.json --output out/2023.pdf"))
+            .value_name("FILE")
+            .takes_value(true)
+            .number_of_values(1)
+            .multiple(true),
+        Arg::with_name("template")
+            .long("template")
+            .help(h("Render results with Handlebars-like template file and write them to path (- for standard output) e.g. --template accountant.txt report.txt"))
+            .value_names(&["TEMPLATE", "PATH"])
+            .takes_value(true)
+            .number_of_values(2),
+        Arg::with_name("google-sheets")
+            .long("google-sheets")
+            .help(h("Create Google Sheet with sheets of dividends, sales, exchange rates used and summary. Takes OAuth client JSON file (type \"TVs and Limited Input devices\") created in Google Cloud console with Sheets API enabled. Authorization code to enter at Google page is printed"))
+            .value_name("CLIENT_FILE")
+            .takes_value(true),
+        Arg::with_name("email")
+            .long("email")
+            .help(h("Send report by e-mail (e.g. to accountant) with settings from TOML file: smtp_url, username, from, to, subject and formats of attachments (pdf by default). Password is taken from SMTP_PASSWORD environment variable. Requires curl"))
+            .value_name("FILE")
+            .takes_value(true),
+        Arg::with_name("ledger")
+            .long("ledger")
+            .help(h("Append figures of each tax year to CSV ledger file, building history of all runs. Latest line of account and tax year is its current figures"))
+            .value_name("FILE")
+            .takes_value(true),
+        database_arg(),
+        account_arg(),
+        Arg::with_name("anonymize")
+            .long("anonymize")
+            .help(h("Replace symbols and scale amounts by random factor in exported reports (--export, --output, --format, --template, --audit), so they can be shared e.g. when reporting parser bug. Summary printed to terminal is not affected")),
+        Arg::with_name("audit")
+            .long("audit")
+            .help(h("Write audit report listing transactions, exchange rates with their tables, formulas and rounding of every figure to text file"))
+            .value_name("FILE")
+            .takes_value(true),
+        dry_run_arg(),
+    ]
+}
+
+/// Summary printed to standard output
+fn summary_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("split-with-spouse")
+            .long("split-with-spouse")
+            .help(h("Split income and tax paid of jointly owned account with spouse. Optional value is your share in percent e.g. --split-with-spouse=60 (default 50)"))
+            .value_name("PERCENT")
+            .takes_value(true)
+            .min_values(0)
+            .max_values(1)
+            .require_equals(true)
+            .validator(validate_share),
+        Arg::with_name("format")
+            .long("format")
+            .help(h("Format of results printed to standard output. human is summary and transaction tables, jsonl prints JSON line of each document and transaction as soon as it is processed, followed by JSON line of each tax year result. Other formats print the same document as --export does"))
+            .value_name("FORMAT")
+            .takes_value(true)
+            .possible_values(&["human", "csv", "json", "jsonl", "xlsx", "pdf", "html", "md", "kv"])
+            .default_value("human"),
+        Arg::with_name("quiet")
+            .short("q")
+            .long("quiet")
+            .help(h("Print only key=value lines of each tax year (tax_year, gross_pln, tax_paid_pln, tax_due_pln) for scripts. Warnings go to standard error, no other messages are printed there"))
+            .conflicts_with_all(&["format", "verbose"]),
+        Arg::with_name("per-ticker")
+            .long("per-ticker")
+            .help(h("Present dividends, tax paid and creditable tax per symbol")),
+        Arg::with_name("epit-guide")
+            .long("epit-guide")
+            .help(h("Present step-by-step instructions of entering computed figures into Twoj e-PIT portal (pl residency only)")),
+        Arg::with_name("per-month")
+            .long("per-month")
+            .help(h("Present dividends and interest with tax withheld per month, to compare with monthly statements")),
+        Arg::with_name("per-country")
+            .long("per-country")
+            .help(h("Present dividends, tax paid and creditable tax per country of source (for PIT/ZG)")),
+    ]
+}
+
+/// PIT-38 figures of single tax year written out or copied
+fn pit38_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("edeklaracja")
+            .long("edeklaracja")
+            .help(h("Write PIT-38 e-Deklaracje XML document of given tax year to file (pl residency only, requires --year and --taxpayer). Schema of the form is taken from tax rules"))
+            .value_name("FILE")
+            .takes_value(true)
+            .requires_all(&["year", "taxpayer"]),
+        Arg::with_name("copy")
+            .long("copy")
+            .help(h("Copy PIT-38 amounts of tax year given with --year (or the latest one) to clipboard as tab separated line in box order, to paste into Twoj e-PIT form (pl residency only)")),
+        Arg::with_name("taxpayer")
+            .long("taxpayer")
+            .help(h("TOML file with identification data of taxpayer used in e-Deklaracje XML: pesel (or nip), first_name, last_name, birth_date, tax_office"))
+            .value_name("FILE")
+            .takes_value(true),
+    ]
+}
+
+/// Service and its user of login and logout subcommands
+fn credential_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("service")
+            .help(h("Service password is used for: smtp (e-mail settings of --email) or etrade (consumer secret of E*TRADE API key)"))
+            .possible_values(&etradeTaxReturnHelper::SERVICES)
+            .required(true),
+        Arg::with_name("user")
+            .help(h("User of service: username of e-mail settings or consumer key of E*TRADE API key"))
+            .required(true),
+    ]
+}
+
+pub fn create_cmd_line_pattern<'a, 'b>(myapp: App<'a, 'b>) -> App<'a, 'b> {
+    myapp
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .subcommand(
+            SubCommand::with_name("simulate")
+                .about(h("Estimate tax due of hypothetical sale of held stock"))
+                .arg(
+                    Arg::with_name("lot")
+                        .long("lot")
+                        .help(h("Held stock to be sold as <YYYY-MM-DD>:<quantity>:<cost basis per share in USD> e.g. 2021-05-03:100:0.0 for RSU"))
+                        .value_name("DATE:QTY:COST")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .required(true)
+                        .validator(|l| parse_lot(&l).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("price")
+                        .long("price")
+                        .help(h("Sale price per share in USD"))
+                        .value_name("USD")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(validate_price),
+                )
+                .arg(
+                    Arg::with_name("split")
+                        .long("split")
+                        .help(h("Stock split since acquisition of lots as <YYYY-MM-DD>:<ratio> e.g. 2024-06-10:10 for 10-for-1 split (0.1 for 1-for-10 reverse split). Lots are to be given with pre-split quantity and cost"))
+                        .value_name("DATE:RATIO")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .validator(|s| parse_split(&s).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("date")
+                        .long("date")
+                        .help(h("Sale date as <YYYY-MM-DD>. Default is today"))
+                        .value_name("DATE")
+                        .takes_value(true)
+                        .validator(validate_date),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("forecast")
+                .about(h("Forecast income and tax of RSU vests remaining in current year"))
+                .arg(
+                    Arg::with_name("vest")
+                        .long("vest")
+                        .help(h("Upcoming vest as <YYYY-MM-DD>:<quantity> e.g. 2023-11-20:25 (see Benefit History at E*TRADE)"))
+                        .value_name("DATE:QTY")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .required(true)
+                        .validator(|v| parse_vest(&v).map(|_| ())),
+                )
+                .arg(
+                    Arg::with_name("price")
+                        .long("price")
+                        .help(h("Expected share price in USD"))
+                        .value_name("USD")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(validate_price),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("interest")
+                .about(h("Compute late payment interest (odsetki za zwloke) of tax paid after deadline (pl residency only)"))
+                .arg(
+                    Arg::with_name("amount")
+                        .long("amount")
+                        .help(h("Tax arrears in PLN"))
+                        .value_name("PLN")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(validate_price),
+                )
+                .arg(
+                    Arg::with_name("deadline")
+                        .long("deadline")
+                        .help(h("Payment deadline as <YYYY-MM-DD> e.g. 2023-04-30"))
+                        .value_name("DATE")
+                        .takes_value(true)
+                        .required(true)
+                        .validator(validate_date),
+                )
+                .arg(
+                    Arg::with_name("payment")
+                        .long("payment")
+                        .help(h("Actual payment date as <YYYY-MM-DD>. Default is today"))
+                        .value_name("DATE")
+                        .takes_value(true)
+                        .validator(validate_date),
+                )
+                .arg(
+                    Arg::with_name("reduced")
+                        .long("reduced")
+                        .help(h("Apply reduced rate (50% of basic rate) e.g. for correction filed within 6 months of deadline and paid within 7 days of filing")),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about(h("Show transactions and figures that changed between two JSON exports (--export json) e.g. after adding missing statement"))
+                .arg(
+                    Arg::with_name("old")
+                        .help(h("JSON export of previous run"))
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("new")
+                        .help(h("JSON export of current run"))
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("compute")
+                .about(h("Compute tax of documents and present summary. Default when no subcommand is given"))
+                .args(&document_args())
+                .arg(documents_arg())
+                .args(&taxation_args())
+                .args(&report_args())
+                .args(&summary_args())
+                .args(&pit38_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("import")
+                .about(h("Compute tax of documents and store their transactions, exchange rates and results in SQLite database. Only key=value lines of each tax year are printed"))
+                .args(&document_args())
+                .arg(documents_arg())
+                .args(&taxation_args())
+                .arg(database_arg().required(true))
+                .arg(account_arg())
+                .arg(dry_run_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("report")
+                .about(h("Compute tax of documents and write reports. Only key=value lines of each tax year are printed"))
+                .args(&document_args())
+                .arg(documents_arg())
+                .args(&taxation_args())
+                .args(&report_args())
+                .group(
+                    ArgGroup::with_name("reports")
+                        .args(&["export", "output", "template", "google-sheets", "email", "ledger", "database", "audit"])
+                        .multiple(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("validate")
+                .about(h("Parse documents and verify consistency of their transactions, without getting exchange rates"))
+                .args(&document_args())
+                .arg(documents_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("rates")
+                .about(h("Print exchange rates applied to transactions of given dates"))
+                .arg(
+                    Arg::with_name("dates")
+                        .help(h("Dates of transactions as <YYYY-MM-DD>"))
+                        .value_name("DATE")
+                        .multiple(true)
+                        .required(true)
+                        .validator(validate_date),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("etrade-api")
+                .about(h("Import dividends, interest and sales of date range from E*TRADE API into JSON statement, to be given as financial document instead of brokerage statement PDFs. Consumer secret of API key is taken from ETRADE_CONSUMER_SECRET environment variable or keychain (login etrade <consumer key>)"))
+                .arg(
+                    Arg::with_name("consumer-key")
+                        .long("consumer-key")
+                        .help(h("Consumer key of E*TRADE API key"))
+                        .value_name("KEY")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("from")
+                        .long("from")
+                        .help(h("First day of imported transactions as <YYYY-MM-DD>"))
+                        .value_name("DATE")
+                        .required(true)
+                        .validator(validate_date),
+                )
+                .arg(
+                    Arg::with_name("to")
+                        .long("to")
+                        .help(h("Last day of imported transactions as <YYYY-MM-DD> (default: today)"))
+                        .value_name("DATE")
+                        .validator(validate_date),
+                )
+                .arg(
+                    Arg::with_name("account-id")
+                        .long("account-id")
+                        .help(h("Number of E*TRADE account to import (default: all accounts)"))
+                        .value_name("ID")
+                        .number_of_values(1)
+                        .multiple(true),
+                )
+                .arg(
+                    Arg::with_name("sandbox")
+                        .long("sandbox")
+                        .help(h("Use E*TRADE sandbox API e.g. to try sandbox API key")),
+                )
+                .arg(
+                    Arg::with_name("statement")
+                        .help(h("JSON statement file transactions are written to e.g. etrade-2023.json"))
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("login")
+                .about(h("Store password of service in keychain of operating system, so it is not kept in plain text files. Password is prompted for, or read from standard input"))
+                .args(&credential_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("logout")
+                .about(h("Remove password of service from keychain"))
+                .args(&credential_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("batch")
+                .about(h("Run compute for each job of YAML job file (documents, year, residency, profile, outputs and other options) and report which jobs succeeded. Jobs are run one after another, each in its own process"))
+                .arg(
+                    Arg::with_name("job-file")
+                        .help(h("YAML file with list of jobs, see README"))
+                        .value_name("JOB_FILE")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("resume")
+                        .long("resume")
+                        .help(h("Skip jobs that succeeded in earlier run of job file, as recorded in <JOB_FILE>.checkpoint. Checkpoint is removed once all jobs succeed")),
+                )
+                .arg(dry_run_arg()),
+        )
+        .subcommand(
+            SubCommand::with_name("serve")
+                .about(h("Serve REST API: upload documents to get their transactions, and summary of taxation as JSON"))
+                .arg(
+                    Arg::with_name("address")
+                        .long("address")
+                        .help(h("Address to listen at. There is no authentication, so keep it local or behind proxy"))
+                        .value_name("HOST:PORT")
+                        .default_value("127.0.0.1:8080"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("watch")
+                .about(h("Watch directory statements are downloaded to: whenever documents are added, changed or removed, compute tax of all of them again, update ledger and reports, and present summary of the current tax year (or the one given with --year). Stop with Ctrl-C"))
+                .arg(
+                    Arg::with_name("directory")
+                        .help(h("Directory of brokerage statements and other documents"))
+                        .value_name("DIR")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("interval")
+                        .long("interval")
+                        .help(h("Seconds between checks of directory. Documents are taxed once directory did not change for that long, so files still being downloaded are not parsed"))
+                        .value_name("SECONDS")
+                        .default_value("10")
+                        .validator(validate_interval),
+                )
+                .args(&document_args())
+                .args(&taxation_args())
+                .args(&report_args())
+                .args(&summary_args()),
+        )
+        .subcommand(
+            SubCommand::with_name("self-update")
+                .about(h("Replace this program with latest release from GitHub: binary of this platform is downloaded and verified against SHA256 checksums published with release, whose signature is verified with public key built into this program"))
+                .arg(
+                    Arg::with_name("check")
+                        .long("check")
+                        .help(h("Only print whether newer release is available")),
+                ),
+        )
+        .args(&global_args())
+        .arg(
+            Arg::with_name("generate-man")
+                .long("generate-man")
+                .help(h("Print man page of this program (roff) and exit, e.g. for packaging: etradeTaxReturnHelper --generate-man > etradeTaxReturnHelper.1")),
+        )
+        // Options of compute subcommand are also accepted without it
+        .args(&document_args())
+        .arg(documents_arg())
+        .args(&taxation_args())
+        .args(&report_args())
+        .args(&summary_args())
+        .args(&pit38_args())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::ErrorKind;
+
+    #[test]
+    fn test_cmdline_de() -> Result<(), clap::Error> {
+        // Init Transactions
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--residency=de",
+            "data/example.pdf",
+        ])?;
+        let residency = matches.value_of("residency").ok_or(clap::Error {
+            message: "Unable to get residency value".to_owned(),
+            kind: ErrorKind::InvalidValue,
+            info: None,
+        })?;
+        match residency {
+            "de" => return Ok(()),
+            _ => clap::Error {
+                message: "Wrong residency value".to_owned(),
+                kind: ErrorKind::InvalidValue,
+                info: None,
+            },
+        };
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_pl() -> Result<(), clap::Error> {
+        // Init Transactions
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--residency=pl",
+            "data/example.pdf",
+        ])?;
+        let residency = matches.value_of("residency").ok_or(clap::Error {
+            message: "Unable to get residency value".to_owned(),
+            kind: ErrorKind::InvalidValue,
+            info: None,
+        })?;
+        match residency {
+            "pl" => return Ok(()),
+            _ => clap::Error {
+                message: "Wrong residency value".to_owned(),
+                kind: ErrorKind::InvalidValue,
+                info: None,
+            },
+        };
+        Ok(())
+    }
+    #[test]
+    fn test_cmdline_default() -> Result<(), clap::Error> {
+        // Init Transactions
+        let myapp = App::new("E-trade tax helper");
+        create_cmd_line_pattern(myapp).get_matches_from_safe(vec!["mytest", "data/example.pdf"])?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_us() -> Result<(), clap::Error> {
+        // Init Transactions
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--residency=us",
+            "data/example.pdf",
+        ])?;
+        let residency = matches.value_of("residency").ok_or(clap::Error {
+            message: "Unable to get residency value".to_owned(),
+            kind: ErrorKind::InvalidValue,
+            info: None,
+        })?;
+        match residency {
+            "us" => return Ok(()),
+            _ => clap::Error {
+                message: "Wrong residency value".to_owned(),
+                kind: ErrorKind::InvalidValue,
+                info: None,
+            },
+        };
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_form() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--form=pit36",
+            "data/example.pdf",
+        ])?;
+        assert_eq!(matches.value_of("form"), Some("pit36"));
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--form=pit37",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_split_with_spouse() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--split-with-spouse",
+            "data/example.pdf",
+        ])?;
+        assert!(matches.is_present("split-with-spouse"));
+        assert_eq!(matches.value_of("split-with-spouse"), None);
+        assert_eq!(matches.values_of("financial documents").unwrap().count(), 1);
+
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--split-with-spouse=60",
+            "data/example.pdf",
+        ])?;
+        assert_eq!(matches.value_of("split-with-spouse"), Some("60"));
+
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--split-with-spouse=100",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_per_country() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--per-country",
+            "--country",
+            "VWRL:ie",
+            "data/example.pdf",
+        ])?;
+        assert!(matches.is_present("per-country"));
+        assert_eq!(
+            parse_country(matches.value_of("country").unwrap()),
+            Ok(("VWRL".to_string(), "IE".to_string()))
+        );
+
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--per-country",
+            "--country",
+            "VWRL:IRL",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_lang() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp)
+            .get_matches_from_safe(vec!["mytest", "data/example.pdf"])?;
+        assert_eq!(matches.value_of("lang"), Some("pl"));
+        assert_eq!(matches.value_of("locale"), Some("pl"));
+
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--lang",
+            "en",
+            "--locale",
+            "c",
+            "data/example.pdf",
+        ])?;
+        assert_eq!(matches.value_of("lang"), Some("en"));
+        assert_eq!(matches.value_of("locale"), Some("c"));
+
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--lang",
+            "de",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_sheltered_accounts() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--sheltered-account",
+            "0848",
+            "--sheltered-account",
+            "1234",
+            "data/example.pdf",
+        ])?;
+        assert_eq!(
+            matches
+                .values_of("sheltered-account")
+                .unwrap()
+                .collect::<Vec<&str>>(),
+            vec!["0848", "1234"]
+        );
+        assert_eq!(matches.values_of("financial documents").unwrap().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_simulate() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "simulate",
+            "--lot",
+            "2021-05-03:100:0.0",
+            "--lot",
+            "2022-08-19:10:28.5",
+            "--price",
+            "31.2",
+        ])?;
+        let simulation = matches.subcommand_matches("simulate").unwrap();
+        assert_eq!(simulation.values_of("lot").unwrap().count(), 2);
+        assert_eq!(simulation.value_of("price"), Some("31.2"));
+        assert_eq!(
+            parse_lot("2022-08-19:10:28.5"),
+            Ok(("08/19/22".to_owned(), 10.0, 28.5))
+        );
+        assert!(parse_lot("2022-08-19:10").is_err());
+        assert_eq!(
+            parse_split("2024-06-10:10"),
+            Ok(("06/10/24".to_owned(), 10.0))
+        );
+        assert!(parse_split("2024-06-10:0").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_forecast() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "forecast",
+            "--vest",
+            "2023-11-20:25",
+            "--price",
+            "31.2",
+        ])?;
+        let forecast = matches.subcommand_matches("forecast").unwrap();
+        assert_eq!(
+            parse_vest(forecast.value_of("vest").unwrap()),
+            Ok(("11/20/23".to_owned(), 25.0))
+        );
+        assert!(parse_vest("2023-11-20").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_interest() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "interest",
+            "--amount",
+            "1200",
+            "--deadline",
+            "2023-04-30",
+            "--payment",
+            "2023-08-10",
+            "--reduced",
+        ])?;
+        let interest = matches.subcommand_matches("interest").unwrap();
+        assert_eq!(interest.value_of("amount"), Some("1200"));
+        assert_eq!(interest.value_of("deadline"), Some("2023-04-30"));
+        assert_eq!(interest.value_of("payment"), Some("2023-08-10"));
+        assert!(interest.is_present("reduced"));
+
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "interest",
+            "--amount",
+            "1200",
+            "--deadline",
+            "30.04.2023",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_export() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--export",
+            "csv",
+            "transactions.csv",
+            "data/example.pdf",
+        ])?;
+        assert_eq!(
+            matches.values_of("export").unwrap().collect::<Vec<&str>>(),
+            vec!["csv", "transactions.csv"]
+        );
+        assert_eq!(matches.values_of("financial documents").unwrap().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_diff() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp)
+            .get_matches_from_safe(vec!["mytest", "diff", "old.json", "new.json"])?;
+        let diff = matches.subcommand_matches("diff").unwrap();
+        assert_eq!(diff.value_of("old"), Some("old.json"));
+        assert_eq!(diff.value_of("new"), Some("new.json"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_output() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--output",
+            "out/2023.json",
+            "--output",
+            "out/2023.pdf",
+            "--export",
+            "csv",
+            "a.csv",
+            "--export",
+            "md",
+            "b.md",
+            "data/example.pdf",
+        ])?;
+        assert_eq!(
+            matches.values_of("output").unwrap().collect::<Vec<&str>>(),
+            vec!["out/2023.json", "out/2023.pdf"]
+        );
+        assert_eq!(matches.values_of("export").unwrap().count(), 4);
+        assert_eq!(matches.values_of("financial documents").unwrap().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_format() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp)
+            .get_matches_from_safe(vec!["mytest", "data/example.pdf"])?;
+        assert_eq!(matches.value_of("format"), Some("human"));
+
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--format",
+            "json",
+            "data/example.pdf",
+        ])?;
+        assert_eq!(
+            etradeTaxReturnHelper::ExportFormat::from_name(matches.value_of("format").unwrap()),
+            Ok(etradeTaxReturnHelper::ExportFormat::Json)
+        );
+
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--format",
+            "yaml",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_quiet() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--quiet",
+            "data/example.pdf",
+        ])?;
+        assert!(matches.is_present("quiet"));
+
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--quiet",
+            "--format",
+            "json",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_verbose() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "compute",
+            "-vv",
+            "data/example.pdf",
+        ])?;
+        let compute = matches.subcommand_matches("compute").unwrap();
+        assert_eq!(compute.occurrences_of("verbose"), 2);
+
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "-q",
+            "-v",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_edeklaracja() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--year",
+            "2023",
+            "--edeklaracja",
+            "pit38.xml",
+            "--taxpayer",
+            "taxpayer.toml",
+            "data/example.pdf",
+        ])?;
+        assert_eq!(matches.value_of("edeklaracja"), Some("pit38.xml"));
+        assert_eq!(matches.value_of("taxpayer"), Some("taxpayer.toml"));
+
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--edeklaracja",
+            "pit38.xml",
+            "--taxpayer",
+            "taxpayer.toml",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_year() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--year=2022",
+            "data/example.pdf",
+        ])?;
+        assert_eq!(matches.value_of("year"), Some("2022"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_invalid_year() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--year=22",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_tax_rate() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--tax-rate=19",
+            "data/example.pdf",
+        ])?;
+        assert_eq!(matches.value_of("tax-rate"), Some("19"));
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--tax-rate=190",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_pit8c() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--year=2022",
+            "--pit8c=10500.20:8200",
+            "data/example.pdf",
+        ])?;
+        assert_eq!(
+            parse_pit8c(matches.value_of("pit8c").unwrap()),
+            Ok((10500.2, 8200.0))
+        );
+        // PIT-8C is issued for single tax year
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--pit8c=10500.20:8200",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_subcommands() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--residency=us",
+            "compute",
+            "--year=2023",
+            "data/example.pdf",
+        ])?;
+        let compute = matches.subcommand_matches("compute").unwrap();
+        assert_eq!(compute.value_of("year"), Some("2023"));
+        // Global options are given before or after subcommand
+        assert_eq!(compute.value_of("residency"), Some("us"));
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "rates",
+            "2023-03-01",
+            "2023-03-02",
+            "--lang=en",
+        ])?;
+        assert_eq!(matches.value_of("lang"), Some("en"));
+        assert_eq!(
+            matches
+                .subcommand_matches("rates")
+                .unwrap()
+                .values_of("dates")
+                .unwrap()
+                .collect::<Vec<&str>>(),
+            vec!["2023-03-01", "2023-03-02"]
+        );
+        // Report needs at least one of report options
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "report",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        // Import needs database
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "import",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        // Summary options are not accepted by validate
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "validate",
+            "--per-ticker",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_record_http() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "compute",
+            "--replay-http=rates.json",
+            "data/example.pdf",
+        ])?;
+        assert_eq!(matches.value_of("replay-http"), Some("rates.json"));
+        // Responses are either recorded or replayed
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--record-http=rates.json",
+            "--replay-http=rates.json",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_dry_run() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "import",
+            "--dry-run",
+            "--database=taxes.db",
+            "data/example.pdf",
+        ])?;
+        let import = matches.subcommand_matches("import").unwrap();
+        assert!(import.is_present("dry-run"));
+        // Recording of responses is a write too
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--dry-run",
+            "--record-http=rates.json",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_profile() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "compute",
+            "--profile=kowalski",
+            "data/example.pdf",
+        ])?;
+        assert_eq!(matches.value_of("profile"), Some("kowalski"));
+        // Profile is a directory name, it can not lead out of profiles
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "--profile=../kowalski",
+            "data/example.pdf",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_batch() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "batch",
+            "--dry-run",
+            "--resume",
+            "clients.yaml",
+        ])?;
+        let batch = matches.subcommand_matches("batch").unwrap();
+        assert_eq!(batch.value_of("job-file"), Some("clients.yaml"));
+        assert!(batch.is_present("dry-run"));
+        assert!(batch.is_present("resume"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmdline_watch() -> Result<(), clap::Error> {
+        let myapp = App::new("E-trade tax helper");
+        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "watch",
+            "--interval=60",
+            "--ledger=ledger.csv",
+            "statements",
+        ])?;
+        let watch = matches.subcommand_matches("watch").unwrap();
+        assert_eq!(watch.value_of("directory"), Some("statements"));
+        assert_eq!(watch.value_of("interval"), Some("60"));
+        assert_eq!(watch.value_of("ledger"), Some("ledger.csv"));
+        let myapp = App::new("E-trade tax helper");
+        let result = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
+            "mytest",
+            "watch",
+            "--interval=0",
+            "statements",
+        ]);
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_withdrawal() -> Result<(), String> {
+        assert_eq!(
+            parse_withdrawal("2023-05-10:1500.25"),
+            Ok(("05/10/23".to_owned(), 1500.25))
+        );
+        assert!(parse_withdrawal("05/10/23:1500.25").is_err());
+        assert!(parse_withdrawal("2023-05-10").is_err());
+        assert!(parse_withdrawal("2023-05-10:-10").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_purchase_fee() -> Result<(), String> {
+        assert_eq!(
+            parse_purchase_fee("2022-01-10:3000:6.95"),
+            Ok(("01/10/22".to_owned(), 3000.0, 6.95))
+        );
+        assert!(parse_purchase_fee("2022-01-10:6.95").is_err());
+        assert!(parse_purchase_fee("2022-01-10:0:6.95").is_err());
+        assert!(parse_purchase_fee("2022-01-10:3000:-1").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_stock_dividend() -> Result<(), String> {
+        assert_eq!(
+            parse_stock_dividend("2023-06-01:intc:2:30.5"),
+            Ok(("06/01/23".to_owned(), "INTC".to_owned(), 2.0, 30.5))
+        );
+        assert!(parse_stock_dividend("2023-06-01:2:30.5").is_err());
+        assert!(parse_stock_dividend("2023-06-01:INTC:0:30.5").is_err());
+        Ok(())
+    }
+}
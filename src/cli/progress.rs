@@ -0,0 +1,111 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use std::sync::Mutex;
+
+struct Current {
+    bar: ProgressBar,
+    step: String,
+    total: usize,
+    failed: usize,
+}
+
+/// Progress bar of each step drawn on standard error (hidden when it is not
+/// terminal), replaced with summary of step when it ends
+#[derive(Default)]
+pub struct ProgressBars {
+    current: Mutex<Option<Current>>,
+}
+
+/// e.g. "Parsing documents: 23 of 25 done, 2 failed"
+fn summary(step: &str, done: usize, total: usize, failed: usize) -> String {
+    match failed {
+        0 => format!("{}: {} of {} done", step, done, total),
+        failed => format!("{}: {} of {} done, {} failed", step, done, total, failed),
+    }
+}
+
+impl etradeTaxReturnHelper::Progress for ProgressBars {
+    fn start(&self, step: &str, total: usize) {
+        let bar = ProgressBar::new(total as u64);
+        if let Ok(style) = ProgressStyle::with_template("{msg} [{bar:30}] {pos}/{len}") {
+            bar.set_style(style.progress_chars("=> "));
+        }
+        bar.set_message(step.to_owned());
+        if let Ok(mut current) = self.current.lock() {
+            *current = Some(Current {
+                bar,
+                step: step.to_owned(),
+                total,
+                failed: 0,
+            });
+        }
+    }
+
+    // Reason of failure is already printed as warning
+    fn advance(&self, _item: &str, failure: Option<&str>) {
+        if let Ok(mut current) = self.current.lock() {
+            if let Some(current) = current.as_mut() {
+                if failure.is_some() {
+                    current.failed += 1;
+                }
+                current.bar.inc(1);
+            }
+        }
+    }
+
+    fn finish(&self) {
+        let current = match self.current.lock() {
+            Ok(mut current) => current.take(),
+            Err(_) => None,
+        };
+        if let Some(current) = current {
+            let hidden = current.bar.is_hidden();
+            let done = current.bar.position() as usize - current.failed;
+            current.bar.finish_and_clear();
+            if !hidden && current.total > 0 {
+                eprintln!(
+                    "{}",
+                    summary(&current.step, done, current.total, current.failed)
+                );
+            }
+        }
+    }
+
+    fn print(&self, line: &str) {
+        let current = self.current.lock();
+        match current.as_ref().ok().and_then(|current| current.as_ref()) {
+            Some(current) => current.bar.suspend(|| eprintln!("{}", line)),
+            None => eprintln!("{}", line),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use etradeTaxReturnHelper::Progress;
+
+    #[test]
+    fn test_progress_bars() -> Result<(), String> {
+        assert_eq!(
+            summary("Parsing documents", 23, 25, 2),
+            "Parsing documents: 23 of 25 done, 2 failed"
+        );
+        assert_eq!(
+            summary("Getting exchange rates", 4, 4, 0),
+            "Getting exchange rates: 4 of 4 done"
+        );
+        let bars = ProgressBars::default();
+        bars.start("Parsing documents", 2);
+        bars.advance("statement.pdf", None);
+        bars.advance("broken.pdf", Some("unexpected content"));
+        {
+            let current = bars.current.lock().map_err(|e| e.to_string())?;
+            let current = current.as_ref().ok_or("step not started")?;
+            assert_eq!(current.bar.position(), 2);
+            assert_eq!(current.failed, 1);
+        }
+        bars.finish();
+        assert!(bars.current.lock().map_err(|e| e.to_string())?.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::BTreeMap;
+use std::io::Read;
+
+use crate::logging::ResultExt;
+use etradeTaxReturnHelper::{Error, InMemory, Lang, Residency, TaxationOptions};
+
+// Larger uploads are rejected, documents of E*TRADE are much smaller
+const MAX_DOCUMENT_SIZE: usize = 64 * 1024 * 1024;
+
+/// Documents uploaded so far, taxed together in order of upload
+#[derive(Default)]
+pub struct Documents {
+    contents: InMemory,
+    names: Vec<String>,
+}
+
+// Status code and JSON body of response
+type Response = (u16, String);
+
+fn json_error(status: u16, message: &str) -> Response {
+    (status, serde_json::json!({ "error": message }).to_string())
+}
+
+fn error_status(e: &Error) -> u16 {
+    match e {
+        Error::Parse(_) | Error::Transactions(_) | Error::Arguments(_) => 422,
+        Error::Rates(_) | Error::Network(_) => 502,
+        Error::Report(_) | Error::Interrupted(_) => 500,
+        Error::MissingFeature(..) => 501,
+    }
+}
+
+/// Percent-encoded part of URL e.g. Brokerage%20Statement.pdf
+fn decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = text
+                    .get(i + 1..i + 3)
+                    .ok_or(format!("invalid escape in {}", text))?;
+                decoded.push(
+                    u8::from_str_radix(hex, 16)
+                        .map_err(|_| format!("invalid escape in {}", text))?,
+                );
+                i += 3;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| format!("invalid name {}", text))
+}
+
+fn query_value<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find_map(|(key, value)| (key == name).then_some(value))
+}
+
+fn summary(
+    rd: &dyn Residency,
+    lang: Lang,
+    documents: &Documents,
+    year: Option<i32>,
+) -> Result<Response, Error> {
+    let options = TaxationOptions {
+        year,
+        lang,
+        source: Some(Box::new(documents.contents.clone())),
+        ..Default::default()
+    };
+    let results = etradeTaxReturnHelper::run_taxation(
+        rd,
+        documents.names.iter().map(|n| n.as_str()),
+        &options,
+    )?;
+    let tax_due: BTreeMap<i32, f32> = results
+        .iter()
+        .filter_map(|(year, result)| {
+            rd.rules_for_year(*year).map(|rules| {
+                (
+                    *year,
+                    etradeTaxReturnHelper::compute_tax_due(result, &rules),
+                )
+            })
+        })
+        .collect();
+    Ok((
+        200,
+        serde_json::json!({ "results": results, "tax_due": tax_due }).to_string(),
+    ))
+}
+
+/// Response to request of REST API:
+///     PUT /documents/<name>  upload document, parsed transactions are returned
+///     GET /documents         names of uploaded documents
+///     DELETE /documents      remove uploaded documents
+///     GET /summary[?year=Y]  figures of each tax year and tax due
+pub fn handle(
+    rd: &dyn Residency,
+    lang: Lang,
+    documents: &mut Documents,
+    method: &str,
+    url: &str,
+    body: Vec<u8>,
+) -> Response {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    match (method, path) {
+        ("GET", "/documents") => (200, serde_json::json!(documents.names).to_string()),
+        ("DELETE", "/documents") => {
+            *documents = Documents::default();
+            (200, serde_json::json!(documents.names).to_string())
+        }
+        ("PUT", path) if path.starts_with("/documents/") => {
+            let name = match decode(&path["/documents/".len()..]) {
+                Ok(name) if !name.is_empty() && !name.contains('/') => name,
+                Ok(name) => return json_error(400, &format!("invalid name {}", name)),
+                Err(e) => return json_error(400, &e),
+            };
+            let mut uploaded = InMemory::default();
+            uploaded.insert(&name, body.clone());
+            match etradeTaxReturnHelper::parse_document(&uploaded, &name, false) {
+                Ok(statement) => {
+                    documents.contents.insert(&name, body);
+                    if !documents.names.contains(&name) {
+                        documents.names.push(name);
+                    }
+                    match serde_json::to_string(&statement) {
+                        Ok(content) => (201, content),
+                        Err(e) => json_error(500, &e.to_string()),
+                    }
+                }
+                Err(e) => json_error(422, &e.to_string()),
+            }
+        }
+        ("GET", "/summary") => {
+            let year = match query_value(query, "year").map(|y| y.parse::<i32>()) {
+                Some(Ok(year)) => Some(year),
+                Some(Err(_)) => return json_error(400, "invalid year"),
+                None => None,
+            };
+            match summary(rd, lang, documents, year) {
+                Ok(response) => response,
+                Err(e) => json_error(error_status(&e), &e.to_string()),
+            }
+        }
+        (_, "/documents") | (_, "/summary") => json_error(405, "method not allowed"),
+        _ => json_error(404, "not found"),
+    }
+}
+
+/// Serve REST API at given address until program is stopped. Requests are
+/// handled one at a time
+pub fn serve(rd: &dyn Residency, lang: Lang, address: &str) -> Result<(), String> {
+    let server = tiny_http::Server::http(address)
+        .map_err(|e| format!("Error listening at {}: {}", address, e))?;
+    println!("Serving REST API at http://{}", address);
+    log::info!("Serving REST API at http://{}", address);
+    let content_type =
+        tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+            .expect_and_log("error creating header");
+    let mut documents = Documents::default();
+    for mut request in server.incoming_requests() {
+        let method = request.method().as_str().to_owned();
+        let url = request.url().to_owned();
+        let mut body = vec![];
+        let (status, content) = match request.body_length() {
+            Some(length) if length > MAX_DOCUMENT_SIZE => json_error(413, "document too large"),
+            _ => match request
+                .as_reader()
+                .take(MAX_DOCUMENT_SIZE as u64 + 1)
+                .read_to_end(&mut body)
+            {
+                Ok(length) if length > MAX_DOCUMENT_SIZE => json_error(413, "document too large"),
+                Ok(_) => {
+                    // Unexpected errors of single request do not stop server
+                    std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        handle(rd, lang, &mut documents, &method, &url, body)
+                    }))
+                    .unwrap_or_else(|_| json_error(500, "internal error, details in log"))
+                }
+                Err(e) => json_error(400, &e.to_string()),
+            },
+        };
+        log::info!("{} {}: {}", method, url, status);
+        let response = tiny_http::Response::from_string(content)
+            .with_status_code(status)
+            .with_header(content_type.clone());
+        if let Err(e) = request.respond(response) {
+            log::warn!("Error responding to {} {}: {}", method, url, e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handle() -> Result<(), String> {
+        let rd: Box<dyn Residency> = Box::new(etradeTaxReturnHelper::us::US {});
+        let mut documents = Documents::default();
+        let mut request = |method: &str, url: &str, body: &[u8]| {
+            handle(&*rd, Lang::En, &mut documents, method, url, body.to_vec())
+        };
+        assert_eq!(
+            request(
+                "PUT",
+                "/documents/crypto%202023.csv",
+                b"Date,Type,Amount,Currency\n2023-05-10,sell,200.5,USD\n"
+            ),
+            (
+                201,
+                "{\"dividends\":[],\"interest\":[],\"sold\":[],\"gains_and_losses\":[],\"crypto\":[[\"05/10/23\",true,200.5,\"USD\"]]}"
+                    .to_owned()
+            )
+        );
+        // Document that could not be parsed is not kept
+        assert_eq!(
+            request("PUT", "/documents/broken.csv", &[0xff, 0xfe]).0,
+            422
+        );
+        assert_eq!(
+            request("GET", "/documents", b""),
+            (200, "[\"crypto 2023.csv\"]".to_owned())
+        );
+        assert_eq!(request("GET", "/summary?year=20x3", b"").0, 400);
+        assert_eq!(request("POST", "/documents", b"").0, 405);
+        assert_eq!(request("GET", "/statements", b"").0, 404);
+        assert_eq!(request("DELETE", "/documents", b""), (200, "[]".to_owned()));
+        assert_eq!(decode("a%2Fb.pdf"), Ok("a/b.pdf".to_owned()));
+        assert!(decode("a%2.pdf").is_err());
+        Ok(())
+    }
+}
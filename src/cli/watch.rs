@@ -0,0 +1,223 @@
+//! Watch of directory statements are downloaded to: its documents are taxed
+//! again whenever one is added, changed or removed
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+
+use etradeTaxReturnHelper::{tr, Lang};
+
+// Extensions of documents parsers recognize
+const EXTENSIONS: [&str; 4] = ["pdf", "xlsx", "csv", "json"];
+
+// Modification time and size of each document of directory, by its path
+type Snapshot = BTreeMap<String, (SystemTime, u64)>;
+
+/// Documents of directory. Hidden files and lock files of office programs
+/// (~$name.xlsx) are skipped, as are partial downloads of browsers, whose
+/// extension is e.g. .crdownload until download completes
+fn scan(dir: &Path) -> Result<Snapshot, String> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|e| format!("Error reading {}: {}", dir.display(), e))?;
+    let mut snapshot = Snapshot::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("Error reading {}: {}", dir.display(), e))?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let extension = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        if name.starts_with('.') || name.starts_with("~$") || !EXTENSIONS.contains(&&*extension) {
+            continue;
+        }
+        let metadata = entry
+            .metadata()
+            .map_err(|e| format!("Error reading {}: {}", path.display(), e))?;
+        if metadata.is_file() {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            snapshot.insert(
+                path.to_string_lossy().to_string(),
+                (modified, metadata.len()),
+            );
+        }
+    }
+    Ok(snapshot)
+}
+
+/// Documents added or changed, and documents removed since older snapshot
+fn differences(old: &Snapshot, new: &Snapshot) -> (Vec<String>, Vec<String>) {
+    let changed = new
+        .iter()
+        .filter(|(path, state)| old.get(*path) != Some(state))
+        .map(|(path, _)| path.clone())
+        .collect();
+    let removed = old
+        .keys()
+        .filter(|path| !new.contains_key(*path))
+        .cloned()
+        .collect();
+    (changed, removed)
+}
+
+fn file_names(paths: &[String]) -> String {
+    paths
+        .iter()
+        .map(|path| {
+            Path::new(path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or(path.clone())
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Wait for interval, checking every moment whether Ctrl-C was pressed
+fn wait(interval: Duration, interrupted: &AtomicBool) {
+    let tick = Duration::from_millis(100);
+    let mut waited = Duration::ZERO;
+    while waited < interval && !interrupted.load(Ordering::Relaxed) {
+        std::thread::sleep(tick.min(interval - waited));
+        waited += tick;
+    }
+}
+
+/// Check directory every interval and call refresh with paths of all its
+/// documents at start and once some were added, changed or removed. Changes
+/// are acted on when directory did not change for whole interval, so files
+/// still being written are not parsed. Errors of refresh are printed and
+/// watching goes on, it ends when Ctrl-C sets interrupted flag
+pub fn watch<E: std::fmt::Display>(
+    dir: &str,
+    interval: Duration,
+    interrupted: &AtomicBool,
+    lang: Lang,
+    mut refresh: impl FnMut(&[String]) -> Result<(), E>,
+) -> Result<(), String> {
+    let dir = Path::new(dir);
+    log::info!(
+        "Watching {} every {} s",
+        dir.display(),
+        interval.as_secs_f32()
+    );
+    let mut taxed = Snapshot::new();
+    let mut seen = scan(dir)?;
+    while !interrupted.load(Ordering::Relaxed) {
+        if seen != taxed {
+            let (changed, removed) = differences(&taxed, &seen);
+            let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
+            if !changed.is_empty() {
+                println!(
+                    "===> {} ({}): {}",
+                    tr(lang, "NOWE LUB ZMIENIONE DOKUMENTY"),
+                    now,
+                    file_names(&changed)
+                );
+            }
+            if !removed.is_empty() {
+                println!(
+                    "===> {} ({}): {}",
+                    tr(lang, "USUNIETE DOKUMENTY"),
+                    now,
+                    file_names(&removed)
+                );
+            }
+            let documents: Vec<String> = seen.keys().cloned().collect();
+            if documents.is_empty() {
+                log::info!("No documents left in {}", dir.display());
+            } else if let Err(e) = refresh(&documents) {
+                // e.g. sales without Gain & Losses document downloaded yet
+                let prefix = tr(lang, "Error");
+                eprintln!(
+                    "{}",
+                    etradeTaxReturnHelper::error(&format!("{}: {}", prefix, e))
+                );
+                log::error!("{}", e);
+            }
+            taxed = seen.clone();
+        }
+        // Directory is scanned until it stays the same for whole interval
+        loop {
+            wait(interval, interrupted);
+            if interrupted.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            let current = scan(dir)?;
+            if current == seen {
+                break;
+            }
+            seen = current;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_watch() -> Result<(), String> {
+        let dir = std::env::temp_dir().join(format!("etrade_watch_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let write = |name: &str, content: &str| {
+            std::fs::write(dir.join(name), content).map_err(|e| e.to_string())
+        };
+        write("Brokerage Statement - XXXX0848 - 202401.pdf", "january")?;
+        write("notes.txt", "not a document")?;
+        write("Brokerage Statement - XXXX0848 - 202402.pdf.crdownload", "")?;
+        write("~$G&L_Expanded.xlsx", "lock")?;
+        let first = scan(&dir)?;
+        let path = |name: &str| dir.join(name).to_string_lossy().to_string();
+        assert_eq!(
+            first.keys().cloned().collect::<Vec<String>>(),
+            vec![path("Brokerage Statement - XXXX0848 - 202401.pdf")]
+        );
+
+        write("Brokerage Statement - XXXX0848 - 202402.pdf", "february")?;
+        write("G&L_Expanded.xlsx", "gains")?;
+        let second = scan(&dir)?;
+        assert_eq!(
+            differences(&first, &second),
+            (
+                vec![
+                    path("Brokerage Statement - XXXX0848 - 202402.pdf"),
+                    path("G&L_Expanded.xlsx")
+                ],
+                vec![]
+            )
+        );
+        std::fs::remove_file(dir.join("G&L_Expanded.xlsx")).map_err(|e| e.to_string())?;
+        write(
+            "Brokerage Statement - XXXX0848 - 202401.pdf",
+            "january corrected",
+        )?;
+        assert_eq!(
+            differences(&second, &scan(&dir)?),
+            (
+                vec![path("Brokerage Statement - XXXX0848 - 202401.pdf")],
+                vec![path("G&L_Expanded.xlsx")]
+            )
+        );
+
+        // Documents are taxed at start, watching ends with Ctrl-C
+        let interrupted = AtomicBool::new(false);
+        let mut refreshed = vec![];
+        watch(
+            &dir.to_string_lossy(),
+            Duration::from_secs(60),
+            &interrupted,
+            Lang::En,
+            |documents| {
+                refreshed.push(documents.len());
+                interrupted.store(true, Ordering::Relaxed);
+                Ok::<(), String>(())
+            },
+        )?;
+        assert_eq!(refreshed, vec![2]);
+        let _ = std::fs::remove_dir_all(&dir);
+        Ok(())
+    }
+}
@@ -0,0 +1,424 @@
+//! Corrections of suspicious values of parsed documents: dates that are not
+//! valid or lie in the future and unexpected withholding ratios of dividends.
+//! User confirms each value or types the correct one, answers are kept in
+//! corrections file keyed by document and entry, and applied in later runs
+
+use std::io::{BufRead, Write};
+
+use crate::{tr, Error, Lang, ParsedStatement};
+use etax_core::transactions::is_expected_withholding;
+
+/// Value of parsed entry flagged by validation
+#[derive(Debug, Clone, PartialEq)]
+struct Finding {
+    // Kind of entry and its number in document e.g. "dividend 2"
+    entry: String,
+    field: &'static str,
+    value: String,
+    reason: String,
+}
+
+/// Answer remembered for value of document entry
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+struct Correction {
+    // File name of document, without directory
+    file: String,
+    entry: String,
+    field: String,
+    // Correction applies as long as parser gives the same value
+    parsed: String,
+    // The same as parsed value when user confirmed it
+    corrected: String,
+}
+
+/// Field of entry, to be read and replaced
+enum Field<'a> {
+    Date(&'a mut String),
+    Amount(&'a mut f32),
+}
+
+impl Field<'_> {
+    fn value(&self) -> String {
+        match self {
+            Field::Date(date) => date.to_string(),
+            Field::Amount(amount) => amount.to_string(),
+        }
+    }
+
+    /// Replace value with typed one. Date is given as parsed (MM/DD/YY) or
+    /// as on command line (YYYY-MM-DD)
+    fn set(&mut self, value: &str) -> Result<(), String> {
+        match self {
+            Field::Date(date) => {
+                let parsed = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                    .or_else(|_| chrono::NaiveDate::parse_from_str(value, "%m/%d/%y"))
+                    .map_err(|_| format!("{} is not date as MM/DD/YY or YYYY-MM-DD", value))?;
+                **date = parsed.format("%m/%d/%y").to_string();
+            }
+            Field::Amount(amount) => {
+                **amount = value
+                    .parse::<f32>()
+                    .map_err(|_| format!("{} is not a number", value))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Field of entry e.g. ("dividend 2", "tax") of statement
+fn field<'a>(statement: &'a mut ParsedStatement, entry: &str, field: &str) -> Option<Field<'a>> {
+    let (kind, number) = entry.split_once(' ')?;
+    let index = number.parse::<usize>().ok()?.checked_sub(1)?;
+    match (kind, field) {
+        ("dividend", _) | ("interest", _) => {
+            let entries = match kind {
+                "dividend" => &mut statement.dividends,
+                _ => &mut statement.interest,
+            };
+            let (date, gross, tax, _) = entries.get_mut(index)?;
+            match field {
+                "date" => Some(Field::Date(date)),
+                "gross" => Some(Field::Amount(gross)),
+                "tax" => Some(Field::Amount(tax)),
+                _ => None,
+            }
+        }
+        ("sale", "trade date") => Some(Field::Date(&mut statement.sold.get_mut(index)?.0)),
+        ("sale", "settlement date") => Some(Field::Date(&mut statement.sold.get_mut(index)?.1)),
+        ("gain", "acquisition date") => Some(Field::Date(
+            &mut statement.gains_and_losses.get_mut(index)?.0,
+        )),
+        ("gain", "sold date") => Some(Field::Date(
+            &mut statement.gains_and_losses.get_mut(index)?.1,
+        )),
+        ("crypto", "date") => Some(Field::Date(&mut statement.crypto.get_mut(index)?.0)),
+        _ => None,
+    }
+}
+
+/// Reason date is suspicious, if it is
+fn check_date(date: &str, today: chrono::NaiveDate) -> Option<String> {
+    match chrono::NaiveDate::parse_from_str(date, "%m/%d/%y") {
+        Err(_) => Some("date is not valid".to_owned()),
+        Ok(date) if date > today => Some("date is in the future".to_owned()),
+        Ok(_) => None,
+    }
+}
+
+/// Suspicious values of entries of statement
+fn findings(statement: &ParsedStatement, today: chrono::NaiveDate) -> Vec<Finding> {
+    let mut findings = vec![];
+    let mut date = |entry: String, field: &'static str, value: &str| {
+        if let Some(reason) = check_date(value, today) {
+            findings.push(Finding {
+                entry,
+                field,
+                value: value.to_owned(),
+                reason,
+            });
+        }
+    };
+    for (kind, entries) in [
+        ("dividend", &statement.dividends),
+        ("interest", &statement.interest),
+    ] {
+        entries
+            .iter()
+            .enumerate()
+            .for_each(|(i, (value, ..))| date(format!("{} {}", kind, i + 1), "date", value));
+    }
+    statement.sold.iter().enumerate().for_each(|(i, sold)| {
+        date(format!("sale {}", i + 1), "trade date", &sold.0);
+        date(format!("sale {}", i + 1), "settlement date", &sold.1);
+    });
+    statement
+        .gains_and_losses
+        .iter()
+        .enumerate()
+        .for_each(|(i, gain)| {
+            date(format!("gain {}", i + 1), "acquisition date", &gain.0);
+            date(format!("gain {}", i + 1), "sold date", &gain.1);
+        });
+    statement
+        .crypto
+        .iter()
+        .enumerate()
+        .for_each(|(i, crypto)| date(format!("crypto {}", i + 1), "date", &crypto.0));
+    statement
+        .dividends
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, gross, tax, _))| !is_expected_withholding(*gross, *tax))
+        .for_each(|(i, (_, gross, tax, symbol))| {
+            findings.push(Finding {
+                entry: format!("dividend {}", i + 1),
+                field: "tax",
+                value: tax.to_string(),
+                reason: format!(
+                    "unexpected withholding ratio {:.1}% of {} dividend ${}",
+                    100.0 * tax / gross,
+                    symbol,
+                    gross
+                ),
+            })
+        });
+    findings
+}
+
+/// Name of document corrections are keyed by, so they apply wherever
+/// document is kept
+fn file_name(path: &str) -> String {
+    std::path::Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or(path.to_owned())
+}
+
+/// Answers of earlier runs kept in corrections file
+#[derive(Debug, Default)]
+pub(crate) struct Corrections {
+    // Without file answers apply to this run only
+    path: Option<String>,
+    corrections: Vec<Correction>,
+    // Answers were given in this run
+    changed: bool,
+}
+
+impl Corrections {
+    /// Corrections of file, none if it does not exist yet
+    pub(crate) fn open(path: &str) -> Result<Self, Error> {
+        let corrections = match std::fs::read(path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .map_err(|e| Error::Report(format!("Error reading corrections {}: {}", path, e)))?,
+            Err(_) => vec![],
+        };
+        Ok(Corrections {
+            path: Some(path.to_owned()),
+            corrections,
+            changed: false,
+        })
+    }
+
+    /// Apply remembered corrections to statement of document, then ask
+    /// about suspicious values not answered yet when prompting
+    pub(crate) fn correct(
+        &mut self,
+        document: &str,
+        statement: &mut ParsedStatement,
+        prompt: Option<(&mut dyn BufRead, &mut dyn Write)>,
+        lang: Lang,
+    ) -> Result<(), Error> {
+        let file = file_name(document);
+        let mut answered: Vec<(String, &'static str)> = vec![];
+        let found = findings(statement, chrono::Local::now().naive_local().date());
+        self.corrections
+            .iter()
+            .filter(|c| c.file == file)
+            .for_each(|c| {
+                let Some(mut value) = field(statement, &c.entry, &c.field) else {
+                    return;
+                };
+                if value.value() != c.parsed {
+                    return;
+                }
+                if let Some(finding) = found
+                    .iter()
+                    .find(|f| f.entry == c.entry && f.field == c.field)
+                {
+                    answered.push((finding.entry.clone(), finding.field));
+                }
+                if c.corrected != c.parsed && value.set(&c.corrected).is_ok() {
+                    log::info!(
+                        "{} of {} in {} corrected from {} to {}",
+                        c.field,
+                        c.entry,
+                        file,
+                        c.parsed,
+                        c.corrected
+                    );
+                }
+            });
+        let Some((input, output)) = prompt else {
+            return Ok(());
+        };
+        for finding in found
+            .into_iter()
+            .filter(|f| !answered.contains(&(f.entry.clone(), f.field)))
+        {
+            let answer = ask(&file, &finding, statement, input, output, lang)
+                .map_err(|e| Error::Report(format!("Error reading correction: {}", e)))?;
+            let Some(corrected) = answer else {
+                // Unanswered values are asked about again next time
+                break;
+            };
+            self.corrections.push(Correction {
+                file: file.clone(),
+                entry: finding.entry,
+                field: finding.field.to_owned(),
+                parsed: finding.value,
+                corrected,
+            });
+            self.changed = true;
+        }
+        Ok(())
+    }
+
+    /// Write answers given in this run to corrections file
+    pub(crate) fn save(&self) -> Result<(), Error> {
+        let Some(path) = self.path.as_ref().filter(|_| self.changed) else {
+            return Ok(());
+        };
+        let content = serde_json::to_string_pretty(&self.corrections)
+            .map_err(|e| Error::Report(format!("Error serializing corrections: {}", e)))?;
+        crate::report::export::write_file(path, content.as_bytes()).map_err(Error::Report)?;
+        log::info!("Corrections saved to {}", path);
+        Ok(())
+    }
+}
+
+/// Ask user to confirm suspicious value or type the correct one, until
+/// valid value is typed. Value is set in statement and returned, none when
+/// input ended
+fn ask(
+    file: &str,
+    finding: &Finding,
+    statement: &mut ParsedStatement,
+    input: &mut dyn BufRead,
+    output: &mut dyn Write,
+    lang: Lang,
+) -> std::io::Result<Option<String>> {
+    writeln!(
+        output,
+        "{}: {}, {}, {}: {} ({})",
+        tr(lang, "PODEJRZANA WARTOSC"),
+        file,
+        finding.entry,
+        finding.field,
+        finding.value,
+        finding.reason
+    )?;
+    loop {
+        write!(
+            output,
+            "{}: ",
+            tr(
+                lang,
+                "Enter potwierdza wartosc, mozna tez wpisac poprawna wartosc"
+            )
+        )?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(Some(finding.value.clone()));
+        }
+        let Some(mut value) = field(statement, &finding.entry, finding.field) else {
+            return Ok(None);
+        };
+        match value.set(line) {
+            Ok(()) => return Ok(Some(value.value())),
+            Err(e) => writeln!(output, "{}", crate::warning(&e))?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statement() -> ParsedStatement {
+        ParsedStatement {
+            dividends: vec![
+                ("03/01/23".to_owned(), 100.0, 15.0, "INTC".to_owned()),
+                ("06/01/23".to_owned(), 100.0, 51.0, "INTC".to_owned()),
+            ],
+            sold: vec![(
+                "13/05/23".to_owned(),
+                "04/05/23".to_owned(),
+                10,
+                30.0,
+                300.0,
+            )],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_findings() -> Result<(), String> {
+        let today = chrono::NaiveDate::from_ymd_opt(2023, 12, 31).ok_or("invalid date")?;
+        let mut statement = statement();
+        statement.crypto = vec![("01/15/24".to_owned(), true, 200.0, "USD".to_owned())];
+        assert_eq!(
+            findings(&statement, today)
+                .iter()
+                .map(|f| (f.entry.as_str(), f.field, f.reason.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("sale 1", "trade date", "date is not valid"),
+                ("crypto 1", "date", "date is in the future"),
+                (
+                    "dividend 2",
+                    "tax",
+                    "unexpected withholding ratio 51.0% of INTC dividend $100"
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_corrections() -> Result<(), String> {
+        let path = std::env::temp_dir()
+            .join(format!("etrade_corrections_{}.json", std::process::id()))
+            .to_string_lossy()
+            .to_string();
+        let document = "statements/Brokerage Statement - XXXX0848 - 202306.pdf";
+        let mut corrections = Corrections::open(&path).map_err(|e| e.to_string())?;
+        let mut statement = statement();
+        // Invalid date is typed again, then tax of dividend is corrected
+        let mut input: &[u8] = b"2023-05-32\n05/13/23\n15\n";
+        let mut output = vec![];
+        corrections
+            .correct(
+                document,
+                &mut statement,
+                Some((&mut input, &mut output)),
+                Lang::En,
+            )
+            .map_err(|e| e.to_string())?;
+        assert_eq!(statement.sold[0].0, "05/13/23");
+        assert_eq!(statement.dividends[1].2, 15.0);
+        let output = String::from_utf8(output).map_err(|e| e.to_string())?;
+        assert!(output.contains(
+            "SUSPICIOUS VALUE: Brokerage Statement - XXXX0848 - 202306.pdf, sale 1, trade date: 13/05/23 (date is not valid)"
+        ));
+        assert!(output.contains("2023-05-32 is not date as MM/DD/YY or YYYY-MM-DD"));
+        corrections.save().map_err(|e| e.to_string())?;
+
+        // Remembered corrections are applied without asking
+        let mut corrections = Corrections::open(&path).map_err(|e| e.to_string())?;
+        let mut statement = self::statement();
+        corrections
+            .correct(document, &mut statement, None, Lang::En)
+            .map_err(|e| e.to_string())?;
+        assert_eq!(statement.sold[0].0, "05/13/23");
+        assert_eq!(statement.dividends[1].2, 15.0);
+        let mut input: &[u8] = b"";
+        let mut output = vec![];
+        let mut statement = self::statement();
+        corrections
+            .correct(
+                document,
+                &mut statement,
+                Some((&mut input, &mut output)),
+                Lang::En,
+            )
+            .map_err(|e| e.to_string())?;
+        assert!(output.is_empty());
+        let _ = std::fs::remove_file(&path);
+        Ok(())
+    }
+}
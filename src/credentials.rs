@@ -0,0 +1,62 @@
+//! Secrets of services (SMTP password, broker API tokens) kept in keychain of
+//! operating system instead of plain text files: Keychain on macOS,
+//! Credential Manager on Windows and Secret Service (GNOME Keyring, KWallet)
+//! on Linux
+
+// Service of keychain entries, user of entry is <service>:<user of service>
+const KEYCHAIN_SERVICE: &str = "etradeTaxReturnHelper";
+
+/// Services secrets are stored for with login subcommand
+pub const SERVICES: [&str; 2] = ["smtp", "etrade"];
+
+fn entry(service: &str, user: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, &format!("{}:{}", service, user))
+        .map_err(|e| format!("Error accessing keychain: {}", e))
+}
+
+/// Keep secret of user of service in keychain, replacing earlier one
+pub fn store_secret(service: &str, user: &str, secret: &str) -> Result<(), String> {
+    entry(service, user)?
+        .set_password(secret)
+        .map_err(|e| format!("Error storing secret of {} in keychain: {}", service, e))
+}
+
+/// Secret of user of service, None if it was not stored
+pub fn load_secret(service: &str, user: &str) -> Result<Option<String>, String> {
+    match entry(service, user)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!(
+            "Error reading secret of {} from keychain: {}",
+            service, e
+        )),
+    }
+}
+
+/// Remove secret of user of service from keychain. False if there was none
+pub fn delete_secret(service: &str, user: &str) -> Result<bool, String> {
+    match entry(service, user)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(format!(
+            "Error removing secret of {} from keychain: {}",
+            service, e
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secrets() -> Result<(), String> {
+        // Keychain of test machine is not touched. Entries of mock keychain
+        // do not share secrets, so only missing ones can be checked
+        keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+        assert_eq!(load_secret("smtp", "jan.kowalski@gmail.com")?, None);
+        assert!(!delete_secret("smtp", "jan.kowalski@gmail.com")?);
+        store_secret("smtp", "jan.kowalski@gmail.com", "secret")?;
+        Ok(())
+    }
+}
@@ -2,11 +2,11 @@ use regex::Regex;
 
 pub struct DE {}
 
-impl etradeTaxReturnHelper::Residency for DE {
+impl crate::Residency for DE {
     fn get_exchange_rates(
         &self,
         dates: &mut std::collections::HashMap<String, Option<(String, f32)>>,
-    ) -> Result<(), String> {
+    ) -> Result<(), crate::RateError> {
         self.get_currency_exchange_rates(dates, "USD", "EUR")
     }
 
@@ -16,15 +16,18 @@ impl etradeTaxReturnHelper::Residency for DE {
         let start_offset = body
             .find(pattern)
             .ok_or(&format!("Error finding pattern: {}", pattern))?;
-        let pattern_slice = &body[start_offset..start_offset + 100]; // 100 characters should be enough
-                                                                     // Extract exchange rate (fp32 value)
+        // 100 characters should be enough
+        let pattern_slice: String = body[start_offset..].chars().take(100).collect();
+        // Extract exchange rate (fp32 value)
         log::info!("Exchange rate slice:  {}", pattern_slice);
-        let re = Regex::new(r"[0-9]+[.][0-9]+").unwrap();
+        let re = Regex::new(r"[0-9]+[.][0-9]+").map_err(|e| e.to_string())?;
 
-        let exchange_rate: f32 = match re.find(pattern_slice) {
-            Some(hit) => hit.as_str().parse::<f32>().unwrap(),
-            None => panic!(),
-        };
+        let exchange_rate: f32 = re
+            .find(&pattern_slice)
+            .ok_or(format!("Error finding exchange rate in: {}", pattern_slice))?
+            .as_str()
+            .parse::<f32>()
+            .map_err(|e| format!("Error parsing exchange rate: {}", e))?;
 
         // Parse date
         let pattern = "USD to EUR on ";
@@ -32,16 +35,16 @@ impl etradeTaxReturnHelper::Residency for DE {
             .find(pattern)
             .ok_or(&format!("Error finding pattern: {}", pattern))?;
         // ..USD to EUR on 2023-2-20....
-        let date_pattern_slice = &body[start_date_offset + pattern.chars().count()..];
+        let date_pattern_slice = &body[start_date_offset + pattern.len()..];
 
-        let re = Regex::new(r"[0-9]+[-][0-9]+-[0-9]+").unwrap();
-        let date_string: &str = match re.find(date_pattern_slice) {
-            Some(hit) => hit.as_str(),
-            None => panic!(),
-        };
+        let re = Regex::new(r"[0-9]+[-][0-9]+-[0-9]+").map_err(|e| e.to_string())?;
+        let date_string: &str = re
+            .find(date_pattern_slice)
+            .ok_or("Error finding date of exchange rate".to_owned())?
+            .as_str();
 
-        let exchange_rate_date =
-            chrono::NaiveDate::parse_from_str(date_string, "%Y-%m-%d").unwrap();
+        let exchange_rate_date = chrono::NaiveDate::parse_from_str(date_string, "%Y-%m-%d")
+            .map_err(|_| format!("Error parsing date of exchange rate: {}", date_string))?;
 
         Ok((
             exchange_rate,
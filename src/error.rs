@@ -0,0 +1,104 @@
+use crate::rates::RateError;
+use crate::status::{EXIT_FAILURE, EXIT_INTERRUPTED, EXIT_NETWORK, EXIT_VALIDATION};
+use etax_parsers::ParseError;
+
+/// Errors ending computation of taxation
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error(transparent)]
+    Rates(#[from] RateError),
+    /// Sold transactions not matching Gains & Losses document
+    #[error("inconsistent transactions: {0}")]
+    Transactions(String),
+    /// Invalid command line argument, tax rules or other input
+    #[error("{0}")]
+    Arguments(String),
+    /// Request to service other than exchange rates (e.g. E*TRADE API) failed
+    #[error("{0}")]
+    Network(String),
+    /// Writing or sending of reports
+    #[error("{0}")]
+    Report(String),
+    /// Run stopped by user, transactions processed so far are in given file
+    #[error("interrupted, transactions processed so far written to {0}")]
+    Interrupted(String),
+    /// Capability (e.g. xlsx export) of cargo feature program was built without
+    #[error("{0} is not available in this build, install it with: cargo install etradeTaxReturnHelper --features {1}")]
+    MissingFeature(String, String),
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::Report(message)
+    }
+}
+
+impl Error {
+    /// Exit code of program ended with this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Parse(_)
+            | Error::Transactions(_)
+            | Error::Arguments(_)
+            | Error::MissingFeature(..) => EXIT_VALIDATION,
+            Error::Rates(_) | Error::Network(_) => EXIT_NETWORK,
+            Error::Report(_) => EXIT_FAILURE,
+            Error::Interrupted(_) => EXIT_INTERRUPTED,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error() -> Result<(), String> {
+        let error = Error::from(RateError::Request {
+            url: "http://api.nbp.pl/api/exchangerates/rates/a/usd/2023-03-30/?format=json"
+                .to_owned(),
+            reason: "connection refused".to_owned(),
+        });
+        assert_eq!(error.exit_code(), EXIT_NETWORK);
+        assert_eq!(
+            error.to_string(),
+            "getting exchange rate from http://api.nbp.pl/api/exchangerates/rates/a/usd/2023-03-30/?format=json failed: connection refused"
+        );
+        let error = Error::from(ParseError::Open {
+            path: "missing.pdf".to_owned(),
+            reason: "No such file or directory".to_owned(),
+        });
+        assert_eq!(error.exit_code(), EXIT_VALIDATION);
+        assert_eq!(
+            error.to_string(),
+            "unable to open missing.pdf: No such file or directory"
+        );
+        assert_eq!(
+            Error::from("Error: unable to write out.csv".to_owned()).exit_code(),
+            EXIT_FAILURE
+        );
+        assert_eq!(
+            Error::Arguments("error getting tax year value: 20x3".to_owned()).exit_code(),
+            EXIT_VALIDATION
+        );
+        assert_eq!(
+            Error::Network("Error: E*TRADE API not available".to_owned()).exit_code(),
+            EXIT_NETWORK
+        );
+        let error = Error::Interrupted("etradeTaxHelper-partial.json".to_owned());
+        assert_eq!(error.exit_code(), EXIT_INTERRUPTED);
+        assert_eq!(
+            error.to_string(),
+            "interrupted, transactions processed so far written to etradeTaxHelper-partial.json"
+        );
+        let error = Error::MissingFeature("xlsx export".to_owned(), "xlsx".to_owned());
+        assert_eq!(error.exit_code(), EXIT_VALIDATION);
+        assert_eq!(
+            error.to_string(),
+            "xlsx export is not available in this build, install it with: cargo install etradeTaxReturnHelper --features xlsx"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,298 @@
+// Started from file manager on Windows, so no console window is opened
+#![cfg_attr(windows, windows_subsystem = "windows")]
+
+use eframe::egui;
+use egui_extras::{Column, TableBuilder};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+
+use etradeTaxReturnHelper::{pl, tr, ExportFormat, Lang, Residency, TaxYearResult};
+
+#[path = "../logging.rs"]
+mod logging;
+use logging::ResultExt;
+
+// Figures of each tax year and tax to be paid
+type Results = Vec<(i32, TaxYearResult, Option<f32>)>;
+
+enum Task {
+    Compute(Receiver<Result<Results, String>>),
+    Export(String, Receiver<Result<Results, String>>),
+}
+
+struct App {
+    documents: Vec<String>,
+    year: String,
+    lang: Lang,
+    task: Option<Task>,
+    results: Results,
+    message: Option<String>,
+}
+
+/// Taxation of documents with Polish residency, run in background thread as
+/// exchange rates are fetched over network
+fn compute(
+    documents: Vec<String>,
+    year: Option<i32>,
+    lang: Lang,
+    export: Vec<(ExportFormat, String)>,
+) -> Result<Results, String> {
+    let rules = etradeTaxReturnHelper::load_tax_rules("pl", None)?;
+    let rd: Box<dyn Residency> = Box::new(pl::PL::new(pl::Form::PIT38, rules, lang));
+    let options = etradeTaxReturnHelper::TaxationOptions {
+        year,
+        export,
+        lang,
+        ..Default::default()
+    };
+    let results =
+        etradeTaxReturnHelper::run_taxation(&*rd, documents.iter().map(|d| d.as_str()), &options)
+            .map_err(|e| e.to_string())?;
+    Ok(results
+        .into_iter()
+        .map(|(year, result)| {
+            let tax_due = rd
+                .rules_for_year(year)
+                .map(|rules| etradeTaxReturnHelper::compute_tax_due(&result, &rules));
+            (year, result, tax_due)
+        })
+        .collect())
+}
+
+impl App {
+    fn tr<'a>(&self, text: &'a str) -> &'a str {
+        tr(self.lang, text)
+    }
+
+    fn start(&mut self, ctx: &egui::Context, export: Option<(ExportFormat, String)>) {
+        let year = match self.year.trim() {
+            "" => None,
+            year => match year.parse::<i32>() {
+                Ok(year) => Some(year),
+                Err(_) => {
+                    self.message = Some(format!("{}: {}", self.tr("Bledny rok"), year));
+                    return;
+                }
+            },
+        };
+        let (sender, receiver) = channel();
+        let (documents, lang, ctx) = (self.documents.clone(), self.lang, ctx.clone());
+        let exports = export.iter().cloned().collect::<Vec<_>>();
+        std::thread::spawn(move || {
+            let _ = sender.send(compute(documents, year, lang, exports));
+            ctx.request_repaint();
+        });
+        self.message = None;
+        self.task = Some(match export {
+            Some((_, path)) => Task::Export(path, receiver),
+            None => Task::Compute(receiver),
+        });
+    }
+
+    // Results of finished background task
+    fn poll(&mut self) {
+        let (receiver, export) = match &self.task {
+            Some(Task::Compute(receiver)) => (receiver, None),
+            Some(Task::Export(path, receiver)) => (receiver, Some(path.clone())),
+            None => return,
+        };
+        let outcome = match receiver.try_recv() {
+            Err(TryRecvError::Empty) => return,
+            Ok(outcome) => outcome,
+            // Thread panicked, reason is logged
+            Err(TryRecvError::Disconnected) => {
+                Err(self.tr("Blad obliczen, szczegoly w logu").to_owned())
+            }
+        };
+        self.task = None;
+        match (outcome, export) {
+            (Ok(results), None) => self.results = results,
+            (Ok(_), Some(path)) => {
+                self.message = Some(format!("{}: {}", self.tr("Zapisano"), path))
+            }
+            (Err(e), _) => self.message = Some(e),
+        }
+    }
+
+    fn add_documents(&mut self, paths: impl Iterator<Item = std::path::PathBuf>) {
+        paths
+            .map(|path| path.to_string_lossy().to_string())
+            .for_each(|path| {
+                if !self.documents.contains(&path) {
+                    self.documents.push(path);
+                }
+            });
+        self.results.clear();
+    }
+
+    fn results_table(&self, ui: &mut egui::Ui) {
+        let titles = [
+            "Rok podatkowy",
+            "Dywidendy przychod",
+            "Dywidendy podatek zaplacony",
+            "Odsetki przychod",
+            "Sprzedaz akcji przychod",
+            "Sprzedaz akcji koszt",
+            "DOPLATA",
+        ];
+        TableBuilder::new(ui)
+            .striped(true)
+            .columns(Column::auto().at_least(90.0), titles.len())
+            .header(20.0, |mut header| {
+                titles.iter().for_each(|title| {
+                    header.col(|ui| {
+                        ui.strong(self.tr(title));
+                    });
+                });
+            })
+            .body(|mut body| {
+                self.results.iter().for_each(|(year, result, tax_due)| {
+                    let amounts = [
+                        result.gross_div,
+                        result.tax_div,
+                        result.gross_interest,
+                        result.gross_sold,
+                        result.cost_sold,
+                    ];
+                    body.row(18.0, |mut row| {
+                        row.col(|ui| {
+                            ui.label(year.to_string());
+                        });
+                        amounts.iter().for_each(|amount| {
+                            row.col(|ui| {
+                                ui.label(etradeTaxReturnHelper::format_amount(*amount, 2));
+                            });
+                        });
+                        row.col(|ui| {
+                            ui.strong(match tax_due {
+                                Some(tax_due) => {
+                                    etradeTaxReturnHelper::format_amount(*tax_due, 2) + " PLN"
+                                }
+                                None => "".to_owned(),
+                            });
+                        });
+                    });
+                });
+            });
+    }
+}
+
+impl eframe::App for App {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.poll();
+        let dropped: Vec<std::path::PathBuf> = ctx.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|file| file.path.clone())
+                .collect()
+        });
+        if !dropped.is_empty() {
+            self.add_documents(dropped.into_iter());
+        }
+        let busy = self.task.is_some();
+
+        egui::TopBottomPanel::top("settings").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.heading("E*TRADE PIT-38");
+                ui.separator();
+                ui.label(self.tr("Rok podatkowy"));
+                ui.add(egui::TextEdit::singleline(&mut self.year).desired_width(50.0));
+                ui.separator();
+                ui.selectable_value(&mut self.lang, Lang::Pl, "Polski");
+                ui.selectable_value(&mut self.lang, Lang::En, "English");
+            });
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.label(self.tr("Przeciagnij tu dokumenty E*TRADE (PDF, XLSX, CSV)"));
+            let mut removed = None;
+            self.documents.iter().enumerate().for_each(|(i, document)| {
+                ui.horizontal(|ui| {
+                    if ui.small_button("x").clicked() {
+                        removed = Some(i);
+                    }
+                    ui.label(document);
+                });
+            });
+            if let Some(i) = removed {
+                self.documents.remove(i);
+                self.results.clear();
+            }
+            ui.horizontal(|ui| {
+                if ui.button(self.tr("Dodaj pliki...")).clicked() {
+                    if let Some(paths) = rfd::FileDialog::new()
+                        .add_filter("E*TRADE", &["pdf", "xlsx", "csv"])
+                        .pick_files()
+                    {
+                        self.add_documents(paths.into_iter());
+                    }
+                }
+                let ready = !busy && !self.documents.is_empty();
+                if ui
+                    .add_enabled(ready, egui::Button::new(self.tr("Oblicz")))
+                    .clicked()
+                {
+                    self.start(ctx, None);
+                }
+                if busy {
+                    ui.spinner();
+                }
+            });
+            ui.separator();
+
+            if !self.results.is_empty() {
+                self.results_table(ui);
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(self.tr("Zapisz raport"));
+                    ["pdf", "xlsx", "csv", "html", "json"]
+                        .iter()
+                        .for_each(|&name| {
+                            let button = egui::Button::new(name.to_uppercase());
+                            if ui.add_enabled(!busy, button).clicked() {
+                                if let Some(path) = rfd::FileDialog::new()
+                                    .add_filter(name, &[name])
+                                    .set_file_name(format!("pit38.{}", name))
+                                    .save_file()
+                                {
+                                    let format = ExportFormat::from_name(name)
+                                        .expect_and_log("error getting export format");
+                                    self.start(
+                                        ctx,
+                                        Some((format, path.to_string_lossy().to_string())),
+                                    );
+                                }
+                            }
+                        });
+                });
+            }
+            if let Some(message) = &self.message {
+                ui.label(message);
+            }
+        });
+    }
+}
+
+fn main() -> Result<(), eframe::Error> {
+    logging::init_logging_infrastructure();
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size([960.0, 600.0])
+            .with_drag_and_drop(true),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "etradeTaxReturnHelper",
+        options,
+        Box::new(|_cc| {
+            Box::new(App {
+                documents: vec![],
+                year: "".to_owned(),
+                lang: Lang::Pl,
+                task: None,
+                results: vec![],
+                message: None,
+            })
+        }),
+    )
+}
@@ -0,0 +1,119 @@
+//! HTTP client shared by exchange rate services and APIs of brokers and
+//! other services. It is created once, so connections (and TLS sessions) to
+//! the same host are reused between requests instead of opened for each
+
+use std::sync::OnceLock;
+use std::time::Duration;
+
+type ReqwestClient = reqwest::blocking::Client;
+
+const USER_AGENT: &str = concat!("etradeTaxReturnHelper/", env!("CARGO_PKG_VERSION"));
+const CONNECT_TIMEOUT_SECS: u64 = 10;
+const TIMEOUT_SECS: u64 = 60;
+// Idle connections are closed before servers usually do it
+const POOL_IDLE_TIMEOUT_SECS: u64 = 30;
+
+// Requests failed due to network errors are sent again after increasing
+// delay: 0.5s, 1s, 2s
+const MAX_RETRIES: u32 = 3;
+const RETRY_DELAY_MS: u64 = 500;
+
+static CLIENT: OnceLock<ReqwestClient> = OnceLock::new();
+
+/// Client with proxies taken from env vars (http_proxy and https_proxy),
+/// TLS 1.2 or later, timeouts and pool of connections to each host as big as
+/// number of concurrent requests
+fn create_client() -> Result<ReqwestClient, String> {
+    let client_error = |e: reqwest::Error| format!("Error creating HTTP client: {}", e);
+    let base_client = ReqwestClient::builder()
+        .user_agent(USER_AGENT)
+        .min_tls_version(reqwest::tls::Version::TLS_1_2)
+        .connect_timeout(Duration::from_secs(CONNECT_TIMEOUT_SECS))
+        .timeout(Duration::from_secs(TIMEOUT_SECS))
+        .pool_idle_timeout(Duration::from_secs(POOL_IDLE_TIMEOUT_SECS))
+        .pool_max_idle_per_host(crate::parallel::MAX_CONCURRENT_REQUESTS);
+    let client = match std::env::var("http_proxy") {
+        Ok(proxy) => base_client.proxy(reqwest::Proxy::http(proxy).map_err(client_error)?),
+        Err(_) => base_client,
+    };
+    let client = match std::env::var("https_proxy") {
+        Ok(proxy) => client.proxy(reqwest::Proxy::https(proxy).map_err(client_error)?),
+        Err(_) => client,
+    };
+    client.build().map_err(client_error)
+}
+
+/// Client created with first request and used by all following ones.
+/// Proxies have to be set in environment before that
+pub(crate) fn shared_client() -> Result<&'static ReqwestClient, String> {
+    if let Some(client) = CLIENT.get() {
+        return Ok(client);
+    }
+    let client = create_client()?;
+    Ok(CLIENT.get_or_init(|| client))
+}
+
+/// Response of request, sent again when connection failed. Requests timed
+/// out are sent again only if they are GET ones, others might have been
+/// handled. Responses with error status are left to caller
+pub(crate) fn send(
+    request: reqwest::blocking::RequestBuilder,
+) -> reqwest::Result<reqwest::blocking::Response> {
+    let idempotent = request
+        .try_clone()
+        .and_then(|request| request.build().ok())
+        .is_some_and(|request| request.method() == reqwest::Method::GET);
+    let mut retries = 0;
+    loop {
+        // Request with streamed body can be sent only once
+        let Some(attempt) = request.try_clone() else {
+            return request.send();
+        };
+        match attempt.send() {
+            Err(e)
+                if retries < MAX_RETRIES && (e.is_connect() || (idempotent && e.is_timeout())) =>
+            {
+                log::warn!("{}, retrying", e);
+                std::thread::sleep(Duration::from_millis(RETRY_DELAY_MS << retries));
+                retries += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_client() -> Result<(), String> {
+        let server = tiny_http::Server::http("127.0.0.1:0").map_err(|e| e.to_string())?;
+        let url = format!("http://{}/", server.server_addr());
+        let requests = std::thread::spawn(move || {
+            (0..2)
+                .map(|_| {
+                    let request = server.recv().expect("Error receiving request");
+                    let peer = *request.remote_addr().expect("Error reading peer address");
+                    let agent = request
+                        .headers()
+                        .iter()
+                        .find(|header| header.field.equiv("User-Agent"))
+                        .map(|header| header.value.to_string());
+                    let _ = request.respond(tiny_http::Response::from_string("ok"));
+                    (peer, agent)
+                })
+                .collect::<Vec<_>>()
+        });
+        assert!(std::ptr::eq(shared_client()?, shared_client()?));
+        for _ in 0..2 {
+            let response = send(shared_client()?.get(&url)).map_err(|e| e.to_string())?;
+            assert_eq!(response.text().map_err(|e| e.to_string())?, "ok");
+        }
+        let requests = requests.join().map_err(|_| "server failed")?;
+        // Second request is sent through connection of first one
+        assert_eq!(requests[0].0, requests[1].0);
+        assert_eq!(requests[0].1, Some(USER_AGENT.to_owned()));
+        Ok(())
+    }
+}
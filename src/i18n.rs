@@ -0,0 +1,409 @@
+/// Language of summary and reports presented to user
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Lang {
+    #[default]
+    Pl,
+    En,
+}
+
+impl Lang {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "pl" => Ok(Lang::Pl),
+            "en" => Ok(Lang::En),
+            _ => Err(format!("Error: unsupported language: {}", name)),
+        }
+    }
+}
+
+/// (Polish, English) texts of summary and reports
+const TRANSLATIONS: &[(&str, &str)] = &[
+    // Summary
+    ("ROK PODATKOWY", "TAX YEAR"),
+    ("UDZIAL PODATNIKA", "TAXPAYER SHARE"),
+    ("UDZIAL MALZONKA", "SPOUSE SHARE"),
+    ("(DYWIDENDY)", "(DIVIDENDS)"),
+    ("(ODSETKI)", "(INTEREST)"),
+    ("(SPRZEDAZ AKCJI)", "(SOLD STOCK)"),
+    ("(DYWIDENDY) PRZYCHOD", "(DIVIDENDS) INCOME"),
+    ("(ODSETKI) PRZYCHOD", "(INTEREST) INCOME"),
+    ("(SPRZEDAZ AKCJI) PRZYCHOD", "(SOLD STOCK) INCOME"),
+    (
+        "(SPRZEDAZ AKCJI, INFORMACYJNIE WG 1099-B)",
+        "(SOLD STOCK, FOR INFORMATION AS IN 1099-B)",
+    ),
+    (
+        "(DYWIDENDY I ODSETKI MIESIECZNIE)",
+        "(DIVIDENDS AND INTEREST PER MONTH)",
+    ),
+    ("(KRYPTOWALUTY)", "(VIRTUAL CURRENCY)"),
+    ("(ROZNICE KURSOWE)", "(EXCHANGE RATE DIFFERENCES)"),
+    ("(SYMULACJA SPRZEDAZY)", "(SALE SIMULATION)"),
+    ("(PROGNOZA RSU)", "(RSU FORECAST)"),
+    ("(ODSETKI ZA ZWLOKE)", "(LATE PAYMENT INTEREST)"),
+    ("(KURS USD)", "(USD EXCHANGE RATE)"),
+    ("(WERYFIKACJA DOKUMENTOW)", "(DOCUMENTS CHECK)"),
+    ("(INFORMACYJNIE)", "(FOR INFORMATION)"),
+    (
+        "(KONTA IKE/IKZE/IRA - INFORMACYJNIE, NIE OPODATKOWANE)",
+        "(IKE/IKZE/IRA ACCOUNTS - FOR INFORMATION, NOT TAXED)",
+    ),
+    ("DO WYKAZANIA W", "TO REPORT IN"),
+    ("PIT-38 (CZESC C, poz. 22-23)", "PIT-38 (PART C, boxes 22-23)"),
+    ("PIT-38 (CZESC E)", "PIT-38 (PART E)"),
+    ("PIT-38 (CZESC G)", "PIT-38 (PART G)"),
+    ("PIT-38 (CZESC G, poz. 45-47)", "PIT-38 (PART G, boxes 45-47)"),
+    (
+        "PIT-36 (ZRYCZALTOWANY PODATEK OD DYWIDEND ZAGRANICZNYCH)",
+        "PIT-36 (FLAT TAX ON FOREIGN DIVIDENDS)",
+    ),
+    ("PRZYCHOD", "INCOME"),
+    ("PRZYCHOD Z ZAGRANICY", "FOREIGN INCOME"),
+    ("KOSZT", "COST"),
+    ("KOSZT UZYSKANIA PRZYCHODU", "TAX DEDUCTIBLE COST"),
+    ("DOCHOD", "GAIN"),
+    ("STRATA", "LOSS"),
+    ("KROTKOTERMINOWE", "SHORT TERM"),
+    ("DLUGOTERMINOWE", "LONG TERM"),
+    ("PRZYCHOD WYKAZANY W PIT-8C", "INCOME REPORTED IN PIT-8C"),
+    ("KOSZT WYKAZANY W PIT-8C", "COST REPORTED IN PIT-8C"),
+    ("RAZEM PRZYCHOD", "TOTAL INCOME"),
+    ("RAZEM KOSZT", "TOTAL COST"),
+    ("ZRYCZALTOWANY PODATEK", "FLAT TAX"),
+    ("ZRYCZALTOWANY PODATEK (PODATEK BELKI)", "FLAT TAX (BELKA TAX)"),
+    ("PODATEK ZAPLACONY", "TAX PAID"),
+    ("PODATEK ZAPLACONY ZAGRANICA", "TAX PAID ABROAD"),
+    ("PODATEK ZAPLACONY ZA GRANICA", "TAX PAID ABROAD"),
+    ("PODATEK DO ODLICZENIA", "CREDITABLE TAX"),
+    ("PODATEK POBRANY", "TAX WITHHELD"),
+    ("DYWIDENDY", "DIVIDENDS"),
+    (
+        "(SUMY W USD DO UZGODNIENIA Z BROKEREM)",
+        "(USD TOTALS TO RECONCILE WITH BROKER)",
+    ),
+    ("SPRZEDAZ AKCJI", "SOLD STOCK"),
+    (
+        "PODATEK POBRANY W USA PONAD STAWKE Z UMOWY",
+        "US TAX WITHHELD ABOVE TREATY RATE",
+    ),
+    (
+        "MOZLIWY DO ODZYSKANIA Z IRS PRZEZ 1040-NR, NIE PODLEGA ODLICZENIU W PL",
+        "REFUNDABLE FROM IRS WITH 1040-NR, NOT CREDITABLE IN PL",
+    ),
+    ("PIT/ZG NR", "PIT/ZG NO."),
+    ("KOD KRAJU", "COUNTRY CODE"),
+    ("PIT-38 (WZOR 17) DO WPISANIA", "PIT-38 (FORM 17) BOXES TO FILL"),
+    ("poz.", "box"),
+    ("PODATEK NALEZNY", "TAX DUE"),
+    (
+        "ZALICZKI / PODATEK POBRANY PRZEZ PLATNIKA",
+        "ADVANCES / TAX WITHHELD BY PAYER",
+    ),
+    ("DOPLATA", "TO PAY"),
+    ("NADPLATA", "OVERPAID"),
+    (
+        "UWAGA! Strata ze sprzedazy akcji NIE pomniejsza zryczaltowanego podatku od dywidend. Podatek od dywidend jest nalezny w calosci, a strate mozna odliczyc jedynie od dochodow ze sprzedazy w kolejnych 5 latach",
+        "WARNING! Loss on sold stock does NOT reduce flat tax on dividends. Tax on dividends is due in full, and loss can only be deducted from gains on sales in the next 5 years",
+    ),
+    (
+        "KOSZTY PONIESIONE W ROKU PODATKOWYM",
+        "COSTS INCURRED IN TAX YEAR",
+    ),
+    (
+        "KOSZTY NIEPOTRACONE W LATACH UBIEGLYCH",
+        "COSTS CARRIED FROM PREVIOUS YEARS",
+    ),
+    (
+        "NADWYZKA KOSZTOW (DO ROZLICZENIA W NASTEPNYM ROKU)",
+        "EXCESS OF COSTS (CARRIED TO NEXT YEAR)",
+    ),
+    (
+        "PRZYCHOD Z DODATNICH ROZNIC KURSOWYCH",
+        "INCOME FROM POSITIVE DIFFERENCES",
+    ),
+    (
+        "KOSZT Z UJEMNYCH ROZNIC KURSOWYCH",
+        "COST FROM NEGATIVE DIFFERENCES",
+    ),
+    ("SZACOWANY PODATEK", "ESTIMATED TAX"),
+    ("WARTOSC AKCJI", "STOCK VALUE"),
+    ("RAZEM WARTOSC AKCJI", "TOTAL STOCK VALUE"),
+    ("ZALEGLOSC PODATKOWA", "TAX ARREARS"),
+    ("DNI ZWLOKI", "DAYS LATE"),
+    ("ODSETKI", "INTEREST"),
+    ("DO ZAPLATY", "TO PAY"),
+    ("KURS Z DNIA", "RATE OF"),
+    ("TABELA", "TABLE"),
+    ("KRYPTOWALUTY", "VIRTUAL CURRENCY"),
+    ("NOWE LUB ZMIENIONE DOKUMENTY", "NEW OR CHANGED DOCUMENTS"),
+    ("USUNIETE DOKUMENTY", "REMOVED DOCUMENTS"),
+    // Corrections of parsed documents
+    ("PODEJRZANA WARTOSC", "SUSPICIOUS VALUE"),
+    (
+        "Enter potwierdza wartosc, mozna tez wpisac poprawna wartosc",
+        "Press Enter to confirm value or type the correct one",
+    ),
+    // Twoj e-PIT walkthrough
+    ("SKOPIOWANO DO SCHOWKA", "COPIED TO CLIPBOARD"),
+    ("KROKI WYPELNIANIA TWOJ E-PIT", "TWOJ E-PIT WALKTHROUGH"),
+    (
+        "Otworz Twoj e-PIT (podatki.gov.pl) i wybierz PIT-38 za rok",
+        "Open Twoj e-PIT (podatki.gov.pl) and choose PIT-38 for year",
+    ),
+    ("WPISZ", "ENTER"),
+    ("SPRAWDZ", "CHECK"),
+    ("CZESC C, PIT-8C, przychod", "PART C, PIT-8C, income"),
+    ("CZESC C, PIT-8C, koszty", "PART C, PIT-8C, costs"),
+    ("CZESC C, inne przychody, przychod", "PART C, other income, income"),
+    ("CZESC C, inne przychody, koszty", "PART C, other income, costs"),
+    ("CZESC C, razem przychod", "PART C, total income"),
+    ("CZESC C, razem koszty", "PART C, total costs"),
+    ("CZESC C, dochod", "PART C, gain"),
+    ("CZESC C, strata", "PART C, loss"),
+    ("CZESC D, podstawa obliczenia podatku", "PART D, tax base"),
+    ("CZESC D, podatek od dochodu", "PART D, tax on gain"),
+    ("CZESC D, podatek nalezny", "PART D, tax due"),
+    (
+        "CZESC G, zryczaltowany podatek od dywidend i odsetek",
+        "PART G, flat tax on dividends and interest",
+    ),
+    (
+        "CZESC G, podatek zaplacony za granica",
+        "PART G, tax paid abroad",
+    ),
+    ("CZESC G, roznica", "PART G, difference"),
+    ("CZESC E, kryptowaluty, przychod", "PART E, virtual currency, income"),
+    ("CZESC E, kryptowaluty, koszty", "PART E, virtual currency, costs"),
+    (
+        "CZESC E, kryptowaluty, koszty z lat ubieglych",
+        "PART E, virtual currency, costs of previous years",
+    ),
+    (
+        "Dywidendy i odsetki wykaz w PIT-36 (zryczaltowany podatek od dywidend zagranicznych)",
+        "Report dividends and interest in PIT-36 (flat tax on foreign dividends)",
+    ),
+    (
+        "Jesli portal wymaga zalacznika PIT/ZG, dane krajow pokaze --per-country",
+        "If portal asks for PIT/ZG attachment, --per-country shows data of each country",
+    ),
+    (
+        "Porownaj z kwota wyliczona przez portal",
+        "Compare with amount computed by portal",
+    ),
+    // Reports
+    ("Raport do zeznania podatkowego E*TRADE", "E*TRADE tax return report"),
+    ("Wygenerowano", "Generated"),
+    ("Dywidendy", "Dividends"),
+    ("Sprzedaz", "Sales"),
+    ("Kursy", "Rates"),
+    ("Podsumowanie", "Summary"),
+    ("Filtr", "Filter"),
+    ("Rodzaj", "Type"),
+    ("Data", "Date"),
+    ("dywidenda", "dividend"),
+    ("odsetki", "interest"),
+    ("Przychod USD", "Gross USD"),
+    ("Podatek USD", "Tax USD"),
+    ("Data kursu", "Exchange rate date"),
+    ("Kurs", "Exchange rate"),
+    ("Przychod", "Gross"),
+    ("Podatek", "Tax"),
+    ("Data rozliczenia", "Settlement date"),
+    ("Data nabycia", "Acquisition date"),
+    ("Przychod ze sprzedazy USD", "Income USD"),
+    ("Prowizje USD", "Fees USD"),
+    ("Koszt nabycia USD", "Cost basis USD"),
+    ("Okres", "Term"),
+    ("Krotki", "Short"),
+    ("Dlugi", "Long"),
+    ("Data kursu rozliczenia", "Settlement exchange rate date"),
+    ("Kurs rozliczenia", "Settlement exchange rate"),
+    ("Data kursu nabycia", "Acquisition exchange rate date"),
+    ("Kurs nabycia", "Acquisition exchange rate"),
+    ("Przychod ze sprzedazy", "Income"),
+    ("Koszt", "Cost"),
+    ("Data transakcji", "Transaction date"),
+    ("Rok podatkowy", "Tax year"),
+    ("Dywidendy przychod", "Dividends gross"),
+    ("Dywidendy podatek zaplacony", "Dividends tax paid"),
+    ("Odsetki przychod", "Interest gross"),
+    ("Odsetki podatek zaplacony", "Interest tax paid"),
+    ("Sprzedaz akcji przychod", "Sold stock income"),
+    ("Sprzedaz akcji koszt", "Sold stock cost"),
+    ("Kryptowaluty przychod", "Virtual currency income"),
+    ("Kryptowaluty koszt", "Virtual currency cost"),
+    ("Stawka podatku", "Tax rate"),
+    ("RAZEM", "TOTAL"),
+    // Review of transactions
+    ("PRZEGLAD TRANSAKCJI", "REVIEW OF TRANSACTIONS"),
+    ("dywidenda, konto chronione", "dividend, sheltered account"),
+    ("kryptowaluty, zakup", "virtual currency, purchase"),
+    ("kryptowaluty, zbycie", "virtual currency, disposal"),
+    ("sprzedaz akcji", "sold stock"),
+    ("Opis", "Description"),
+    ("Kwota", "Amount"),
+    ("Podatek / koszt", "Tax / cost"),
+    ("Prowizje", "Fees"),
+    ("Oblicz podatek z transakcji:", "Compute tax of transactions:"),
+    ("wykluczone:", "excluded:"),
+    ("t/n", "y/n"),
+    (
+        "gora/dol: wybor, lewo/prawo: kwota, spacja: wyklucz, e: edytuj, c: oblicz, q: przerwij",
+        "up/down: select, left/right: amount, space: exclude, e: edit, c: compute, q: quit",
+    ),
+    // Graphical frontend
+    (
+        "Przeciagnij tu dokumenty E*TRADE (PDF, XLSX, CSV)",
+        "Drop E*TRADE documents (PDF, XLSX, CSV) here",
+    ),
+    ("Dodaj pliki...", "Add files..."),
+    ("Oblicz", "Compute"),
+    ("Zapisz raport", "Save report"),
+    ("Zapisano", "Saved"),
+    ("Bledny rok", "Invalid year"),
+    ("Blad obliczen, szczegoly w logu", "Computation failed, see log for details"),    // Command line help
+    ("Plik TOML z domyslnymi wartosciami opcji: residency, year, lang, locale, account, sheltered_accounts i proxy (domyslnie: ~/.config/etradetaxhelper/config.toml, %APPDATA%\\etradetaxhelper\\config.toml w Windows, ~/Library/Application Support/etradetaxhelper/config.toml w macOS). Opcje podane w wierszu polecen maja pierwszenstwo", "TOML file with defaults of options: residency, year, lang, locale, account, sheltered_accounts and proxy (default: ~/.config/etradetaxhelper/config.toml, %APPDATA%\\etradetaxhelper\\config.toml on Windows, ~/Library/Application Support/etradetaxhelper/config.toml on macOS). Options given on command line take precedence"),
+    ("Profil klienta np. kowalski, aby obslugiwac wielu klientow na jednym komputerze. Domyslne wartosci opcji sa czytane z profiles/<NAME>/config.toml obok domyslnego pliku konfiguracji, dane sa dopisywane do ledger.csv w tym katalogu, chyba ze podano --ledger, a wzgledne sciezki --database i --ledger sa umieszczane w tym katalogu, a zapisywanych raportow w jego katalogu output. Katalog jest tworzony przy pierwszym uzyciu", "Client profile e.g. kowalski, so many clients can be processed on one machine. Its defaults of options are read from profiles/<NAME>/config.toml next to default config file, figures are appended to ledger.csv there unless --ledger is given, and relative paths of --database and --ledger are placed in that directory and of written reports in its output directory. Directory is created on first use"),
+    ("Katalog wtyczek parserow: programow czytajacych dokumenty innych brokerow, zob. README (domyslnie: plugins obok domyslnego pliku konfiguracji np. ~/.config/etradetaxhelper/plugins). Dokumenty pasujace do wzorcow wtyczki sa przez nia czytane", "Directory of parser plugins: programs parsing documents of other brokers, see README (default: plugins next to default config file e.g. ~/.config/etradetaxhelper/plugins). Documents matching patterns of plugin are parsed by it"),
+    ("Kraj rezydencji podatkowej np. pl , us ...", "Country of residence e.g. pl , us ..."),
+    ("Jezyk podsumowania, tabel transakcji, raportow i pomocy: pl lub en. Bez tej opcji pomoc jest w jezyku ustawien regionalnych systemu (LANG). Podsumowanie jest tlumaczone tylko dla rezydencji pl", "Language of summary, transaction tables, reports and help: pl or en. Unless given, help is in language of system locale (LANG). Summary is translated for pl residency only"),
+    ("Format kwot PLN w podsumowaniu: pl (12 345,68), en (12,345.68) lub c (12345.68)", "Number format of PLN amounts in summary: pl (12 345,68), en (12,345.68) or c (12345.68)"),
+    ("Formularz zeznania, w ktorym wykazywane sa dywidendy (tylko rezydencja pl). Sprzedaz akcji jest zawsze wykazywana w PIT-38", "Polish tax return form where dividends are reported (pl residency only). Sold stock is always reported in PIT-38"),
+    ("Plik TOML z zasadami podatkowymi (stawki, limity z umow, zaokraglenia) zastepujacymi wbudowane. Zob. tax_rules.toml", "TOML file with tax rules (rates, treaty caps, rounding) overriding bundled ones. See tax_rules.toml"),
+    ("Stawka zryczaltowanego podatku w procentach zastepujaca te z zasad podatkowych np. 19 (tylko rezydencja pl)", "Flat tax rate in percent overriding one from tax rules e.g. 19 (pl residency only)"),
+    ("Zaokraglaj kwoty posrednie tak jak kalkulator Twoj e-PIT, aby podatek do zaplaty zgadzal sie z nim co do zlotowki (tylko rezydencja pl)", "Round intermediate amounts the way Twoj e-PIT calculator does, so tax to be paid matches it to PLN (pl residency only)"),
+    ("Zapisz odpowiedzi serwisow kursow walut do pliku FILE, aby uruchomienie mozna bylo pozniej powtorzyc z --replay-http", "Record responses of exchange rate services to FILE, so the run can be repeated later with --replay-http"),
+    ("Pobierz kursy walut z odpowiedzi zapisanych przez --record-http zamiast z sieci, np. aby odtworzyc wczesniejsze uruchomienie podczas kontroli", "Take exchange rates from responses recorded with --record-http instead of network, e.g. to reproduce past run during audit"),
+    ("Nie pokazuj paskow postepu czytania dokumentow i pobierania kursow walut. Sa pokazywane tylko, gdy standardowe wyjscie bledow jest terminalem", "Do not show progress bars of parsing documents and getting exchange rates. They are shown only when standard error is terminal"),
+    ("Nie koloruj wyjscia. Kolory wylacza tez zmienna srodowiskowa NO_COLOR", "Do not color output. Colors are also disabled by NO_COLOR environment variable"),
+    ("Format komunikatow na standardowym wyjsciu bledow: text lub json, czyli wiersz JSON kazdego wpisu z polami time, level, target i message. Etapy uruchomienia (czytanie kazdego dokumentu, kursy, eksport kazdego pliku, zadania wsadowe) sa wpisami info z polami stage, file, duration_ms i outcome, wypisywanymi chyba ze podano --quiet", "Format of messages printed to standard error: text, or json for JSON line of each record with time, level, target and message. Stages of run (parse of each document, rates, export of each file, batch jobs) are info records with stage, file, duration_ms and outcome fields, printed unless --quiet is given"),
+    ("Wypisz tabele transakcji przed podsumowaniem i komunikaty informacyjne na standardowe wyjscie bledow. Podane dwukrotnie (-vv) rowniez komunikaty diagnostyczne. Zmienna srodowiskowa RUST_LOG ma pierwszenstwo dla komunikatow", "Print transaction tables before summary and info messages to standard error. Given twice (-vv) also debug messages. RUST_LOG environment variable takes precedence for messages"),
+    ("Rok podatkowy do rozliczenia np. 2023. Transakcje z innych lat sa pomijane", "Tax year to compute e.g. 2023. Transactions from other years are skipped"),
+    ("Data dywidendy, wedlug ktorej przypisywany jest kurs waluty i rok podatkowy", "Date of dividend used for exchange rate and tax year assignment"),
+    ("Numer konta (jak w wyciagu np. 0848, wystarcza ostatnie cyfry) konta z ulga podatkowa (IRA, IKE, IKZE). Jego dywidendy nie sa opodatkowane i sa pokazywane tylko informacyjnie, pozostale konta tego samego wyciagu sa opodatkowane", "Account number (as in brokerage statement e.g. 0848, last digits are enough) of tax-sheltered account (IRA, IKE, IKZE). Its dividends are not taxed and only presented for information, other accounts of the same statement are taxed"),
+    ("Dywidenda wyplacona w akcjach jako <YYYY-MM-DD>:<symbol>:<ilosc>:<cena akcji w USD> np. 2023-06-01:INTC:2:30.5\nWartosc otrzymanych akcji jest opodatkowana jak dywidenda i jest ich kosztem nabycia przy sprzedazy", "Dividend paid in shares as <YYYY-MM-DD>:<symbol>:<quantity>:<price per share in USD> e.g. 2023-06-01:INTC:2:30.5\nValue of received shares is taxed as dividend income and is their cost basis when sold"),
+    ("Zapisuj transakcje przeczytanych dokumentow do pliku punktu kontrolnego co kilka dokumentow, aby przerwane lub zakonczone awaria uruchomienie z wieloma dokumentami bylo wznawiane od tego miejsca po ponownym uruchomieniu z tym samym plikiem. Dokumenty zmienione w miedzyczasie sa czytane ponownie. Plik jest usuwany po zakonczeniu uruchomienia", "Save transactions of parsed documents to checkpoint file every few documents, so run of many documents that was interrupted or crashed resumes from there when run again with the same checkpoint file. Documents changed since are parsed again. File is removed once run completes"),
+    ("Wyciagi (brokerage statement) PDF, dokumenty Gain & Losses xlsx i eksporty CSV transakcji kryptowalut\n\nWyciagi mozna pobrac z:\n\thttps://edoc.etrade.com/e/t/onlinedocs/docsearch?doc_type=stmt\n\nDokumenty Gain&Losses mozna pobrac z:\n\thttps://us.etrade.com/etx/sp/stockplan#/myAccount/gainsLosses\n", "Brokerage statement PDFs, Gain & Losses xlsx documents and CSV exports of virtual currency transactions\n\nBrokerege statements can be downloaded from:\n\thttps://edoc.etrade.com/e/t/onlinedocs/docsearch?doc_type=stmt\n\nGain&Losses documents can be downloaded from:\n\thttps://us.etrade.com/etx/sp/stockplan#/myAccount/gainsLosses\n"),
+    ("Prowizje i oplaty zaplacone przy zakupie akcji jako <YYYY-MM-DD>:<koszt>:<oplaty> w USD np. 2022-01-10:3000:6.95\nOplaty sa dodawane do kosztu sprzedanych akcji nabytych tego dnia, w proporcji ich kosztu do kosztu zakupu", "Commission and fees paid for purchase of stock as <YYYY-MM-DD>:<cost>:<fees> in USD e.g. 2022-01-10:3000:6.95\nFees are added to cost basis of sold shares acquired that day, in proportion of their cost to cost of purchase"),
+    ("Gotowka USD wyplacona z konta u brokera jako <YYYY-MM-DD>:<kwota> np. 2023-05-10:1500.25\nGdy podana, liczone sa roznice kursowe od gotowki USD", "USD cash withdrawn from broker account as <YYYY-MM-DD>:<amount> e.g. 2023-05-10:1500.25\nWhen given, exchange rate differences on USD cash are computed"),
+    ("Przychod i koszt sprzedanych akcji z PIT-8C wystawionego przez polskiego brokera jako <przychod>:<koszt> np. 10500.20:8200 (wymaga --year)", "Income and cost of sold stock from PIT-8C issued by Polish broker as <income>:<cost> e.g. 10500.20:8200 (requires --year)"),
+    ("Podatek juz zaplacony za rok podatkowy (zaliczki lub podatek pobrany przez polskiego platnika) np. 250 (wymaga --year). Jest odejmowany od podatku do zaplaty", "Tax already paid for the tax year (advance payments or tax withheld by Polish payer) e.g. 250 (requires --year). It is subtracted from tax to be paid"),
+    ("Nadwyzka kosztow kryptowalut nad przychodem z lat ubieglych (PIT-38 czesc E) np. 1200.50", "Excess of virtual currency costs over income from previous years (PIT-38 part E) e.g. 1200.50"),
+    ("Kraj zrodla papieru wartosciowego innego niz US jako <symbol>:<kod kraju> np. VWRL:IE (uzywany przez --per-country i eksporty)", "Country of source of security other than US as <symbol>:<country code> e.g. VWRL:IE (used by --per-country and exports)"),
+    ("Przejrzyj przeczytane transakcje w interfejsie terminalowym przed obliczeniem podatku: wyklucz je lub popraw, a nastepnie zatwierdz", "Review parsed transactions in terminal UI before computing tax: exclude or correct them, then confirm"),
+    ("Zapisz transakcje, zastosowane kursy walut i wyniki kazdego roku podatkowego w pliku bazy danych SQLite. Ponowne uruchomienie dla konta i roku podatkowego zastepuje jego zapisane dane", "Store transactions, applied exchange rates and results of each tax year in SQLite database file. Rerun of account and tax year replaces its stored data"),
+    ("Nazwa konta, pod ktora dane sa zapisywane w ksiedze lub bazie danych np. etrade lub joint", "Name of account figures are recorded under in ledger or database e.g. etrade or joint"),
+    ("Przeczytaj dokumenty i pobierz kursy walut, ale zamiast zapisywac pliki, dopisywac wiersze ksiegi, zapisywac rekordy bazy danych, tworzyc arkusze i wysylac e-maile tylko wypisz, co zostaloby zrobione. Wyniki sa nadal wypisywane", "Parse documents and get exchange rates, but only report files that would be written, ledger lines appended, database records stored, sheets created and e-mails sent instead of doing it. Results are still printed"),
+    ("Zapisz wyniki do pliku w podanym formacie np. --export csv transactions.csv\n\tcsv - wszystkie przetworzone transakcje (daty, symbole, kwoty USD, kursy walut i kwoty w walucie docelowej)\n\tjson - transakcje, dane kazdego roku podatkowego i zastosowane zasady podatkowe\n\txlsx - skoroszyt z arkuszami dywidend, sprzedazy, uzytych kursow walut i podsumowania\n\tpdf - raport tego samego do wydruku i archiwizacji\n\thtml - raport tego samego z tabelami do sortowania i filtrowania\n\tmd - raport tego samego jako tabele Markdown\n\thuman - raport tego samego jako wyrownane tabele tekstowe\n\tkv - wiersze klucz=wartosc glownych danych, jak wypisuje --quiet\n\tjsonl - wiersz JSON kazdej transakcji i wyniku roku podatkowego\nWyniki sa wypisywane na standardowe wyjscie, jesli sciezka to -", "Write results to file of given format e.g. --export csv transactions.csv\n\tcsv - all processed transactions (dates, symbols, USD amounts, exchange rates and amounts in target currency)\n\tjson - transactions, figures of each tax year and tax rules applied\n\txlsx - workbook with sheets of dividends, sales, exchange rates used and summary\n\tpdf - printable report of the same for archiving\n\thtml - report of the same with sortable and filterable tables\n\tmd - report of the same as Markdown tables\n\thuman - report of the same as aligned text tables\n\tkv - key=value lines of main figures as printed by --quiet\n\tjsonl - JSON line of each transaction and tax year result\nResults are printed to standard output if path is -"),
+    ("Zapisz wyniki do pliku w formacie wynikajacym z jego rozszerzenia: .csv, .json, .xlsx, .pdf, .html, .md, .txt (human) lub .env (kv). Brakujace katalogi sa tworzone. Moze byc podane wielokrotnie np. --output out/2023.json --output out/2023.pdf", "Write results to file in format given by its extension: .csv, .json, .xlsx, .pdf, .html, .md, .txt (human) or .env (kv). Missing directories are created. Can be given multiple times e.g. --output out/2023.json --output out/2023.pdf"),
+    ("Wygeneruj wyniki z pliku szablonu w skladni podobnej do Handlebars i zapisz je pod sciezka (- dla standardowego wyjscia) np. --template accountant.txt report.txt", "Render results with Handlebars-like template file and write them to path (- for standard output) e.g. --template accountant.txt report.txt"),
+    ("Utworz arkusz Google z arkuszami dywidend, sprzedazy, uzytych kursow walut i podsumowania. Przyjmuje plik JSON klienta OAuth (typu \"TVs and Limited Input devices\") utworzony w konsoli Google Cloud z wlaczonym Sheets API. Wypisywany jest kod autoryzacji do wpisania na stronie Google", "Create Google Sheet with sheets of dividends, sales, exchange rates used and summary. Takes OAuth client JSON file (type \"TVs and Limited Input devices\") created in Google Cloud console with Sheets API enabled. Authorization code to enter at Google page is printed"),
+    ("Wyslij raport e-mailem (np. do ksiegowego) z ustawieniami z pliku TOML: smtp_url, username, from, to, subject i formaty zalacznikow (domyslnie pdf). Haslo jest brane ze zmiennej srodowiskowej SMTP_PASSWORD. Wymaga curl", "Send report by e-mail (e.g. to accountant) with settings from TOML file: smtp_url, username, from, to, subject and formats of attachments (pdf by default). Password is taken from SMTP_PASSWORD environment variable. Requires curl"),
+    ("Dopisz dane kazdego roku podatkowego do ksiegi CSV, budujac historie wszystkich uruchomien. Ostatni wiersz konta i roku podatkowego zawiera jego aktualne dane", "Append figures of each tax year to CSV ledger file, building history of all runs. Latest line of account and tax year is its current figures"),
+    ("Zastap symbole i przeskaluj kwoty losowym czynnikiem w eksportowanych raportach (--export, --output, --format, --template, --audit), aby mozna je bylo udostepnic np. zglaszajac blad parsera. Podsumowanie wypisywane w terminalu nie jest zmieniane", "Replace symbols and scale amounts by random factor in exported reports (--export, --output, --format, --template, --audit), so they can be shared e.g. when reporting parser bug. Summary printed to terminal is not affected"),
+    ("Zapisz do pliku tekstowego raport kontrolny z transakcjami, kursami walut z ich tabelami, wzorami i zaokragleniami kazdej kwoty", "Write audit report listing transactions, exchange rates with their tables, formulas and rounding of every figure to text file"),
+    ("Podziel przychod i zaplacony podatek konta wspolnego z malzonkiem. Opcjonalna wartosc to Twoj udzial w procentach np. --split-with-spouse=60 (domyslnie 50)", "Split income and tax paid of jointly owned account with spouse. Optional value is your share in percent e.g. --split-with-spouse=60 (default 50)"),
+    ("Zapisz do pliku dokument XML e-Deklaracje PIT-38 podanego roku podatkowego (tylko rezydencja pl, wymaga --year i --taxpayer). Schemat formularza jest brany z zasad podatkowych", "Write PIT-38 e-Deklaracje XML document of given tax year to file (pl residency only, requires --year and --taxpayer). Schema of the form is taken from tax rules"),
+    ("Skopiuj kwoty PIT-38 roku podatkowego podanego w --year (lub ostatniego) do schowka jako wiersz rozdzielony tabulatorami w kolejnosci pozycji, do wklejenia w formularz Twoj e-PIT (tylko rezydencja pl)", "Copy PIT-38 amounts of tax year given with --year (or the latest one) to clipboard as tab separated line in box order, to paste into Twoj e-PIT form (pl residency only)"),
+    ("Plik TOML z danymi identyfikacyjnymi podatnika uzywanymi w XML e-Deklaracji: pesel (lub nip), first_name, last_name, birth_date, tax_office", "TOML file with identification data of taxpayer used in e-Deklaracje XML: pesel (or nip), first_name, last_name, birth_date, tax_office"),
+    ("Format wynikow wypisywanych na standardowe wyjscie. human to podsumowanie i tabele transakcji, jsonl wypisuje wiersz JSON kazdego dokumentu i transakcji zaraz po ich przetworzeniu, a nastepnie wiersz JSON wyniku kazdego roku podatkowego. Pozostale formaty wypisuja ten sam dokument co --export", "Format of results printed to standard output. human is summary and transaction tables, jsonl prints JSON line of each document and transaction as soon as it is processed, followed by JSON line of each tax year result. Other formats print the same document as --export does"),
+    ("Wypisz tylko wiersze klucz=wartosc kazdego roku podatkowego (tax_year, gross_pln, tax_paid_pln, tax_due_pln) dla skryptow. Ostrzezenia trafiaja na standardowe wyjscie bledow, zadne inne komunikaty nie sa tam wypisywane", "Print only key=value lines of each tax year (tax_year, gross_pln, tax_paid_pln, tax_due_pln) for scripts. Warnings go to standard error, no other messages are printed there"),
+    ("Pokaz dywidendy, zaplacony podatek i podatek do odliczenia dla kazdego symbolu", "Present dividends, tax paid and creditable tax per symbol"),
+    ("Pokaz instrukcje krok po kroku wpisania obliczonych kwot w portalu Twoj e-PIT (tylko rezydencja pl)", "Present step-by-step instructions of entering computed figures into Twoj e-PIT portal (pl residency only)"),
+    ("Pokaz dywidendy i odsetki z pobranym podatkiem w kazdym miesiacu, do porownania z miesiecznymi wyciagami", "Present dividends and interest with tax withheld per month, to compare with monthly statements"),
+    ("Pokaz dywidendy, zaplacony podatek i podatek do odliczenia dla kazdego kraju zrodla (do PIT/ZG)", "Present dividends, tax paid and creditable tax per country of source (for PIT/ZG)"),
+    ("Serwis, do ktorego uzywane jest haslo: smtp (ustawienia e-mail z --email) lub etrade (consumer secret klucza API E*TRADE)", "Service password is used for: smtp (e-mail settings of --email) or etrade (consumer secret of E*TRADE API key)"),
+    ("Uzytkownik serwisu: nazwa uzytkownika z ustawien e-mail lub consumer key klucza API E*TRADE", "User of service: username of e-mail settings or consumer key of E*TRADE API key"),
+    ("Oszacuj podatek nalezny od hipotetycznej sprzedazy posiadanych akcji", "Estimate tax due of hypothetical sale of held stock"),
+    ("Posiadane akcje do sprzedania jako <YYYY-MM-DD>:<ilosc>:<koszt nabycia akcji w USD> np. 2021-05-03:100:0.0 dla RSU", "Held stock to be sold as <YYYY-MM-DD>:<quantity>:<cost basis per share in USD> e.g. 2021-05-03:100:0.0 for RSU"),
+    ("Cena sprzedazy akcji w USD", "Sale price per share in USD"),
+    ("Podzial akcji od nabycia pakietow jako <YYYY-MM-DD>:<wspolczynnik> np. 2024-06-10:10 dla podzialu 10 do 1 (0.1 dla scalenia 1 do 10). Pakiety podaje sie z iloscia i kosztem sprzed podzialu", "Stock split since acquisition of lots as <YYYY-MM-DD>:<ratio> e.g. 2024-06-10:10 for 10-for-1 split (0.1 for 1-for-10 reverse split). Lots are to be given with pre-split quantity and cost"),
+    ("Data sprzedazy jako <YYYY-MM-DD>. Domyslnie dzisiaj", "Sale date as <YYYY-MM-DD>. Default is today"),
+    ("Prognozuj przychod i podatek od RSU pozostalych do nabycia w biezacym roku", "Forecast income and tax of RSU vests remaining in current year"),
+    ("Nadchodzace nabycie jako <YYYY-MM-DD>:<ilosc> np. 2023-11-20:25 (zob. Benefit History w E*TRADE)", "Upcoming vest as <YYYY-MM-DD>:<quantity> e.g. 2023-11-20:25 (see Benefit History at E*TRADE)"),
+    ("Spodziewana cena akcji w USD", "Expected share price in USD"),
+    ("Oblicz odsetki za zwloke od podatku zaplaconego po terminie (tylko rezydencja pl)", "Compute late payment interest (odsetki za zwloke) of tax paid after deadline (pl residency only)"),
+    ("Zaleglosc podatkowa w PLN", "Tax arrears in PLN"),
+    ("Termin platnosci jako <YYYY-MM-DD> np. 2023-04-30", "Payment deadline as <YYYY-MM-DD> e.g. 2023-04-30"),
+    ("Faktyczna data zaplaty jako <YYYY-MM-DD>. Domyslnie dzisiaj", "Actual payment date as <YYYY-MM-DD>. Default is today"),
+    ("Zastosuj obnizona stawke (50% stawki podstawowej) np. dla korekty zlozonej w ciagu 6 miesiecy od terminu i zaplaconej w ciagu 7 dni od jej zlozenia", "Apply reduced rate (50% of basic rate) e.g. for correction filed within 6 months of deadline and paid within 7 days of filing"),
+    ("Pokaz transakcje i dane, ktore zmienily sie miedzy dwoma eksportami JSON (--export json) np. po dodaniu brakujacego wyciagu", "Show transactions and figures that changed between two JSON exports (--export json) e.g. after adding missing statement"),
+    ("Eksport JSON poprzedniego uruchomienia", "JSON export of previous run"),
+    ("Eksport JSON biezacego uruchomienia", "JSON export of current run"),
+    ("Oblicz podatek z dokumentow i pokaz podsumowanie. Domyslne, gdy nie podano podpolecenia", "Compute tax of documents and present summary. Default when no subcommand is given"),
+    ("Oblicz podatek z dokumentow i zapisz ich transakcje, kursy walut i wyniki w bazie danych SQLite. Wypisywane sa tylko wiersze klucz=wartosc kazdego roku podatkowego", "Compute tax of documents and store their transactions, exchange rates and results in SQLite database. Only key=value lines of each tax year are printed"),
+    ("Oblicz podatek z dokumentow i zapisz raporty. Wypisywane sa tylko wiersze klucz=wartosc kazdego roku podatkowego", "Compute tax of documents and write reports. Only key=value lines of each tax year are printed"),
+    ("Przeczytaj dokumenty i sprawdz spojnosc ich transakcji, bez pobierania kursow walut", "Parse documents and verify consistency of their transactions, without getting exchange rates"),
+    ("Wypisz kursy walut stosowane do transakcji z podanych dat", "Print exchange rates applied to transactions of given dates"),
+    ("Daty transakcji jako <YYYY-MM-DD>", "Dates of transactions as <YYYY-MM-DD>"),
+    ("Importuj dywidendy, odsetki i sprzedaze z zakresu dat z API E*TRADE do wyciagu JSON, podawanego jako dokument zamiast wyciagow PDF. Consumer secret klucza API jest brany ze zmiennej srodowiskowej ETRADE_CONSUMER_SECRET lub z magazynu kluczy (login etrade <consumer key>)", "Import dividends, interest and sales of date range from E*TRADE API into JSON statement, to be given as financial document instead of brokerage statement PDFs. Consumer secret of API key is taken from ETRADE_CONSUMER_SECRET environment variable or keychain (login etrade <consumer key>)"),
+    ("Consumer key klucza API E*TRADE", "Consumer key of E*TRADE API key"),
+    ("Pierwszy dzien importowanych transakcji jako <YYYY-MM-DD>", "First day of imported transactions as <YYYY-MM-DD>"),
+    ("Ostatni dzien importowanych transakcji jako <YYYY-MM-DD> (domyslnie: dzisiaj)", "Last day of imported transactions as <YYYY-MM-DD> (default: today)"),
+    ("Numer konta E*TRADE do importu (domyslnie: wszystkie konta)", "Number of E*TRADE account to import (default: all accounts)"),
+    ("Uzyj API sandbox E*TRADE np. aby wyprobowac klucz API sandbox", "Use E*TRADE sandbox API e.g. to try sandbox API key"),
+    ("Plik wyciagu JSON, do ktorego zapisywane sa transakcje np. etrade-2023.json", "JSON statement file transactions are written to e.g. etrade-2023.json"),
+    ("Zapisz haslo serwisu w magazynie kluczy systemu operacyjnego, aby nie bylo przechowywane w plikach tekstowych. Haslo jest wpisywane z klawiatury lub czytane ze standardowego wejscia", "Store password of service in keychain of operating system, so it is not kept in plain text files. Password is prompted for, or read from standard input"),
+    ("Usun haslo serwisu z magazynu kluczy", "Remove password of service from keychain"),
+    ("Uruchom compute dla kazdego zadania z pliku zadan YAML (dokumenty, rok, rezydencja, profil, pliki wynikowe i inne opcje) i pokaz, ktore zadania sie powiodly. Zadania sa uruchamiane po kolei, kazde we wlasnym procesie", "Run compute for each job of YAML job file (documents, year, residency, profile, outputs and other options) and report which jobs succeeded. Jobs are run one after another, each in its own process"),
+    ("Plik YAML z lista zadan, zob. README", "YAML file with list of jobs, see README"),
+    ("Pomin zadania, ktore powiodly sie we wczesniejszym uruchomieniu pliku zadan, zapisane w <JOB_FILE>.checkpoint. Punkt kontrolny jest usuwany, gdy wszystkie zadania sie powioda", "Skip jobs that succeeded in earlier run of job file, as recorded in <JOB_FILE>.checkpoint. Checkpoint is removed once all jobs succeed"),
+    ("Udostepnij REST API: przeslij dokumenty, aby otrzymac ich transakcje i podsumowanie opodatkowania jako JSON", "Serve REST API: upload documents to get their transactions, and summary of taxation as JSON"),
+    ("Adres nasluchiwania. Nie ma uwierzytelniania, wiec uzywaj go lokalnie lub za proxy", "Address to listen at. There is no authentication, so keep it local or behind proxy"),
+    ("Obserwuj katalog, do ktorego pobierane sa wyciagi: gdy dokumenty zostana dodane, zmienione lub usuniete, oblicz ponownie podatek ich wszystkich, zaktualizuj ksiege i raporty oraz pokaz podsumowanie biezacego roku podatkowego (lub podanego w --year). Zakoncz przez Ctrl-C", "Watch directory statements are downloaded to: whenever documents are added, changed or removed, compute tax of all of them again, update ledger and reports, and present summary of the current tax year (or the one given with --year). Stop with Ctrl-C"),
+    ("Katalog wyciagow i innych dokumentow", "Directory of brokerage statements and other documents"),
+    ("Liczba sekund miedzy sprawdzeniami katalogu. Dokumenty sa opodatkowywane, gdy katalog nie zmienil sie przez ten czas, wiec pliki jeszcze pobierane nie sa przetwarzane", "Seconds between checks of directory. Documents are taxed once directory did not change for that long, so files still being downloaded are not parsed"),
+    ("Pytaj o potwierdzenie lub poprawienie podejrzanych wartosci przeczytanych dokumentow (niepoprawne lub przyszle daty, nieoczekiwany stosunek podatku pobranego od dywidend). Odpowiedzi sa zapamietywane w pliku poprawek i stosowane do tych samych dokumentow w kolejnych uruchomieniach", "Ask to confirm or correct suspicious values of parsed documents (invalid or future dates, unexpected withholding ratio of dividends). Answers are remembered in corrections file and applied to the same documents in later runs"),
+    ("Plik, w ktorym zapamietywane sa poprawki przeczytanych dokumentow (domyslnie: corrections.json obok domyslnego pliku konfiguracji lub w katalogu profilu)", "File corrections of parsed documents are remembered in (default: corrections.json next to default config file, or in profile directory)"),
+    ("Polacz transakcje z tego samego dnia i symbolu przed dopasowaniem partii z G&L do sprzedazy, tak jak raportuja je niektorzy brokerzy: sprzedaze staja sie jedna sprzedaza, a akcje kupione tego dnia sa sprzedawane przed partiami z G&L, wiec koszt sprzedanych akcji moze sie zmienic", "Net trades of the same day and symbol before lots of G&L are matched with sales, as some brokers report them: sales become one sale and shares bought that day are taken as sold before lots of G&L, so cost of sold stock may change"),
+    ("Zastap ten program najnowszym wydaniem z GitHub: plik wykonywalny tej platformy jest pobierany i weryfikowany sumami kontrolnymi SHA256 opublikowanymi z wydaniem, ktorych podpis jest weryfikowany kluczem publicznym wbudowanym w ten program", "Replace this program with latest release from GitHub: binary of this platform is downloaded and verified against SHA256 checksums published with release, whose signature is verified with public key built into this program"),
+    ("Tylko wypisz, czy dostepne jest nowsze wydanie", "Only print whether newer release is available"),
+    ("Wypisz strone man tego programu (roff) i zakoncz, np. do pakietowania: etradeTaxReturnHelper --generate-man > etradeTaxReturnHelper.1", "Print man page of this program (roff) and exit, e.g. for packaging: etradeTaxReturnHelper --generate-man > etradeTaxReturnHelper.1"),
+    ("Wyplata powinna byc podana jako <YYYY-MM-DD>:<kwota w USD>", "Withdrawal should be given as <YYYY-MM-DD>:<amount in USD>"),
+    ("Oplaty zakupu powinny byc podane jako <YYYY-MM-DD>:<koszt w USD>:<oplaty w USD>", "Purchase fee should be given as <YYYY-MM-DD>:<cost in USD>:<fees in USD>"),
+    ("Pakiet powinien byc podany jako <YYYY-MM-DD>:<ilosc>:<koszt nabycia akcji w USD>", "Lot should be given as <YYYY-MM-DD>:<quantity>:<cost basis per share in USD>"),
+    ("Nabycie powinno byc podane jako <YYYY-MM-DD>:<ilosc>", "Vest should be given as <YYYY-MM-DD>:<quantity>"),
+    ("Dywidenda w akcjach powinna byc podana jako <YYYY-MM-DD>:<symbol>:<ilosc>:<cena akcji w USD>", "Stock dividend should be given as <YYYY-MM-DD>:<symbol>:<quantity>:<price per share in USD>"),
+    ("Podzial akcji powinien byc podany jako <YYYY-MM-DD>:<wspolczynnik>", "Stock split should be given as <YYYY-MM-DD>:<ratio>"),
+    ("Kwoty PIT-8C powinny byc podane jako <przychod>:<koszt> np. 10500.20:8200", "PIT-8C figures should be given as <income>:<cost> e.g. 10500.20:8200"),
+    ("Kraj powinien byc podany jako <symbol>:<dwuliterowy kod kraju> np. VWRL:IE", "Country should be given as <symbol>:<two letter country code> e.g. VWRL:IE"),
+    ("Rok podatkowy powinien byc liczba od 2000 do", "Tax year should be a number between 2000 and"),
+    ("Data powinna byc podana jako <YYYY-MM-DD>", "Date should be given as <YYYY-MM-DD>"),
+    ("Cena powinna byc liczba dodatnia", "Price should be a positive number"),
+    ("Odstep powinien byc dodatnia liczba sekund", "Interval should be a positive number of seconds"),
+    ("Stawka podatku powinna byc procentem wiekszym od 0 i mniejszym od 100", "Tax rate should be a percentage between 0 and 100 (exclusive)"),
+    ("Udzial powinien byc procentem wiekszym od 0 i mniejszym od 100", "Share should be a percentage between 0 and 100 (exclusive)"),
+    ("Zaplacony podatek powinien byc liczba nieujemna", "Prepaid tax should be a non-negative number"),
+    ("Koszt z lat ubieglych powinien byc liczba nieujemna", "Carried cost should be a non-negative number"),
+    ("podano:", "got:"),
+    ("Blad", "Error"),
+];
+
+/// Text in given language. Text is given in either language, unknown texts
+/// (e.g. symbols, dates) are returned as they are
+pub fn tr(lang: Lang, text: &str) -> &str {
+    TRANSLATIONS
+        .iter()
+        .find(|(pl, en)| *pl == text || *en == text)
+        .map(|(pl, en)| match lang {
+            Lang::Pl => *pl,
+            Lang::En => *en,
+        })
+        .unwrap_or(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tr() -> Result<(), String> {
+        assert_eq!(tr(Lang::En, "DOPLATA"), "TO PAY");
+        assert_eq!(tr(Lang::Pl, "DOPLATA"), "DOPLATA");
+        assert_eq!(tr(Lang::Pl, "Exchange rate"), "Kurs");
+        assert_eq!(tr(Lang::En, "INTC"), "INTC");
+        assert_eq!(Lang::from_name("en"), Ok(Lang::En));
+        assert!(Lang::from_name("de").is_err());
+        Ok(())
+    }
+}
@@ -1,50 +1,407 @@
+//! Tax return of E*TRADE documents: income, tax paid and cost basis of
+//! dividends, sold stock and interest converted with exchange rates of tax
+//! residency. TaxReturnBuilder computes TaxReturn of documents read through
+//! DocumentSource, with residency of pl, de or us module (or own Residency)
+//! and exchange rates requested with HttpClient.
+//!
+//! Documented API follows semver: until 1.0 incompatible changes are made
+//! only in releases bumping minor version (0.2 to 0.3). Fields may be added to
+//! result structs in any release, so construct them with ..Default::default().
+//! Items hidden from documentation are used by command line program and GUI
+//! of this repository and may change in any release
+
+// Library is named after the program
+#![allow(non_snake_case)]
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "broker-api"))]
+mod broker;
+mod checkpoint;
+mod corrections;
+#[cfg(not(target_arch = "wasm32"))]
+mod credentials;
+pub mod de;
+mod error;
+#[cfg(not(target_arch = "wasm32"))]
+mod http;
+mod i18n;
+mod locale;
 mod logging;
-mod pdfparser;
-mod transactions;
-mod xlsxparser;
+#[cfg(not(target_arch = "wasm32"))]
+mod oauth;
+mod parallel;
+pub mod pl;
+mod progress;
+mod rates;
+mod report;
+#[cfg(not(target_arch = "wasm32"))]
+mod review;
+mod schema;
+mod simulation;
+mod status;
+mod tax_return;
+#[cfg(not(target_arch = "wasm32"))]
+mod update;
+pub mod us;
 
-use chrono;
+// Terminal UI is not available in browser
+#[cfg(target_arch = "wasm32")]
+mod review {
+    pub(crate) fn review_transactions(
+        _collected: crate::CollectedTransactions,
+        _brokers: crate::TransactionBrokers,
+        _lang: crate::Lang,
+    ) -> Result<(crate::CollectedTransactions, crate::TransactionBrokers), crate::Error> {
+        Err(crate::Error::Report(
+            "Review of transactions is not available in browser".to_owned(),
+        ))
+    }
+}
 
-type ReqwestClient = reqwest::blocking::Client;
+// Keychain is not available in browser, secrets are never found there
+#[cfg(target_arch = "wasm32")]
+mod credentials {
+    pub const SERVICES: [&str; 2] = ["smtp", "etrade"];
 
-pub use logging::ResultExt;
-use transactions::{
+    const UNAVAILABLE: &str = "Keychain is not available in browser";
+
+    pub fn store_secret(_service: &str, _user: &str, _secret: &str) -> Result<(), String> {
+        Err(UNAVAILABLE.to_owned())
+    }
+
+    pub fn load_secret(_service: &str, _user: &str) -> Result<Option<String>, String> {
+        Ok(None)
+    }
+
+    pub fn delete_secret(_service: &str, _user: &str) -> Result<bool, String> {
+        Err(UNAVAILABLE.to_owned())
+    }
+}
+
+use std::collections::BTreeMap;
+use std::io::IsTerminal;
+
+pub use error::Error;
+pub use etax_core::{
+    compute_tax_due, load_tax_rules, BrokerIncome, HoldingTerm, SoldTransaction, TaxRules,
+    TaxYearResult, Transaction, YearlyTaxRules,
+};
+pub use etax_parsers::{
+    parse_document, parse_statement, Document, DocumentSource, FileSystem, InMemory, ParseError,
+    ParsedStatement,
+};
+pub use i18n::Lang;
+pub use rates::{set_http_client, HttpClient, RateError};
+pub use report::export::ExportFormat;
+pub use tax_return::{TaxReturn, TaxReturnBuilder, TaxYear};
+
+use etax_core::tax::{
+    aggregate_per_country, compute_crypto_per_year, compute_div_taxation_per_symbol,
+    compute_excess_withholding, compute_income_per_month, compute_taxation_per_year, tax_year,
+};
+use etax_core::transactions::{
+    compare_crypto_transactions, compare_detailed_sold_transactions, compare_div_transactions,
     create_detailed_div_transactions, create_detailed_sold_transactions,
-    reconstruct_sold_transactions, verify_dividends_transactions,
+    filter_div_transactions_by_year, filter_sold_transactions_by_year, net_dividend_corrections,
+    net_same_day_trades, reconstruct_sold_transactions, sort_div_transactions,
+    sort_sold_transactions, verify_dividends_transactions, verify_withholding_ratios,
 };
 
-#[derive(Debug, PartialEq, PartialOrd)]
-pub struct Transaction {
-    pub transaction_date: String,
-    pub gross_us: f32,
-    pub tax_us: f32,
-    pub exchange_rate_date: String,
-    pub exchange_rate: f32,
-}
+// Used by command line program and GUI of this repository. Not covered by
+// semver: hidden from documentation and changed in any release
+#[doc(hidden)]
+#[cfg(all(not(target_arch = "wasm32"), feature = "broker-api"))]
+pub use broker::etrade::import_etrade;
+#[doc(hidden)]
+pub use credentials::{delete_secret, load_secret, store_secret, SERVICES};
+#[doc(hidden)]
+pub use etax_core::{
+    capital_gains, error, highlight, late_payment_interest, set_color_enabled, split_with_spouse,
+    tax_rules_for_year, warning, Income, Pit38Schema,
+};
+#[doc(hidden)]
+#[cfg(not(target_arch = "wasm32"))]
+pub use etax_parsers::{discover_plugins, set_plugins, Plugin};
+#[doc(hidden)]
+pub use i18n::tr;
+#[doc(hidden)]
+pub use locale::{format_amount, set_locale, Locale};
+#[doc(hidden)]
+pub use logging::ResultExt;
+#[doc(hidden)]
+pub use progress::{set_progress, Progress};
+#[doc(hidden)]
+pub use rates::{Recorder, Replayer};
+#[doc(hidden)]
+pub use report::database::read_database;
+#[doc(hidden)]
+pub use report::diff::diff_exports;
+#[doc(hidden)]
+pub use report::export::write_file;
+#[doc(hidden)]
+pub use report::ledger::read_ledger;
+#[doc(hidden)]
+pub use simulation::{forecast_vests, simulate_sale};
+#[doc(hidden)]
+pub use status::{
+    exit_code, set_interruption_flag, EXIT_FAILURE, EXIT_INTERRUPTED, EXIT_NETWORK, EXIT_OK,
+    EXIT_PARTIAL_PARSE, EXIT_VALIDATION,
+};
+#[doc(hidden)]
+#[cfg(not(target_arch = "wasm32"))]
+pub use update::{check_for_update, install_update, Release};
 
-// 1. settlement date
-// 2. date of purchase
-// 3. net income
-// 4. cost cost basis
-#[derive(Debug, PartialEq, PartialOrd)]
-pub struct SoldTransaction {
-    pub settlement_date: String,
-    pub acquisition_date: String,
-    pub income_us: f32,
-    pub cost_basis: f32,
-    pub exchange_rate_settlement_date: String,
-    pub exchange_rate_settlement: f32,
-    pub exchange_rate_acquisition_date: String,
-    pub exchange_rate_acquisition: f32,
+/// Options of single run of taxation
+#[doc(hidden)]
+#[derive(Debug, Default)]
+pub struct TaxationOptions {
+    // If given then only this tax year is reported
+    pub year: Option<i32>,
+    // (date, amount in USD) of cash moved out of broker account. If any
+    // are given then exchange rate differences on USD cash are computed
+    pub withdrawals: Vec<(String, f32)>,
+    // (acquisition date, cost of purchase in USD, commission and fees in USD)
+    // of bought stock. Fees are added to cost basis of sold shares of purchase
+    pub purchase_fees: Vec<(String, f32, f32)>,
+    // Trades of the same day and symbol are netted before lots are matched
+    // with sales, as some brokers report them
+    pub net_same_day_trades: bool,
+    // Account numbers (e.g. 0848) of tax-sheltered accounts (IRA, IKE, IKZE).
+    // Dividends are matched by account number read from statement
+    pub sheltered_accounts: Vec<String>,
+    // (income, cost) of sold stock from PIT-8C issued by Polish brokers for
+    // given tax year. Combined with foreign income in capital gains summary
+    pub pit8c: Option<(f32, f32)>,
+    // Tax already paid for given tax year (advance payments, tax withheld
+    // by Polish payer). Subtracted from tax to be paid
+    pub prepaid_tax: Option<f32>,
+    // Excess of virtual currency costs over income carried from years
+    // preceding documents given
+    pub crypto_carried_cost: f32,
+    // Dividends are assigned to tax year and exchange rate by record date
+    // instead of payment date
+    pub use_record_date: bool,
+    // (date, symbol, quantity, price per share in USD) of dividends paid in
+    // shares. Value of received shares is taxed as dividend income
+    pub stock_dividends: Vec<(String, String, f32, f32)>,
+    // (format, path) of files all processed transactions and results are
+    // written to
+    pub export: Vec<(ExportFormat, String)>,
+    // Path of text file audit report (inputs, exchange rate tables, formulas
+    // and rounding of every figure) is written to
+    pub audit: Option<String>,
+    // (path, account) of CSV ledger figures of each tax year are appended to,
+    // building history of all runs
+    pub ledger: Option<(String, String)>,
+    // (path, account) of SQLite database transactions, exchange rates and
+    // results of each tax year are stored in
+    pub database: Option<(String, String)>,
+    // OAuth client file used to create Google Sheet with results
+    pub google_sheets: Option<String>,
+    // Path of TOML file with settings of e-mail report is sent with
+    pub email: Option<String>,
+    // (template file, path) results are rendered to with user template
+    pub template: Option<(String, String)>,
+    // Symbols and amounts of exported reports are obfuscated so they can be
+    // shared e.g. when reporting parser bug
+    pub anonymize: bool,
+    // Parsed transactions are shown in terminal UI to be excluded or
+    // corrected before tax is computed
+    pub review: bool,
+    // Path of file answers about suspicious values of parsed documents are
+    // kept in, applied to the same documents in later runs
+    pub corrections: Option<String>,
+    // User is asked in terminal to confirm or correct suspicious values of
+    // parsed documents (invalid dates, unexpected withholding ratios)
+    pub correct: bool,
+    // Nothing is written (exports, ledger, database, audit report, template,
+    // Google Sheet, e-mail), what would be is reported instead
+    pub dry_run: bool,
+    // Path of file statements of parsed documents are checkpointed to, so
+    // interrupted run is resumed without parsing them again. Removed once
+    // run completes
+    pub checkpoint: Option<String>,
+    // Format of results printed to standard output. Transaction tables are
+    // printed only in human format, other formats replace them
+    pub format: ExportFormat,
+    // Tables of all transactions are printed before summary (human format)
+    pub transaction_tables: bool,
+    // Country of source (e.g. IE) of securities not sourced in US, keyed by symbol
+    pub countries: std::collections::HashMap<String, String>,
+    // Language of transaction tables and exported reports
+    pub lang: Lang,
+    // Documents are read from it instead of local files e.g. in WASM build
+    pub source: Option<Box<dyn DocumentSource>>,
 }
 
 pub trait Residency {
     //    fn get_exchange_rate(&self, transaction_date: &str) -> Result<(String, f32), String>;
     fn present_result(&self, gross_div: f32, tax_div: f32, gross_sold: f32, cost_sold: f32);
+    fn present_year(&self, year: i32) {
+        println!("===> TAX YEAR: {}", year);
+    }
+    fn present_share(&self, spouse: bool, share: f32) {
+        match spouse {
+            false => println!("===> YOUR SHARE: {}%", share),
+            true => println!("===> SPOUSE SHARE: {}%", share),
+        }
+    }
+    fn present_holding_terms(&self, short_term: (f32, f32), long_term: (f32, f32)) {
+        println!(
+            "===> (SOLD STOCK, SHORT-TERM) PROCEEDS: ${} COST BASIS: ${}",
+            short_term.0, short_term.1
+        );
+        println!(
+            "===> (SOLD STOCK, LONG-TERM) PROCEEDS: ${} COST BASIS: ${}",
+            long_term.0, long_term.1
+        );
+    }
+    fn present_pit8c(&self, pit8c: (f32, f32), gross_sold: f32, cost_sold: f32) {
+        println!("===> (PIT-8C) INCOME: {} COST: {}", pit8c.0, pit8c.1);
+        println!(
+            "===> (SOLD STOCK, COMBINED WITH PIT-8C) INCOME: {} COST: {}",
+            pit8c.0 + gross_sold,
+            pit8c.1 + cost_sold
+        );
+    }
+    /// Tax rules applicable to given tax year, if residency defines them
+    fn rules_for_year(&self, _year: i32) -> Option<TaxRules> {
+        None
+    }
+    fn present_tax_due(&self, _result: &TaxYearResult) {}
+    /// Step-by-step instructions of entering figures into tax return portal
+    fn present_epit_guide(&self, _result: &TaxYearResult) {
+        log::warn!("Tax return portal walkthrough is not available for this residency");
+    }
+    fn present_per_symbol(&self, per_symbol: &BTreeMap<String, (f32, f32, f32)>) {
+        per_symbol
+            .iter()
+            .for_each(|(symbol, (gross, tax, creditable))| {
+                println!(
+                    "===> (DIVIDENDS) {}: INCOME: {} TAX PAID: {} CREDITABLE TAX: {}",
+                    symbol, gross, tax, creditable
+                );
+            });
+    }
+    fn present_per_country(&self, per_country: &BTreeMap<String, (f32, f32, f32)>) {
+        per_country
+            .iter()
+            .for_each(|(country, (gross, tax, creditable))| {
+                println!(
+                    "===> (DIVIDENDS FROM {}) INCOME: {} TAX PAID: {} CREDITABLE TAX: {}",
+                    country, gross, tax, creditable
+                );
+            });
+    }
+    fn present_per_broker(&self, per_broker: &BTreeMap<String, BrokerIncome>) {
+        per_broker.iter().for_each(|(broker, income)| {
+            println!(
+                "===> (BROKER {}) DIVIDENDS: {} TAX PAID: {} INTEREST: {} TAX PAID: {} SOLD STOCK INCOME: {} COST: {}",
+                broker,
+                income.gross_div,
+                income.tax_div,
+                income.gross_interest,
+                income.tax_interest,
+                income.gross_sold,
+                income.cost_sold
+            );
+            if income.crypto_income != 0.0 || income.crypto_cost != 0.0 {
+                println!(
+                    "===> (BROKER {}, VIRTUAL CURRENCIES) INCOME: {} COST: {}",
+                    broker, income.crypto_income, income.crypto_cost
+                );
+            }
+            income
+                .per_country
+                .iter()
+                .for_each(|(country, (gross, tax, creditable))| {
+                    println!(
+                        "===> (BROKER {}, DIVIDENDS FROM {}) INCOME: {} TAX PAID: {} CREDITABLE TAX: {}",
+                        broker, country, gross, tax, creditable
+                    );
+                });
+        });
+    }
+    fn present_excess_withholding(&self, excess_us: f32) {
+        println!(
+            "===> (US WITHHOLDING ABOVE TREATY RATE, RECLAIMABLE VIA 1040-NR) ${}",
+            excess_us
+        );
+    }
+    fn present_crypto(&self, income: f32, cost: f32, carried_cost: f32) {
+        println!(
+            "===> (VIRTUAL CURRENCIES) INCOME: {} COST: {} COST CARRIED FROM PREVIOUS YEARS: {}",
+            income, cost, carried_cost
+        );
+    }
+    fn present_interest(&self, gross_interest: f32, tax_interest: f32) {
+        println!("===> (INTEREST) INCOME: {}", gross_interest);
+        println!("===> (INTEREST) TAX PAID: {}", tax_interest);
+    }
+    fn present_fx_differences(&self, fx_income: f32, fx_cost: f32) {
+        println!("===> (EXCHANGE RATE DIFFERENCES) INCOME: {}", fx_income);
+        println!("===> (EXCHANGE RATE DIFFERENCES) COST: {}", fx_cost);
+    }
+    fn present_simulation(&self, income: f32, cost: f32) {
+        println!("===> (SIMULATED SALE) INCOME: {}", income);
+        println!("===> (SIMULATED SALE) TAX DEDUCTIBLE COST: {}", cost);
+    }
+    fn present_vests_forecast(&self, forecast: &[(String, f32)]) {
+        forecast.iter().for_each(|(vest_date, income)| {
+            println!("===> (RSU VEST FORECAST) {}: INCOME: {}", vest_date, income);
+        });
+        println!(
+            "===> (RSU VEST FORECAST) TOTAL INCOME: {}",
+            forecast.iter().map(|(_, income)| income).sum::<f32>()
+        );
+    }
+    fn present_exchange_rate(&self, date: &str, rate_date: &str, rate: f32) {
+        println!(
+            "===> (EXCHANGE RATE) {}: {} (RATE OF {})",
+            date, rate, rate_date
+        );
+    }
+    fn present_late_payment_interest(&self, amount: f32, days: i64, interest: f32) {
+        println!(
+            "===> (LATE PAYMENT) TAX ARREARS: {} DAYS LATE: {} INTEREST: {}",
+            amount, days, interest
+        );
+    }
+    fn present_per_month(&self, per_month: &BTreeMap<String, (f32, f32, f32, f32)>) {
+        per_month
+            .iter()
+            .for_each(|(month, (gross_us, tax_us, gross, tax))| {
+                println!(
+                    "===> (MONTHLY) {}: INCOME: ${} TAX WITHHELD: ${} INCOME: {} TAX WITHHELD: {}",
+                    month, gross_us, tax_us, gross, tax
+                );
+            });
+    }
+    fn present_totals_us(&self, result: &TaxYearResult) {
+        println!(
+            "===> (USD TOTALS) DIVIDENDS: ${} TAX WITHHELD: ${} INTEREST: ${} TAX WITHHELD: ${} SOLD STOCK PROCEEDS: ${} COST BASIS: ${}",
+            result.gross_div_us,
+            result.tax_div_us,
+            result.gross_interest_us,
+            result.tax_interest_us,
+            result.proceeds_short_us + result.proceeds_long_us,
+            result.cost_short_us + result.cost_long_us
+        );
+    }
+    fn present_sheltered(&self, gross_div_us: f32, tax_div_us: f32) {
+        println!(
+            "===> (TAX-SHELTERED ACCOUNTS, NOT TAXED) DIVIDENDS: ${} TAX PAID: ${}",
+            gross_div_us, tax_div_us
+        );
+    }
     fn get_exchange_rates(
         &self,
         dates: &mut std::collections::HashMap<String, Option<(String, f32)>>,
-    ) -> Result<(), String>;
+    ) -> Result<(), RateError>;
+    /// Number of table exchange rate of given date (YYYY-MM-DD) was published
+    /// in, if known e.g. NBP table 041/A/NBP/2023
+    fn exchange_rate_table(&self, _rate_date: &str) -> Option<String> {
+        None
+    }
 
     // Default parser (not to be used)
     fn parse_exchange_rates(&self, _body: &str) -> Result<(f32, String), String> {
@@ -56,130 +413,565 @@ pub trait Residency {
         dates: &mut std::collections::HashMap<String, Option<(String, f32)>>,
         from: &str,
         to: &str,
-    ) -> Result<(), String> {
-        // proxies are taken from env vars: http_proxy and https_proxy
-        let http_proxy = std::env::var("http_proxy");
-        let https_proxy = std::env::var("https_proxy");
-
-        // If there is proxy then pick first URL
-        let base_client = ReqwestClient::builder();
-        let client = match &http_proxy {
-            Ok(proxy) => base_client
-                .proxy(reqwest::Proxy::http(proxy).expect_and_log("Error setting HTTP proxy")),
-            Err(_) => base_client,
-        };
-        let client = match &https_proxy {
-            Ok(proxy) => client
-                .proxy(reqwest::Proxy::https(proxy).expect_and_log("Error setting HTTP proxy")),
-            Err(_) => client,
-        };
-        let client = client.build().expect_and_log("Could not create client");
-
-        // Example URL: https://www.exchange-rates.org/Rate/USD/EUR/2-27-2021
-
-        let base_exchange_rate_url = "https://www.exchange-rates.org/Rate/";
-
-        dates.iter_mut().for_each(|(date, val)| {
-            let mut converted_date = chrono::NaiveDate::parse_from_str(&date, "%m/%d/%y").unwrap();
-
-            converted_date = converted_date
-                .checked_sub_signed(chrono::Duration::days(1))
-                .expect_and_log("Error traversing date");
-
-            let exchange_rate_url: String = base_exchange_rate_url.to_string()
-                + &format!("{}/{}/{}", from, to, converted_date.format("%m-%d-%Y"))
-                + "/?format=json";
-
-            let body = client.get(&(exchange_rate_url)).send();
-            let actual_body = body.expect_and_log(&format!(
-                "Getting Exchange Rate from Exchange-Rates.org ({}) failed",
-                exchange_rate_url
-            ));
-            if actual_body.status().is_success() {
-                log::info!("RESPONSE {:#?}", actual_body);
-
-                let exchange_rates_response = actual_body
-                    .text()
-                    .expect_and_log("Error converting response to Text");
-                log::info!("body of exchange_rate = {:#?}", &exchange_rates_response);
-                // parsing text response
-                if let Ok((exchange_rate, exchange_rate_date)) =
-                    self.parse_exchange_rates(&exchange_rates_response)
-                {
-                    *val = Some((exchange_rate_date, exchange_rate));
-                }
-            } else {
-                panic!("Error getting exchange rate");
-            }
-        });
-
-        Ok(())
+    ) -> Result<(), RateError> {
+        rates::get_currency_exchange_rates(dates, from, to, |body| self.parse_exchange_rates(body))
     }
 }
 
-fn compute_div_taxation(transactions: Vec<Transaction>) -> (f32, f32) {
-    // Gross income from dividends in target currency (PLN, EUR etc.)
-    let gross_us_pl: f32 = transactions
-        .iter()
-        .map(|x| x.exchange_rate * x.gross_us)
-        .sum();
-    // Tax paid in US in PLN
-    let tax_us_pl: f32 = transactions
-        .iter()
-        .map(|x| x.exchange_rate * x.tax_us)
-        .sum();
-    (gross_us_pl, tax_us_pl)
+// Verified transactions of documents: dividends, dividends of tax-sheltered
+// accounts, interest, virtual currency and sold stock
+pub(crate) type CollectedTransactions = (
+    Vec<(String, f32, f32, String)>,
+    Vec<(String, f32, f32, String)>,
+    Vec<(String, f32, f32, String)>,
+    Vec<(String, bool, f32, String)>,
+    Vec<(String, String, String, f32, f32, f32, HoldingTerm)>,
+);
+
+// Broker of each dividend, interest, sold stock and virtual currency
+// transaction of collected transactions, in their order
+pub(crate) type TransactionBrokers = (Vec<String>, Vec<String>, Vec<String>, Vec<String>);
+
+// (date, amount in USD) of cash received on broker account
+type CashInflows = Vec<(String, f32)>;
+
+/// Transactions of all brokers in order given by compare (brokers of equal
+/// transactions in order of their names), and broker each of them is tagged with
+fn sort_tagged<T>(
+    mut tagged: Vec<(T, String)>,
+    compare: fn(&T, &T) -> std::cmp::Ordering,
+) -> (Vec<T>, Vec<String>) {
+    tagged
+        .sort_by(|(a, a_broker), (b, b_broker)| compare(a, b).then_with(|| a_broker.cmp(b_broker)));
+    tagged.into_iter().unzip()
 }
 
-fn compute_sold_taxation(transactions: Vec<SoldTransaction>) -> (f32, f32) {
-    // Net income from sold stock in target currency (PLN, EUR etc.)
-    let gross_us_pl: f32 = transactions
-        .iter()
-        .map(|x| x.exchange_rate_settlement * x.income_us)
-        .sum();
-    // Cost of income e.g. cost_basis[target currency]
-    let cost_us_pl: f32 = transactions
-        .iter()
-        .map(|x| x.exchange_rate_acquisition * x.cost_basis)
-        .sum();
-    (gross_us_pl, cost_us_pl)
+/// Whether account number read from statement (e.g. XXXX-0848) is one of
+/// tax-sheltered accounts. Only digits are compared and statements mask
+/// leading ones, so given account matches by its last digits
+fn is_sheltered(account: &str, sheltered_accounts: &[String]) -> bool {
+    let digits = |number: &str| -> String { number.chars().filter(char::is_ascii_digit).collect() };
+    let account = digits(account);
+    sheltered_accounts.iter().any(|sheltered| {
+        let sheltered = digits(sheltered);
+        !sheltered.is_empty() && account.ends_with(&sheltered)
+    })
 }
 
-pub fn run_taxation(
-    rd: &Box<dyn Residency>,
-    names: clap::Values,
-) -> Result<(f32, f32, f32, f32), String> {
-    let mut parsed_div_transactions: Vec<(String, f32, f32)> = vec![];
-    let mut parsed_sold_transactions: Vec<(String, String, i32, f32, f32)> = vec![];
-    let mut parsed_gain_and_losses: Vec<(String, String, f32, f32, f32)> = vec![];
+/// Parse documents, skip transactions of other tax years and verify the rest.
+/// Also returns (date, amount in USD) of cash received in all years (net
+/// dividends and interest, net proceeds of sold stock) if withdrawals are
+/// given, as cash withdrawn in tax year may come from earlier years.
+/// Transactions of each broker are processed on their own (e.g. lots of one
+/// broker are not matched with sales of another) and broker of each of them
+/// is returned too
+fn collect_transactions<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+    options: &TaxationOptions,
+    stream: bool,
+) -> Result<(CollectedTransactions, TransactionBrokers, CashInflows), Error> {
+    let year = options.year;
+    let mut brokers: BTreeMap<String, ParsedStatement> = BTreeMap::new();
+    let mut sheltered_div_transactions: Vec<(String, f32, f32, String)> = vec![];
+    // Virtual currency transactions of all brokers and broker of each
+    let mut tagged_crypto_transactions: Vec<((String, bool, f32, String), String)> = vec![];
 
     // 1. Parse PDF and XLSX documents to get list of transactions
-    names.for_each(|x| {
-        // If name contains .pdf then parse as pdf
-        // if name contains .xlsx then parse as spreadsheet
-        if x.contains(".pdf") {
-            let (mut div_t, mut sold_t, _) = pdfparser::parse_brokerage_statement(x);
-            parsed_div_transactions.append(&mut div_t);
-            parsed_sold_transactions.append(&mut sold_t);
-        } else {
-            parsed_gain_and_losses.append(&mut xlsxparser::parse_gains_and_losses(x));
+    let names: Vec<&str> = names.into_iter().collect();
+    let source: &dyn DocumentSource = match &options.source {
+        Some(source) => source.as_ref(),
+        None => &FileSystem,
+    };
+    // Documents are parsed by worker threads, then taken in given order
+    let checkpoint = options
+        .checkpoint
+        .as_ref()
+        .map(|path| checkpoint::Checkpoint::open(path, options.use_record_date, options.dry_run));
+    let step = progress::start("Parsing documents", names.len());
+    let statements = parallel::map(&names, parallel::parsing_workers(), |x| {
+        // Documents not parsed yet are skipped when run is interrupted
+        if status::interrupted() {
+            return None;
         }
+        let started = std::time::Instant::now();
+        let statement = match &checkpoint {
+            Some(checkpoint) => checkpoint.parse(source, x, options.use_record_date),
+            None => parse_document(source, x, options.use_record_date),
+        };
+        match &statement {
+            Ok(_) => {
+                logging::event("parse", Some(x), started, None);
+                step.advance(x, None);
+            }
+            Err(e) => {
+                logging::event("parse", Some(x), started, Some(&e.to_string()));
+                step.advance(x, Some(&e.to_string()));
+            }
+        }
+        Some(statement)
     });
-    // 2. Verify Transactions
-    match verify_dividends_transactions(&parsed_div_transactions) {
+    if let Some(checkpoint) = &checkpoint {
+        checkpoint.save();
+    }
+    drop(step);
+    let mut corrections = match &options.corrections {
+        Some(path) => corrections::Corrections::open(path)?,
+        None => corrections::Corrections::default(),
+    };
+    if options.correct && !std::io::stdin().is_terminal() {
+        return Err(Error::Report(
+            "Correction of suspicious values needs interactive terminal".to_owned(),
+        ));
+    }
+    for (x, statement) in names.into_iter().zip(statements) {
+        // Document that fails to parse is skipped, so the rest is still processed
+        let mut statement = match statement {
+            None => {
+                log::warn!("{} not parsed, run interrupted", x);
+                continue;
+            }
+            Some(Ok(statement)) => statement,
+            Some(Err(e)) => {
+                let msg = format!("{}, document skipped", e);
+                progress::print(&warning(&msg));
+                log::warn!("{}", msg);
+                status::set_parse_failed();
+                continue;
+            }
+        };
+        let mut terminal = options
+            .correct
+            .then(|| (std::io::stdin().lock(), std::io::stderr()));
+        let prompt = terminal.as_mut().map(|(input, output)| {
+            (
+                input as &mut dyn std::io::BufRead,
+                output as &mut dyn std::io::Write,
+            )
+        });
+        corrections.correct(x, &mut statement, prompt, options.lang)?;
+        if stream {
+            let document = serde_json::json!({
+                "path": x,
+                "dividends": statement.dividends.len(),
+                "sold": statement.sold.len() + statement.gains_and_losses.len(),
+                "interest": statement.interest.len(),
+                "crypto": statement.crypto.len(),
+            });
+            println!(
+                "{}",
+                report::export::json_line("document", &document, vec![])
+                    .expect_and_log("Error serializing document")
+            );
+        }
+        // Each transaction is tagged with broker of its document
+        let broker_name = etax_parsers::broker_of(x);
+        let broker = brokers.entry(broker_name.clone()).or_default();
+        // Statement may cover several accounts, so each dividend is assigned
+        // by account number read from statement
+        let accounts = std::mem::take(&mut statement.dividend_accounts);
+        for (i, dividend) in statement.dividends.drain(..).enumerate() {
+            match accounts.get(i) {
+                Some(account) if is_sheltered(account, &options.sheltered_accounts) => {
+                    log::info!(
+                        "Dividend of {} paid on {} to tax-sheltered account {}",
+                        dividend.3,
+                        dividend.0,
+                        account
+                    );
+                    sheltered_div_transactions.push(dividend);
+                }
+                _ => broker.dividends.push(dividend),
+            }
+        }
+        // Symbols of sales are kept aligned with sales of other documents
+        statement
+            .sold_symbols
+            .resize(statement.sold.len(), String::new());
+        broker.sold_symbols.append(&mut statement.sold_symbols);
+        broker.sold.append(&mut statement.sold);
+        broker.bought.append(&mut statement.bought);
+        broker.interest.append(&mut statement.interest);
+        broker
+            .gains_and_losses
+            .append(&mut statement.gains_and_losses);
+        tagged_crypto_transactions.extend(
+            statement
+                .crypto
+                .drain(..)
+                .map(|transaction| (transaction, broker_name.clone())),
+        );
+    }
+    if !options.dry_run {
+        corrections.save()?;
+    }
+    // Shares received as dividend are income of their value at payment date
+    let stock_dividends = &mut brokers
+        .entry(etax_parsers::DEFAULT_BROKER.to_owned())
+        .or_default()
+        .dividends;
+    options
+        .stock_dividends
+        .iter()
+        .for_each(|(date, symbol, quantity, price)| {
+            log::info!(
+                "Stock dividend of {} {} shares on {} valued at ${}",
+                quantity,
+                symbol,
+                date,
+                quantity * price
+            );
+            stock_dividends.push((date.clone(), quantity * price, 0.0, symbol.clone()));
+        });
+    // Order of documents (and of their parsing) does not change results
+    sort_div_transactions(&mut sheltered_div_transactions);
+    let (parsed_crypto_transactions, crypto_brokers) =
+        sort_tagged(tagged_crypto_transactions, compare_crypto_transactions);
+    // Reversals may be posted in other statements than the corrected dividends
+    sheltered_div_transactions =
+        net_dividend_corrections(sheltered_div_transactions).map_err(Error::Transactions)?;
+    if let Some(year) = year {
+        sheltered_div_transactions =
+            filter_div_transactions_by_year(sheltered_div_transactions, year)
+                .map_err(Error::Transactions)?;
+    }
+
+    let mut inflows: Vec<(String, f32)> = vec![];
+    let mut per_broker = vec![];
+    for (broker, statement) in brokers {
+        let ParsedStatement {
+            dividends: mut div_transactions,
+            interest: mut interest_transactions,
+            sold: mut sold_transactions,
+            sold_symbols,
+            bought,
+            mut gains_and_losses,
+            ..
+        } = statement;
+        // Trades are netted while sales are aligned with their symbols
+        if options.net_same_day_trades {
+            (sold_transactions, gains_and_losses) = net_same_day_trades(
+                &sold_transactions,
+                &sold_symbols,
+                &bought,
+                &gains_and_losses,
+            )
+            .map_err(Error::Transactions)?;
+        }
+        sort_div_transactions(&mut div_transactions);
+        sort_div_transactions(&mut interest_transactions);
+        sort_sold_transactions(&mut sold_transactions);
+        div_transactions =
+            net_dividend_corrections(div_transactions).map_err(Error::Transactions)?;
+
+        // USD cash received from dividends and interest. Dividends paid in
+        // shares bring no cash
+        if !options.withdrawals.is_empty() {
+            div_transactions
+                .iter()
+                .filter(|(date, gross_us, _, symbol)| {
+                    !options
+                        .stock_dividends
+                        .iter()
+                        .any(|(d, s, quantity, price)| {
+                            d == date && s == symbol && quantity * price == *gross_us
+                        })
+                })
+                .chain(interest_transactions.iter())
+                .for_each(|(date, gross_us, tax_us, _)| {
+                    inflows.push((date.clone(), gross_us - tax_us))
+                });
+        }
+
+        // 2. Skip transactions not belonging to requested tax year
+        if let Some(year) = year {
+            div_transactions = filter_div_transactions_by_year(div_transactions, year)
+                .map_err(Error::Transactions)?;
+            interest_transactions = filter_div_transactions_by_year(interest_transactions, year)
+                .map_err(Error::Transactions)?;
+        }
+
+        // 3. Verify and create full sold transactions info needed for TAX purposes
+        let mut detailed_sold_transactions = reconstruct_sold_transactions(
+            &sold_transactions,
+            &gains_and_losses,
+            &options.purchase_fees,
+        )
+        .map_err(Error::Transactions)?;
+        // and proceeds of sold stock
+        if !options.withdrawals.is_empty() {
+            detailed_sold_transactions.iter().for_each(
+                |(_, settlement_date, _, income, _, fees, _)| {
+                    inflows.push((settlement_date.clone(), income - fees))
+                },
+            );
+        }
+        if let Some(year) = year {
+            detailed_sold_transactions =
+                filter_sold_transactions_by_year(detailed_sold_transactions, year)
+                    .map_err(Error::Transactions)?;
+        }
+        per_broker.push((
+            broker,
+            div_transactions,
+            interest_transactions,
+            detailed_sold_transactions,
+        ));
+    }
+    let mut tagged_div_transactions = vec![];
+    let mut tagged_interest_transactions = vec![];
+    let mut tagged_sold_transactions = vec![];
+    for (broker, dividends, interest, sold) in per_broker {
+        tagged_div_transactions.extend(dividends.into_iter().map(|t| (t, broker.clone())));
+        tagged_interest_transactions.extend(interest.into_iter().map(|t| (t, broker.clone())));
+        tagged_sold_transactions.extend(sold.into_iter().map(|t| (t, broker.clone())));
+    }
+    let (parsed_div_transactions, div_brokers) =
+        sort_tagged(tagged_div_transactions, compare_div_transactions);
+    let (parsed_interest_transactions, interest_brokers) =
+        sort_tagged(tagged_interest_transactions, compare_div_transactions);
+    let (detailed_sold_transactions, sold_brokers) =
+        sort_tagged(tagged_sold_transactions, compare_detailed_sold_transactions);
+    let brokers = (div_brokers, interest_brokers, sold_brokers, crypto_brokers);
+
+    // 4. Verify Transactions
+    match verify_dividends_transactions(&parsed_div_transactions).map_err(Error::Transactions)? {
         Ok(()) => log::info!("Dividends transactions are consistent"),
         Err(msg) => {
-            println!("{}", msg);
+            eprintln!("{}", warning(&msg));
             log::warn!("{}", msg);
         }
     }
+    match verify_withholding_ratios(&parsed_div_transactions) {
+        Ok(()) => log::info!("Dividends withholding ratios are as expected"),
+        Err(msg) => {
+            eprintln!("{}", warning(&msg));
+            log::warn!("{}", msg);
+        }
+    }
+    Ok((
+        (
+            parsed_div_transactions,
+            sheltered_div_transactions,
+            parsed_interest_transactions,
+            parsed_crypto_transactions,
+            detailed_sold_transactions,
+        ),
+        brokers,
+        inflows,
+    ))
+}
+
+/// Parse and verify given documents without getting exchange rates. Returns
+/// numbers of (dividend, interest, sold stock, virtual currency) transactions
+#[doc(hidden)]
+pub fn validate_documents<'a>(
+    names: impl IntoIterator<Item = &'a str>,
+    options: &TaxationOptions,
+) -> Result<(usize, usize, usize, usize), Error> {
+    let ((dividends, sheltered_dividends, interest, crypto, sold), _, _) =
+        collect_transactions(names, options, false)?;
+    if let Some(path) = unless_dry_run(options, &options.checkpoint) {
+        checkpoint::remove(path);
+    }
+    Ok((
+        dividends.len() + sheltered_dividends.len(),
+        interest.len(),
+        sold.len(),
+        crypto.len(),
+    ))
+}
+
+/// Income of each broker per tax year, if transactions come from several
+/// brokers. It is computed from transactions of broker only, the same way
+/// figures of year are. crypto_transactions are (date, is sale, amount in
+/// target currency)
+fn compute_per_broker(
+    rd: &dyn Residency,
+    brokers: &TransactionBrokers,
+    transactions: &[Transaction],
+    interest_transactions: &[Transaction],
+    sold_transactions: &[SoldTransaction],
+    crypto_transactions: &[(String, bool, f32)],
+    countries: &std::collections::HashMap<String, String>,
+) -> Result<BTreeMap<i32, BTreeMap<String, BrokerIncome>>, Error> {
+    let (div_brokers, interest_brokers, sold_brokers, crypto_brokers) = brokers;
+    let mut names: Vec<&String> = div_brokers
+        .iter()
+        .chain(interest_brokers)
+        .chain(sold_brokers)
+        .chain(crypto_brokers)
+        .collect();
+    names.sort();
+    names.dedup();
+    let mut per_year: BTreeMap<i32, BTreeMap<String, BrokerIncome>> = BTreeMap::new();
+    if names.len() < 2 {
+        return Ok(per_year);
+    }
+    for name in names {
+        let incomes = transactions
+            .iter()
+            .zip(div_brokers)
+            .filter(|(_, broker)| *broker == name)
+            .map(|(t, _)| Income::dividend(t))
+            .chain(
+                interest_transactions
+                    .iter()
+                    .zip(interest_brokers)
+                    .filter(|(_, broker)| *broker == name)
+                    .map(|(t, _)| Income::interest(t)),
+            )
+            .chain(
+                sold_transactions
+                    .iter()
+                    .zip(sold_brokers)
+                    .filter(|(_, broker)| *broker == name)
+                    .map(|(t, _)| Income::sale(t)),
+            )
+            .collect::<Result<Vec<Income>, String>>()
+            .map_err(Error::Transactions)?;
+        let mut div_per_year: BTreeMap<i32, Vec<Income>> = BTreeMap::new();
+        incomes
+            .iter()
+            .filter(|income| matches!(income, Income::Dividend { .. }))
+            .for_each(|income| {
+                div_per_year
+                    .entry(income.tax_year())
+                    .or_default()
+                    .push(income.clone())
+            });
+        for (income_year, result) in compute_taxation_per_year(incomes) {
+            let per_symbol = compute_div_taxation_per_symbol(
+                &div_per_year.remove(&income_year).unwrap_or_default(),
+                rd.rules_for_year(income_year)
+                    .map(|rules| rules.treaty_withholding_rate),
+            );
+            per_year.entry(income_year).or_default().insert(
+                name.clone(),
+                BrokerIncome {
+                    gross_div: result.gross_div,
+                    tax_div: result.tax_div,
+                    gross_interest: result.gross_interest,
+                    tax_interest: result.tax_interest,
+                    gross_sold: result.gross_sold,
+                    cost_sold: result.cost_sold,
+                    per_country: aggregate_per_country(&per_symbol, countries),
+                    ..Default::default()
+                },
+            );
+        }
+        let crypto = crypto_transactions
+            .iter()
+            .zip(crypto_brokers)
+            .filter(|(_, broker)| *broker == name)
+            .map(|(t, _)| t.clone())
+            .collect();
+        for (crypto_year, (income, cost, _)) in
+            compute_crypto_per_year(crypto, 0.0).map_err(Error::Transactions)?
+        {
+            let broker_income = per_year
+                .entry(crypto_year)
+                .or_default()
+                .entry(name.clone())
+                .or_default();
+            broker_income.crypto_income = income;
+            broker_income.crypto_cost = cost;
+        }
+    }
+    Ok(per_year)
+}
+
+// Partial results of interrupted run, written to current directory
+const PARTIAL_RESULTS: &str = "etradeTaxHelper-partial.json";
+
+/// Write out transactions processed before run was interrupted, so the work
+/// is not lost. Returns error ending the run
+fn write_partial(
+    dividends: &[(String, f32, f32, String)],
+    interest: &[(String, f32, f32, String)],
+    crypto: &[(String, bool, f32, String)],
+    sold: &[(String, String, String, f32, f32, f32, HoldingTerm)],
+    dates: &std::collections::HashMap<String, Option<(String, f32)>>,
+) -> Error {
+    let written = report::export::format_partial(dividends, interest, crypto, sold, dates)
+        .and_then(|content| report::export::write_file(PARTIAL_RESULTS, content.as_bytes()));
+    match written {
+        Ok(()) => Error::Interrupted(PARTIAL_RESULTS.to_owned()),
+        Err(e) => Error::Report(e),
+    }
+}
+
+/// Target of write given in options, none in dry run
+fn unless_dry_run<'a, T>(options: &TaxationOptions, target: &'a Option<T>) -> Option<&'a T> {
+    target.as_ref().filter(|_| !options.dry_run)
+}
+
+/// Writes run with options would do besides printing results (outputs "-")
+fn planned_writes(options: &TaxationOptions, outputs: &[(ExportFormat, String)]) -> Vec<String> {
+    let mut writes: Vec<String> = vec![];
+    if let Some((path, account)) = &options.ledger {
+        writes.push(format!("append results of {} to ledger {}", account, path));
+    }
+    if let Some((path, account)) = &options.database {
+        writes.push(format!(
+            "store transactions and results of {} in database {}",
+            account, path
+        ));
+    }
+    if let Some(path) = &options.audit {
+        writes.push(format!("write audit report to {}", path));
+    }
+    if let Some((template_path, path)) = &options.template {
+        writes.push(format!("render template {} to {}", template_path, path));
+    }
+    outputs
+        .iter()
+        .filter(|(_, path)| path != "-")
+        .for_each(|(_, path)| writes.push(format!("write results to {}", path)));
+    if options.google_sheets.is_some() {
+        writes.push("create Google Sheet".to_owned());
+    }
+    if let Some(path) = &options.email {
+        writes.push(format!("send report by e-mail configured in {}", path));
+    }
+    writes
+}
 
-    // 3. Verify and create full sold transactions info needed for TAX purposes
-    let detailed_sold_transactions =
-        reconstruct_sold_transactions(&parsed_sold_transactions, &parsed_gain_and_losses)
-            .expect_and_log("Error reconstructing detailed sold transactions.");
+/// Returns computed figures for every tax year found in given documents
+#[doc(hidden)]
+pub fn run_taxation<'a>(
+    rd: &dyn Residency,
+    names: impl IntoIterator<Item = &'a str>,
+    options: &TaxationOptions,
+) -> Result<BTreeMap<i32, TaxYearResult>, Error> {
+    let withdrawals = &options.withdrawals;
+    // JSON lines are printed as soon as documents are parsed and transactions
+    // resolved. Anonymized ones need all results so they are printed at the end
+    let stream = options.format == ExportFormat::JsonLines && !options.anonymize;
 
-    // 4. Get Exchange rates
+    // 1.-4. Parse documents and verify their transactions
+    let (mut collected, mut brokers, inflows) = collect_transactions(names, options, stream)?;
+    if options.review {
+        (collected, brokers) = review::review_transactions(collected, brokers, options.lang)?;
+    }
+    if status::interrupted() {
+        let (dividends, _, interest, crypto, sold) = &collected;
+        return Err(write_partial(
+            dividends,
+            interest,
+            crypto,
+            sold,
+            &std::collections::HashMap::new(),
+        ));
+    }
+    let (
+        parsed_div_transactions,
+        sheltered_div_transactions,
+        parsed_interest_transactions,
+        parsed_crypto_transactions,
+        detailed_sold_transactions,
+    ) = collected;
+
+    // 5. Get Exchange rates
     // Gather all trade , settlement and transaction dates into hash map to be passed to
     // get_exchange_rate
     // Hash map : Key(event date) -> (preceeding date, exchange_rate)
@@ -187,129 +979,584 @@ pub fn run_taxation(
         std::collections::HashMap::new();
     parsed_div_transactions
         .iter()
-        .for_each(|(trade_date, _, _)| {
-            if dates.contains_key(trade_date) == false {
+        .for_each(|(trade_date, _, _, _)| {
+            if !dates.contains_key(trade_date) {
                 dates.insert(trade_date.clone(), None);
             }
         });
+    parsed_interest_transactions
+        .iter()
+        .for_each(|(transaction_date, _, _, _)| {
+            if !dates.contains_key(transaction_date) {
+                dates.insert(transaction_date.clone(), None);
+            }
+        });
     detailed_sold_transactions.iter().for_each(
-        |(trade_date, settlement_date, acquisition_date, _, _)| {
-            if dates.contains_key(trade_date) == false {
+        |(trade_date, settlement_date, acquisition_date, _, _, _, _)| {
+            if !dates.contains_key(trade_date) {
                 dates.insert(trade_date.clone(), None);
             }
-            if dates.contains_key(settlement_date) == false {
+            if !dates.contains_key(settlement_date) {
                 dates.insert(settlement_date.clone(), None);
             }
-            if dates.contains_key(acquisition_date) == false {
+            if !dates.contains_key(acquisition_date) {
                 dates.insert(acquisition_date.clone(), None);
             }
         },
     );
+    // Crypto transactions given in target currency (e.g. PLN) need no exchange rate
+    parsed_crypto_transactions
+        .iter()
+        .filter(|(_, _, _, currency)| currency == "USD")
+        .for_each(|(transaction_date, _, _, _)| {
+            if !dates.contains_key(transaction_date) {
+                dates.insert(transaction_date.clone(), None);
+            }
+        });
+    withdrawals
+        .iter()
+        .chain(inflows.iter())
+        .for_each(|(cash_date, _)| {
+            if !dates.contains_key(cash_date) {
+                dates.insert(cash_date.clone(), None);
+            }
+        });
 
-    rd.get_exchange_rates(&mut dates)
-        .expect_and_log("Error: unable to get exchange rates");
+    let started = std::time::Instant::now();
+    let rates = rd.get_exchange_rates(&mut dates);
+    logging::event(
+        "rates",
+        None,
+        started,
+        rates.as_ref().err().map(|e| e.to_string()).as_deref(),
+    );
+    rates?;
+    if status::interrupted() {
+        return Err(write_partial(
+            &parsed_div_transactions,
+            &parsed_interest_transactions,
+            &parsed_crypto_transactions,
+            &detailed_sold_transactions,
+            &dates,
+        ));
+    }
 
     // Make a detailed_div_transactions
-    let transactions = create_detailed_div_transactions(parsed_div_transactions, &dates);
-    let sold_transactions = create_detailed_sold_transactions(detailed_sold_transactions, &dates);
+    let transactions = create_detailed_div_transactions(parsed_div_transactions, &dates)
+        .map_err(Error::Transactions)?;
+    let sold_transactions = create_detailed_sold_transactions(detailed_sold_transactions, &dates)
+        .map_err(Error::Transactions)?;
+    let interest_transactions =
+        create_detailed_div_transactions(parsed_interest_transactions, &dates)
+            .map_err(Error::Transactions)?;
+    if stream {
+        for (kind, t) in transactions
+            .iter()
+            .map(|t| ("dividend", t))
+            .chain(interest_transactions.iter().map(|t| ("interest", t)))
+        {
+            println!("{}", report::export::json_line(kind, t, vec![])?);
+        }
+        for t in sold_transactions.iter() {
+            println!("{}", report::export::json_line("sold", t, vec![])?);
+        }
+    }
+    if options.format == ExportFormat::Human && options.transaction_tables {
+        report::export::transaction_tables(
+            &transactions,
+            &interest_transactions,
+            &sold_transactions,
+            options.lang,
+        )
+        .iter()
+        .for_each(|line| println!("{}", line));
+    }
 
-    let (gross_div, tax_div) = compute_div_taxation(transactions);
-    let (gross_sold, cost_sold) = compute_sold_taxation(sold_transactions);
-    Ok((gross_div, tax_div, gross_sold, cost_sold))
+    let crypto_transactions: Vec<(String, bool, f32)> = parsed_crypto_transactions
+        .into_iter()
+        .map(|(transaction_date, is_sale, amount, currency)| {
+            let exchange_rate = match currency.as_str() {
+                "USD" => etax_core::transactions::exchange_rate_of(&dates, &transaction_date)?.1,
+                _ => 1.0,
+            };
+            Ok((transaction_date, is_sale, amount * exchange_rate))
+        })
+        .collect::<Result<Vec<(String, bool, f32)>, String>>()
+        .map_err(Error::Transactions)?;
+    let per_broker = compute_per_broker(
+        rd,
+        &brokers,
+        &transactions,
+        &interest_transactions,
+        &sold_transactions,
+        &crypto_transactions,
+        &options.countries,
+    )?;
+    let incomes = transactions
+        .iter()
+        .map(Income::dividend)
+        .chain(interest_transactions.iter().map(Income::interest))
+        .chain(sold_transactions.iter().map(Income::sale))
+        .collect::<Result<Vec<Income>, String>>()
+        .map_err(Error::Transactions)?;
+
+    let excess_withholdings: Vec<(i32, f32)> = incomes
+        .iter()
+        .filter_map(|income| {
+            let tax_year = income.tax_year();
+            rd.rules_for_year(tax_year).map(|rules| {
+                (
+                    tax_year,
+                    compute_excess_withholding(income, rules.treaty_withholding_rate),
+                )
+            })
+        })
+        .collect();
+
+    let mut div_per_year: BTreeMap<i32, Vec<Income>> = BTreeMap::new();
+    incomes
+        .iter()
+        .filter(|income| matches!(income, Income::Dividend { .. }))
+        .for_each(|income| {
+            div_per_year
+                .entry(income.tax_year())
+                .or_default()
+                .push(income.clone())
+        });
+    let income_per_month = compute_income_per_month(&incomes);
+    let per_symbol: Vec<_> = div_per_year
+        .iter()
+        .map(|(div_year, dividends)| {
+            let treaty_withholding_rate = rd
+                .rules_for_year(*div_year)
+                .map(|rules| rules.treaty_withholding_rate);
+            (
+                *div_year,
+                compute_div_taxation_per_symbol(dividends, treaty_withholding_rate),
+            )
+        })
+        .collect();
+
+    let mut outputs: Vec<(ExportFormat, String)> = options.export.to_vec();
+    if options.format != ExportFormat::Human && !stream {
+        outputs.push((options.format.clone(), "-".to_owned()));
+    }
+    // Dry run reports writes instead of doing them, printed results remain
+    if options.dry_run {
+        planned_writes(options, &outputs).iter().for_each(|write| {
+            let msg = format!("Dry run, would {}", write);
+            eprintln!("{}", warning(&msg));
+            log::info!("{}", msg);
+        });
+        outputs.retain(|(_, path)| path == "-");
+    }
+    let ledger = unless_dry_run(options, &options.ledger);
+    let database = unless_dry_run(options, &options.database);
+    let audit = unless_dry_run(options, &options.audit);
+    let template = unless_dry_run(options, &options.template);
+    let google_sheets = unless_dry_run(options, &options.google_sheets);
+    let email = unless_dry_run(options, &options.email);
+    let exported = match outputs.is_empty()
+        && audit.is_none()
+        && template.is_none()
+        && google_sheets.is_none()
+        && email.is_none()
+        && database.is_none()
+    {
+        false => Some((transactions, interest_transactions, sold_transactions)),
+        true => None,
+    };
+    let mut results = compute_taxation_per_year(incomes);
+    income_per_month
+        .into_iter()
+        .for_each(|(income_year, per_month)| {
+            results.entry(income_year).or_default().per_month = per_month;
+        });
+    per_symbol.into_iter().for_each(|(div_year, per_symbol)| {
+        let result = results.entry(div_year).or_default();
+        result.per_country = aggregate_per_country(&per_symbol, &options.countries);
+        result.per_symbol = per_symbol;
+    });
+    per_broker
+        .into_iter()
+        .for_each(|(broker_year, per_broker)| {
+            results.entry(broker_year).or_default().per_broker = per_broker;
+        });
+    excess_withholdings
+        .into_iter()
+        .for_each(|(excess_year, excess_us)| {
+            results
+                .entry(excess_year)
+                .or_default()
+                .excess_withholding_us += excess_us;
+        });
+    if !withdrawals.is_empty() {
+        // Ledger is built from cash of all years, differences are reported
+        // for requested tax year only
+        etax_core::compute_fx_differences(&inflows, withdrawals, &dates)
+            .map_err(Error::Transactions)?
+            .into_iter()
+            .filter(|(fx_year, _)| options.year.is_none_or(|year| *fx_year == year))
+            .for_each(|(fx_year, (fx_income, fx_cost))| {
+                let result = results.entry(fx_year).or_default();
+                result.fx_income = fx_income;
+                result.fx_cost = fx_cost;
+            });
+    }
+    compute_crypto_per_year(crypto_transactions, options.crypto_carried_cost)
+        .map_err(Error::Transactions)?
+        .into_iter()
+        .for_each(|(crypto_year, (income, cost, carried_cost))| {
+            let result = results.entry(crypto_year).or_default();
+            result.crypto_income = income;
+            result.crypto_cost = cost;
+            result.crypto_carried_cost = carried_cost;
+        });
+    for (transaction_date, gross_us, tax_us, _) in sheltered_div_transactions.iter() {
+        let result = results
+            .entry(tax_year(transaction_date).map_err(Error::Transactions)?)
+            .or_default();
+        result.sheltered_gross_div_us += gross_us;
+        result.sheltered_tax_div_us += tax_us;
+    }
+    if let Some(year) = options.year {
+        results.retain(|result_year, _| *result_year == year);
+        let result = results.entry(year).or_default();
+        if let Some((pit8c_income, pit8c_cost)) = options.pit8c {
+            result.pit8c_income = pit8c_income;
+            result.pit8c_cost = pit8c_cost;
+        }
+        if let Some(prepaid_tax) = options.prepaid_tax {
+            result.prepaid_tax = prepaid_tax;
+        }
+    }
+    if stream {
+        for (result_year, result) in results.iter() {
+            let tax_year = ("tax_year", serde_json::Value::from(*result_year));
+            println!(
+                "{}",
+                report::export::json_line("result", result, vec![tax_year])?
+            );
+        }
+    }
+    let rules: BTreeMap<i32, TaxRules> = results
+        .keys()
+        .filter_map(|result_year| {
+            rd.rules_for_year(*result_year)
+                .map(|rules| (*result_year, rules))
+        })
+        .collect();
+    if let Some((path, account)) = ledger {
+        report::ledger::append_ledger(path, account, &results, &rules)?;
+        log::info!("Results appended to ledger {}", path);
+    }
+    if let Some((transactions, interest_transactions, sold_transactions)) = exported {
+        if let Some((path, account)) = database {
+            report::database::store(
+                path,
+                account,
+                &transactions,
+                &interest_transactions,
+                &sold_transactions,
+                &results,
+                &rules,
+            )?;
+            log::info!("Transactions and results stored in database {}", path);
+        }
+        let (transactions, interest_transactions, sold_transactions, anonymized) =
+            match options.anonymize {
+                true => {
+                    let (transactions, interest_transactions, sold_transactions, results) =
+                        report::anonymize::anonymize(
+                            &transactions,
+                            &interest_transactions,
+                            &sold_transactions,
+                            &results,
+                        );
+                    (
+                        transactions,
+                        interest_transactions,
+                        sold_transactions,
+                        Some(results),
+                    )
+                }
+                false => (transactions, interest_transactions, sold_transactions, None),
+            };
+        let exported_results = anonymized.as_ref().unwrap_or(&results);
+        if let Some(path) = audit {
+            let mut tables: std::collections::HashMap<String, String> =
+                std::collections::HashMap::new();
+            transactions
+                .iter()
+                .chain(interest_transactions.iter())
+                .map(|t| t.exchange_rate_date.clone())
+                .chain(sold_transactions.iter().flat_map(|t| {
+                    [
+                        t.exchange_rate_settlement_date.clone(),
+                        t.exchange_rate_acquisition_date.clone(),
+                    ]
+                }))
+                .for_each(|rate_date| {
+                    if let Some(table) = rd.exchange_rate_table(&rate_date) {
+                        tables.insert(rate_date, table);
+                    }
+                });
+            let lines = report::audit::audit_lines(
+                &transactions,
+                &interest_transactions,
+                &sold_transactions,
+                exported_results,
+                &rules,
+                &tables,
+            );
+            report::export::write_file(path, (lines.join("\n") + "\n").as_bytes())?;
+            log::info!("Audit report written to {}", path);
+        }
+        if let Some((template_path, path)) = template {
+            report::export::render_template(
+                template_path,
+                path,
+                &transactions,
+                &interest_transactions,
+                &sold_transactions,
+                exported_results,
+                &rules,
+            )?;
+        }
+        for (format, path) in outputs.iter() {
+            let started = std::time::Instant::now();
+            let exported = report::export::format_results(
+                format,
+                &transactions,
+                &interest_transactions,
+                &sold_transactions,
+                exported_results,
+                &rules,
+                options.lang,
+            )
+            .and_then(|content| report::export::write_output(path, &content));
+            if path != "-" {
+                logging::event(
+                    "export",
+                    Some(path),
+                    started,
+                    exported.as_ref().err().map(|e| e.to_string()).as_deref(),
+                );
+            }
+            exported?;
+        }
+        if let Some(client_file) = google_sheets {
+            let url = report::sheets::export_google_sheets(
+                client_file,
+                &transactions,
+                &interest_transactions,
+                &sold_transactions,
+                exported_results,
+                &rules,
+                options.lang,
+            )?;
+            eprintln!("Google Sheet created: {}", url);
+            log::info!("Google Sheet created: {}", url);
+        }
+        if let Some(config_path) = email {
+            let config = report::email::load_config(config_path)?;
+            let attachments = config
+                .formats()?
+                .into_iter()
+                .map(|format| {
+                    let content = report::export::format_results(
+                        &format,
+                        &transactions,
+                        &interest_transactions,
+                        &sold_transactions,
+                        exported_results,
+                        &rules,
+                        options.lang,
+                    )?;
+                    Ok((format, content))
+                })
+                .collect::<Result<Vec<(ExportFormat, Vec<u8>)>, String>>()?;
+            report::email::send_report(&config, &attachments)?;
+            log::info!("Report sent to {}", config.to);
+        }
+    }
+    if let Some(path) = unless_dry_run(options, &options.checkpoint) {
+        checkpoint::remove(path);
+    }
+    Ok(results)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    #[test]
-    fn test_simple_div_taxation() -> Result<(), String> {
-        // Init Transactions
-        let transactions: Vec<Transaction> = vec![Transaction {
-            transaction_date: "N/A".to_string(),
-            gross_us: 100.0,
-            tax_us: 25.0,
-            exchange_rate_date: "N/A".to_string(),
-            exchange_rate: 4.0,
-        }];
-        assert_eq!(compute_div_taxation(transactions), (400.0, 100.0));
-        Ok(())
-    }
 
     #[test]
-    fn test_div_taxation() -> Result<(), String> {
-        // Init Transactions
-        let transactions: Vec<Transaction> = vec![
-            Transaction {
-                transaction_date: "N/A".to_string(),
-                gross_us: 100.0,
-                tax_us: 25.0,
-                exchange_rate_date: "N/A".to_string(),
-                exchange_rate: 4.0,
-            },
-            Transaction {
-                transaction_date: "N/A".to_string(),
-                gross_us: 126.0,
-                tax_us: 10.0,
-                exchange_rate_date: "N/A".to_string(),
-                exchange_rate: 3.5,
-            },
-        ];
+    fn test_sheltered_accounts() -> Result<(), String> {
+        assert!(is_sheltered("XXXX-0848", &["0848".to_owned()]));
+        assert!(is_sheltered("1234-0848", &["XXXX0848".to_owned()]));
+        assert!(!is_sheltered("XXXX-1848", &["20848".to_owned()]));
+        assert!(!is_sheltered("XXXX-0848", &["".to_owned()]));
+        assert!(!is_sheltered("", &["0848".to_owned()]));
+
+        // Statement of two accounts: only dividends of sheltered one are dropped
+        let mut documents = InMemory::default();
+        documents.insert(
+            "statement.json",
+            br#"{"dividends": [["03/01/22", 100.0, 15.0, "INTC"], ["03/01/22", 50.0, 7.5, "INTC"],
+                               ["06/01/22", 20.0, 3.0, "DLB"]],
+                 "dividend_accounts": ["XXXX-1234", "XXXX-0848", "XXXX-1234"]}"#
+                .to_vec(),
+        );
+        let options = TaxationOptions {
+            sheltered_accounts: vec!["0848".to_owned()],
+            source: Some(Box::new(documents)),
+            ..Default::default()
+        };
+        let ((dividends, sheltered_dividends, _, _, _), _, _) =
+            collect_transactions(["statement.json"], &options, false).map_err(|e| e.to_string())?;
         assert_eq!(
-            compute_div_taxation(transactions),
-            (400.0 + 126.0 * 3.5, 100.0 + 10.0 * 3.5)
+            dividends,
+            vec![
+                ("03/01/22".to_owned(), 100.0, 15.0, "INTC".to_owned()),
+                ("06/01/22".to_owned(), 20.0, 3.0, "DLB".to_owned())
+            ]
+        );
+        assert_eq!(
+            sheltered_dividends,
+            vec![("03/01/22".to_owned(), 50.0, 7.5, "INTC".to_owned())]
         );
-        Ok(())
-    }
 
-    #[test]
-    fn test_simple_sold_taxation() -> Result<(), String> {
-        // Init Transactions
-        let transactions: Vec<SoldTransaction> = vec![SoldTransaction {
-            settlement_date: "N/A".to_string(),
-            acquisition_date: "N/A".to_string(),
-            income_us: 100.0,
-            cost_basis: 70.0,
-            exchange_rate_settlement_date: "N/A".to_string(),
-            exchange_rate_settlement: 5.0,
-            exchange_rate_acquisition_date: "N/A".to_string(),
-            exchange_rate_acquisition: 6.0,
-        }];
+        // The same dividends with account numbers read from PDF statement
+        let options = TaxationOptions {
+            sheltered_accounts: vec!["0848".to_owned()],
+            ..Default::default()
+        };
+        let ((pdf_dividends, pdf_sheltered_dividends, _, _, _), _, _) =
+            collect_transactions(["data/example-accounts.pdf"], &options, false)
+                .map_err(|e| e.to_string())?;
         assert_eq!(
-            compute_sold_taxation(transactions),
-            (100.0 * 5.0, 70.0 * 6.0)
+            (pdf_dividends, pdf_sheltered_dividends),
+            (dividends, sheltered_dividends)
         );
         Ok(())
     }
-
     #[test]
-    fn test_sold_taxation() -> Result<(), String> {
-        // Init Transactions
-        let transactions: Vec<SoldTransaction> = vec![
-            SoldTransaction {
-                settlement_date: "N/A".to_string(),
-                acquisition_date: "N/A".to_string(),
-                income_us: 100.0,
-                cost_basis: 70.0,
-                exchange_rate_settlement_date: "N/A".to_string(),
-                exchange_rate_settlement: 5.0,
-                exchange_rate_acquisition_date: "N/A".to_string(),
-                exchange_rate_acquisition: 6.0,
-            },
-            SoldTransaction {
-                settlement_date: "N/A".to_string(),
-                acquisition_date: "N/A".to_string(),
-                income_us: 10.0,
-                cost_basis: 4.0,
-                exchange_rate_settlement_date: "N/A".to_string(),
-                exchange_rate_settlement: 2.0,
-                exchange_rate_acquisition_date: "N/A".to_string(),
-                exchange_rate_acquisition: 3.0,
-            },
+    fn test_per_broker() -> Result<(), String> {
+        let dividend = |date: &str, gross_us: f32, tax_us: f32, symbol: &str| Transaction {
+            transaction_date: date.to_owned(),
+            gross_us,
+            tax_us,
+            symbol: symbol.to_owned(),
+            exchange_rate_date: date.to_owned(),
+            exchange_rate: 4.0,
+        };
+        let transactions = vec![
+            dividend("03/01/23", 100.0, 15.0, "INTC"),
+            dividend("06/01/23", 50.0, 0.0, "VUSA"),
         ];
+        let sold_transactions = vec![SoldTransaction {
+            settlement_date: "05/04/23".to_owned(),
+            acquisition_date: "01/03/22".to_owned(),
+            income_us: 300.0,
+            cost_basis: 200.0,
+            fees_us: 0.0,
+            term: HoldingTerm::Long,
+            exchange_rate_settlement_date: "05/03/23".to_owned(),
+            exchange_rate_settlement: 4.0,
+            exchange_rate_acquisition_date: "12/30/21".to_owned(),
+            exchange_rate_acquisition: 4.0,
+        }];
+        let crypto_transactions = vec![
+            ("02/01/23".to_owned(), false, 1000.0),
+            ("09/01/23".to_owned(), true, 1500.0),
+        ];
+        // Transactions sorted together keep broker they are tagged with, also
+        // equal transactions of different brokers
+        let (sorted, brokers) = sort_tagged(
+            vec![
+                (
+                    transactions[1].transaction_date.clone(),
+                    "revolut".to_owned(),
+                ),
+                (
+                    transactions[0].transaction_date.clone(),
+                    "revolut".to_owned(),
+                ),
+                (
+                    transactions[0].transaction_date.clone(),
+                    "E*TRADE".to_owned(),
+                ),
+            ],
+            |a, b| a.cmp(b),
+        );
+        assert_eq!(sorted, vec!["03/01/23", "03/01/23", "06/01/23"]);
+        assert_eq!(brokers, vec!["E*TRADE", "revolut", "revolut"]);
+        let brokers: TransactionBrokers = (
+            vec!["E*TRADE".to_owned(), "revolut".to_owned()],
+            vec![],
+            vec!["revolut".to_owned()],
+            vec!["revolut".to_owned(), "revolut".to_owned()],
+        );
+
+        let rd: Box<dyn Residency> = Box::new(us::US {});
+        let countries = std::collections::HashMap::from([("VUSA".to_owned(), "IE".to_owned())]);
+        let per_broker = compute_per_broker(
+            &*rd,
+            &brokers,
+            &transactions,
+            &[],
+            &sold_transactions,
+            &crypto_transactions,
+            &countries,
+        )
+        .map_err(|e| e.to_string())?;
         assert_eq!(
-            compute_sold_taxation(transactions),
-            (100.0 * 5.0 + 10.0 * 2.0, 70.0 * 6.0 + 4.0 * 3.0)
+            per_broker,
+            BTreeMap::from([(
+                2023,
+                BTreeMap::from([
+                    (
+                        "E*TRADE".to_owned(),
+                        BrokerIncome {
+                            gross_div: 400.0,
+                            tax_div: 60.0,
+                            per_country: BTreeMap::from([("US".to_owned(), (400.0, 60.0, 60.0))]),
+                            ..Default::default()
+                        }
+                    ),
+                    (
+                        "revolut".to_owned(),
+                        BrokerIncome {
+                            gross_div: 200.0,
+                            gross_sold: 1200.0,
+                            cost_sold: 800.0,
+                            crypto_income: 1500.0,
+                            crypto_cost: 1000.0,
+                            per_country: BTreeMap::from([("IE".to_owned(), (200.0, 0.0, 0.0))]),
+                            ..Default::default()
+                        }
+                    ),
+                ])
+            )])
+        );
+        // Documents of single broker have no breakdown
+        let brokers: TransactionBrokers = (
+            vec!["E*TRADE".to_owned(), "E*TRADE".to_owned()],
+            vec![],
+            vec!["E*TRADE".to_owned()],
+            vec!["E*TRADE".to_owned(), "E*TRADE".to_owned()],
         );
+        assert!(compute_per_broker(
+            &*rd,
+            &brokers,
+            &transactions,
+            &[],
+            &sold_transactions,
+            &crypto_transactions,
+            &countries
+        )
+        .map_err(|e| e.to_string())?
+        .is_empty());
         Ok(())
     }
 }
@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Number formatting of amounts printed in summary
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Locale {
+    /// 12 345,68
+    #[default]
+    Pl,
+    /// 12,345.68
+    En,
+    /// 12345.68 as printed by previous versions, easy to parse by scripts
+    C,
+}
+
+impl Locale {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "pl" => Ok(Locale::Pl),
+            "en" => Ok(Locale::En),
+            "c" => Ok(Locale::C),
+            _ => Err(format!("Error: unsupported locale: {}", name)),
+        }
+    }
+}
+
+// Set by --locale
+static LOCALE: AtomicU8 = AtomicU8::new(Locale::Pl as u8);
+
+pub fn set_locale(locale: Locale) {
+    LOCALE.store(locale as u8, Ordering::Relaxed);
+}
+
+fn locale() -> Locale {
+    match LOCALE.load(Ordering::Relaxed) {
+        x if x == Locale::En as u8 => Locale::En,
+        x if x == Locale::C as u8 => Locale::C,
+        _ => Locale::Pl,
+    }
+}
+
+fn format_amount_in(locale: Locale, amount: f32, decimals: usize) -> String {
+    let (thousands, decimal) = match locale {
+        Locale::Pl => (" ", ","),
+        Locale::En => (",", "."),
+        Locale::C => return format!("{:.*}", decimals, amount),
+    };
+    let plain = format!("{:.*}", decimals, amount.abs());
+    let (whole, fraction) = plain.split_at(plain.find('.').unwrap_or(plain.len()));
+    let mut grouped = String::new();
+    whole.chars().enumerate().for_each(|(i, digit)| {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped += thousands;
+        }
+        grouped.push(digit);
+    });
+    // Sign is taken from rounded value, so -0.001 is printed as 0,00
+    let sign = match plain.trim_start_matches(['0', '.']).is_empty() || amount >= 0.0 {
+        true => "",
+        false => "-",
+    };
+    match fraction.is_empty() {
+        true => format!("{}{}", sign, grouped),
+        false => format!("{}{}{}{}", sign, grouped, decimal, &fraction[1..]),
+    }
+}
+
+/// Amount with given number of decimal places formatted according to --locale
+pub fn format_amount(amount: f32, decimals: usize) -> String {
+    format_amount_in(locale(), amount, decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_amount() -> Result<(), String> {
+        assert_eq!(format_amount_in(Locale::Pl, 12345.678, 2), "12 345,68");
+        assert_eq!(format_amount_in(Locale::Pl, -1234567.0, 0), "-1 234 567");
+        assert_eq!(format_amount_in(Locale::Pl, 999.999, 2), "1 000,00");
+        assert_eq!(format_amount_in(Locale::Pl, 135.15, 2), "135,15");
+        assert_eq!(format_amount_in(Locale::Pl, -0.001, 2), "0,00");
+        assert_eq!(format_amount_in(Locale::En, 12345.678, 2), "12,345.68");
+        assert_eq!(format_amount_in(Locale::C, 12345.678, 2), "12345.68");
+        assert!(Locale::from_name("de").is_err());
+        Ok(())
+    }
+}
@@ -1,99 +1,918 @@
-use clap::{App, AppSettings, Arg};
+use chrono::Datelike;
+use clap::{App, AppSettings};
 
-mod de;
+mod cli;
 mod logging;
-mod pl;
-mod us;
+#[cfg(feature = "edeklaracje")]
+use cli::edeklaracje;
+use cli::{
+    clipboard, config, create_cmd_line_pattern, man, parse_country, parse_lot, parse_pit8c,
+    parse_purchase_fee, parse_split, parse_stock_dividend, parse_vest, parse_withdrawal,
+};
 use etradeTaxReturnHelper::run_taxation;
-use logging::ResultExt;
-
-fn create_cmd_line_pattern<'a, 'b>(myapp: App<'a, 'b>) -> App<'a, 'b> {
-    myapp
-        .arg(
-            Arg::with_name("residency")
-                .long("residency")
-                .help("Country of residence e.g. pl , us ...")
-                .value_name("FILE")
-                .takes_value(true)
-                .default_value("pl"),
-        )
-        .arg(
-            Arg::with_name("financial documents")
-                .help("Brokerage statement PDFs  and Gain & Losses xlsx documents\n\nBrokerege statements can be downloaded from:\n\thttps://edoc.etrade.com/e/t/onlinedocs/docsearch?doc_type=stmt\n\nGain&Losses documents can be downloaded from:\n\thttps://us.etrade.com/etx/sp/stockplan#/myAccount/gainsLosses\n")
-                .multiple(true)
-                .required(true),
-        )
+use etradeTaxReturnHelper::{de, pl, us, Error};
+
+// Failures of run as errors of given kind, with message of what failed
+trait Context<T> {
+    fn context(self, error: fn(String) -> Error, msg: &str) -> Result<T, Error>;
+}
+
+impl<T, E: std::fmt::Display> Context<T> for Result<T, E> {
+    fn context(self, error: fn(String) -> Error, msg: &str) -> Result<T, Error> {
+        self.map_err(|e| error(format!("{}: {}", msg, e)))
+    }
 }
 
+impl<T> Context<T> for Option<T> {
+    fn context(self, error: fn(String) -> Error, msg: &str) -> Result<T, Error> {
+        self.ok_or_else(|| error(msg.to_owned()))
+    }
+}
+
+fn present_year_result(
+    rd: &dyn etradeTaxReturnHelper::Residency,
+    result: &etradeTaxReturnHelper::TaxYearResult,
+    show_fx_differences: bool,
+    show_per_symbol: bool,
+    show_per_country: bool,
+    show_epit_guide: bool,
+) {
+    if result.gross_div_us != 0.0 || result.gross_interest_us != 0.0 || result.gross_sold != 0.0 {
+        rd.present_totals_us(result);
+    }
+    rd.present_result(
+        result.gross_div,
+        result.tax_div,
+        result.gross_sold,
+        result.cost_sold,
+    );
+    if show_per_symbol && !result.per_symbol.is_empty() {
+        rd.present_per_symbol(&result.per_symbol);
+    }
+    if show_per_country && !result.per_country.is_empty() {
+        rd.present_per_country(&result.per_country);
+    }
+    if !result.per_broker.is_empty() {
+        rd.present_per_broker(&result.per_broker);
+    }
+    if result.gross_sold != 0.0 {
+        rd.present_holding_terms(
+            (result.proceeds_short_us, result.cost_short_us),
+            (result.proceeds_long_us, result.cost_long_us),
+        );
+    }
+    if result.pit8c_income != 0.0 || result.pit8c_cost != 0.0 {
+        rd.present_pit8c(
+            (result.pit8c_income, result.pit8c_cost),
+            result.gross_sold,
+            result.cost_sold,
+        );
+    }
+    if result.crypto_income != 0.0 || result.crypto_cost != 0.0 || result.crypto_carried_cost != 0.0
+    {
+        rd.present_crypto(
+            result.crypto_income,
+            result.crypto_cost,
+            result.crypto_carried_cost,
+        );
+    }
+    if result.excess_withholding_us > 0.0 {
+        rd.present_excess_withholding(result.excess_withholding_us);
+    }
+    rd.present_tax_due(result);
+    if show_epit_guide {
+        rd.present_epit_guide(result);
+    }
+    if result.gross_interest != 0.0 {
+        rd.present_interest(result.gross_interest, result.tax_interest);
+    }
+    if show_fx_differences {
+        rd.present_fx_differences(result.fx_income, result.fx_cost);
+    }
+}
+
+/// Your share of income and tax of account split with spouse, in percent
+fn spouse_share(args: &clap::ArgMatches) -> Result<Option<f32>, Error> {
+    match args.is_present("split-with-spouse") {
+        true => Ok(Some(
+            args.value_of("split-with-spouse")
+                .unwrap_or("50")
+                .parse::<f32>()
+                .context(Error::Arguments, "error getting share value")?,
+        )),
+        false => Ok(None),
+    }
+}
+
+/// Summary of each tax year with details asked for on command line
+fn present_results(
+    rd: &dyn etradeTaxReturnHelper::Residency,
+    results: &std::collections::BTreeMap<i32, etradeTaxReturnHelper::TaxYearResult>,
+    options: &etradeTaxReturnHelper::TaxationOptions,
+    args: &clap::ArgMatches,
+) -> Result<(), Error> {
+    // Results in other formats were already printed by run_taxation
+    if options.format != etradeTaxReturnHelper::ExportFormat::Human {
+        return Ok(());
+    }
+    if results.is_empty() {
+        rd.present_result(0.0, 0.0, 0.0, 0.0);
+    }
+    let spouse_share = spouse_share(args)?;
+    let show_per_symbol = args.is_present("per-ticker");
+    let show_per_country = args.is_present("per-country");
+    let show_per_month = args.is_present("per-month");
+    let show_epit_guide = args.is_present("epit-guide");
+    results.iter().for_each(|(year, result)| {
+        rd.present_year(*year);
+        match spouse_share {
+            Some(share) => {
+                let (own, spouse) = etradeTaxReturnHelper::split_with_spouse(result, share);
+                rd.present_share(false, share);
+                present_year_result(
+                    rd,
+                    &own,
+                    !options.withdrawals.is_empty(),
+                    show_per_symbol,
+                    show_per_country,
+                    show_epit_guide,
+                );
+                rd.present_share(true, 100.0 - share);
+                present_year_result(
+                    rd,
+                    &spouse,
+                    !options.withdrawals.is_empty(),
+                    show_per_symbol,
+                    show_per_country,
+                    show_epit_guide,
+                );
+            }
+            None => present_year_result(
+                rd,
+                result,
+                !options.withdrawals.is_empty(),
+                show_per_symbol,
+                show_per_country,
+                show_epit_guide,
+            ),
+        }
+        // Statements cover whole account so months are not split with spouse
+        if show_per_month && !result.per_month.is_empty() {
+            rd.present_per_month(&result.per_month);
+        }
+        if !options.sheltered_accounts.is_empty() {
+            rd.present_sheltered(result.sheltered_gross_div_us, result.sheltered_tax_div_us);
+        }
+    });
+    Ok(())
+}
+
+/// Exit codes: 0 success, 1 other error, 2 some documents could not be
+/// parsed and were skipped, 3 network failure (e.g. exchange rates not
+/// available), 4 invalid command line arguments or input files
 fn main() {
+    let code = match run() {
+        Ok(()) => etradeTaxReturnHelper::exit_code(),
+        Err(e) => {
+            let prefix = etradeTaxReturnHelper::tr(cli::help_lang(), "Error");
+            eprintln!(
+                "{}",
+                etradeTaxReturnHelper::error(&format!("{}: {}", prefix, e))
+            );
+            log::error!("{}", e);
+            e.exit_code()
+        }
+    };
+    use std::io::Write;
+    let _ = std::io::stdout().flush();
+    std::process::exit(code);
+}
+
+fn run() -> Result<(), Error> {
     const VERSION: &str = env!("CARGO_PKG_VERSION");
     logging::init_logging_infrastructure();
 
+    let args =
+        config::with_config_defaults(std::env::args().collect()).map_err(Error::Arguments)?;
+    cli::set_help_lang(config::help_lang(&args));
     let myapp = App::new("etradeTaxHelper ".to_string() + VERSION)
         .setting(AppSettings::ArgRequiredElseHelp);
-    let matches = create_cmd_line_pattern(myapp).get_matches();
+    let matches = match create_cmd_line_pattern(myapp).get_matches_from_safe(args) {
+        Ok(matches) => matches,
+        // Help and version are printed to standard output with success
+        Err(e) if !e.use_stderr() => {
+            use std::io::Write;
+            let _ = writeln!(std::io::stdout(), "{}", cli::localize(&e.message));
+            std::process::exit(etradeTaxReturnHelper::EXIT_OK);
+        }
+        Err(e) => {
+            eprintln!("{}", cli::localize(&e.message));
+            std::process::exit(etradeTaxReturnHelper::EXIT_VALIDATION);
+        }
+    };
+
+    if matches.is_present("generate-man") {
+        // Man page is installed by packages, so it is always in English
+        cli::set_help_lang(etradeTaxReturnHelper::Lang::En);
+        print!(
+            "{}",
+            man::man_page(
+                || create_cmd_line_pattern(App::new("etradeTaxHelper ".to_string() + VERSION)),
+                "etradeTaxReturnHelper",
+                VERSION,
+                env!("CARGO_PKG_DESCRIPTION"),
+            )
+        );
+        return Ok(());
+    }
+    etradeTaxReturnHelper::set_color_enabled(!matches.is_present("no-color"));
+    etradeTaxReturnHelper::set_locale(
+        etradeTaxReturnHelper::Locale::from_name(
+            matches
+                .value_of("locale")
+                .context(Error::Arguments, "error getting locale value")?,
+        )
+        .context(Error::Arguments, "error getting locale value")?,
+    );
 
     log::info!("Started etradeTaxHelper");
 
+    if let Some(path) = matches.value_of("record-http") {
+        let recorder = etradeTaxReturnHelper::Recorder::new(path)?;
+        etradeTaxReturnHelper::set_http_client(Box::new(recorder))
+            .context(Error::Report, "error setting HTTP client")?;
+    }
+    if let Some(path) = matches.value_of("replay-http") {
+        let replayer = etradeTaxReturnHelper::Replayer::open(path)
+            .context(Error::Arguments, "error reading recorded responses")?;
+        etradeTaxReturnHelper::set_http_client(Box::new(replayer))
+            .context(Error::Report, "error setting HTTP client")?;
+    }
+
+    let plugins = match matches.value_of("plugins") {
+        Some(dir) => etradeTaxReturnHelper::discover_plugins(dir)?,
+        None => match config::default_plugins_dir() {
+            Some(dir) if dir.is_dir() => {
+                etradeTaxReturnHelper::discover_plugins(&dir.to_string_lossy())?
+            }
+            _ => vec![],
+        },
+    };
+    etradeTaxReturnHelper::set_plugins(plugins)
+        .context(Error::Report, "error setting parser plugins")?;
+
+    // Without subcommand options of compute are given at top level
+    let (command, args) = match matches.subcommand() {
+        (name, Some(subcommand)) => (name, subcommand),
+        _ => ("compute", &matches),
+    };
+
+    // -q prints only key=value lines, -v adds transaction tables and info
+    // messages, -vv debug ones
+    let verbosity = match args.is_present("quiet") {
+        true => -1,
+        false => matches
+            .occurrences_of("verbose")
+            .max(args.occurrences_of("verbose")) as i32,
+    };
+    // Records of stages are info ones, to be monitored in JSON format
+    let json_log = matches.value_of("log-format") == Some("json");
+    logging::set_json_format(json_log);
+    logging::set_verbosity(match json_log && verbosity == 0 {
+        true => 1,
+        false => verbosity,
+    });
+
+    #[cfg(not(feature = "edeklaracje"))]
+    if args.is_present("edeklaracja") {
+        return Err(Error::MissingFeature(
+            "PIT-38 e-Deklaracje XML".to_owned(),
+            "edeklaracje".to_owned(),
+        ));
+    }
+
+    if !matches.is_present("no-progress") && !args.is_present("quiet") {
+        etradeTaxReturnHelper::set_progress(Box::new(cli::progress::ProgressBars::default()))
+            .context(Error::Report, "error setting progress bars")?;
+    }
+
+    if let Some(diff) = matches.subcommand_matches("diff") {
+        etradeTaxReturnHelper::diff_exports(
+            diff.value_of("old")
+                .context(Error::Arguments, "error getting old export")?,
+            diff.value_of("new")
+                .context(Error::Arguments, "error getting new export")?,
+        )
+        .context(Error::Arguments, "error comparing exports")?
+        .iter()
+        .for_each(|line| println!("{}", line));
+        return Ok(());
+    }
+
+    #[cfg(not(feature = "broker-api"))]
+    if matches.subcommand_matches("etrade-api").is_some() {
+        return Err(Error::MissingFeature(
+            "E*TRADE API import".to_owned(),
+            "broker-api".to_owned(),
+        ));
+    }
+    #[cfg(feature = "broker-api")]
+    if let Some(api) = matches.subcommand_matches("etrade-api") {
+        let date = |name: &str| {
+            api.value_of(name)
+                .map(|d| {
+                    chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d")
+                        .context(Error::Arguments, "error getting date value")
+                })
+                .transpose()
+        };
+        let accounts: Vec<String> = match api.values_of("account-id") {
+            Some(values) => values.map(|a| a.to_owned()).collect(),
+            None => vec![],
+        };
+        cli::etrade::import(
+            api.value_of("consumer-key")
+                .context(Error::Arguments, "error getting consumer key")?,
+            api.is_present("sandbox"),
+            &accounts,
+            (
+                date("from")?.context(Error::Arguments, "error getting from date")?,
+                date("to")?.unwrap_or(chrono::Local::now().naive_local().date()),
+            ),
+            api.value_of("statement")
+                .context(Error::Arguments, "error getting statement path")?,
+        )
+        .context(Error::Network, "error importing E*TRADE transactions")?;
+        return Ok(());
+    }
+
+    if let Some(login) = matches.subcommand_matches("login") {
+        cli::login::login(
+            login
+                .value_of("service")
+                .context(Error::Arguments, "error getting service")?,
+            login
+                .value_of("user")
+                .context(Error::Arguments, "error getting user")?,
+        )
+        .context(Error::Report, "error storing password")?;
+        return Ok(());
+    }
+
+    if let Some(logout) = matches.subcommand_matches("logout") {
+        cli::login::logout(
+            logout
+                .value_of("service")
+                .context(Error::Arguments, "error getting service")?,
+            logout
+                .value_of("user")
+                .context(Error::Arguments, "error getting user")?,
+        )
+        .context(Error::Report, "error removing password")?;
+        return Ok(());
+    }
+
+    if let Some(update) = matches.subcommand_matches("self-update") {
+        let release = etradeTaxReturnHelper::check_for_update().map_err(Error::Network)?;
+        match release {
+            None => println!(
+                "etradeTaxReturnHelper {} is up to date",
+                env!("CARGO_PKG_VERSION")
+            ),
+            Some(release) if update.is_present("check") => println!(
+                "etradeTaxReturnHelper {} is available (installed: {}), update with: etradeTaxReturnHelper self-update",
+                release.version,
+                env!("CARGO_PKG_VERSION")
+            ),
+            Some(release) => {
+                let exe = etradeTaxReturnHelper::install_update(&release)?;
+                println!(
+                    "etradeTaxReturnHelper updated from {} to {} ({})",
+                    env!("CARGO_PKG_VERSION"),
+                    release.version,
+                    exe.display()
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(batch) = matches.subcommand_matches("batch") {
+        let path = batch
+            .value_of("job-file")
+            .context(Error::Arguments, "error getting job file")?;
+        let outcomes = cli::batch::run_jobs(
+            path,
+            batch.is_present("dry-run"),
+            batch.is_present("resume"),
+            matches.value_of("log-format").unwrap_or("text"),
+        )?;
+        let failed = cli::batch::present(path, &outcomes);
+        return match failed {
+            0 => Ok(()),
+            failed => Err(Error::Report(format!(
+                "{} of {} jobs failed",
+                failed,
+                outcomes.len()
+            ))),
+        };
+    }
+
+    let profile = match matches.value_of("profile") {
+        Some(name) => Some(config::Profile::open(name)?),
+        None => None,
+    };
+    if let Some(profile) = &profile {
+        log::info!("Profile {} in {}", profile.name, profile.dir.display());
+        if !args.is_present("dry-run") && profile.create()? {
+            let msg = format!(
+                "Created profile {} in {}",
+                profile.name,
+                profile.dir.display()
+            );
+            eprintln!("{}", etradeTaxReturnHelper::warning(&msg));
+            log::warn!("{}", msg);
+        }
+    }
+    // Reports of profile are written to its output directory, ledger and
+    // database are kept in its directory
+    let output = |path: &str| match &profile {
+        Some(profile) => profile.output(path),
+        None => path.to_owned(),
+    };
+    let record = |path: &str| match &profile {
+        Some(profile) => profile.record(path),
+        None => path.to_owned(),
+    };
+
     let residency = matches
         .value_of("residency")
-        .expect_and_log("error getting residency value");
+        .context(Error::Arguments, "error getting residency value")?;
+    let lang = etradeTaxReturnHelper::Lang::from_name(
+        matches
+            .value_of("lang")
+            .context(Error::Arguments, "error getting lang value")?,
+    )
+    .context(Error::Arguments, "error getting lang value")?;
+    let form = match matches.value_of("form") {
+        Some("pit36") => pl::Form::PIT36,
+        _ => pl::Form::PIT38,
+    };
     let rd: Box<dyn etradeTaxReturnHelper::Residency> = match residency {
         "de" => Box::new(de::DE {}),
-        "pl" => Box::new(pl::PL {}),
+        "pl" => {
+            let mut rules =
+                etradeTaxReturnHelper::load_tax_rules("pl", matches.value_of("tax-rules"))
+                    .context(Error::Arguments, "error loading tax rules")?;
+            if let Some(rate) = matches.value_of("tax-rate") {
+                let rate = rate
+                    .parse::<f32>()
+                    .context(Error::Arguments, "error getting tax rate value")?;
+                rules.values_mut().for_each(|r| r.tax_rate = rate / 100.0);
+            }
+            if matches.is_present("epit-rounding") {
+                rules.values_mut().for_each(|r| r.epit_rounding = true);
+            }
+            Box::new(pl::PL::new(form, rules, lang))
+        }
         "us" => Box::new(us::US {}),
-        _ => panic!(
-            "{}",
-            &format!("Error: unimplemented residency: {}", residency)
-        ),
+        _ => return Err(format!("Error: unimplemented residency: {}", residency).into()),
     };
 
-    let pdfnames = matches
-        .values_of("financial documents")
-        .expect_and_log("error getting brokarage statements pdfs names.\n\nBrokerege statements can be downloaded from:\n\nhttps://edoc.etrade.com/e/t/onlinedocs/docsearch?doc_type=stmt\n\n");
+    if let Some(simulation) = matches.subcommand_matches("simulate") {
+        let lots: Vec<(String, f32, f32)> = simulation
+            .values_of("lot")
+            .context(Error::Arguments, "error getting lots")?
+            .map(|l| parse_lot(l).context(Error::Arguments, "error getting lot value"))
+            .collect::<Result<_, Error>>()?;
+        let splits: Vec<(String, f32)> = match simulation.values_of("split") {
+            Some(values) => values
+                .map(|s| parse_split(s).context(Error::Arguments, "error getting split value"))
+                .collect::<Result<_, Error>>()?,
+            None => vec![],
+        };
+        let price = simulation
+            .value_of("price")
+            .context(Error::Arguments, "error getting price value")?
+            .parse::<f32>()
+            .context(Error::Arguments, "error getting price value")?;
+        let sale_date = match simulation.value_of("date") {
+            Some(d) => chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d")
+                .context(Error::Arguments, "error getting sale date value")?,
+            None => chrono::Local::now().naive_local().date(),
+        };
+        let (income, cost) = etradeTaxReturnHelper::simulate_sale(
+            &*rd,
+            &lots,
+            &splits,
+            &sale_date.format("%m/%d/%y").to_string(),
+            price,
+        )?;
+        rd.present_simulation(income, cost);
+        return Ok(());
+    }
 
-    let (gross_div, tax_div, gross_sold, cost_sold) = run_taxation(&rd, pdfnames).unwrap();
+    if let Some(forecast) = matches.subcommand_matches("forecast") {
+        let vests: Vec<(String, f32)> = forecast
+            .values_of("vest")
+            .context(Error::Arguments, "error getting vests")?
+            .map(|v| parse_vest(v).context(Error::Arguments, "error getting vest value"))
+            .collect::<Result<_, Error>>()?;
+        let price = forecast
+            .value_of("price")
+            .context(Error::Arguments, "error getting price value")?
+            .parse::<f32>()
+            .context(Error::Arguments, "error getting price value")?;
+        let forecast = etradeTaxReturnHelper::forecast_vests(&*rd, &vests, price)?;
+        rd.present_vests_forecast(&forecast);
+        return Ok(());
+    }
 
-    rd.present_result(gross_div, tax_div, gross_sold, cost_sold);
-}
+    if let Some(interest) = matches.subcommand_matches("interest") {
+        if residency != "pl" {
+            return Err(format!(
+                "Error: late payment interest is not implemented for residency: {}",
+                residency
+            )
+            .into());
+        }
+        let amount = interest
+            .value_of("amount")
+            .context(Error::Arguments, "error getting amount value")?
+            .parse::<f32>()
+            .context(Error::Arguments, "error getting amount value")?;
+        let deadline = chrono::NaiveDate::parse_from_str(
+            interest
+                .value_of("deadline")
+                .context(Error::Arguments, "error getting deadline value")?,
+            "%Y-%m-%d",
+        )
+        .context(Error::Arguments, "error getting deadline value")?;
+        let payment_date = match interest.value_of("payment") {
+            Some(d) => chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d")
+                .context(Error::Arguments, "error getting payment date value")?,
+            None => chrono::Local::now().naive_local().date(),
+        };
+        let late_interest = etradeTaxReturnHelper::late_payment_interest(
+            amount,
+            &deadline.format("%m/%d/%y").to_string(),
+            &payment_date.format("%m/%d/%y").to_string(),
+            interest.is_present("reduced"),
+        )
+        .context(Error::Arguments, "error computing late payment interest")?;
+        rd.present_late_payment_interest(
+            amount,
+            (payment_date - deadline).num_days().max(0),
+            late_interest,
+        );
+        return Ok(());
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use clap::{App, Arg, ArgMatches, ErrorKind, Values};
+    if command == "rates" {
+        let dates: Vec<chrono::NaiveDate> = args
+            .values_of("dates")
+            .context(Error::Arguments, "error getting dates")?
+            .map(|d| {
+                chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d")
+                    .context(Error::Arguments, "error getting date value")
+            })
+            .collect::<Result<_, Error>>()?;
+        let mut rate_dates: std::collections::HashMap<String, Option<(String, f32)>> = dates
+            .iter()
+            .map(|d| (d.format("%m/%d/%y").to_string(), None))
+            .collect();
+        rd.get_exchange_rates(&mut rate_dates)?;
+        dates.iter().for_each(|d| {
+            if let Some(Some((rate_date, rate))) = rate_dates.get(&d.format("%m/%d/%y").to_string())
+            {
+                rd.present_exchange_rate(&d.format("%Y-%m-%d").to_string(), rate_date, *rate);
+            }
+        });
+        return Ok(());
+    }
 
-    #[test]
-    fn test_exchange_rate_de() -> Result<(), String> {
-        let rd: Box<dyn etradeTaxReturnHelper::Residency> = Box::new(de::DE {});
+    if command == "serve" {
+        let address = args
+            .value_of("address")
+            .context(Error::Arguments, "error getting address value")?;
+        return Ok(cli::serve::serve(&*rd, lang, address)?);
+    }
 
-        let mut dates: std::collections::HashMap<String, Option<(String, f32)>> =
-            std::collections::HashMap::new();
-        dates.insert("02/21/23".to_owned(), None);
-        rd.get_exchange_rates(&mut dates).unwrap();
+    // Watch presents year to date figures, unless other year is given
+    let year = args
+        .value_of("year")
+        .map(|y| {
+            y.parse::<i32>()
+                .context(Error::Arguments, "error getting tax year value")
+        })
+        .transpose()?
+        .or((command == "watch").then(|| chrono::Local::now().year()));
 
-        let (exchange_rate_date, exchange_rate) = dates.remove("02/21/23").unwrap().unwrap();
-        assert_eq!(
-            (exchange_rate_date, exchange_rate),
-            ("2023-02-20".to_owned(), 0.93561)
-        );
-        Ok(())
+    let withdrawals: Vec<(String, f32)> = match args.values_of("withdrawal") {
+        Some(values) => values
+            .map(|w| {
+                parse_withdrawal(w).context(Error::Arguments, "error getting withdrawal value")
+            })
+            .collect::<Result<_, Error>>()?,
+        None => vec![],
+    };
+
+    let options = etradeTaxReturnHelper::TaxationOptions {
+        year,
+        withdrawals,
+        purchase_fees: match args.values_of("purchase-fee") {
+            Some(values) => values
+                .map(|f| {
+                    parse_purchase_fee(f)
+                        .context(Error::Arguments, "error getting purchase fee value")
+                })
+                .collect::<Result<_, Error>>()?,
+            None => vec![],
+        },
+        net_same_day_trades: args.is_present("net-same-day"),
+        sheltered_accounts: match args.values_of("sheltered-account") {
+            Some(values) => values.map(|a| a.to_owned()).collect(),
+            None => vec![],
+        },
+        pit8c: args
+            .value_of("pit8c")
+            .map(|p| parse_pit8c(p).context(Error::Arguments, "error getting PIT-8C value"))
+            .transpose()?,
+        crypto_carried_cost: args
+            .value_of("crypto-carried-cost")
+            .map(|c| {
+                c.parse::<f32>()
+                    .context(Error::Arguments, "error getting crypto carried cost value")
+            })
+            .transpose()?
+            .unwrap_or(0.0),
+        use_record_date: args.value_of("income-date") == Some("record"),
+        export: args
+            .values_of("export")
+            .map(|values| {
+                values
+                    .collect::<Vec<&str>>()
+                    .chunks(2)
+                    .map(|pair| {
+                        let format = etradeTaxReturnHelper::ExportFormat::from_name(pair[0])
+                            .context(Error::Arguments, "error getting export format")?;
+                        Ok((format, output(pair[1])))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()
+            })
+            .transpose()?
+            .unwrap_or_default()
+            .into_iter()
+            .map(Ok)
+            .chain(args.values_of("output").into_iter().flatten().map(|path| {
+                let format = etradeTaxReturnHelper::ExportFormat::from_path(path)
+                    .context(Error::Arguments, "error getting output format")?;
+                Ok((format, output(path)))
+            }))
+            .collect::<Result<_, Error>>()?,
+        audit: args.value_of("audit").map(output),
+        anonymize: args.is_present("anonymize"),
+        review: args.is_present("review"),
+        dry_run: args.is_present("dry-run"),
+        checkpoint: args.value_of("checkpoint").map(record),
+        corrections: match (args.value_of("corrections"), &profile) {
+            (Some(c), _) => Some(record(c)),
+            (None, Some(profile)) => Some(profile.record("corrections.json")),
+            (None, None) => {
+                config::default_corrections_path().map(|path| path.to_string_lossy().to_string())
+            }
+        },
+        correct: args.is_present("correct"),
+        transaction_tables: verbosity > 0,
+        google_sheets: args.value_of("google-sheets").map(|g| g.to_owned()),
+        email: args.value_of("email").map(|e| e.to_owned()),
+        ledger: match (args.value_of("ledger"), &profile) {
+            (Some(l), _) => Some(record(l)),
+            (None, Some(profile)) => Some(profile.ledger()),
+            (None, None) => None,
+        }
+        .map(|l| {
+            let account = args
+                .value_of("account")
+                .context(Error::Arguments, "error getting account value")?;
+            Ok::<_, Error>((l, account.to_owned()))
+        })
+        .transpose()?,
+        database: args
+            .value_of("database")
+            .map(|d| {
+                let account = args
+                    .value_of("account")
+                    .context(Error::Arguments, "error getting account value")?;
+                Ok::<_, Error>((record(d), account.to_owned()))
+            })
+            .transpose()?,
+        template: args
+            .values_of("template")
+            .map(|mut values| {
+                let template = values
+                    .next()
+                    .context(Error::Arguments, "error getting template file")?;
+                let path = values
+                    .next()
+                    .context(Error::Arguments, "error getting template path")?;
+                Ok::<_, Error>((template.to_owned(), output(path)))
+            })
+            .transpose()?,
+        // Results of import and report are printed as with --quiet
+        format: match args.is_present("quiet") || !matches!(command, "compute" | "watch") {
+            true => etradeTaxReturnHelper::ExportFormat::KeyValue,
+            false => etradeTaxReturnHelper::ExportFormat::from_name(
+                args.value_of("format")
+                    .context(Error::Arguments, "error getting format value")?,
+            )
+            .context(Error::Arguments, "error getting format value")?,
+        },
+        lang,
+        countries: match args.values_of("country") {
+            Some(values) => values
+                .map(|c| parse_country(c).context(Error::Arguments, "error getting country value"))
+                .collect::<Result<_, Error>>()?,
+            None => std::collections::HashMap::new(),
+        },
+        stock_dividends: match args.values_of("stock-dividend") {
+            Some(values) => values
+                .map(|d| {
+                    parse_stock_dividend(d)
+                        .context(Error::Arguments, "error getting stock dividend value")
+                })
+                .collect::<Result<_, Error>>()?,
+            None => vec![],
+        },
+        prepaid_tax: args
+            .value_of("prepaid-tax")
+            .map(|p| {
+                p.parse::<f32>()
+                    .context(Error::Arguments, "error getting prepaid tax value")
+            })
+            .transpose()?,
+        source: None,
+    };
+
+    // Ctrl-C stops taxation with transactions processed so far written out,
+    // pressed again it ends program at once
+    let interrupted = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    signal_hook::flag::register_conditional_shutdown(
+        signal_hook::consts::SIGINT,
+        etradeTaxReturnHelper::EXIT_INTERRUPTED,
+        interrupted.clone(),
+    )
+    .context(Error::Report, "error handling Ctrl-C")?;
+    signal_hook::flag::register(signal_hook::consts::SIGINT, interrupted.clone())
+        .context(Error::Report, "error handling Ctrl-C")?;
+    etradeTaxReturnHelper::set_interruption_flag(interrupted.clone())
+        .context(Error::Report, "error handling Ctrl-C")?;
+
+    if command == "watch" {
+        let dir = args
+            .value_of("directory")
+            .context(Error::Arguments, "error getting directory")?;
+        let interval = args
+            .value_of("interval")
+            .context(Error::Arguments, "error getting interval value")?
+            .parse::<u64>()
+            .context(Error::Arguments, "error getting interval value")?;
+        cli::watch::watch(
+            dir,
+            std::time::Duration::from_secs(interval),
+            &interrupted,
+            lang,
+            |documents| {
+                let results = run_taxation(&*rd, documents.iter().map(|d| d.as_str()), &options)?;
+                present_results(&*rd, &results, &options, args)?;
+                Ok::<(), Error>(())
+            },
+        )?;
+        return Ok(());
     }
 
-    #[test]
-    fn test_exchange_rate_pl() -> Result<(), String> {
-        let rd: Box<dyn etradeTaxReturnHelper::Residency> = Box::new(pl::PL {});
+    let pdfnames = args
+        .values_of("financial documents")
+        .context(Error::Arguments, "error getting brokarage statements pdfs names.\n\nBrokerege statements can be downloaded from:\n\nhttps://edoc.etrade.com/e/t/onlinedocs/docsearch?doc_type=stmt\n\n")?;
 
-        let mut dates: std::collections::HashMap<String, Option<(String, f32)>> =
-            std::collections::HashMap::new();
-        dates.insert("03/01/21".to_owned(), None);
-        rd.get_exchange_rates(&mut dates).unwrap();
-        let (exchange_rate_date, exchange_rate) = dates.remove("03/01/21").unwrap().unwrap();
-        assert_eq!(
-            (exchange_rate_date, exchange_rate),
-            ("2021-02-26".to_owned(), 3.7247)
+    if command == "validate" {
+        let (dividends, interest, sold, crypto) =
+            etradeTaxReturnHelper::validate_documents(pdfnames, &options)?;
+        [
+            ("DYWIDENDY", dividends),
+            ("ODSETKI", interest),
+            ("SPRZEDAZ AKCJI", sold),
+            ("KRYPTOWALUTY", crypto),
+        ]
+        .iter()
+        .for_each(|(kind, count)| {
+            println!(
+                "===> {} {}: {}",
+                etradeTaxReturnHelper::tr(lang, "(WERYFIKACJA DOKUMENTOW)"),
+                etradeTaxReturnHelper::tr(lang, kind),
+                count
+            );
+        });
+        return Ok(());
+    }
+
+    let results = run_taxation(&*rd, pdfnames, &options)?;
+
+    present_results(&*rd, &results, &options, args)?;
+    let spouse_share = spouse_share(args)?;
+
+    #[cfg(feature = "edeklaracje")]
+    if let Some(path) = args.value_of("edeklaracja").map(output) {
+        if residency != "pl" {
+            return Err(format!(
+                "Error: e-Deklaracje are not implemented for residency: {}",
+                residency
+            )
+            .into());
+        }
+        let year = year.context(Error::Arguments, "error getting tax year value")?;
+        let result = results
+            .get(&year)
+            .ok_or(format!("Error: no results for tax year {}", year))?;
+        let own =
+            spouse_share.map(|share| etradeTaxReturnHelper::split_with_spouse(result, share).0);
+        let rules = rd.rules_for_year(year).context(
+            Error::Arguments,
+            &format!("no tax rules for tax year {}", year),
+        )?;
+        let schema = rules.pit38_schema.clone().context(Error::Arguments, &format!(
+            "no PIT-38 e-Deklaracje schema for tax year {} in tax rules. Add pit38_schema with --tax-rules",
+            year
+        ))?;
+        let taxpayer = edeklaracje::load_taxpayer(
+            args.value_of("taxpayer")
+                .context(Error::Arguments, "error getting taxpayer file")?,
+        )
+        .context(Error::Arguments, "error loading taxpayer data")?;
+        let fields = pl::pit38_fields(own.as_ref().unwrap_or(result), &rules, &form);
+        let xml = edeklaracje::pit38_xml(year, &taxpayer, &schema, &fields);
+        if options.dry_run {
+            let msg = format!("Dry run, would write PIT-38 e-Deklaracja to {}", path);
+            eprintln!("{}", etradeTaxReturnHelper::warning(&msg));
+            log::info!("{}", msg);
+        } else {
+            etradeTaxReturnHelper::write_file(&path, xml.as_bytes())
+                .context(Error::Report, &format!("error writing {}", path))?;
+            log::info!("PIT-38 e-Deklaracja written to {}", path);
+        }
+    }
+
+    if args.is_present("copy") {
+        if residency != "pl" {
+            return Err(format!(
+                "Error: copying PIT-38 figures is not implemented for residency: {}",
+                residency
+            )
+            .into());
+        }
+        // Tax year given with --year or the latest one
+        let (year, result) = match year {
+            Some(year) => (
+                year,
+                results
+                    .get(&year)
+                    .ok_or(format!("Error: no results for tax year {}", year))?,
+            ),
+            None => results
+                .iter()
+                .next_back()
+                .map(|(year, result)| (*year, result))
+                .context(Error::Report, "no results to copy")?,
+        };
+        let own =
+            spouse_share.map(|share| etradeTaxReturnHelper::split_with_spouse(result, share).0);
+        let rules = rd.rules_for_year(year).context(
+            Error::Arguments,
+            &format!("no tax rules for tax year {}", year),
+        )?;
+        let fields = pl::pit38_fields(own.as_ref().unwrap_or(result), &rules, &form);
+        clipboard::copy_to_clipboard(&pl::pit38_clipboard_text(&fields))
+            .context(Error::Report, "error copying PIT-38 figures")?;
+        println!(
+            "===> {} ({}): {} {}",
+            etradeTaxReturnHelper::tr(lang, "SKOPIOWANO DO SCHOWKA"),
+            year,
+            etradeTaxReturnHelper::tr(lang, "poz."),
+            fields
+                .iter()
+                .filter(|(field, _)| *field != 30)
+                .map(|(field, _)| field.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
         );
-        Ok(())
     }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logging::ResultExt;
 
     #[test]
     fn test_exchange_rate_us() -> Result<(), String> {
@@ -107,94 +926,16 @@ mod tests {
         Ok(())
     }
 
-    #[test]
-    fn test_cmdline_de() -> Result<(), clap::Error> {
-        // Init Transactions
-        let myapp = App::new("E-trade tax helper");
-        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
-            "mytest",
-            "--residency=de",
-            "data/example.pdf",
-        ])?;
-        let residency = matches.value_of("residency").ok_or(clap::Error {
-            message: "Unable to get residency value".to_owned(),
-            kind: ErrorKind::InvalidValue,
-            info: None,
-        })?;
-        match residency {
-            "de" => return Ok(()),
-            _ => clap::Error {
-                message: "Wrong residency value".to_owned(),
-                kind: ErrorKind::InvalidValue,
-                info: None,
-            },
-        };
-        Ok(())
-    }
-
-    #[test]
-    fn test_cmdline_pl() -> Result<(), clap::Error> {
-        // Init Transactions
-        let myapp = App::new("E-trade tax helper");
-        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
-            "mytest",
-            "--residency=pl",
-            "data/example.pdf",
-        ])?;
-        let residency = matches.value_of("residency").ok_or(clap::Error {
-            message: "Unable to get residency value".to_owned(),
-            kind: ErrorKind::InvalidValue,
-            info: None,
-        })?;
-        match residency {
-            "pl" => return Ok(()),
-            _ => clap::Error {
-                message: "Wrong residency value".to_owned(),
-                kind: ErrorKind::InvalidValue,
-                info: None,
-            },
-        };
-        Ok(())
-    }
-    #[test]
-    fn test_cmdline_default() -> Result<(), clap::Error> {
-        // Init Transactions
-        let myapp = App::new("E-trade tax helper");
-        create_cmd_line_pattern(myapp).get_matches_from_safe(vec!["mytest", "data/example.pdf"])?;
-        Ok(())
-    }
-
-    #[test]
-    fn test_cmdline_us() -> Result<(), clap::Error> {
-        // Init Transactions
-        let myapp = App::new("E-trade tax helper");
-        let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
-            "mytest",
-            "--residency=us",
-            "data/example.pdf",
-        ])?;
-        let residency = matches.value_of("residency").ok_or(clap::Error {
-            message: "Unable to get residency value".to_owned(),
-            kind: ErrorKind::InvalidValue,
-            info: None,
-        })?;
-        match residency {
-            "us" => return Ok(()),
-            _ => clap::Error {
-                message: "Wrong residency value".to_owned(),
-                kind: ErrorKind::InvalidValue,
-                info: None,
-            },
-        };
-        Ok(())
-    }
-
     #[test]
     #[ignore]
     fn test_dividends_taxation() -> Result<(), clap::Error> {
         // Get all brokerage with dividends only
         let myapp = App::new("E-trade tax helper").setting(AppSettings::ArgRequiredElseHelp);
-        let rd: Box<dyn etradeTaxReturnHelper::Residency> = Box::new(pl::PL {});
+        let rd: Box<dyn etradeTaxReturnHelper::Residency> = Box::new(pl::PL::new(
+            pl::Form::PIT38,
+            etradeTaxReturnHelper::load_tax_rules("pl", None).unwrap(),
+            etradeTaxReturnHelper::Lang::Pl,
+        ));
         // Check printed values or returned values?
         let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
             "mytest",
@@ -213,8 +954,21 @@ mod tests {
             .values_of("financial documents")
             .expect_and_log("error getting brokarage statements pdfs names");
         assert_eq!(
-            etradeTaxReturnHelper::run_taxation(&rd, pdfnames),
-            Ok((14062.57, 2109.3772, 395.45355, 91.156715))
+            etradeTaxReturnHelper::run_taxation(
+                &*rd,
+                pdfnames,
+                &etradeTaxReturnHelper::TaxationOptions::default()
+            ),
+            Ok(std::collections::BTreeMap::from([(
+                2022,
+                etradeTaxReturnHelper::TaxYearResult {
+                    gross_div: 14062.57,
+                    tax_div: 2109.3772,
+                    gross_sold: 395.45355,
+                    cost_sold: 91.156715,
+                    ..Default::default()
+                }
+            )]))
         );
         Ok(())
     }
@@ -224,7 +978,11 @@ mod tests {
     fn test_sold_dividends_taxation() -> Result<(), clap::Error> {
         // Get all brokerage with dividends only
         let myapp = App::new("E-trade tax helper").setting(AppSettings::ArgRequiredElseHelp);
-        let rd: Box<dyn etradeTaxReturnHelper::Residency> = Box::new(pl::PL {});
+        let rd: Box<dyn etradeTaxReturnHelper::Residency> = Box::new(pl::PL::new(
+            pl::Form::PIT38,
+            etradeTaxReturnHelper::load_tax_rules("pl", None).unwrap(),
+            etradeTaxReturnHelper::Lang::Pl,
+        ));
         let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
             "mytest",
             "data/Brokerage Statement - XXXX0848 - 202202.pdf",
@@ -237,8 +995,21 @@ mod tests {
             .values_of("financial documents")
             .expect_and_log("error getting brokarage statements pdfs names");
         assert_eq!(
-            etradeTaxReturnHelper::run_taxation(&rd, pdfnames),
-            Ok((2930.206, 439.54138, 395.45355, 91.156715))
+            etradeTaxReturnHelper::run_taxation(
+                &*rd,
+                pdfnames,
+                &etradeTaxReturnHelper::TaxationOptions::default()
+            ),
+            Ok(std::collections::BTreeMap::from([(
+                2022,
+                etradeTaxReturnHelper::TaxYearResult {
+                    gross_div: 2930.206,
+                    tax_div: 439.54138,
+                    gross_sold: 395.45355,
+                    cost_sold: 91.156715,
+                    ..Default::default()
+                }
+            )]))
         );
         Ok(())
     }
@@ -248,7 +1019,11 @@ mod tests {
     fn test_sold_dividends_only_taxation() -> Result<(), clap::Error> {
         // Get all brokerage with dividends only
         let myapp = App::new("E-trade tax helper").setting(AppSettings::ArgRequiredElseHelp);
-        let rd: Box<dyn etradeTaxReturnHelper::Residency> = Box::new(pl::PL {});
+        let rd: Box<dyn etradeTaxReturnHelper::Residency> = Box::new(pl::PL::new(
+            pl::Form::PIT38,
+            etradeTaxReturnHelper::load_tax_rules("pl", None).unwrap(),
+            etradeTaxReturnHelper::Lang::Pl,
+        ));
         let matches = create_cmd_line_pattern(myapp).get_matches_from_safe(vec![
             "mytest",
             "data/Brokerage Statement - XXXX0848 - 202206.pdf",
@@ -257,8 +1032,21 @@ mod tests {
             .values_of("financial documents")
             .expect_and_log("error getting brokarage statements pdfs names");
         assert_eq!(
-            etradeTaxReturnHelper::run_taxation(&rd, pdfnames),
-            Ok((3272.3125, 490.82773, 0.0, 0.0)),
+            etradeTaxReturnHelper::run_taxation(
+                &*rd,
+                pdfnames,
+                &etradeTaxReturnHelper::TaxationOptions::default()
+            ),
+            Ok(std::collections::BTreeMap::from([(
+                2022,
+                etradeTaxReturnHelper::TaxYearResult {
+                    gross_div: 3272.3125,
+                    tax_div: 490.82773,
+                    gross_sold: 0.0,
+                    cost_sold: 0.0,
+                    ..Default::default()
+                }
+            )])),
         );
         Ok(())
     }
@@ -0,0 +1,558 @@
+//! OAuth handshakes of services data is imported from or exported to:
+//! OAuth 1.0a (E*TRADE) and OAuth 2.0 with device code (Google) or browser
+//! redirected to local listener. Tokens are kept in keychain between runs and
+//! refreshed, so user authorizes again only once they expire
+
+#[cfg(feature = "broker-api")]
+use hmac::{Hmac, Mac};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Digest;
+use std::collections::HashMap;
+
+type ReqwestClient = reqwest::blocking::Client;
+
+// User has that long to authorize in browser redirected to local listener
+const REDIRECT_TIMEOUT_SECS: u64 = 300;
+
+/// Text percent-encoded as OAuth requires (RFC 3986): all but unreserved
+/// characters are escaped
+pub(crate) fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Percent-encoded value of form e.g. token of response
+fn percent_decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = text
+                    .get(i + 1..i + 3)
+                    .ok_or(format!("invalid escape in {}", text))?;
+                decoded.push(
+                    u8::from_str_radix(hex, 16)
+                        .map_err(|_| format!("invalid escape in {}", text))?,
+                );
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| format!("invalid value {}", text))
+}
+
+/// Decoded values of form encoded text e.g. query of URL
+fn form_values(text: &str) -> Result<HashMap<String, String>, String> {
+    text.split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| Ok((percent_decode(key)?, percent_decode(value)?)))
+        .collect()
+}
+
+fn random_bytes(count: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![0; count];
+    getrandom::getrandom(&mut bytes).map_err(|e| format!("Error getting random bytes: {}", e))?;
+    Ok(bytes)
+}
+
+/// Base64 of URL and file names (RFC 4648) without padding
+fn base64_url(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+/// Value of service and user kept in keychain e.g. token. Keychain that can
+/// not be used is the same as no value there
+pub(crate) fn load_cached<T: DeserializeOwned>(service: &str, user: &str) -> Option<T> {
+    match crate::credentials::load_secret(service, user) {
+        Ok(Some(value)) => serde_json::from_str(&value).ok(),
+        Ok(None) => None,
+        Err(e) => {
+            log::warn!("{}", e);
+            None
+        }
+    }
+}
+
+/// Keep value of service and user in keychain, if keychain can be used
+pub(crate) fn store_cached<T: Serialize>(service: &str, user: &str, value: &T) {
+    let stored = serde_json::to_string(value)
+        .map_err(|e| e.to_string())
+        .and_then(|value| crate::credentials::store_secret(service, user, &value));
+    if let Err(e) = stored {
+        log::warn!("{}", e);
+    }
+}
+
+/// HMAC-SHA1 signature of request with given OAuth and query parameters,
+/// signed with consumer secret and secret of token (empty before it is given)
+#[cfg(feature = "broker-api")]
+fn signature(
+    method: &str,
+    url: &str,
+    params: &[(&str, &str)],
+    consumer_secret: &str,
+    token_secret: &str,
+) -> String {
+    let mut encoded: Vec<(String, String)> = params
+        .iter()
+        .map(|(key, value)| (percent_encode(key), percent_encode(value)))
+        .collect();
+    encoded.sort();
+    let params = encoded
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<String>>()
+        .join("&");
+    let base = format!(
+        "{}&{}&{}",
+        method,
+        percent_encode(url),
+        percent_encode(&params)
+    );
+    let key = format!(
+        "{}&{}",
+        percent_encode(consumer_secret),
+        percent_encode(token_secret)
+    );
+    let mut mac =
+        Hmac::<sha1::Sha1>::new_from_slice(key.as_bytes()).expect("HMAC accepts key of any length");
+    mac.update(base.as_bytes());
+    base64::encode(mac.finalize().into_bytes())
+}
+
+/// Consumer (API key) of OAuth 1.0a and token with its secret once obtained
+#[cfg(feature = "broker-api")]
+pub(crate) struct OAuth1 {
+    pub consumer_key: String,
+    pub consumer_secret: String,
+    pub token: Option<(String, String)>,
+}
+
+#[cfg(feature = "broker-api")]
+impl OAuth1 {
+    /// Authorization header of request of url with query parameters. oauth
+    /// contains extra OAuth parameters e.g. verifier
+    pub(crate) fn authorization(
+        &self,
+        method: &str,
+        url: &str,
+        query: &[(&str, &str)],
+        oauth: &[(&str, &str)],
+    ) -> Result<String, String> {
+        let nonce = base64_url(&random_bytes(16)?);
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let mut params: Vec<(&str, &str)> = vec![
+            ("oauth_consumer_key", &self.consumer_key),
+            ("oauth_nonce", &nonce),
+            ("oauth_signature_method", "HMAC-SHA1"),
+            ("oauth_timestamp", &timestamp),
+            ("oauth_version", "1.0"),
+        ];
+        params.extend(oauth);
+        let token_secret = match &self.token {
+            Some((token, secret)) => {
+                params.push(("oauth_token", token));
+                secret.as_str()
+            }
+            None => "",
+        };
+        let signature = signature(
+            method,
+            url,
+            &params.iter().chain(query).cloned().collect::<Vec<_>>(),
+            &self.consumer_secret,
+            token_secret,
+        );
+        params.push(("oauth_signature", &signature));
+        Ok(format!(
+            "OAuth realm=\"\",{}",
+            params
+                .iter()
+                .map(|(key, value)| format!("{}=\"{}\"", key, percent_encode(value)))
+                .collect::<Vec<String>>()
+                .join(",")
+        ))
+    }
+
+    /// Status and body of signed GET request
+    pub(crate) fn get(
+        &self,
+        client: &ReqwestClient,
+        url: &str,
+        query: &[(&str, &str)],
+        oauth: &[(&str, &str)],
+    ) -> Result<(u16, String), String> {
+        let response = crate::http::send(client.get(url).query(query).header(
+            "Authorization",
+            self.authorization("GET", url, query, oauth)?,
+        ))
+        .map_err(|e| format!("Error calling {}: {}", url, e))?;
+        let status = response.status().as_u16();
+        let body = response
+            .text()
+            .map_err(|e| format!("Error reading response of {}: {}", url, e))?;
+        log::info!("{}: {}", url, status);
+        Ok((status, body))
+    }
+
+    /// Token given in form encoded response of token request
+    fn request_token(
+        &mut self,
+        client: &ReqwestClient,
+        url: &str,
+        oauth: &[(&str, &str)],
+    ) -> Result<(), String> {
+        let body = match self.get(client, url, &[], oauth)? {
+            (200, body) => body,
+            (status, body) => return Err(format!("Error: {} returned {}: {}", url, status, body)),
+        };
+        let values = form_values(&body)?;
+        match (values.get("oauth_token"), values.get("oauth_token_secret")) {
+            (Some(token), Some(secret)) => {
+                self.token = Some((token.clone(), secret.clone()));
+                Ok(())
+            }
+            _ => Err(format!("Error: no token in response of {}: {}", url, body)),
+        }
+    }
+
+    /// Access token of three-legged flow: user opens authorization URL made
+    /// of request token, logs in and gives verification code shown there
+    pub(crate) fn authorize(
+        &mut self,
+        client: &ReqwestClient,
+        (request_token_url, access_token_url): (&str, &str),
+        authorization_url: impl Fn(&str) -> String,
+        verifier: &dyn Fn(&str) -> Result<String, String>,
+    ) -> Result<(), String> {
+        self.token = None;
+        self.request_token(client, request_token_url, &[("oauth_callback", "oob")])?;
+        let token = self.token.as_ref().map(|(token, _)| token.clone());
+        let code = verifier(&authorization_url(&token.unwrap_or_default()))?;
+        self.request_token(client, access_token_url, &[("oauth_verifier", code.trim())])
+    }
+
+    /// Whether renewal of access token (e.g. inactive for some time) succeeded
+    pub(crate) fn renew(&self, client: &ReqwestClient, url: &str) -> Result<bool, String> {
+        Ok(self.get(client, url, &[], &[])?.0 == 200)
+    }
+}
+
+/// Token of OAuth 2.0, with refresh token if service gives it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Token {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    // Seconds since epoch
+    pub expires_at: Option<i64>,
+}
+
+impl Token {
+    fn from_response(body: &Value, refresh_token: Option<&str>) -> Option<Token> {
+        Some(Token {
+            access_token: body.get("access_token")?.as_str()?.to_owned(),
+            // Refresh token is not always given again when refreshed
+            refresh_token: body
+                .get("refresh_token")
+                .and_then(|t| t.as_str())
+                .or(refresh_token)
+                .map(|t| t.to_owned()),
+            expires_at: body
+                .get("expires_in")
+                .and_then(|e| e.as_i64())
+                .map(|e| chrono::Utc::now().timestamp() + e),
+        })
+    }
+
+    /// Access token expires within a minute
+    pub(crate) fn expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|at| at <= chrono::Utc::now().timestamp() + 60)
+    }
+}
+
+/// Client of OAuth 2.0 service
+pub(crate) struct OAuth2 {
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub authorization_url: String,
+    pub token_url: String,
+    pub scope: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeviceCode {
+    device_code: String,
+    user_code: String,
+    verification_url: String,
+    interval: u64,
+}
+
+impl OAuth2 {
+    /// Response of token endpoint to form (with client credentials added)
+    fn token_request(
+        &self,
+        client: &ReqwestClient,
+        form: &[(&str, &str)],
+    ) -> Result<Value, String> {
+        let mut form = form.to_vec();
+        form.push(("client_id", &self.client_id));
+        if let Some(secret) = &self.client_secret {
+            form.push(("client_secret", secret));
+        }
+        crate::http::send(client.post(&self.token_url).form(&form))
+            .map_err(|e| format!("Error calling {}: {}", self.token_url, e))?
+            .json()
+            .map_err(|e| format!("Error reading response of {}: {}", self.token_url, e))
+    }
+
+    fn token(&self, client: &ReqwestClient, form: &[(&str, &str)]) -> Result<Token, String> {
+        let body = self.token_request(client, form)?;
+        Token::from_response(&body, None).ok_or(format!("Error: authorization failed: {}", body))
+    }
+
+    /// Token of device flow: user opens verification URL on any device and
+    /// enters code given to show
+    pub(crate) fn authorize_device(
+        &self,
+        client: &ReqwestClient,
+        device_code_url: &str,
+        show: &dyn Fn(&str, &str),
+    ) -> Result<Token, String> {
+        let device: DeviceCode = serde_json::from_value(
+            crate::http::send(client.post(device_code_url).form(&[
+                ("client_id", self.client_id.as_str()),
+                ("scope", &self.scope),
+            ]))
+            .and_then(|response| response.json::<Value>())
+            .map_err(|e| format!("Error calling {}: {}", device_code_url, e))?,
+        )
+        .map_err(|e| format!("Error reading device code: {}", e))?;
+        show(&device.verification_url, &device.user_code);
+        let mut interval = device.interval.max(1);
+        loop {
+            std::thread::sleep(std::time::Duration::from_secs(interval));
+            let body = self.token_request(
+                client,
+                &[
+                    ("device_code", &device.device_code),
+                    ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+                ],
+            )?;
+            if let Some(token) = Token::from_response(&body, None) {
+                return Ok(token);
+            }
+            match body.get("error").and_then(|e| e.as_str()) {
+                Some("authorization_pending") => (),
+                Some("slow_down") => interval += 5,
+                _ => return Err(format!("Error: authorization failed: {}", body)),
+            }
+        }
+    }
+
+    /// Token of authorization code flow with PKCE: browser opened at
+    /// authorization URL is redirected with code to listener at 127.0.0.1
+    pub(crate) fn authorize_redirect(
+        &self,
+        client: &ReqwestClient,
+        open: &dyn Fn(&str) -> Result<(), String>,
+    ) -> Result<Token, String> {
+        let server = tiny_http::Server::http("127.0.0.1:0")
+            .map_err(|e| format!("Error listening for authorization: {}", e))?;
+        let redirect_uri = format!("http://{}/", server.server_addr());
+        let verifier = base64_url(&random_bytes(32)?);
+        let challenge = base64_url(&sha2::Sha256::digest(verifier.as_bytes()));
+        let state = base64_url(&random_bytes(16)?);
+        let query = [
+            ("response_type", "code"),
+            ("client_id", self.client_id.as_str()),
+            ("redirect_uri", &redirect_uri),
+            ("scope", &self.scope),
+            ("state", &state),
+            ("code_challenge", &challenge),
+            ("code_challenge_method", "S256"),
+        ];
+        open(&format!(
+            "{}?{}",
+            self.authorization_url,
+            query
+                .iter()
+                .map(|(key, value)| format!("{}={}", key, percent_encode(value)))
+                .collect::<Vec<String>>()
+                .join("&")
+        ))?;
+        let code = loop {
+            let request = server
+                .recv_timeout(std::time::Duration::from_secs(REDIRECT_TIMEOUT_SECS))
+                .map_err(|e| format!("Error waiting for authorization: {}", e))?
+                .ok_or("Error: authorization not given in time".to_owned())?;
+            let values = form_values(request.url().split_once('?').map_or("", |(_, q)| q))?;
+            // Other requests of browser e.g. of favicon are not redirects
+            if !values.contains_key("state") {
+                let _ = request.respond(tiny_http::Response::empty(404));
+                continue;
+            }
+            let result = match (values.get("state"), values.get("code")) {
+                (Some(s), Some(code)) if *s == state => Ok(code.clone()),
+                (Some(s), _) if *s != state => {
+                    Err("Error: authorization of other request".to_owned())
+                }
+                _ => Err(format!(
+                    "Error: authorization refused: {}",
+                    values.get("error").cloned().unwrap_or_default()
+                )),
+            };
+            let message = match &result {
+                Ok(_) => "Authorized, you can close this window",
+                Err(_) => "Authorization failed, you can close this window",
+            };
+            let _ = request.respond(tiny_http::Response::from_string(message));
+            break result?;
+        };
+        self.token(
+            client,
+            &[
+                ("grant_type", "authorization_code"),
+                ("code", &code),
+                ("redirect_uri", &redirect_uri),
+                ("code_verifier", &verifier),
+            ],
+        )
+    }
+
+    /// New access token of refresh token
+    pub(crate) fn refresh(
+        &self,
+        client: &ReqwestClient,
+        refresh_token: &str,
+    ) -> Result<Token, String> {
+        let body = self.token_request(
+            client,
+            &[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+            ],
+        )?;
+        Token::from_response(&body, Some(refresh_token))
+            .ok_or(format!("Error: refresh of token failed: {}", body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "broker-api")]
+    #[test]
+    fn test_signature() -> Result<(), String> {
+        // Example of Twitter OAuth 1.0a documentation
+        assert_eq!(
+            signature(
+                "POST",
+                "https://api.twitter.com/1.1/statuses/update.json",
+                &[
+                    (
+                        "status",
+                        "Hello Ladies + Gentlemen, a signed OAuth request!"
+                    ),
+                    ("include_entities", "true"),
+                    ("oauth_consumer_key", "xvz1evFS4wEEPTGEFPHBog"),
+                    ("oauth_nonce", "kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg"),
+                    ("oauth_signature_method", "HMAC-SHA1"),
+                    ("oauth_timestamp", "1318622958"),
+                    (
+                        "oauth_token",
+                        "370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb"
+                    ),
+                    ("oauth_version", "1.0"),
+                ],
+                "kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw",
+                "LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE",
+            ),
+            "hCtSmYh+iHYCEqBWrE7C7hYmtUk="
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_encoding() -> Result<(), String> {
+        assert_eq!(percent_encode("a b&c~"), "a%20b%26c~");
+        assert_eq!(percent_decode("a%2Bb+c"), Ok("a+b c".to_owned()));
+        // PKCE challenge of verifier
+        assert_eq!(
+            base64_url(&sha2::Sha256::digest(
+                b"dBjftJeZ4CVP-mJ0kjcTjtmGvkkF1Xml7D5Vw2mHJ3A"
+            )),
+            "V6OkcGzylpZCIE7xfP0HyDauXxWr9ncPwMVFL_hPyZU"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_authorize_redirect() -> Result<(), String> {
+        // Token endpoint of service
+        let server = tiny_http::Server::http("127.0.0.1:0").map_err(|e| e.to_string())?;
+        let token_url = format!("http://{}/token", server.server_addr());
+        let service = std::thread::spawn(move || {
+            let mut request = server.recv().expect("Error receiving token request");
+            let mut form = String::new();
+            request
+                .as_reader()
+                .read_to_string(&mut form)
+                .expect("Error reading token request");
+            let _ = request.respond(tiny_http::Response::from_string(
+                r#"{"access_token":"access","refresh_token":"refresh","expires_in":3600}"#,
+            ));
+            form
+        });
+        let oauth = OAuth2 {
+            client_id: "client".to_owned(),
+            client_secret: None,
+            authorization_url: "https://example.com/authorize".to_owned(),
+            token_url,
+            scope: "read".to_owned(),
+        };
+        let client = ReqwestClient::builder()
+            .no_proxy()
+            .build()
+            .map_err(|e| e.to_string())?;
+        let browser = client.clone();
+        // Browser redirected by service with code and state of request
+        let open = |url: &str| {
+            let query = form_values(url.split_once('?').map_or("", |(_, q)| q))?;
+            let redirect = format!(
+                "{}?code=secret-code&state={}",
+                query["redirect_uri"], query["state"]
+            );
+            let browser = browser.clone();
+            std::thread::spawn(move || browser.get(redirect).send());
+            Ok(())
+        };
+        let token = oauth.authorize_redirect(&client, &open)?;
+        assert_eq!(token.access_token, "access");
+        assert_eq!(token.refresh_token, Some("refresh".to_owned()));
+        assert!(!token.expired());
+        let form = form_values(&service.join().map_err(|_| "token endpoint failed")?)?;
+        assert_eq!(form["grant_type"], "authorization_code");
+        assert_eq!(form["code"], "secret-code");
+        assert_eq!(form["code_verifier"].len(), 43);
+        Ok(())
+    }
+}
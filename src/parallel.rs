@@ -0,0 +1,68 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::logging::ResultExt;
+
+// Exchange rate services are not to be flooded with requests
+pub(crate) const MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// Number of threads parsing documents
+pub(crate) fn parsing_workers() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Results of f applied to each item, in order of items. Items are taken by
+/// given number of worker threads, next one as soon as worker is free.
+/// Browser has no threads, so there items are processed one by one
+pub(crate) fn map<I: Sync, T: Send>(
+    items: &[I],
+    workers: usize,
+    f: impl Fn(&I) -> T + Sync,
+) -> Vec<T> {
+    if cfg!(target_arch = "wasm32") || workers <= 1 || items.len() <= 1 {
+        return items.iter().map(f).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let results: Vec<Mutex<Option<T>>> = items.iter().map(|_| Mutex::new(None)).collect();
+    std::thread::scope(|scope| {
+        for _ in 0..workers.min(items.len()) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(i) else {
+                    break;
+                };
+                let result = f(item);
+                if let Ok(mut slot) = results[i].lock() {
+                    *slot = Some(result);
+                }
+            });
+        }
+    });
+    results
+        .into_iter()
+        .map(|slot| {
+            slot.into_inner()
+                .ok()
+                .flatten()
+                .expect_and_log("Error getting result of worker thread")
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map() -> Result<(), String> {
+        let items: Vec<u64> = (0..100).collect();
+        // Earlier items take longer, so they finish out of order
+        let squares = map(&items, 4, |i| {
+            std::thread::sleep(std::time::Duration::from_micros(100 - i));
+            i * i
+        });
+        assert_eq!(squares, items.iter().map(|i| i * i).collect::<Vec<u64>>());
+        assert!(map(&Vec::<u64>::new(), 4, |i| *i).is_empty());
+        Ok(())
+    }
+}
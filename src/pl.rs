@@ -1,114 +1,871 @@
-use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
 
-pub use crate::logging::ResultExt;
+use crate::{
+    capital_gains, compute_tax_due, format_amount, highlight, tax_rules_for_year, tr, warning,
+    BrokerIncome, Lang, TaxRules, TaxYearResult, YearlyTaxRules,
+};
 
-pub struct PL {}
+/// Tax return form where dividends are reported. Sold stock is always reported in PIT-38
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Form {
+    PIT38,
+    PIT36,
+}
 
-type ReqwestClient = reqwest::blocking::Client;
+pub struct PL {
+    pub form: Form,
+    rules: YearlyTaxRules,
+    lang: Lang,
+    // Tax year being presented. Selects tax rules to apply
+    tax_year: Cell<Option<i32>>,
+    // NBP tables exchange rates were published in, keyed by rate date
+    tables: RefCell<HashMap<String, String>>,
+}
 
-// Example response: {"table":"A",
-//                    "currency":"dolar amerykański",
-//                    "code":"USD",
-//                    "rates":[{"no":"039/A/NBP/2021",
-//                              "effectiveDate":"2021-02-26",
-//                              "mid":3.7247}]}
+impl PL {
+    pub fn new(form: Form, rules: YearlyTaxRules, lang: Lang) -> Self {
+        PL {
+            form,
+            rules,
+            lang,
+            tax_year: Cell::new(None),
+            tables: RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn tax_rules(&self) -> TaxRules {
+        tax_rules_for_year(&self.rules, self.tax_year.get())
+    }
 
-#[derive(Debug, Deserialize, Serialize)]
-struct NBPResponse<T> {
-    table: String,
-    currency: String,
-    code: String,
-    rates: Vec<T>,
+    fn tr<'a>(&self, text: &'a str) -> &'a str {
+        tr(self.lang, text)
+    }
 }
 
-#[derive(Debug, Deserialize, Serialize)]
-#[allow(non_snake_case)]
-struct ExchangeRate {
-    no: String,
-    effectiveDate: String,
-    mid: f32,
+/// PIT-38 boxes (poz.) with their values as filled in PIT-38(17) form.
+/// Capital gains go to parts C and D (realized exchange rate differences on
+/// USD cash together with sold stock as other income), dividends and interest to part G
+/// (only if reported in PIT-38). Amounts are rounded the way the form requires:
+/// tax bases and tax due to full PLN, other amounts to grosz
+pub fn pit38_fields(result: &TaxYearResult, rules: &TaxRules, form: &Form) -> Vec<(u32, f32)> {
+    let to_pln = |amount: f32| amount.round();
+    let to_grosz = |amount: f32| (amount * 100.0).round() / 100.0;
+    let (income, cost) = capital_gains(result);
+    let gains = (income - cost).max(0.0);
+    let tax_base = to_pln(gains);
+    let gains_tax = to_grosz(rules.tax_rate * tax_base);
+    let mut fields = vec![
+        (20, to_grosz(result.pit8c_income)),
+        (21, to_grosz(result.pit8c_cost)),
+        (22, to_grosz(result.gross_sold + result.fx_income)),
+        (23, to_grosz(result.cost_sold + result.fx_cost)),
+        (24, to_grosz(income)),
+        (25, to_grosz(cost)),
+        (26, to_grosz(gains)),
+        (27, to_grosz((cost - income).max(0.0))),
+        (29, tax_base),
+        (30, rules.tax_rate * 100.0),
+        (31, gains_tax),
+        (33, to_pln(gains_tax)),
+    ];
+    if *form == Form::PIT38 {
+        let div_tax = to_grosz(rules.tax_rate * (result.gross_div + result.gross_interest));
+        let creditable_tax_div = result
+            .tax_div
+            .min(rules.treaty_withholding_rate * result.gross_div);
+        let paid_abroad = to_grosz(creditable_tax_div + result.tax_interest).min(div_tax);
+        fields.push((45, div_tax));
+        fields.push((46, paid_abroad));
+        fields.push((47, to_pln(div_tax - paid_abroad)));
+    }
+    fields
 }
 
-impl etradeTaxReturnHelper::Residency for PL {
+/// Steps of typing computed figures into Twoj e-PIT portal, following order
+/// of PIT-38 form. Boxes the portal computes by itself are to be checked only
+pub fn epit_guide_lines(
+    year: Option<i32>,
+    result: &TaxYearResult,
+    rules: &TaxRules,
+    form: &Form,
+    lang: Lang,
+) -> Vec<String> {
+    let boxes = [
+        (20, "WPISZ", "CZESC C, PIT-8C, przychod"),
+        (21, "WPISZ", "CZESC C, PIT-8C, koszty"),
+        (22, "WPISZ", "CZESC C, inne przychody, przychod"),
+        (23, "WPISZ", "CZESC C, inne przychody, koszty"),
+        (24, "SPRAWDZ", "CZESC C, razem przychod"),
+        (25, "SPRAWDZ", "CZESC C, razem koszty"),
+        (26, "SPRAWDZ", "CZESC C, dochod"),
+        (27, "SPRAWDZ", "CZESC C, strata"),
+        (29, "SPRAWDZ", "CZESC D, podstawa obliczenia podatku"),
+        (31, "SPRAWDZ", "CZESC D, podatek od dochodu"),
+        (33, "SPRAWDZ", "CZESC D, podatek nalezny"),
+        (
+            45,
+            "WPISZ",
+            "CZESC G, zryczaltowany podatek od dywidend i odsetek",
+        ),
+        (46, "WPISZ", "CZESC G, podatek zaplacony za granica"),
+        (47, "SPRAWDZ", "CZESC G, roznica"),
+    ];
+    let mut steps = vec![match year {
+        Some(year) => format!(
+            "{} {}",
+            tr(
+                lang,
+                "Otworz Twoj e-PIT (podatki.gov.pl) i wybierz PIT-38 za rok"
+            ),
+            year
+        ),
+        None => tr(
+            lang,
+            "Otworz Twoj e-PIT (podatki.gov.pl) i wybierz PIT-38 za rok",
+        )
+        .to_owned(),
+    }];
+    pit38_fields(result, rules, form)
+        .iter()
+        .filter(|(_, value)| *value != 0.0)
+        .for_each(|(field, value)| {
+            if let Some((_, action, description)) = boxes.iter().find(|(b, _, _)| b == field) {
+                steps.push(format!(
+                    "{} {} {} ({}): {:.2}",
+                    tr(lang, action),
+                    tr(lang, "poz."),
+                    field,
+                    tr(lang, description),
+                    value
+                ));
+            }
+        });
+    [
+        ("CZESC E, kryptowaluty, przychod", result.crypto_income),
+        ("CZESC E, kryptowaluty, koszty", result.crypto_cost),
+        (
+            "CZESC E, kryptowaluty, koszty z lat ubieglych",
+            result.crypto_carried_cost,
+        ),
+    ]
+    .iter()
+    .filter(|(_, value)| *value != 0.0)
+    .for_each(|(description, value)| {
+        steps.push(format!(
+            "{} ({}): {:.2}",
+            tr(lang, "WPISZ"),
+            tr(lang, description),
+            value
+        ))
+    });
+    if *form == Form::PIT36 && result.gross_div + result.gross_interest > 0.0 {
+        steps.push(
+            tr(lang, "Dywidendy i odsetki wykaz w PIT-36 (zryczaltowany podatek od dywidend zagranicznych)").to_owned(),
+        );
+    }
+    if result.gross_div > 0.0 {
+        steps.push(
+            tr(
+                lang,
+                "Jesli portal wymaga zalacznika PIT/ZG, dane krajow pokaze --per-country",
+            )
+            .to_owned(),
+        );
+    }
+    let to_pay = compute_tax_due(result, rules) - result.prepaid_tax;
+    steps.push(match to_pay >= 0.0 {
+        true => format!(
+            "{} {}: {} PLN",
+            tr(lang, "Porownaj z kwota wyliczona przez portal"),
+            tr(lang, "DOPLATA"),
+            format_amount(to_pay, 2)
+        ),
+        false => format!(
+            "{} {}: {} PLN",
+            tr(lang, "Porownaj z kwota wyliczona przez portal"),
+            tr(lang, "NADPLATA"),
+            format_amount(-to_pay, 2)
+        ),
+    });
+    steps
+        .iter()
+        .enumerate()
+        .map(|(i, step)| format!("{}. {}", i + 1, step))
+        .collect()
+}
+
+/// PIT-38 amounts in box order as tab separated line, so that pasted into
+/// Twoj e-PIT form each one lands in the next field. Decimal comma is used as
+/// in Polish forms. Tax rate (poz. 30) is printed on the form so it is skipped
+pub fn pit38_clipboard_text(fields: &[(u32, f32)]) -> String {
+    fields
+        .iter()
+        .filter(|(field, _)| *field != 30)
+        .map(|(_, value)| format!("{:.2}", value).replace('.', ","))
+        .collect::<Vec<String>>()
+        .join("\t")
+}
+
+impl crate::Residency for PL {
     fn get_exchange_rates(
         &self,
         dates: &mut std::collections::HashMap<String, Option<(String, f32)>>,
-    ) -> Result<(), String> {
-        // proxies are taken from env vars: http_proxy and https_proxy
-        let http_proxy = std::env::var("http_proxy");
-        let https_proxy = std::env::var("https_proxy");
-
-        // If there is proxy then pick first URL
-        let base_client = ReqwestClient::builder();
-        let client = match &http_proxy {
-            Ok(proxy) => base_client
-                .proxy(reqwest::Proxy::http(proxy).expect_and_log("Error setting HTTP proxy")),
-            Err(_) => base_client,
-        };
-        let client = match &https_proxy {
-            Ok(proxy) => client
-                .proxy(reqwest::Proxy::https(proxy).expect_and_log("Error setting HTTP proxy")),
-            Err(_) => client,
-        };
-        let client = client
-            .build()
-            .expect_and_log("Could not create REST API client");
-
-        let base_exchange_rate_url = "http://api.nbp.pl/api/exchangerates/rates/a/";
-
-        dates.iter_mut().for_each(|(date, val)| {
-            let mut converted_date = chrono::NaiveDate::parse_from_str(&date, "%m/%d/%y").unwrap();
-
-            // Try to get exchange rate going backwards with dates till success
-            let mut is_success = false;
-            while is_success == false {
-                converted_date = converted_date
-                    .checked_sub_signed(chrono::Duration::days(1))
-                    .expect_and_log("Error traversing date");
-
-                let exchange_rate_url: String = base_exchange_rate_url.to_string()
-                    + &format!("usd/{}", converted_date.format("%Y-%m-%d"))
-                    + "/?format=json";
-
-                let body = client.get(&(exchange_rate_url)).send();
-                let actual_body = body.expect_and_log(&format!(
-                    "Getting Exchange Rate from NBP ({}) failed",
-                    exchange_rate_url
-                ));
-                is_success = actual_body.status().is_success();
-                if is_success == true {
-                    log::info!("RESPONSE {:#?}", actual_body);
-
-                    let nbp_response = actual_body
-                        .json::<NBPResponse<ExchangeRate>>()
-                        .expect_and_log("Error converting response to JSON");
-                    log::info!("body of exchange_rate = {:#?}", nbp_response);
-                    let exchange_rate = nbp_response.rates[0].mid;
-                    let exchange_rate_date = format!("{}", converted_date.format("%Y-%m-%d"));
-                    *val = Some((exchange_rate_date, exchange_rate));
-                };
+    ) -> Result<(), crate::RateError> {
+        crate::rates::nbp::get_exchange_rates(
+            crate::rates::http_client()?,
+            dates,
+            &mut self.tables.borrow_mut(),
+        )
+    }
+
+    fn exchange_rate_table(&self, rate_date: &str) -> Option<String> {
+        self.tables.borrow().get(rate_date).cloned()
+    }
+
+    fn present_year(&self, year: i32) {
+        self.tax_year.set(Some(year));
+        println!("===> {}: {}", self.tr("ROK PODATKOWY"), year);
+    }
+
+    fn present_share(&self, spouse: bool, share: f32) {
+        match spouse {
+            false => println!("===> {}: {}%", self.tr("UDZIAL PODATNIKA"), share),
+            true => println!("===> {}: {}%", self.tr("UDZIAL MALZONKA"), share),
+        }
+    }
+
+    fn present_holding_terms(&self, short_term: (f32, f32), long_term: (f32, f32)) {
+        let category = self.tr("(SPRZEDAZ AKCJI, INFORMACYJNIE WG 1099-B)");
+        [
+            ("KROTKOTERMINOWE", short_term),
+            ("DLUGOTERMINOWE", long_term),
+        ]
+        .iter()
+        .for_each(|(term, (proceeds, cost))| {
+            println!(
+                "===> {} {}: {} ${:.2} {} ${:.2}",
+                category,
+                self.tr(term),
+                self.tr("PRZYCHOD"),
+                proceeds,
+                self.tr("KOSZT"),
+                cost
+            );
+        });
+    }
+
+    fn present_pit8c(&self, pit8c: (f32, f32), gross_sold: f32, cost_sold: f32) {
+        let category = self.tr("(SPRZEDAZ AKCJI)");
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("PRZYCHOD WYKAZANY W PIT-8C"),
+            format_amount(pit8c.0, 2)
+        );
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("KOSZT WYKAZANY W PIT-8C"),
+            format_amount(pit8c.1, 2)
+        );
+        let income = pit8c.0 + gross_sold;
+        let cost = pit8c.1 + cost_sold;
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("RAZEM PRZYCHOD"),
+            format_amount(income, 2)
+        );
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("RAZEM KOSZT"),
+            format_amount(cost, 2)
+        );
+        match income >= cost {
+            true => println!(
+                "===> {} {}: {} PLN",
+                category,
+                self.tr("DOCHOD"),
+                format_amount(income - cost, 2)
+            ),
+            false => println!(
+                "===> {} {}: {} PLN",
+                category,
+                self.tr("STRATA"),
+                format_amount(cost - income, 2)
+            ),
+        }
+    }
+
+    fn rules_for_year(&self, year: i32) -> Option<TaxRules> {
+        Some(tax_rules_for_year(&self.rules, Some(year)))
+    }
+
+    fn present_excess_withholding(&self, excess_us: f32) {
+        println!(
+            "===> {} {}: ${:.2} ({})",
+            self.tr("(INFORMACYJNIE)"),
+            self.tr("PODATEK POBRANY W USA PONAD STAWKE Z UMOWY"),
+            excess_us,
+            self.tr("MOZLIWY DO ODZYSKANIA Z IRS PRZEZ 1040-NR, NIE PODLEGA ODLICZENIU W PL")
+        );
+    }
+
+    fn present_per_symbol(&self, per_symbol: &BTreeMap<String, (f32, f32, f32)>) {
+        per_symbol
+            .iter()
+            .for_each(|(symbol, (gross, tax, creditable))| {
+                println!(
+                    "===> {} {}: {}: {} PLN {}: {} PLN {}: {} PLN",
+                    self.tr("(DYWIDENDY)"),
+                    symbol,
+                    self.tr("PRZYCHOD"),
+                    format_amount(*gross, 2),
+                    self.tr("PODATEK ZAPLACONY"),
+                    format_amount(*tax, 2),
+                    self.tr("PODATEK DO ODLICZENIA"),
+                    format_amount(*creditable, 2)
+                );
+            });
+    }
+
+    fn present_per_month(&self, per_month: &BTreeMap<String, (f32, f32, f32, f32)>) {
+        per_month
+            .iter()
+            .for_each(|(month, (gross_us, tax_us, gross, tax))| {
+                println!(
+                    "===> {} {}: {}: ${:.2} {}: ${:.2} ({} PLN / {} PLN)",
+                    self.tr("(DYWIDENDY I ODSETKI MIESIECZNIE)"),
+                    month,
+                    self.tr("PRZYCHOD"),
+                    gross_us,
+                    self.tr("PODATEK POBRANY"),
+                    tax_us,
+                    format_amount(*gross, 2),
+                    format_amount(*tax, 2)
+                );
+            });
+    }
+
+    fn present_per_broker(&self, per_broker: &BTreeMap<String, BrokerIncome>) {
+        // Sums of brokers are the figures of PIT-38, sums per country the
+        // figures of PIT/ZG
+        per_broker.iter().for_each(|(broker, income)| {
+            let category = format!("(BROKER {})", broker);
+            println!(
+                "===> {} {}: {} PLN {}: {} PLN",
+                category,
+                self.tr("(DYWIDENDY) PRZYCHOD"),
+                format_amount(income.gross_div, 2),
+                self.tr("PODATEK ZAPLACONY ZAGRANICA"),
+                format_amount(income.tax_div, 2)
+            );
+            println!(
+                "===> {} {}: {} PLN {}: {} PLN",
+                category,
+                self.tr("(ODSETKI) PRZYCHOD"),
+                format_amount(income.gross_interest, 2),
+                self.tr("PODATEK ZAPLACONY ZAGRANICA"),
+                format_amount(income.tax_interest, 2)
+            );
+            println!(
+                "===> {} {}: {} PLN {}: {} PLN",
+                category,
+                self.tr("(SPRZEDAZ AKCJI) PRZYCHOD"),
+                format_amount(income.gross_sold, 2),
+                self.tr("KOSZT"),
+                format_amount(income.cost_sold, 2)
+            );
+            if income.crypto_income != 0.0 || income.crypto_cost != 0.0 {
+                println!(
+                    "===> {} {} {}: {} PLN {}: {} PLN",
+                    category,
+                    self.tr("(KRYPTOWALUTY)"),
+                    self.tr("PRZYCHOD"),
+                    format_amount(income.crypto_income, 2),
+                    self.tr("KOSZT"),
+                    format_amount(income.crypto_cost, 2)
+                );
             }
+            income
+                .per_country
+                .iter()
+                .for_each(|(country, (gross, tax, creditable))| {
+                    println!(
+                        "     {} {}: {}: {} PLN {}: {} PLN {}: {} PLN",
+                        self.tr("KOD KRAJU"),
+                        country,
+                        self.tr("PRZYCHOD Z ZAGRANICY"),
+                        format_amount(*gross, 2),
+                        self.tr("PODATEK ZAPLACONY ZA GRANICA"),
+                        format_amount(*tax, 2),
+                        self.tr("PODATEK DO ODLICZENIA"),
+                        format_amount(*creditable, 2)
+                    );
+                });
         });
-        Ok(())
+    }
+
+    fn present_per_country(&self, per_country: &BTreeMap<String, (f32, f32, f32)>) {
+        // Separate PIT/ZG attachment is filed for each country of source
+        per_country
+            .iter()
+            .enumerate()
+            .for_each(|(i, (country, (gross, tax, creditable)))| {
+                println!(
+                    "===> {} {}: {}: {}",
+                    self.tr("PIT/ZG NR"),
+                    i + 1,
+                    self.tr("KOD KRAJU"),
+                    country
+                );
+                println!(
+                    "     {}: {} PLN",
+                    self.tr("PRZYCHOD Z ZAGRANICY"),
+                    format_amount(*gross, 2)
+                );
+                println!(
+                    "     {}: {} PLN",
+                    self.tr("PODATEK ZAPLACONY ZA GRANICA"),
+                    format_amount(*tax, 2)
+                );
+                println!(
+                    "     {}: {} PLN",
+                    self.tr("PODATEK DO ODLICZENIA"),
+                    format_amount(*creditable, 2)
+                );
+            });
+    }
+
+    fn present_tax_due(&self, result: &TaxYearResult) {
+        let tax_due = compute_tax_due(result, &self.tax_rules());
+        let (income, cost) = capital_gains(result);
+        let gains = income - cost;
+        if gains < 0.0 && result.gross_div > 0.0 {
+            let msg = self.tr("UWAGA! Strata ze sprzedazy akcji NIE pomniejsza zryczaltowanego podatku od dywidend. Podatek od dywidend jest nalezny w calosci, a strate mozna odliczyc jedynie od dochodow ze sprzedazy w kolejnych 5 latach");
+            eprintln!("{}", warning(msg));
+            log::warn!("{}", msg);
+        }
+        println!("===> {}:", self.tr("PIT-38 (WZOR 17) DO WPISANIA"));
+        pit38_fields(result, &self.tax_rules(), &self.form)
+            .iter()
+            .filter(|(_, value)| *value != 0.0)
+            .for_each(|(field, value)| {
+                println!(
+                    "     {} {}: {}",
+                    self.tr("poz."),
+                    field,
+                    format_amount(*value, 2)
+                )
+            });
+        println!(
+            "===> {}: {} PLN",
+            self.tr("PODATEK NALEZNY"),
+            format_amount(tax_due, 2)
+        );
+        if result.prepaid_tax != 0.0 {
+            println!(
+                "===> {}: {} PLN",
+                self.tr("ZALICZKI / PODATEK POBRANY PRZEZ PLATNIKA"),
+                format_amount(result.prepaid_tax, 2)
+            );
+        }
+        let to_pay = tax_due - result.prepaid_tax;
+        match to_pay >= 0.0 {
+            true => println!(
+                "{}",
+                highlight(&format!(
+                    "===> {}: {} PLN",
+                    self.tr("DOPLATA"),
+                    format_amount(to_pay, 2)
+                ))
+            ),
+            false => println!(
+                "{}",
+                highlight(&format!(
+                    "===> {}: {} PLN",
+                    self.tr("NADPLATA"),
+                    format_amount(-to_pay, 2)
+                ))
+            ),
+        }
+    }
+
+    fn present_epit_guide(&self, result: &TaxYearResult) {
+        println!("===> {}:", self.tr("KROKI WYPELNIANIA TWOJ E-PIT"));
+        epit_guide_lines(
+            self.tax_year.get(),
+            result,
+            &self.tax_rules(),
+            &self.form,
+            self.lang,
+        )
+        .iter()
+        .for_each(|step| println!("     {}", step));
+    }
+
+    fn present_crypto(&self, income: f32, cost: f32, carried_cost: f32) {
+        let category = self.tr("(KRYPTOWALUTY)");
+        println!(
+            "===> {} {}: {}",
+            category,
+            self.tr("DO WYKAZANIA W"),
+            self.tr("PIT-38 (CZESC E)")
+        );
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("PRZYCHOD"),
+            format_amount(income, 2)
+        );
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("KOSZTY PONIESIONE W ROKU PODATKOWYM"),
+            format_amount(cost, 2)
+        );
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("KOSZTY NIEPOTRACONE W LATACH UBIEGLYCH"),
+            format_amount(carried_cost, 2)
+        );
+        let gains = income - cost - carried_cost;
+        match gains >= 0.0 {
+            true => println!(
+                "===> {} {}: {} PLN",
+                category,
+                self.tr("DOCHOD"),
+                format_amount(gains, 2)
+            ),
+            false => println!(
+                "===> {} {}: {} PLN",
+                category,
+                self.tr("NADWYZKA KOSZTOW (DO ROZLICZENIA W NASTEPNYM ROKU)"),
+                format_amount(-gains, 2)
+            ),
+        }
+    }
+
+    fn present_interest(&self, gross_interest: f32, tax_interest: f32) {
+        let rules = self.tax_rules();
+        let category = self.tr("(ODSETKI)");
+        println!(
+            "===> {} {}: {}",
+            category,
+            self.tr("DO WYKAZANIA W"),
+            self.tr("PIT-38 (CZESC G)")
+        );
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("PRZYCHOD Z ZAGRANICY"),
+            format_amount(gross_interest, 2)
+        );
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("ZRYCZALTOWANY PODATEK (PODATEK BELKI)"),
+            format_amount(rules.round(rules.tax_rate * gross_interest), 2)
+        );
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("PODATEK ZAPLACONY ZAGRANICA"),
+            format_amount(tax_interest, 2)
+        );
+    }
+
+    fn present_fx_differences(&self, fx_income: f32, fx_cost: f32) {
+        let category = self.tr("(ROZNICE KURSOWE)");
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("PRZYCHOD Z DODATNICH ROZNIC KURSOWYCH"),
+            format_amount(fx_income, 2)
+        );
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("KOSZT Z UJEMNYCH ROZNIC KURSOWYCH"),
+            format_amount(fx_cost, 2)
+        );
+    }
+
+    fn present_simulation(&self, income: f32, cost: f32) {
+        let category = self.tr("(SYMULACJA SPRZEDAZY)");
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("PRZYCHOD Z ZAGRANICY"),
+            format_amount(income, 2)
+        );
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("KOSZT UZYSKANIA PRZYCHODU"),
+            format_amount(cost, 2)
+        );
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("DOCHOD"),
+            format_amount(income - cost, 2)
+        );
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("SZACOWANY PODATEK"),
+            format_amount(self.tax_rules().tax_rate * (income - cost).max(0.0), 2)
+        );
+    }
+
+    fn present_vests_forecast(&self, forecast: &[(String, f32)]) {
+        let tax_rate = self.tax_rules().tax_rate;
+        let category = self.tr("(PROGNOZA RSU)");
+        forecast.iter().for_each(|(vest_date, income)| {
+            println!(
+                "===> {} {}: {}: {} PLN, {}: {} PLN",
+                category,
+                chrono::NaiveDate::parse_from_str(vest_date, "%m/%d/%y")
+                    .unwrap()
+                    .format("%Y-%m-%d"),
+                self.tr("WARTOSC AKCJI"),
+                format_amount(*income, 2),
+                self.tr("SZACOWANY PODATEK"),
+                format_amount(tax_rate * income, 2)
+            );
+        });
+        let total: f32 = forecast.iter().map(|(_, income)| income).sum();
+        println!(
+            "===> {} {}: {} PLN, {}: {} PLN",
+            category,
+            self.tr("RAZEM WARTOSC AKCJI"),
+            format_amount(total, 2),
+            self.tr("SZACOWANY PODATEK"),
+            format_amount(tax_rate * total, 2)
+        );
+    }
+
+    fn present_exchange_rate(&self, date: &str, rate_date: &str, rate: f32) {
+        println!(
+            "===> {} {}: {} PLN ({} {}, {} {})",
+            self.tr("(KURS USD)"),
+            date,
+            format_amount(rate, 4),
+            self.tr("KURS Z DNIA"),
+            rate_date,
+            self.tr("TABELA"),
+            self.exchange_rate_table(rate_date).unwrap_or_default()
+        );
+    }
+
+    fn present_late_payment_interest(&self, amount: f32, days: i64, interest: f32) {
+        let category = self.tr("(ODSETKI ZA ZWLOKE)");
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("ZALEGLOSC PODATKOWA"),
+            format_amount(amount, 0)
+        );
+        println!("===> {} {}: {}", category, self.tr("DNI ZWLOKI"), days);
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("ODSETKI"),
+            format_amount(interest, 0)
+        );
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("DO ZAPLATY"),
+            format_amount(amount + interest, 0)
+        );
+    }
+
+    fn present_totals_us(&self, result: &TaxYearResult) {
+        let category = self.tr("(SUMY W USD DO UZGODNIENIA Z BROKEREM)");
+        [
+            ("DYWIDENDY", result.gross_div_us, result.tax_div_us),
+            ("ODSETKI", result.gross_interest_us, result.tax_interest_us),
+        ]
+        .iter()
+        .filter(|(_, gross, tax)| *gross != 0.0 || *tax != 0.0)
+        .for_each(|(kind, gross, tax)| {
+            println!(
+                "===> {} {}: {}: ${:.2} {}: ${:.2}",
+                category,
+                self.tr(kind),
+                self.tr("PRZYCHOD"),
+                gross,
+                self.tr("PODATEK POBRANY"),
+                tax
+            );
+        });
+        if result.gross_sold != 0.0 {
+            println!(
+                "===> {} {}: {}: ${:.2} {}: ${:.2}",
+                category,
+                self.tr("SPRZEDAZ AKCJI"),
+                self.tr("PRZYCHOD"),
+                result.proceeds_short_us + result.proceeds_long_us,
+                self.tr("KOSZT"),
+                result.cost_short_us + result.cost_long_us
+            );
+        }
+    }
+
+    fn present_sheltered(&self, gross_div_us: f32, tax_div_us: f32) {
+        println!(
+            "===> {} {}: ${:.2} {}: ${:.2}",
+            self.tr("(KONTA IKE/IKZE/IRA - INFORMACYJNIE, NIE OPODATKOWANE)"),
+            self.tr("DYWIDENDY"),
+            gross_div_us,
+            self.tr("PODATEK POBRANY"),
+            tax_div_us
+        );
     }
 
     fn present_result(&self, gross_div: f32, tax_div: f32, gross_sold: f32, cost_sold: f32) {
-        println!("(DYWIDENDY) PRZYCHOD Z ZAGRANICY: {:.2} PLN", gross_div);
+        let rules = self.tax_rules();
+        let div_form = match self.form {
+            Form::PIT38 => "PIT-38 (CZESC G, poz. 45-47)",
+            Form::PIT36 => "PIT-36 (ZRYCZALTOWANY PODATEK OD DYWIDEND ZAGRANICZNYCH)",
+        };
+        let category = self.tr("(DYWIDENDY)");
+        println!(
+            "===> {} {}: {}",
+            category,
+            self.tr("DO WYKAZANIA W"),
+            self.tr(div_form)
+        );
+        println!(
+            "{} {}: {} PLN",
+            category,
+            self.tr("PRZYCHOD Z ZAGRANICY"),
+            format_amount(gross_div, 2)
+        );
         println!(
-            "===> (DYWIDENDY) ZRYCZALTOWANY PODATEK: {:.2} PLN",
-            (0.19 * gross_div)
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("ZRYCZALTOWANY PODATEK"),
+            format_amount(rules.round(rules.tax_rate * gross_div), 2)
         );
         println!(
-            "===> (DYWIDENDY) PODATEK ZAPLACONY ZAGRANICA: {:.2} PLN",
-            tax_div
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("PODATEK ZAPLACONY ZAGRANICA"),
+            format_amount(tax_div, 2)
         );
+        let category = self.tr("(SPRZEDAZ AKCJI)");
         println!(
-            "===> (SPRZEDAZ AKCJI) PRZYCHOD Z ZAGRANICY: {:.2} PLN",
-            gross_sold
+            "===> {} {}: {}",
+            category,
+            self.tr("DO WYKAZANIA W"),
+            self.tr("PIT-38 (CZESC C, poz. 22-23)")
         );
         println!(
-            "===> (SPRZEDAZ AKCJI) KOSZT UZYSKANIA PRZYCHODU: {:.2} PLN",
-            cost_sold
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("PRZYCHOD Z ZAGRANICY"),
+            format_amount(gross_sold, 2)
         );
+        println!(
+            "===> {} {}: {} PLN",
+            category,
+            self.tr("KOSZT UZYSKANIA PRZYCHODU"),
+            format_amount(cost_sold, 2)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pit38_fields() -> Result<(), String> {
+        let rules = TaxRules {
+            tax_rate: 0.19,
+            treaty_withholding_rate: 0.15,
+            round_to: 0.01,
+            epit_rounding: false,
+            pit38_schema: None,
+        };
+        let result = TaxYearResult {
+            gross_div: 1000.25,
+            tax_div: 150.0,
+            gross_sold: 3000.5,
+            cost_sold: 2500.0,
+            ..Default::default()
+        };
+        let fields: BTreeMap<u32, f32> = pit38_fields(&result, &rules, &Form::PIT38)
+            .into_iter()
+            .collect();
+        assert_eq!(fields[&22], 3000.5);
+        assert_eq!(fields[&26], 500.5);
+        assert_eq!(fields[&29], 501.0);
+        assert_eq!(fields[&31], 95.19);
+        assert_eq!(fields[&33], 95.0);
+        assert_eq!(fields[&45], 190.05);
+        assert_eq!(fields[&46], 150.0);
+        assert_eq!(fields[&47], 40.0);
+        // Dividends reported in PIT-36 are not part of PIT-38
+        assert!(pit38_fields(&result, &rules, &Form::PIT36)
+            .iter()
+            .all(|(field, _)| *field < 45));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pit38_clipboard_text() -> Result<(), String> {
+        assert_eq!(
+            pit38_clipboard_text(&[(22, 3000.5), (23, 2500.0), (30, 19.0), (33, 95.0)]),
+            "3000,50\t2500,00\t95,00"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_epit_guide_lines() -> Result<(), String> {
+        let rules = TaxRules {
+            tax_rate: 0.19,
+            treaty_withholding_rate: 0.15,
+            round_to: 0.01,
+            epit_rounding: false,
+            pit38_schema: None,
+        };
+        let result = TaxYearResult {
+            gross_div: 1000.25,
+            tax_div: 150.0,
+            gross_sold: 3000.5,
+            cost_sold: 2500.0,
+            ..Default::default()
+        };
+        let lines = epit_guide_lines(Some(2023), &result, &rules, &Form::PIT38, Lang::Pl);
+        assert_eq!(
+            lines[0],
+            "1. Otworz Twoj e-PIT (podatki.gov.pl) i wybierz PIT-38 za rok 2023"
+        );
+        assert_eq!(
+            lines[1],
+            "2. WPISZ poz. 22 (CZESC C, inne przychody, przychod): 3000.50"
+        );
+        assert!(lines.contains(
+            &"10. WPISZ poz. 45 (CZESC G, zryczaltowany podatek od dywidend i odsetek): 190.05"
+                .to_owned()
+        ));
+        assert_eq!(
+            lines.last(),
+            Some(&"14. Porownaj z kwota wyliczona przez portal DOPLATA: 135,15 PLN".to_owned())
+        );
+        let lines = epit_guide_lines(Some(2023), &result, &rules, &Form::PIT38, Lang::En);
+        assert_eq!(
+            lines[1],
+            "2. ENTER box 22 (PART C, other income, income): 3000.50"
+        );
+        Ok(())
     }
 }
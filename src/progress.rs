@@ -0,0 +1,59 @@
+use std::sync::OnceLock;
+
+/// Receiver of progress of long running steps: parsing of documents and
+/// getting exchange rates, e.g. progress bars of command line program
+pub trait Progress: Send + Sync {
+    /// Step of given number of items started e.g. "Parsing documents"
+    fn start(&self, step: &str, total: usize);
+    /// Item of current step done, with reason if it failed
+    fn advance(&self, item: &str, failure: Option<&str>);
+    /// Current step ended, also when computation stops with error
+    fn finish(&self);
+    /// Print line to standard error without breaking progress drawn there
+    fn print(&self, line: &str) {
+        eprintln!("{}", line);
+    }
+}
+
+static PROGRESS: OnceLock<Box<dyn Progress>> = OnceLock::new();
+
+/// Report progress of following computations to given receiver
+pub fn set_progress(progress: Box<dyn Progress>) -> Result<(), String> {
+    PROGRESS
+        .set(progress)
+        .map_err(|_| "Progress receiver is already set".to_owned())
+}
+
+/// Line printed to standard error, e.g. warning of skipped document
+pub(crate) fn print(line: &str) {
+    match PROGRESS.get() {
+        Some(progress) => progress.print(line),
+        None => eprintln!("{}", line),
+    }
+}
+
+/// Step started with start(), finished when dropped
+pub(crate) struct Step;
+
+pub(crate) fn start(step: &str, total: usize) -> Step {
+    if let Some(progress) = PROGRESS.get() {
+        progress.start(step, total);
+    }
+    Step
+}
+
+impl Step {
+    pub(crate) fn advance(&self, item: &str, failure: Option<&str>) {
+        if let Some(progress) = PROGRESS.get() {
+            progress.advance(item, failure);
+        }
+    }
+}
+
+impl Drop for Step {
+    fn drop(&mut self) {
+        if let Some(progress) = PROGRESS.get() {
+            progress.finish();
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Exchange rates of dates transactions happened: NBP for Polish residency,
+//! exchange-rates.org for other ones
+
+pub(crate) mod nbp;
+mod replay;
+
+pub use nbp_client::{HttpClient, RateError};
+pub use replay::{Recorder, Replayer};
+
+use std::sync::OnceLock;
+
+/// Reqwest client requests of other services are sent with
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct ReqwestClient(reqwest::blocking::Client);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl HttpClient for ReqwestClient {
+    fn get(&self, url: &str) -> Result<(u16, String), RateError> {
+        let request_error = |e: reqwest::Error| RateError::Request {
+            url: url.to_owned(),
+            reason: e.to_string(),
+        };
+        let response = crate::http::send(self.0.get(url)).map_err(request_error)?;
+        log::info!("RESPONSE {:#?}", response);
+        let status = response.status().as_u16();
+        let body = response.text().map_err(|e| RateError::Response {
+            url: url.to_owned(),
+            reason: e.to_string(),
+        })?;
+        Ok((status, body))
+    }
+}
+
+// Set by set_http_client or created with first request
+static HTTP_CLIENT: OnceLock<Box<dyn HttpClient>> = OnceLock::new();
+
+/// Use given client for exchange rate requests. It has to be set before the
+/// first request, otherwise reqwest client is used
+pub fn set_http_client(client: Box<dyn HttpClient>) -> Result<(), String> {
+    HTTP_CLIENT
+        .set(client)
+        .map_err(|_| "HTTP client is already in use".to_owned())
+}
+
+pub(crate) fn http_client() -> Result<&'static dyn HttpClient, RateError> {
+    if let Some(client) = HTTP_CLIENT.get() {
+        return Ok(client.as_ref());
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let client = create_client()?;
+        Ok(HTTP_CLIENT.get_or_init(|| Box::new(client)).as_ref())
+    }
+    #[cfg(target_arch = "wasm32")]
+    Err(RateError::Client("no HTTP client set".to_owned()))
+}
+
+/// Client shared with other services (clones share its connections)
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn create_client() -> Result<ReqwestClient, RateError> {
+    crate::http::shared_client()
+        .cloned()
+        .map(ReqwestClient)
+        .map_err(RateError::Client)
+}
+
+/// Date of transaction (MM/DD/YY)
+fn transaction_date(date: &str) -> Result<chrono::NaiveDate, RateError> {
+    chrono::NaiveDate::parse_from_str(date, "%m/%d/%y")
+        .map_err(|_| RateError::Date(date.to_owned()))
+}
+
+/// Day preceding date of transaction (MM/DD/YY)
+fn day_before(date: &str) -> Result<chrono::NaiveDate, RateError> {
+    transaction_date(date)?
+        .checked_sub_signed(chrono::Duration::days(1))
+        .ok_or(RateError::Date(date.to_owned()))
+}
+
+/// Rates of day preceding each date from exchange-rates.org. Response body
+/// is parsed with given residency specific parser
+pub(crate) fn get_currency_exchange_rates(
+    dates: &mut std::collections::HashMap<String, Option<(String, f32)>>,
+    from: &str,
+    to: &str,
+    parse: impl Fn(&str) -> Result<(f32, String), String>,
+) -> Result<(), RateError> {
+    let client = http_client()?;
+
+    // Example URL: https://www.exchange-rates.org/Rate/USD/EUR/2-27-2021
+
+    let base_exchange_rate_url = "https://www.exchange-rates.org/Rate/";
+
+    let get_response = |date: &String| -> Result<String, RateError> {
+        if crate::status::interrupted() {
+            return Err(RateError::Interrupted(date.clone()));
+        }
+        let converted_date = day_before(date)?;
+
+        let exchange_rate_url: String = base_exchange_rate_url.to_string()
+            + &format!("{}/{}/{}", from, to, converted_date.format("%m-%d-%Y"))
+            + "/?format=json";
+
+        let (status, exchange_rates_response) = client.get(&exchange_rate_url)?;
+        if !(200..300).contains(&status) {
+            return Err(RateError::Response {
+                url: exchange_rate_url,
+                reason: format!("status {}", status),
+            });
+        }
+        log::info!("body of exchange_rate = {:#?}", &exchange_rates_response);
+        Ok(exchange_rates_response)
+    };
+
+    // Dates are requested concurrently, responses are parsed in turn
+    let requested: Vec<String> = dates.keys().cloned().collect();
+    let step = crate::progress::start("Getting exchange rates", requested.len());
+    let responses = crate::parallel::map(
+        &requested,
+        crate::parallel::MAX_CONCURRENT_REQUESTS,
+        get_response,
+    );
+    for (date, response) in requested.into_iter().zip(responses) {
+        let response = match response {
+            Ok(response) => response,
+            // Rate is left missing, as of other dates not requested
+            Err(RateError::Interrupted(_)) => continue,
+            Err(e) => return Err(e),
+        };
+        // parsing text response
+        match parse(&response) {
+            Ok((exchange_rate, exchange_rate_date)) => {
+                dates.insert(date.clone(), Some((exchange_rate_date, exchange_rate)));
+                step.advance(&date, None);
+            }
+            Err(e) => step.advance(&date, Some(&e)),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+
+use super::{HttpClient, RateError};
+
+/// Average NBP rates (table A) of last business day preceding each date.
+/// Numbers of tables rates were published in are stored keyed by rate date
+pub(crate) fn get_exchange_rates(
+    client: &dyn HttpClient,
+    dates: &mut HashMap<String, Option<(String, f32)>>,
+    tables: &mut HashMap<String, String>,
+) -> Result<(), RateError> {
+    // (rate date, rate, table number) of date
+    let get_rate = |date: &String| -> Result<(String, f32, String), RateError> {
+        if crate::status::interrupted() {
+            return Err(RateError::Interrupted(date.clone()));
+        }
+        nbp_client::get_exchange_rate(client, "usd", super::transaction_date(date)?)
+    };
+
+    // Dates are requested concurrently
+    let requested: Vec<String> = dates.keys().cloned().collect();
+    let step = crate::progress::start("Getting exchange rates", requested.len());
+    let rates = crate::parallel::map(
+        &requested,
+        crate::parallel::MAX_CONCURRENT_REQUESTS,
+        |date| {
+            let rate = get_rate(date);
+            match &rate {
+                Ok(_) => step.advance(date, None),
+                Err(RateError::Interrupted(_)) => (),
+                Err(e) => step.advance(date, Some(&e.to_string())),
+            }
+            rate
+        },
+    );
+    for (date, rate) in requested.into_iter().zip(rates) {
+        let (exchange_rate_date, rate, table) = match rate {
+            Ok(rate) => rate,
+            // Rate is left missing, as of other dates not requested
+            Err(RateError::Interrupted(_)) => continue,
+            Err(e) => return Err(e),
+        };
+        tables.insert(exchange_rate_date.clone(), table);
+        dates.insert(date, Some((exchange_rate_date, rate)));
+    }
+    Ok(())
+}
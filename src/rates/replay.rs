@@ -0,0 +1,187 @@
+//! Recording of exchange rate responses to fixture file and their replay,
+//! so run can be repeated later with exactly the same rates
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use super::{HttpClient, RateError};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Response {
+    status: u16,
+    body: String,
+}
+
+// Responses keyed by URL
+type Responses = BTreeMap<String, Response>;
+
+// Version of fixture file written. Version 1 was JSON object of responses
+// keyed by URL, without version
+const FIXTURE_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Fixture {
+    version: u32,
+    responses: Responses,
+}
+
+/// Responses of fixture file of any version up to current one
+fn read_fixture(path: &str, content: &str) -> Result<Responses, String> {
+    let error = |e: serde_json::Error| format!("Error parsing recorded responses {}: {}", path, e);
+    let fixture: serde_json::Value = serde_json::from_str(content).map_err(error)?;
+    let version = match fixture.get("version") {
+        Some(version) => version.as_u64().ok_or(format!(
+            "Error: invalid version of recorded responses {}",
+            path
+        ))? as u32,
+        None => 1,
+    };
+    crate::schema::check_version(path, version, FIXTURE_VERSION)?;
+    match version {
+        1 => serde_json::from_value(fixture).map_err(error),
+        _ => Ok(serde_json::from_value::<Fixture>(fixture)
+            .map_err(error)?
+            .responses),
+    }
+}
+
+/// Client passing requests to other one and writing their responses to
+/// fixture file, rewritten after each response
+pub struct Recorder {
+    client: Box<dyn HttpClient>,
+    path: String,
+    responses: Mutex<Responses>,
+}
+
+impl Recorder {
+    /// Record responses of reqwest client
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(path: &str) -> Result<Self, RateError> {
+        Ok(Self::wrapping(Box::new(super::create_client()?), path))
+    }
+
+    pub fn wrapping(client: Box<dyn HttpClient>, path: &str) -> Self {
+        Recorder {
+            client,
+            path: path.to_owned(),
+            responses: Mutex::new(Responses::new()),
+        }
+    }
+}
+
+impl HttpClient for Recorder {
+    fn get(&self, url: &str) -> Result<(u16, String), RateError> {
+        let (status, body) = self.client.get(url)?;
+        let mut responses = self
+            .responses
+            .lock()
+            .map_err(|e| RateError::Client(e.to_string()))?;
+        responses.insert(
+            url.to_owned(),
+            Response {
+                status,
+                body: body.clone(),
+            },
+        );
+        let write_error = |e: String| {
+            RateError::Client(format!(
+                "unable to record responses to {}: {}",
+                self.path, e
+            ))
+        };
+        let fixture = Fixture {
+            version: FIXTURE_VERSION,
+            responses: responses.clone(),
+        };
+        let content =
+            serde_json::to_string_pretty(&fixture).map_err(|e| write_error(e.to_string()))?;
+        std::fs::write(&self.path, content).map_err(|e| write_error(e.to_string()))?;
+        Ok((status, body))
+    }
+}
+
+/// Client answering with responses recorded by Recorder, without network.
+/// Requests not recorded fail
+#[derive(Debug)]
+pub struct Replayer {
+    path: String,
+    responses: Responses,
+}
+
+impl Replayer {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| format!("Error reading recorded responses {}: {}", path, e))?;
+        Ok(Replayer {
+            path: path.to_owned(),
+            responses: read_fixture(path, &content)?,
+        })
+    }
+}
+
+impl HttpClient for Replayer {
+    fn get(&self, url: &str) -> Result<(u16, String), RateError> {
+        match self.responses.get(url) {
+            Some(response) => Ok((response.status, response.body.clone())),
+            None => Err(RateError::Request {
+                url: url.to_owned(),
+                reason: format!("response not recorded in {}", self.path),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct FakeClient;
+
+    impl HttpClient for FakeClient {
+        fn get(&self, url: &str) -> Result<(u16, String), RateError> {
+            match url.contains("2023-03-03") {
+                true => Ok((200, r#"{"table":"A","currency":"dolar amerykański","code":"USD","rates":[{"no":"044/A/NBP/2023","effectiveDate":"2023-03-03","mid":4.4286}]}"#.to_owned())),
+                false => Ok((404, "404 NotFound - Not Found - Brak danych".to_owned())),
+            }
+        }
+    }
+
+    #[test]
+    fn test_record_and_replay() -> Result<(), String> {
+        let path =
+            std::env::temp_dir().join(format!("etrade_responses_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        let rates = |client: &dyn HttpClient| -> Result<_, String> {
+            // Sunday, so preceding days are requested until Friday
+            let mut dates = HashMap::from([("03/05/23".to_owned(), None)]);
+            let mut tables = HashMap::new();
+            super::super::nbp::get_exchange_rates(client, &mut dates, &mut tables)
+                .map_err(|e| e.to_string())?;
+            Ok((dates, tables))
+        };
+        let recorded = rates(&Recorder::wrapping(Box::new(FakeClient), path))?;
+        let replayer = Replayer::open(path);
+        std::fs::remove_file(path).map_err(|e| e.to_string())?;
+        let replayer = replayer?;
+        assert_eq!(replayer.responses.len(), 2);
+        assert_eq!(rates(&replayer)?, recorded);
+        assert_eq!(
+            recorded.0["03/05/23"],
+            Some(("2023-03-03".to_owned(), 4.4286))
+        );
+        assert!(replayer
+            .get("https://api.nbp.pl/api/exchangerates/rates/a/usd/2023-03-02/?format=json")
+            .is_err());
+
+        // Fixture written before versions were introduced
+        let responses = read_fixture(
+            "old.json",
+            r#"{"https://api.nbp.pl/api/exchangerates/rates/a/usd/2023-03-04/?format=json":{"status":404,"body":"404 NotFound"}}"#,
+        )?;
+        assert_eq!(responses.values().next().map(|r| r.status), Some(404));
+        assert!(read_fixture("new.json", r#"{"version":3,"responses":{}}"#).is_err());
+        Ok(())
+    }
+}
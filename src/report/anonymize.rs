@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{SoldTransaction, TaxYearResult, Transaction};
+
+/// Symbols replaced with SYMBOL1, SYMBOL2... in order of first appearance
+fn symbol_names<'a>(
+    transactions: impl Iterator<Item = &'a Transaction>,
+) -> HashMap<String, String> {
+    let mut names: HashMap<String, String> = HashMap::new();
+    transactions.for_each(|t| {
+        if !names.contains_key(&t.symbol) {
+            let name = format!("SYMBOL{}", names.len() + 1);
+            names.insert(t.symbol.clone(), name);
+        }
+    });
+    names
+}
+
+fn scale_result(
+    result: &TaxYearResult,
+    factor: f32,
+    names: &HashMap<String, String>,
+) -> TaxYearResult {
+    let scale3 = |(a, b, c): &(f32, f32, f32)| (a * factor, b * factor, c * factor);
+    TaxYearResult {
+        gross_div: result.gross_div * factor,
+        tax_div: result.tax_div * factor,
+        per_symbol: result
+            .per_symbol
+            .iter()
+            .map(|(symbol, amounts)| {
+                (
+                    names.get(symbol).cloned().unwrap_or(symbol.clone()),
+                    scale3(amounts),
+                )
+            })
+            .collect(),
+        per_country: result
+            .per_country
+            .iter()
+            .map(|(country, amounts)| (country.clone(), scale3(amounts)))
+            .collect(),
+        per_broker: result
+            .per_broker
+            .iter()
+            .map(|(broker, income)| (broker.clone(), income.scaled(factor)))
+            .collect(),
+        per_month: result
+            .per_month
+            .iter()
+            .map(|(month, (a, b, c, d))| {
+                (
+                    month.clone(),
+                    (a * factor, b * factor, c * factor, d * factor),
+                )
+            })
+            .collect(),
+        gross_sold: result.gross_sold * factor,
+        cost_sold: result.cost_sold * factor,
+        gross_interest: result.gross_interest * factor,
+        tax_interest: result.tax_interest * factor,
+        fx_income: result.fx_income * factor,
+        fx_cost: result.fx_cost * factor,
+        proceeds_short_us: result.proceeds_short_us * factor,
+        cost_short_us: result.cost_short_us * factor,
+        proceeds_long_us: result.proceeds_long_us * factor,
+        cost_long_us: result.cost_long_us * factor,
+        pit8c_income: result.pit8c_income * factor,
+        pit8c_cost: result.pit8c_cost * factor,
+        excess_withholding_us: result.excess_withholding_us * factor,
+        crypto_income: result.crypto_income * factor,
+        crypto_cost: result.crypto_cost * factor,
+        crypto_carried_cost: result.crypto_carried_cost * factor,
+        prepaid_tax: result.prepaid_tax * factor,
+        sheltered_gross_div_us: result.sheltered_gross_div_us * factor,
+        sheltered_tax_div_us: result.sheltered_tax_div_us * factor,
+        gross_div_us: result.gross_div_us * factor,
+        tax_div_us: result.tax_div_us * factor,
+        gross_interest_us: result.gross_interest_us * factor,
+        tax_interest_us: result.tax_interest_us * factor,
+    }
+}
+
+fn anonymize_with(
+    factor: f32,
+    transactions: &[Transaction],
+    interest_transactions: &[Transaction],
+    sold_transactions: &[SoldTransaction],
+    results: &BTreeMap<i32, TaxYearResult>,
+) -> (
+    Vec<Transaction>,
+    Vec<Transaction>,
+    Vec<SoldTransaction>,
+    BTreeMap<i32, TaxYearResult>,
+) {
+    let names = symbol_names(transactions.iter().chain(interest_transactions.iter()));
+    let scale = |t: &Transaction| Transaction {
+        symbol: names.get(&t.symbol).cloned().unwrap_or(t.symbol.clone()),
+        gross_us: t.gross_us * factor,
+        tax_us: t.tax_us * factor,
+        ..t.clone()
+    };
+    (
+        transactions.iter().map(scale).collect(),
+        interest_transactions.iter().map(scale).collect(),
+        sold_transactions
+            .iter()
+            .map(|t| SoldTransaction {
+                income_us: t.income_us * factor,
+                cost_basis: t.cost_basis * factor,
+                fees_us: t.fees_us * factor,
+                ..t.clone()
+            })
+            .collect(),
+        results
+            .iter()
+            .map(|(year, result)| (*year, scale_result(result, factor, &names)))
+            .collect(),
+    )
+}
+
+/// Transactions and results safe to share e.g. when reporting parser bug.
+/// Symbols are replaced and all amounts are multiplied by the same random
+/// factor (0.5 - 1.5), so totals, tax rates and structure are preserved.
+/// Dates and exchange rates (public NBP data) are kept
+pub fn anonymize(
+    transactions: &[Transaction],
+    interest_transactions: &[Transaction],
+    sold_transactions: &[SoldTransaction],
+    results: &BTreeMap<i32, TaxYearResult>,
+) -> (
+    Vec<Transaction>,
+    Vec<Transaction>,
+    Vec<SoldTransaction>,
+    BTreeMap<i32, TaxYearResult>,
+) {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos());
+    let factor = 0.5 + (nanos % 1000) as f32 / 1000.0;
+    anonymize_with(
+        factor,
+        transactions,
+        interest_transactions,
+        sold_transactions,
+        results,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anonymize() -> Result<(), String> {
+        let transaction = |symbol: &str| Transaction {
+            transaction_date: "03/01/23".to_string(),
+            gross_us: 100.0,
+            tax_us: 15.0,
+            symbol: symbol.to_string(),
+            exchange_rate_date: "2023-02-28".to_string(),
+            exchange_rate: 4.0,
+        };
+        let results = BTreeMap::from([(
+            2023,
+            TaxYearResult {
+                gross_div: 800.0,
+                tax_div: 120.0,
+                per_symbol: BTreeMap::from([
+                    ("INTC".to_owned(), (400.0, 60.0, 60.0)),
+                    ("VWRL".to_owned(), (400.0, 60.0, 60.0)),
+                ]),
+                ..Default::default()
+            },
+        )]);
+        let (transactions, _, _, results) = anonymize_with(
+            0.5,
+            &[
+                transaction("VWRL"),
+                transaction("INTC"),
+                transaction("VWRL"),
+            ],
+            &[],
+            &[],
+            &results,
+        );
+        assert_eq!(
+            transactions
+                .iter()
+                .map(|t| t.symbol.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["SYMBOL1", "SYMBOL2", "SYMBOL1"]
+        );
+        assert_eq!(transactions[0].gross_us, 50.0);
+        assert_eq!(transactions[0].tax_us, 7.5);
+        assert_eq!(transactions[0].exchange_rate, 4.0);
+        assert_eq!(transactions[0].transaction_date, "03/01/23");
+        assert_eq!(results[&2023].gross_div, 400.0);
+        assert_eq!(results[&2023].per_symbol["SYMBOL1"], (200.0, 30.0, 30.0));
+        Ok(())
+    }
+}
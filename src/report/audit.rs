@@ -0,0 +1,291 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{SoldTransaction, TaxRules, TaxYearResult, Transaction};
+use etax_core::tax::tax_year;
+
+fn iso_date(date: &str) -> String {
+    chrono::NaiveDate::parse_from_str(date, "%m/%d/%y")
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or(date.to_owned())
+}
+
+/// Exchange rate with date and number of table it was published in
+fn rate_source(rate: f32, rate_date: &str, tables: &HashMap<String, String>) -> String {
+    match tables.get(rate_date) {
+        Some(table) => format!("{:.4} (rate of {}, table {})", rate, rate_date, table),
+        None => format!("{:.4} (rate of {})", rate, rate_date),
+    }
+}
+
+fn of_year(transactions: &[Transaction], year: i32) -> Vec<&Transaction> {
+    transactions
+        .iter()
+        .filter(|t| tax_year(&t.transaction_date) == Ok(year))
+        .collect()
+}
+
+fn income_lines(
+    title: &str,
+    transactions: &Vec<&Transaction>,
+    tables: &HashMap<String, String>,
+) -> Vec<String> {
+    let mut lines = vec![format!("-- {} --", title)];
+    transactions.iter().for_each(|t| {
+        let rate = rate_source(t.exchange_rate, &t.exchange_rate_date, tables);
+        lines.push(format!(
+            "  {} {}: gross {:.2} USD * {} = {:.4}; tax paid {:.2} USD * {:.4} = {:.4}",
+            iso_date(&t.transaction_date),
+            t.symbol,
+            t.gross_us,
+            rate,
+            t.gross_us * t.exchange_rate,
+            t.tax_us,
+            t.exchange_rate,
+            t.tax_us * t.exchange_rate
+        ));
+    });
+    let gross: f32 = transactions
+        .iter()
+        .map(|t| t.gross_us * t.exchange_rate)
+        .sum();
+    let tax: f32 = transactions
+        .iter()
+        .map(|t| t.tax_us * t.exchange_rate)
+        .sum();
+    lines.push(format!(
+        "  Total (sum of unrounded amounts): gross {:.2}, tax paid {:.2}",
+        gross, tax
+    ));
+    lines
+}
+
+fn sales_lines(
+    transactions: &Vec<&SoldTransaction>,
+    tables: &HashMap<String, String>,
+) -> Vec<String> {
+    let mut lines = vec!["-- Sold stock --".to_owned()];
+    transactions.iter().for_each(|t| {
+        lines.push(format!(
+            "  settled {} (acquired {}): (income {:.2} USD - fees {:.2} USD) * {} = {:.4}; cost basis {:.2} USD * {} = {:.4}",
+            iso_date(&t.settlement_date),
+            iso_date(&t.acquisition_date),
+            t.income_us,
+            t.fees_us,
+            rate_source(t.exchange_rate_settlement, &t.exchange_rate_settlement_date, tables),
+            (t.income_us - t.fees_us) * t.exchange_rate_settlement,
+            t.cost_basis,
+            rate_source(t.exchange_rate_acquisition, &t.exchange_rate_acquisition_date, tables),
+            t.cost_basis * t.exchange_rate_acquisition
+        ));
+    });
+    let income: f32 = transactions
+        .iter()
+        .map(|t| (t.income_us - t.fees_us) * t.exchange_rate_settlement)
+        .sum();
+    let cost: f32 = transactions
+        .iter()
+        .map(|t| t.cost_basis * t.exchange_rate_acquisition)
+        .sum();
+    lines.push(format!(
+        "  Total (sum of unrounded amounts): income {:.2}, cost {:.2}",
+        income, cost
+    ));
+    lines
+}
+
+/// Steps of tax due computation with formulas and rounding applied, following
+/// compute_tax_due. Returns the lines and tax due
+fn tax_due_lines(result: &TaxYearResult, rules: &TaxRules) -> (Vec<String>, f32) {
+    let to_pln = |amount: f32| amount.round();
+    let to_grosz = |amount: f32| (amount * 100.0).round() / 100.0;
+    let epit = rules.epit_rounding;
+    let round_base = |amount: f32| match epit {
+        true => to_pln(amount),
+        false => rules.round(amount),
+    };
+    let round_tax = |amount: f32| match epit {
+        true => to_pln(to_grosz(amount)),
+        false => rules.round(amount),
+    };
+    let round_div_tax = |amount: f32| match epit {
+        true => to_grosz(amount),
+        false => rules.round(amount),
+    };
+    let round_div_due = |amount: f32| match epit {
+        true => to_pln(amount),
+        false => amount,
+    };
+    let rounding = match epit {
+        true => "e-PIT (tax bases and tax due to full PLN, other taxes to grosz)".to_owned(),
+        false => format!("to {}", rules.round_to),
+    };
+    let mut lines = vec![
+        "-- Tax due --".to_owned(),
+        format!(
+            "  Tax rate {}, treaty withholding rate {}, rounding {}",
+            rules.tax_rate, rules.treaty_withholding_rate, rounding
+        ),
+    ];
+
+    let (income, cost) = crate::capital_gains(result);
+    let gains = income - cost;
+    let gains_base = round_base(gains.max(0.0));
+    let gains_tax = round_tax(rules.tax_rate * gains_base);
+    lines.push(format!(
+        "  Gains = (sold income {:.2} + exchange rate differences income {:.2} + PIT-8C income {:.2}) - (sold cost {:.2} + exchange rate differences cost {:.2} + PIT-8C cost {:.2}) = {:.2}",
+        result.gross_sold, result.fx_income, result.pit8c_income, result.cost_sold, result.fx_cost, result.pit8c_cost, gains
+    ));
+    lines.push(format!(
+        "  Gains tax = round({} * round(max(gains, 0) = {:.2})) = {:.2}",
+        rules.tax_rate, gains_base, gains_tax
+    ));
+
+    let crypto_gains = result.crypto_income - result.crypto_cost - result.crypto_carried_cost;
+    let crypto_base = round_base(crypto_gains.max(0.0));
+    let crypto_tax = round_tax(rules.tax_rate * crypto_base);
+    lines.push(format!(
+        "  Virtual currency gains = income {:.2} - cost {:.2} - carried cost {:.2} = {:.2}",
+        result.crypto_income, result.crypto_cost, result.crypto_carried_cost, crypto_gains
+    ));
+    lines.push(format!(
+        "  Virtual currency tax = round({} * round(max(gains, 0) = {:.2})) = {:.2}",
+        rules.tax_rate, crypto_base, crypto_tax
+    ));
+
+    let div_tax = round_div_tax(rules.tax_rate * (result.gross_div + result.gross_interest));
+    lines.push(format!(
+        "  Dividends and interest tax = round({} * (dividends {:.2} + interest {:.2})) = {:.2}",
+        rules.tax_rate, result.gross_div, result.gross_interest, div_tax
+    ));
+    let creditable_tax_div = result
+        .tax_div
+        .min(rules.treaty_withholding_rate * result.gross_div);
+    lines.push(format!(
+        "  Creditable dividends tax = min(tax paid {:.2}, {} * dividends {:.2}) = {:.2}",
+        result.tax_div, rules.treaty_withholding_rate, result.gross_div, creditable_tax_div
+    ));
+    let paid_abroad = round_div_tax(creditable_tax_div + result.tax_interest).min(div_tax);
+    lines.push(format!(
+        "  Tax paid abroad = min(round(creditable {:.2} + interest tax paid {:.2}), {:.2}) = {:.2}",
+        creditable_tax_div, result.tax_interest, div_tax, paid_abroad
+    ));
+    let div_due = round_div_due(div_tax - paid_abroad);
+    let tax_due = gains_tax + crypto_tax + div_due;
+    lines.push(format!(
+        "  Tax due = gains tax {:.2} + virtual currency tax {:.2} + (dividends and interest tax - tax paid abroad) {:.2} = {:.2}",
+        gains_tax, crypto_tax, div_due, tax_due
+    ));
+    if result.prepaid_tax != 0.0 {
+        lines.push(format!(
+            "  To pay = tax due {:.2} - prepaid tax {:.2} = {:.2}",
+            tax_due,
+            result.prepaid_tax,
+            tax_due - result.prepaid_tax
+        ));
+    }
+    (lines, tax_due)
+}
+
+/// Audit report listing for each tax year the transactions with amounts and
+/// exchange rates (with tables they were published in) used, and every step
+/// of computation of presented figures. tables map exchange rate date to table
+pub fn audit_lines(
+    transactions: &[Transaction],
+    interest_transactions: &[Transaction],
+    sold_transactions: &[SoldTransaction],
+    results: &BTreeMap<i32, TaxYearResult>,
+    rules: &BTreeMap<i32, TaxRules>,
+    tables: &HashMap<String, String>,
+) -> Vec<String> {
+    let mut lines = vec!["E*TRADE tax return audit report".to_owned()];
+    results.iter().for_each(|(year, result)| {
+        lines.push("".to_owned());
+        lines.push(format!("== Tax year {} ==", year));
+        let dividends = of_year(transactions, *year);
+        if !dividends.is_empty() {
+            lines.extend(income_lines("Dividends", &dividends, tables));
+        }
+        let interest = of_year(interest_transactions, *year);
+        if !interest.is_empty() {
+            lines.extend(income_lines("Interest", &interest, tables));
+        }
+        let sold: Vec<&SoldTransaction> = sold_transactions
+            .iter()
+            .filter(|t| tax_year(&t.settlement_date) == Ok(*year))
+            .collect();
+        if !sold.is_empty() {
+            lines.extend(sales_lines(&sold, tables));
+        }
+        match rules.get(year) {
+            Some(rules) => lines.extend(tax_due_lines(result, rules).0),
+            None => lines.push("-- Tax due: no tax rules for the year --".to_owned()),
+        }
+    });
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compute_tax_due;
+
+    #[test]
+    fn test_tax_due_lines() -> Result<(), String> {
+        let result = TaxYearResult {
+            gross_div: 1000.33,
+            tax_div: 150.05,
+            gross_interest: 10.12,
+            gross_sold: 5000.49,
+            cost_sold: 3000.0,
+            crypto_income: 100.0,
+            crypto_cost: 40.6,
+            ..Default::default()
+        };
+        let mut rules = TaxRules {
+            tax_rate: 0.19,
+            treaty_withholding_rate: 0.15,
+            round_to: 1.0,
+            epit_rounding: false,
+            pit38_schema: None,
+        };
+        assert_eq!(
+            tax_due_lines(&result, &rules).1,
+            compute_tax_due(&result, &rules)
+        );
+        rules.round_to = 0.01;
+        assert_eq!(
+            tax_due_lines(&result, &rules).1,
+            compute_tax_due(&result, &rules)
+        );
+        rules.epit_rounding = true;
+        assert_eq!(
+            tax_due_lines(&result, &rules).1,
+            compute_tax_due(&result, &rules)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_audit_lines() -> Result<(), String> {
+        let transactions = vec![Transaction {
+            transaction_date: "03/01/23".to_string(),
+            gross_us: 100.0,
+            tax_us: 15.0,
+            symbol: "INTC".to_string(),
+            exchange_rate_date: "2023-02-28".to_string(),
+            exchange_rate: 4.0,
+        }];
+        let tables = HashMap::from([("2023-02-28".to_string(), "041/A/NBP/2023".to_string())]);
+        let lines = audit_lines(
+            &transactions,
+            &[],
+            &[],
+            &BTreeMap::from([(2023, TaxYearResult::default())]),
+            &BTreeMap::new(),
+            &tables,
+        );
+        assert!(lines.contains(&"== Tax year 2023 ==".to_owned()));
+        assert!(lines.contains(&"  2023-03-01 INTC: gross 100.00 USD * 4.0000 (rate of 2023-02-28, table 041/A/NBP/2023) = 400.0000; tax paid 15.00 USD * 4.0000 = 60.0000".to_owned()));
+        Ok(())
+    }
+}
@@ -0,0 +1,304 @@
+use rusqlite::{params, Connection};
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::ledger::{figures, FIGURES};
+use crate::{SoldTransaction, TaxRules, TaxYearResult, Transaction};
+use etax_core::tax::tax_year;
+
+// Amounts are in USD and rates convert them to target currency (PLN, EUR etc.).
+// Dates are YYYY-MM-DD so they can be compared in queries
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    account TEXT NOT NULL,
+    tax_year INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    date TEXT NOT NULL,
+    acquisition_date TEXT,
+    symbol TEXT,
+    gross_us REAL NOT NULL,
+    tax_us REAL,
+    cost_basis_us REAL,
+    fees_us REAL,
+    exchange_rate_date TEXT NOT NULL,
+    exchange_rate REAL NOT NULL,
+    acquisition_exchange_rate_date TEXT,
+    acquisition_exchange_rate REAL
+);
+CREATE INDEX IF NOT EXISTS transactions_account_year ON transactions (account, tax_year);
+CREATE TABLE IF NOT EXISTS exchange_rates (
+    rate_date TEXT PRIMARY KEY,
+    rate REAL NOT NULL
+);
+CREATE TABLE IF NOT EXISTS results (
+    account TEXT NOT NULL,
+    tax_year INTEGER NOT NULL,
+    recorded TEXT NOT NULL,
+    figure TEXT NOT NULL,
+    amount REAL NOT NULL,
+    PRIMARY KEY (account, tax_year, figure)
+);
+";
+
+// Statements bringing database of each version to the next one. Version is
+// kept in user_version of database: 0 for new one and the one written before
+// versions were introduced, whose tables are those of version 1
+const MIGRATIONS: [&str; 1] = [SCHEMA];
+
+fn iso_date(date: &str) -> String {
+    chrono::NaiveDate::parse_from_str(date, "%m/%d/%y")
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or(date.to_owned())
+}
+
+/// Database migrated to current version
+fn open(path: &str) -> Result<Connection, String> {
+    let mut connection =
+        Connection::open(path).map_err(|e| format!("Error opening database {}: {}", path, e))?;
+    let error = |e: rusqlite::Error| format!("Error migrating database {}: {}", path, e);
+    let version: u32 = connection
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(error)?;
+    crate::schema::check_version(path, version, MIGRATIONS.len() as u32)?;
+    let db = connection.transaction().map_err(error)?;
+    for migration in MIGRATIONS.iter().skip(version as usize) {
+        db.execute_batch(migration).map_err(error)?;
+    }
+    db.execute_batch(&format!("PRAGMA user_version = {}", MIGRATIONS.len()))
+        .map_err(error)?;
+    db.commit().map_err(error)?;
+    Ok(connection)
+}
+
+/// Store transactions, exchange rates applied to them and results of each tax
+/// year in SQLite database. Transactions and results of account and tax years
+/// already stored are replaced, so documents can be imported year by year
+pub fn store(
+    path: &str,
+    account: &str,
+    transactions: &[Transaction],
+    interest_transactions: &[Transaction],
+    sold_transactions: &[SoldTransaction],
+    results: &BTreeMap<i32, TaxYearResult>,
+    rules: &BTreeMap<i32, TaxRules>,
+) -> Result<(), String> {
+    let mut connection = open(path)?;
+    let error = |e: rusqlite::Error| format!("Error storing in database {}: {}", path, e);
+    let db = connection.transaction().map_err(error)?;
+    let years: BTreeSet<i32> = results
+        .keys()
+        .map(|year| Ok(*year))
+        .chain(
+            transactions
+                .iter()
+                .chain(interest_transactions.iter())
+                .map(|t| tax_year(&t.transaction_date)),
+        )
+        .chain(
+            sold_transactions
+                .iter()
+                .map(|t| tax_year(&t.settlement_date)),
+        )
+        .collect::<Result<BTreeSet<i32>, String>>()?;
+    for year in years.iter() {
+        db.execute(
+            "DELETE FROM transactions WHERE account = ?1 AND tax_year = ?2",
+            params![account, year],
+        )
+        .map_err(error)?;
+        db.execute(
+            "DELETE FROM results WHERE account = ?1 AND tax_year = ?2",
+            params![account, year],
+        )
+        .map_err(error)?;
+    }
+    for (kind, t) in transactions
+        .iter()
+        .map(|t| ("dividend", t))
+        .chain(interest_transactions.iter().map(|t| ("interest", t)))
+    {
+        db.execute(
+            "INSERT INTO transactions (account, tax_year, kind, date, symbol, gross_us, tax_us, exchange_rate_date, exchange_rate)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                account,
+                tax_year(&t.transaction_date)?,
+                kind,
+                iso_date(&t.transaction_date),
+                t.symbol,
+                t.gross_us,
+                t.tax_us,
+                t.exchange_rate_date,
+                t.exchange_rate
+            ],
+        )
+        .map_err(error)?;
+    }
+    for t in sold_transactions.iter() {
+        db.execute(
+            "INSERT INTO transactions (account, tax_year, kind, date, acquisition_date, gross_us, cost_basis_us, fees_us, exchange_rate_date, exchange_rate, acquisition_exchange_rate_date, acquisition_exchange_rate)
+             VALUES (?1, ?2, 'sold', ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                account,
+                tax_year(&t.settlement_date)?,
+                iso_date(&t.settlement_date),
+                iso_date(&t.acquisition_date),
+                t.income_us,
+                t.cost_basis,
+                t.fees_us,
+                t.exchange_rate_settlement_date,
+                t.exchange_rate_settlement,
+                t.exchange_rate_acquisition_date,
+                t.exchange_rate_acquisition
+            ],
+        )
+        .map_err(error)?;
+    }
+    for (rate_date, rate) in transactions
+        .iter()
+        .chain(interest_transactions.iter())
+        .map(|t| (&t.exchange_rate_date, t.exchange_rate))
+        .chain(sold_transactions.iter().flat_map(|t| {
+            [
+                (&t.exchange_rate_settlement_date, t.exchange_rate_settlement),
+                (
+                    &t.exchange_rate_acquisition_date,
+                    t.exchange_rate_acquisition,
+                ),
+            ]
+        }))
+    {
+        db.execute(
+            "INSERT OR REPLACE INTO exchange_rates (rate_date, rate) VALUES (?1, ?2)",
+            params![rate_date, rate],
+        )
+        .map_err(error)?;
+    }
+    let recorded = chrono::Local::now()
+        .naive_local()
+        .date()
+        .format("%Y-%m-%d")
+        .to_string();
+    for (year, result) in results.iter() {
+        for (figure, amount) in FIGURES.iter().zip(figures(result, rules.get(year))) {
+            if let Some(amount) = amount {
+                db.execute(
+                    "INSERT INTO results (account, tax_year, recorded, figure, amount) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![account, year, recorded, figure, amount],
+                )
+                .map_err(error)?;
+            }
+        }
+    }
+    db.commit().map_err(error)
+}
+
+/// Figures of each (account, tax year) stored in database, keyed by name
+/// e.g. gross_sold, the same as read from ledger
+pub fn read_database(path: &str) -> Result<super::Figures, String> {
+    let connection = open(path)?;
+    let error = |e: rusqlite::Error| format!("Error reading database {}: {}", path, e);
+    let mut statement = connection
+        .prepare("SELECT account, tax_year, figure, amount FROM results")
+        .map_err(error)?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i32>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, f32>(3)?,
+            ))
+        })
+        .map_err(error)?;
+    let mut entries: super::Figures = BTreeMap::new();
+    for row in rows {
+        let (account, year, figure, amount) = row.map_err(error)?;
+        entries
+            .entry((account, year))
+            .or_default()
+            .insert(figure, amount);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::HoldingTerm;
+
+    #[test]
+    fn test_database() -> Result<(), String> {
+        let path =
+            std::env::temp_dir().join(format!("etrade_database_{}.sqlite", std::process::id()));
+        let path = path.to_str().unwrap();
+        let dividend = |date: &str, gross_us: f32| Transaction {
+            transaction_date: date.to_string(),
+            gross_us,
+            tax_us: gross_us * 0.15,
+            symbol: "INTC".to_string(),
+            exchange_rate_date: "2023-02-28".to_string(),
+            exchange_rate: 4.0,
+        };
+        let sold = SoldTransaction {
+            settlement_date: "03/03/23".to_string(),
+            acquisition_date: "12/01/21".to_string(),
+            income_us: 100.0,
+            cost_basis: 70.0,
+            fees_us: 0.5,
+            term: HoldingTerm::Long,
+            exchange_rate_settlement_date: "2023-03-02".to_string(),
+            exchange_rate_settlement: 4.4,
+            exchange_rate_acquisition_date: "2021-11-30".to_string(),
+            exchange_rate_acquisition: 4.1,
+        };
+        let result = |gross_div: f32| TaxYearResult {
+            gross_div,
+            ..Default::default()
+        };
+        store(
+            path,
+            "etrade",
+            &[dividend("03/01/22", 10.0), dividend("03/01/23", 20.0)],
+            &[],
+            std::slice::from_ref(&sold),
+            &BTreeMap::from([(2022, result(40.0)), (2023, result(80.0))]),
+            &BTreeMap::new(),
+        )?;
+        // Import of 2023 again after adding missing statement
+        store(
+            path,
+            "etrade",
+            &[dividend("03/01/23", 20.0), dividend("06/01/23", 30.0)],
+            &[],
+            &[sold],
+            &BTreeMap::from([(2023, result(200.0))]),
+            &BTreeMap::new(),
+        )?;
+        let entries = read_database(path)?;
+        let connection = Connection::open(path).map_err(|e| e.to_string())?;
+        let count = |query: &str| -> Result<i64, String> {
+            connection
+                .query_row(query, [], |row| row.get(0))
+                .map_err(|e| e.to_string())
+        };
+        let transactions = count("SELECT COUNT(*) FROM transactions WHERE tax_year = 2023")?;
+        let rates = count("SELECT COUNT(*) FROM exchange_rates")?;
+        let version = count("PRAGMA user_version")?;
+        // Database of newer release
+        connection
+            .execute_batch("PRAGMA user_version = 99")
+            .map_err(|e| e.to_string())?;
+        drop(connection);
+        let newer = read_database(path);
+        std::fs::remove_file(path).map_err(|e| e.to_string())?;
+        assert_eq!(transactions, 3);
+        assert_eq!(rates, 3);
+        assert_eq!(version, MIGRATIONS.len() as i64);
+        assert!(newer.is_err());
+        assert_eq!(entries[&("etrade".to_owned(), 2022)]["gross_div"], 40.0);
+        assert_eq!(entries[&("etrade".to_owned(), 2023)]["gross_div"], 200.0);
+        // No tax rules given
+        assert_eq!(entries[&("etrade".to_owned(), 2023)].get("tax_due"), None);
+        Ok(())
+    }
+}
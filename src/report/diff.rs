@@ -0,0 +1,205 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+fn text(item: &Value, name: &str) -> String {
+    item.get(name)
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_owned()
+}
+
+fn number(item: &Value, name: &str) -> f64 {
+    item.get(name).and_then(|v| v.as_f64()).unwrap_or_default()
+}
+
+/// One line description of dividend, interest or sold transaction of JSON export
+fn describe(kind: &str, item: &Value) -> String {
+    match kind {
+        "sold" => format!(
+            "settled {} acquired {}: income {:.2} USD, fees {:.2} USD, cost basis {:.2} USD, rates {:.4}/{:.4}",
+            text(item, "settlement_date"),
+            text(item, "acquisition_date"),
+            number(item, "income_us"),
+            number(item, "fees_us"),
+            number(item, "cost_basis"),
+            number(item, "exchange_rate_settlement"),
+            number(item, "exchange_rate_acquisition")
+        ),
+        _ => format!(
+            "{} {}: gross {:.2} USD, tax {:.2} USD, rate {:.4}",
+            text(item, "transaction_date"),
+            text(item, "symbol"),
+            number(item, "gross_us"),
+            number(item, "tax_us"),
+            number(item, "exchange_rate")
+        ),
+    }
+}
+
+/// Numeric figures of tax year keyed by path e.g. per_symbol.INTC.0
+fn flatten(prefix: &str, value: &Value, figures: &mut BTreeMap<String, f64>) {
+    let path = |name: &str| match prefix.is_empty() {
+        true => name.to_owned(),
+        false => format!("{}.{}", prefix, name),
+    };
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .for_each(|(name, value)| flatten(&path(name), value, figures)),
+        Value::Array(items) => items
+            .iter()
+            .enumerate()
+            .for_each(|(i, value)| flatten(&path(&i.to_string()), value, figures)),
+        _ => {
+            if let Some(number) = value.as_f64() {
+                figures.insert(prefix.to_owned(), number);
+            }
+        }
+    }
+}
+
+fn transaction_lines(kind: &str, title: &str, old: &Value, new: &Value) -> Vec<String> {
+    let describe_all = |export: &Value| -> Vec<String> {
+        export
+            .get(kind)
+            .and_then(|v| v.as_array())
+            .map(|items| items.iter().map(|item| describe(kind, item)).collect())
+            .unwrap_or_default()
+    };
+    let mut removed = describe_all(old);
+    let mut added = vec![];
+    // Transactions present in both are matched one to one, so duplicates count
+    describe_all(new).into_iter().for_each(|line| {
+        match removed.iter().position(|old_line| *old_line == line) {
+            Some(i) => {
+                removed.remove(i);
+            }
+            None => added.push(line),
+        }
+    });
+    if removed.is_empty() && added.is_empty() {
+        return vec![];
+    }
+    let mut lines = vec![format!("== {} ==", title)];
+    lines.extend(removed.iter().map(|line| format!("- {}", line)));
+    lines.extend(added.iter().map(|line| format!("+ {}", line)));
+    lines
+}
+
+fn results_lines(old: &Value, new: &Value) -> Vec<String> {
+    let figures_of = |export: &Value| -> BTreeMap<String, BTreeMap<String, f64>> {
+        let mut years = BTreeMap::new();
+        if let Some(results) = export.get("results").and_then(|r| r.as_object()) {
+            results.iter().for_each(|(year, result)| {
+                let mut figures = BTreeMap::new();
+                flatten("", result, &mut figures);
+                years.insert(year.clone(), figures);
+            });
+        }
+        years
+    };
+    let (old, new) = (figures_of(old), figures_of(new));
+    let empty = BTreeMap::new();
+    let mut years: Vec<&String> = old.keys().chain(new.keys()).collect();
+    years.sort();
+    years.dedup();
+    let mut lines = vec![];
+    years.iter().for_each(|year| {
+        let (old_figures, new_figures) = (
+            old.get(*year).unwrap_or(&empty),
+            new.get(*year).unwrap_or(&empty),
+        );
+        let mut names: Vec<&String> = old_figures.keys().chain(new_figures.keys()).collect();
+        names.sort();
+        names.dedup();
+        let changed: Vec<String> = names
+            .iter()
+            .filter_map(|name| {
+                let before = old_figures.get(*name).copied().unwrap_or_default();
+                let after = new_figures.get(*name).copied().unwrap_or_default();
+                // Differences below grosz are float noise
+                match (after - before).abs() >= 0.005 {
+                    true => Some(format!(
+                        "  {}: {:.2} -> {:.2} ({:+.2})",
+                        name,
+                        before,
+                        after,
+                        after - before
+                    )),
+                    false => None,
+                }
+            })
+            .collect();
+        let title = match (old.contains_key(*year), new.contains_key(*year)) {
+            (false, true) => format!("== Tax year {} (added) ==", year),
+            (true, false) => format!("== Tax year {} (removed) ==", year),
+            _ => format!("== Tax year {} ==", year),
+        };
+        if !changed.is_empty() {
+            lines.push(title);
+            lines.extend(changed);
+        }
+    });
+    lines
+}
+
+/// Differences between two JSON exports: removed (-) and added (+)
+/// transactions, and figures of each tax year that changed
+pub fn diff_lines(old: &Value, new: &Value) -> Vec<String> {
+    let mut lines = vec![];
+    lines.extend(transaction_lines("dividends", "Dividends", old, new));
+    lines.extend(transaction_lines("interest", "Interest", old, new));
+    lines.extend(transaction_lines("sold", "Sold stock", old, new));
+    lines.extend(results_lines(old, new));
+    if lines.is_empty() {
+        lines.push("No differences".to_owned());
+    }
+    lines
+}
+
+/// Differences between JSON exports (--export json) of two runs read from files
+pub fn diff_exports(old_path: &str, new_path: &str) -> Result<Vec<String>, String> {
+    let read = |path: &str| -> Result<Value, String> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| format!("Error reading {}: {}", path, e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Error parsing {}: {}", path, e))
+    };
+    Ok(diff_lines(&read(old_path)?, &read(new_path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines() -> Result<(), String> {
+        let old: Value = serde_json::from_str(
+            r#"{"dividends":[{"transaction_date":"03/01/23","symbol":"INTC","gross_us":100.0,"tax_us":15.0,"exchange_rate":4.0}],
+                "interest":[],"sold":[],
+                "results":{"2023":{"gross_div":400.0,"tax_div":60.0,"per_symbol":{"INTC":[400.0,60.0,60.0]}}}}"#,
+        )
+        .map_err(|e| e.to_string())?;
+        let new: Value = serde_json::from_str(
+            r#"{"dividends":[{"transaction_date":"03/01/23","symbol":"INTC","gross_us":100.0,"tax_us":15.0,"exchange_rate":4.0},
+                             {"transaction_date":"06/01/23","symbol":"INTC","gross_us":50.0,"tax_us":7.5,"exchange_rate":4.2}],
+                "interest":[],"sold":[],
+                "results":{"2023":{"gross_div":610.0,"tax_div":91.5,"per_symbol":{"INTC":[610.0,91.5,91.5]}}}}"#,
+        )
+        .map_err(|e| e.to_string())?;
+        assert_eq!(
+            diff_lines(&old, &new),
+            vec![
+                "== Dividends ==",
+                "+ 06/01/23 INTC: gross 50.00 USD, tax 7.50 USD, rate 4.2000",
+                "== Tax year 2023 ==",
+                "  gross_div: 400.00 -> 610.00 (+210.00)",
+                "  per_symbol.INTC.0: 400.00 -> 610.00 (+210.00)",
+                "  per_symbol.INTC.1: 60.00 -> 91.50 (+31.50)",
+                "  per_symbol.INTC.2: 60.00 -> 91.50 (+31.50)",
+                "  tax_div: 60.00 -> 91.50 (+31.50)",
+            ]
+        );
+        assert_eq!(diff_lines(&old, &old), vec!["No differences"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,208 @@
+use serde::Deserialize;
+
+use crate::ExportFormat;
+
+/// Settings of e-mail with report, read from TOML file e.g.
+///     smtp_url = "smtps://smtp.gmail.com:465"
+///     username = "jan.kowalski@gmail.com"
+///     from = "jan.kowalski@gmail.com"
+///     to = "biuro@ksiegowa.pl"
+///     formats = ["pdf", "csv"]
+/// Password is taken from SMTP_PASSWORD environment variable or keychain
+/// (stored with: etradeTaxReturnHelper login smtp <username>), so it is not
+/// kept in the file. Report is attached in each of formats (pdf by default)
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct EmailConfig {
+    pub smtp_url: String,
+    pub username: Option<String>,
+    pub from: String,
+    pub to: String,
+    pub subject: Option<String>,
+    pub formats: Option<Vec<String>>,
+}
+
+impl EmailConfig {
+    /// Formats report is attached in
+    pub fn formats(&self) -> Result<Vec<ExportFormat>, String> {
+        match &self.formats {
+            Some(formats) => formats.iter().map(|f| ExportFormat::from_name(f)).collect(),
+            None => Ok(vec![ExportFormat::Pdf]),
+        }
+    }
+}
+
+pub fn load_config(path: &str) -> Result<EmailConfig, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Error reading e-mail settings {}: {}", path, e))?;
+    toml::from_str(&content).map_err(|e| format!("Error parsing e-mail settings: {}", e))
+}
+
+fn content_type(format: &ExportFormat) -> &'static str {
+    match format {
+        ExportFormat::Csv => "text/csv",
+        ExportFormat::Json => "application/json",
+        ExportFormat::JsonLines => "application/jsonl",
+        ExportFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        ExportFormat::Pdf => "application/pdf",
+        ExportFormat::Html => "text/html",
+        ExportFormat::Markdown => "text/markdown",
+        ExportFormat::Human | ExportFormat::KeyValue => "text/plain",
+    }
+}
+
+/// Base64 (RFC 2045) with lines of 76 characters
+fn base64_lines(bytes: &[u8]) -> String {
+    base64::encode(bytes)
+        .as_bytes()
+        .chunks(76)
+        .map(|line| String::from_utf8_lossy(line))
+        .collect::<Vec<_>>()
+        .join("\r\n")
+}
+
+/// MIME message with text body and report attached in each of formats
+fn mime_message(
+    config: &EmailConfig,
+    date: &str,
+    attachments: &[(ExportFormat, Vec<u8>)],
+) -> String {
+    let boundary = "etradeTaxReturnHelper-report";
+    let mut message = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMIME-Version: 1.0\r\nContent-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n",
+        config.from,
+        config.to,
+        config
+            .subject
+            .clone()
+            .unwrap_or("E*TRADE tax return report".to_owned()),
+        date,
+        boundary
+    );
+    message += &format!(
+        "--{}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\nReport attached.\r\n",
+        boundary
+    );
+    attachments.iter().for_each(|(format, content)| {
+        let name = format!("etrade-tax-report.{}", format.extension());
+        message += &format!(
+            "--{}\r\nContent-Type: {}; name=\"{}\"\r\nContent-Disposition: attachment; filename=\"{}\"\r\nContent-Transfer-Encoding: base64\r\n\r\n{}\r\n",
+            boundary,
+            content_type(format),
+            name,
+            name,
+            base64_lines(content)
+        );
+    });
+    message += &format!("--{}--\r\n", boundary);
+    message
+}
+
+/// Send e-mail with attachments through SMTP server using curl, which is
+/// available on Linux, macOS and Windows 10 or later
+pub fn send_report(
+    config: &EmailConfig,
+    attachments: &[(ExportFormat, Vec<u8>)],
+) -> Result<(), String> {
+    use std::io::Write;
+    let password = match (&config.username, std::env::var("SMTP_PASSWORD")) {
+        (_, Ok(password)) => password,
+        (Some(username), Err(_)) => {
+            crate::credentials::load_secret("smtp", username)?.unwrap_or_default()
+        }
+        (None, Err(_)) => String::new(),
+    };
+    let message = mime_message(config, &chrono::Local::now().to_rfc2822(), attachments);
+    // Standard input takes credentials, so message is uploaded from file
+    // readable only by user (0600) that is removed once dropped
+    let mut message_file = tempfile::Builder::new()
+        .prefix("etrade_report_")
+        .suffix(".eml")
+        .tempfile()
+        .map_err(|e| format!("Error creating e-mail message file: {}", e))?;
+    message_file
+        .write_all(message.as_bytes())
+        .map_err(|e| format!("Error writing e-mail message file: {}", e))?;
+    let mut command = std::process::Command::new("curl");
+    command
+        .args(["--silent", "--show-error", "--ssl-reqd"])
+        .args(["--url", &config.smtp_url])
+        .args(["--mail-from", &config.from])
+        .args(["--mail-rcpt", &config.to])
+        .arg("--upload-file")
+        .arg(message_file.path())
+        // Credentials are passed as curl config on standard input, so they
+        // are not visible in list of processes
+        .args(["--config", "-"])
+        .stdin(std::process::Stdio::piped());
+    let mut child = command.spawn().map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => {
+            "Error: curl is needed to send e-mail, but it was not found. Install curl or add it to PATH".to_owned()
+        }
+        _ => format!("Error running curl: {}", e),
+    })?;
+    // Closing standard input lets curl go on
+    let mut stdin = child.stdin.take();
+    if let (Some(username), Some(stdin)) = (&config.username, stdin.as_mut()) {
+        let user = format!("{}:{}", username, password)
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"");
+        stdin
+            .write_all(format!("user = \"{}\"\n", user).as_bytes())
+            .map_err(|e| format!("Error running curl: {}", e))?;
+    }
+    drop(stdin);
+    let status = child
+        .wait()
+        .map_err(|e| format!("Error running curl: {}", e))?;
+    match status {
+        status if status.success() => Ok(()),
+        status => Err(format!(
+            "Error sending e-mail to {}: curl {}",
+            config.to, status
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64_lines() -> Result<(), String> {
+        assert_eq!(base64_lines(b"PIT-38"), "UElULTM4");
+        assert_eq!(base64_lines(b"P"), "UA==");
+        let encoded = base64_lines(&[0u8; 60]);
+        let lines: Vec<&str> = encoded.split("\r\n").collect();
+        assert_eq!((lines.len(), lines[0].len()), (2, 76));
+        Ok(())
+    }
+
+    #[test]
+    fn test_mime_message() -> Result<(), String> {
+        let config: EmailConfig = toml::from_str(
+            r#"
+            smtp_url = "smtps://smtp.example.com:465"
+            username = "jan"
+            from = "jan@example.com"
+            to = "biuro@example.com"
+            formats = ["pdf", "csv"]
+            "#,
+        )
+        .map_err(|e| e.to_string())?;
+        assert_eq!(
+            config.formats()?,
+            vec![ExportFormat::Pdf, ExportFormat::Csv]
+        );
+        let message = mime_message(
+            &config,
+            "Mon, 1 Apr 2024 10:00:00 +0200",
+            &[(ExportFormat::Csv, b"type".to_vec())],
+        );
+        assert!(message.starts_with("From: jan@example.com\r\nTo: biuro@example.com\r\n"));
+        assert!(message.contains(
+            "Content-Type: text/csv; name=\"etrade-tax-report.csv\"\r\nContent-Disposition: attachment; filename=\"etrade-tax-report.csv\"\r\nContent-Transfer-Encoding: base64\r\n\r\ndHlwZQ==\r\n"
+        ));
+        assert!(message.ends_with("--etradeTaxReturnHelper-report--\r\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,1213 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::i18n::{tr, Lang};
+use crate::{compute_tax_due, HoldingTerm, SoldTransaction, TaxRules, TaxYearResult, Transaction};
+
+/// Format results are printed or exported in. Human is summary presented by
+/// residency on terminal, or text report when written to file
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum ExportFormat {
+    #[default]
+    Human,
+    Csv,
+    Json,
+    Xlsx,
+    Pdf,
+    Html,
+    Markdown,
+    KeyValue,
+    JsonLines,
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        match name {
+            "human" => Ok(ExportFormat::Human),
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            "xlsx" => Ok(ExportFormat::Xlsx),
+            "pdf" => Ok(ExportFormat::Pdf),
+            "html" => Ok(ExportFormat::Html),
+            "md" => Ok(ExportFormat::Markdown),
+            "kv" => Ok(ExportFormat::KeyValue),
+            "jsonl" => Ok(ExportFormat::JsonLines),
+            _ => Err(format!("Error: unsupported export format: {}", name)),
+        }
+    }
+
+    /// Extension of files of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Human => "txt",
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Xlsx => "xlsx",
+            ExportFormat::Pdf => "pdf",
+            ExportFormat::Html => "html",
+            ExportFormat::Markdown => "md",
+            ExportFormat::KeyValue => "env",
+            ExportFormat::JsonLines => "jsonl",
+        }
+    }
+
+    /// Format given by extension of file path e.g. report.html. Text files
+    /// (.txt) get human format and .env files key=value lines
+    pub fn from_path(path: &str) -> Result<Self, String> {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .ok_or(format!("Error: no extension to tell format of {}", path))?;
+        match extension.as_str() {
+            "txt" => Ok(ExportFormat::Human),
+            "htm" => Ok(ExportFormat::Html),
+            "markdown" => Ok(ExportFormat::Markdown),
+            "env" => Ok(ExportFormat::KeyValue),
+            name => ExportFormat::from_name(name),
+        }
+    }
+}
+
+/// Write content to file creating its missing parent directories
+pub fn write_file(path: &str, content: &[u8]) -> Result<(), String> {
+    if let Some(parent) = std::path::Path::new(path).parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Error creating directory {}: {}", parent.display(), e))?;
+        }
+    }
+    std::fs::write(path, content).map_err(|e| format!("Error writing {}: {}", path, e))
+}
+
+fn to_iso_date(date: &str) -> String {
+    chrono::NaiveDate::parse_from_str(date, "%m/%d/%y")
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or(date.to_owned())
+}
+
+/// One line per transaction. Amounts without _usd suffix are in target currency
+/// (PLN, EUR etc.). Acquisition columns are given for sold stock only
+fn format_csv(
+    transactions: &[Transaction],
+    interest_transactions: &[Transaction],
+    sold_transactions: &[SoldTransaction],
+) -> String {
+    let mut content = "type,date,symbol,gross_usd,tax_usd,exchange_rate_date,exchange_rate,gross,tax,acquisition_date,cost_usd,acquisition_exchange_rate_date,acquisition_exchange_rate,cost\n".to_owned();
+    let mut push_income = |kind: &str, t: &Transaction| {
+        content += &format!(
+            "{},{},{},{:.2},{:.2},{},{},{:.2},{:.2},,,,,\n",
+            kind,
+            to_iso_date(&t.transaction_date),
+            t.symbol,
+            t.gross_us,
+            t.tax_us,
+            t.exchange_rate_date,
+            t.exchange_rate,
+            t.gross_us * t.exchange_rate,
+            t.tax_us * t.exchange_rate
+        );
+    };
+    transactions.iter().for_each(|t| push_income("dividend", t));
+    interest_transactions
+        .iter()
+        .for_each(|t| push_income("interest", t));
+    sold_transactions.iter().for_each(|t| {
+        // Fees are charged at settlement so they reduce income
+        content += &format!(
+            "sold,{},,{:.2},0.00,{},{},{:.2},0.00,{},{:.2},{},{},{:.2}\n",
+            to_iso_date(&t.settlement_date),
+            t.income_us - t.fees_us,
+            t.exchange_rate_settlement_date,
+            t.exchange_rate_settlement,
+            (t.income_us - t.fees_us) * t.exchange_rate_settlement,
+            to_iso_date(&t.acquisition_date),
+            t.cost_basis,
+            t.exchange_rate_acquisition_date,
+            t.exchange_rate_acquisition,
+            t.cost_basis * t.exchange_rate_acquisition
+        );
+    });
+    content
+}
+
+/// Single JSON line of item with its type e.g. {"type":"dividend",...} and
+/// extra fields
+pub(crate) fn json_line<T: Serialize>(
+    kind: &str,
+    item: &T,
+    extra: Vec<(&str, serde_json::Value)>,
+) -> Result<String, String> {
+    let mut value =
+        serde_json::to_value(item).map_err(|e| format!("Error serializing {}: {}", kind, e))?;
+    if let serde_json::Value::Object(map) = &mut value {
+        map.insert("type".to_owned(), serde_json::Value::from(kind));
+        extra.into_iter().for_each(|(name, field)| {
+            map.insert(name.to_owned(), field);
+        });
+    }
+    serde_json::to_string(&value).map_err(|e| format!("Error serializing {}: {}", kind, e))
+}
+
+/// Transactions of interrupted run as parsed from documents (dates as there,
+/// amounts in USD) with exchange rates obtained before interruption
+pub(crate) fn format_partial(
+    dividends: &[(String, f32, f32, String)],
+    interest: &[(String, f32, f32, String)],
+    crypto: &[(String, bool, f32, String)],
+    sold: &[(String, String, String, f32, f32, f32, HoldingTerm)],
+    dates: &HashMap<String, Option<(String, f32)>>,
+) -> Result<String, String> {
+    let exchange_rates: BTreeMap<&String, &(String, f32)> = dates
+        .iter()
+        .filter_map(|(date, rate)| rate.as_ref().map(|rate| (date, rate)))
+        .collect();
+    let content = serde_json::json!({
+        "partial": true,
+        // (date, gross, tax withheld, symbol)
+        "dividends": dividends,
+        "interest": interest,
+        // (date, is sale, amount, currency)
+        "crypto": crypto,
+        // (trade date, settlement date, acquisition date, income, cost basis, fees, term)
+        "sold": sold,
+        // Date of transaction: (date of rate, rate)
+        "exchange_rates": exchange_rates,
+    });
+    serde_json::to_string_pretty(&content)
+        .map_err(|e| format!("Error serializing partial results: {}", e))
+}
+
+/// JSON line of each processed transaction followed by JSON line of each tax
+/// year result
+fn format_json_lines(
+    transactions: &[Transaction],
+    interest_transactions: &[Transaction],
+    sold_transactions: &[SoldTransaction],
+    results: &BTreeMap<i32, TaxYearResult>,
+) -> Result<String, String> {
+    let mut lines: Vec<String> = vec![];
+    for t in transactions.iter() {
+        lines.push(json_line("dividend", t, vec![])?);
+    }
+    for t in interest_transactions.iter() {
+        lines.push(json_line("interest", t, vec![])?);
+    }
+    for t in sold_transactions.iter() {
+        lines.push(json_line("sold", t, vec![])?);
+    }
+    for (year, result) in results.iter() {
+        lines.push(json_line(
+            "result",
+            result,
+            vec![("tax_year", serde_json::Value::from(*year))],
+        )?);
+    }
+    Ok(lines.iter().map(|line| line.clone() + "\n").collect())
+}
+
+/// Full results: processed transactions, figures of each tax year and tax
+/// rules applied to them
+#[derive(Serialize)]
+struct JsonExport<'a> {
+    dividends: &'a [Transaction],
+    interest: &'a [Transaction],
+    sold: &'a [SoldTransaction],
+    results: &'a BTreeMap<i32, TaxYearResult>,
+    rules: &'a BTreeMap<i32, TaxRules>,
+}
+
+#[derive(Debug, PartialEq)]
+enum CellValue {
+    Text(String),
+    Number(f64),
+}
+
+fn text_row(texts: &[&str]) -> Vec<CellValue> {
+    texts
+        .iter()
+        .map(|t| CellValue::Text(t.to_string()))
+        .collect()
+}
+
+/// Rows with texts (headers, transaction types, terms) in given language
+fn localize_rows(rows: &[Vec<CellValue>], lang: Lang) -> Vec<Vec<CellValue>> {
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .map(|cell| match cell {
+                    CellValue::Text(text) => CellValue::Text(tr(lang, text).to_owned()),
+                    CellValue::Number(number) => CellValue::Number(*number),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Dividends and interest with header row
+fn dividend_rows(
+    transactions: &[Transaction],
+    interest_transactions: &[Transaction],
+) -> Vec<Vec<CellValue>> {
+    let mut dividends = vec![text_row(&[
+        "Type",
+        "Date",
+        "Symbol",
+        "Gross USD",
+        "Tax USD",
+        "Exchange rate date",
+        "Exchange rate",
+        "Gross",
+        "Tax",
+    ])];
+    transactions
+        .iter()
+        .map(|t| ("dividend", t))
+        .chain(interest_transactions.iter().map(|t| ("interest", t)))
+        .for_each(|(kind, t)| {
+            dividends.push(vec![
+                CellValue::Text(kind.to_owned()),
+                CellValue::Text(to_iso_date(&t.transaction_date)),
+                CellValue::Text(t.symbol.clone()),
+                CellValue::Number(t.gross_us as f64),
+                CellValue::Number(t.tax_us as f64),
+                CellValue::Text(t.exchange_rate_date.clone()),
+                CellValue::Number(t.exchange_rate as f64),
+                CellValue::Number((t.gross_us * t.exchange_rate) as f64),
+                CellValue::Number((t.tax_us * t.exchange_rate) as f64),
+            ]);
+        });
+    dividends
+}
+
+/// Sold stock with header row
+fn sales_rows(sold_transactions: &[SoldTransaction]) -> Vec<Vec<CellValue>> {
+    let mut sales = vec![text_row(&[
+        "Settlement date",
+        "Acquisition date",
+        "Income USD",
+        "Fees USD",
+        "Cost basis USD",
+        "Term",
+        "Settlement exchange rate date",
+        "Settlement exchange rate",
+        "Acquisition exchange rate date",
+        "Acquisition exchange rate",
+        "Income",
+        "Cost",
+    ])];
+    sold_transactions.iter().for_each(|t| {
+        sales.push(vec![
+            CellValue::Text(to_iso_date(&t.settlement_date)),
+            CellValue::Text(to_iso_date(&t.acquisition_date)),
+            CellValue::Number(t.income_us as f64),
+            CellValue::Number(t.fees_us as f64),
+            CellValue::Number(t.cost_basis as f64),
+            CellValue::Text(format!("{:?}", t.term)),
+            CellValue::Text(t.exchange_rate_settlement_date.clone()),
+            CellValue::Number(t.exchange_rate_settlement as f64),
+            CellValue::Text(t.exchange_rate_acquisition_date.clone()),
+            CellValue::Number(t.exchange_rate_acquisition as f64),
+            CellValue::Number(((t.income_us - t.fees_us) * t.exchange_rate_settlement) as f64),
+            CellValue::Number((t.cost_basis * t.exchange_rate_acquisition) as f64),
+        ]);
+    });
+    sales
+}
+
+/// Row summing numeric columns of given rows (header excluded). Exchange
+/// rates are not summed
+fn totals_row(rows: &[Vec<CellValue>]) -> Vec<CellValue> {
+    let header = match rows.first() {
+        Some(header) => header,
+        None => return vec![],
+    };
+    header
+        .iter()
+        .enumerate()
+        .map(|(c, title)| {
+            let is_rate = matches!(title, CellValue::Text(t) if t.contains("rate"));
+            let values: Vec<f64> = rows
+                .iter()
+                .skip(1)
+                .filter_map(|row| match row.get(c) {
+                    Some(CellValue::Number(n)) => Some(*n),
+                    _ => None,
+                })
+                .collect();
+            match (c, is_rate || values.is_empty()) {
+                (0, _) => CellValue::Text("TOTAL".to_owned()),
+                (_, true) => CellValue::Text("".to_owned()),
+                (_, false) => CellValue::Number(values.iter().sum()),
+            }
+        })
+        .collect()
+}
+
+/// Aligned tables of dividends (with interest) and sold stock followed by
+/// their totals, as presented in terminal
+pub fn transaction_tables(
+    transactions: &[Transaction],
+    interest_transactions: &[Transaction],
+    sold_transactions: &[SoldTransaction],
+    lang: Lang,
+) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    [
+        dividend_rows(transactions, interest_transactions),
+        sales_rows(sold_transactions),
+    ]
+    .into_iter()
+    .filter(|rows| rows.len() > 1)
+    .for_each(|mut rows| {
+        rows.push(totals_row(&rows));
+        let table = text_table(&localize_rows(&rows, lang));
+        let width = table.iter().map(|l| l.chars().count()).max().unwrap_or(0);
+        let separator = "-".repeat(width);
+        lines.push(table[0].clone());
+        lines.push(separator.clone());
+        lines.extend(table[1..table.len() - 1].iter().cloned());
+        lines.push(separator);
+        lines.push(table[table.len() - 1].clone());
+        lines.push("".to_owned());
+    });
+    lines
+}
+
+/// Sheets (name, rows) of workbook: dividends and interest, sales, exchange
+/// rates used and summary of each tax year. First row of each sheet is header
+fn build_sheets(
+    transactions: &[Transaction],
+    interest_transactions: &[Transaction],
+    sold_transactions: &[SoldTransaction],
+    results: &BTreeMap<i32, TaxYearResult>,
+    rules: &BTreeMap<i32, TaxRules>,
+) -> Vec<(&'static str, Vec<Vec<CellValue>>)> {
+    let mut rates: BTreeMap<chrono::NaiveDate, (String, f32)> = BTreeMap::new();
+    let mut add_rate = |date: &str, rate_date: &str, rate: f32| {
+        if let Ok(d) = chrono::NaiveDate::parse_from_str(date, "%m/%d/%y") {
+            rates.insert(d, (rate_date.to_owned(), rate));
+        }
+    };
+    transactions
+        .iter()
+        .chain(interest_transactions.iter())
+        .for_each(|t| add_rate(&t.transaction_date, &t.exchange_rate_date, t.exchange_rate));
+    sold_transactions.iter().for_each(|t| {
+        add_rate(
+            &t.settlement_date,
+            &t.exchange_rate_settlement_date,
+            t.exchange_rate_settlement,
+        );
+        add_rate(
+            &t.acquisition_date,
+            &t.exchange_rate_acquisition_date,
+            t.exchange_rate_acquisition,
+        );
+    });
+
+    let mut rates_used = vec![text_row(&[
+        "Transaction date",
+        "Exchange rate date",
+        "Exchange rate",
+    ])];
+    rates.iter().for_each(|(date, (rate_date, rate))| {
+        rates_used.push(vec![
+            CellValue::Text(date.format("%Y-%m-%d").to_string()),
+            CellValue::Text(rate_date.clone()),
+            CellValue::Number(*rate as f64),
+        ]);
+    });
+
+    let mut summary = vec![text_row(&[
+        "Tax year",
+        "Dividends gross",
+        "Dividends tax paid",
+        "Interest gross",
+        "Interest tax paid",
+        "Sold stock income",
+        "Sold stock cost",
+        "Virtual currency income",
+        "Virtual currency cost",
+        "Tax rate",
+    ])];
+    results.iter().for_each(|(year, result)| {
+        let mut row = vec![CellValue::Number(*year as f64)];
+        row.extend(
+            [
+                result.gross_div,
+                result.tax_div,
+                result.gross_interest,
+                result.tax_interest,
+                result.gross_sold,
+                result.cost_sold,
+                result.crypto_income,
+                result.crypto_cost,
+            ]
+            .iter()
+            .map(|amount| CellValue::Number(*amount as f64)),
+        );
+        row.push(match rules.get(year) {
+            Some(rules) => CellValue::Number(rules.tax_rate as f64),
+            None => CellValue::Text("".to_owned()),
+        });
+        summary.push(row);
+    });
+
+    vec![
+        (
+            "Dividends",
+            dividend_rows(transactions, interest_transactions),
+        ),
+        ("Sales", sales_rows(sold_transactions)),
+        ("Rates", rates_used),
+        ("Summary", summary),
+    ]
+}
+
+/// Localized sheets (name, rows) with cells as JSON values, as sent to
+/// Google Sheets API
+pub(crate) fn sheet_values(
+    transactions: &[Transaction],
+    interest_transactions: &[Transaction],
+    sold_transactions: &[SoldTransaction],
+    results: &BTreeMap<i32, TaxYearResult>,
+    rules: &BTreeMap<i32, TaxRules>,
+    lang: Lang,
+) -> Vec<(String, Vec<Vec<serde_json::Value>>)> {
+    build_sheets(
+        transactions,
+        interest_transactions,
+        sold_transactions,
+        results,
+        rules,
+    )
+    .iter()
+    .map(|(name, rows)| {
+        let rows = localize_rows(rows, lang)
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        CellValue::Text(text) => serde_json::Value::from(text.as_str()),
+                        CellValue::Number(number) => serde_json::Value::from(*number),
+                    })
+                    .collect()
+            })
+            .collect();
+        (tr(lang, name).to_owned(), rows)
+    })
+    .collect()
+}
+
+#[cfg(feature = "xlsx")]
+fn format_xlsx(sheets: Vec<(&str, Vec<Vec<CellValue>>)>, lang: Lang) -> Result<Vec<u8>, String> {
+    let xlsx_error = |e: rust_xlsxwriter::XlsxError| format!("Error creating workbook: {}", e);
+    let mut workbook = rust_xlsxwriter::Workbook::new();
+    let bold = rust_xlsxwriter::Format::new().set_bold();
+    for (name, rows) in sheets {
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(tr(lang, name)).map_err(xlsx_error)?;
+        for (r, row) in localize_rows(&rows, lang).iter().enumerate() {
+            for (c, cell) in row.iter().enumerate() {
+                match cell {
+                    CellValue::Text(text) if r == 0 => {
+                        worksheet.write_string_with_format(r as u32, c as u16, text, &bold)
+                    }
+                    CellValue::Text(text) => worksheet.write_string(r as u32, c as u16, text),
+                    CellValue::Number(number) => {
+                        worksheet.write_number(r as u32, c as u16, *number)
+                    }
+                }
+                .map_err(xlsx_error)?;
+            }
+        }
+    }
+    workbook.save_to_buffer().map_err(xlsx_error)
+}
+
+// Workbook writer is left out of builds without xlsx feature
+#[cfg(not(feature = "xlsx"))]
+fn format_xlsx(_sheets: Vec<(&str, Vec<Vec<CellValue>>)>, _lang: Lang) -> Result<Vec<u8>, String> {
+    Err(crate::Error::MissingFeature("xlsx export".to_owned(), "xlsx".to_owned()).to_string())
+}
+
+fn format_cell(cell: &CellValue) -> String {
+    match cell {
+        CellValue::Text(text) => text.clone(),
+        CellValue::Number(number) if number.fract() == 0.0 => format!("{:.0}", number),
+        // Amounts in grosz/cents, exchange rates with 4 decimal places
+        CellValue::Number(number) => {
+            let formatted = format!("{:.4}", number);
+            let trimmed = formatted.trim_end_matches('0');
+            match trimmed.len() - trimmed.find('.').unwrap_or(0) {
+                len if len < 3 => format!("{:.2}", number),
+                _ => trimmed.to_owned(),
+            }
+        }
+    }
+}
+
+/// Rows of sheet as lines of text with aligned columns. Numbers are right aligned
+fn text_table(rows: &[Vec<CellValue>]) -> Vec<String> {
+    let mut widths: Vec<usize> = vec![];
+    rows.iter().for_each(|row| {
+        row.iter().enumerate().for_each(|(c, cell)| {
+            let width = format_cell(cell).chars().count();
+            match widths.get_mut(c) {
+                Some(w) => *w = (*w).max(width),
+                None => widths.push(width),
+            }
+        })
+    });
+    rows.iter()
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .map(|(c, cell)| match cell {
+                    CellValue::Number(_) => format!("{:>w$}", format_cell(cell), w = widths[c]),
+                    CellValue::Text(_) => format!("{:<w$}", format_cell(cell), w = widths[c]),
+                })
+                .collect::<Vec<String>>()
+                .join("  ")
+                .trim_end()
+                .to_owned()
+        })
+        .collect()
+}
+
+/// key=value lines of main figures of each tax year, starting with tax_year.
+/// Names and meaning of keys are kept stable for scripts: gross_pln is income
+/// from dividends, interest, sold stock and virtual currency, tax_paid_pln is
+/// tax paid abroad and tax_due_pln is tax due (if tax rules of year are known)
+fn format_key_value(
+    results: &BTreeMap<i32, TaxYearResult>,
+    rules: &BTreeMap<i32, TaxRules>,
+) -> String {
+    let mut content = String::new();
+    results.iter().for_each(|(year, result)| {
+        let gross = result.gross_div
+            + result.gross_interest
+            + result.gross_sold
+            + result.pit8c_income
+            + result.crypto_income;
+        content += &format!("tax_year={}\n", year);
+        content += &format!("gross_pln={:.2}\n", gross);
+        content += &format!("tax_paid_pln={:.2}\n", result.tax_div + result.tax_interest);
+        if let Some(rules) = rules.get(year) {
+            content += &format!("tax_due_pln={:.2}\n", compute_tax_due(result, rules));
+        }
+    });
+    content
+}
+
+const REPORT_TITLE: &str = "E*TRADE tax return report";
+
+/// Report as lines of text: summary first, then exchange rates used and
+/// transaction tables
+fn report_lines(sheets: &Vec<(&str, Vec<Vec<CellValue>>)>, lang: Lang) -> Vec<String> {
+    let mut lines = vec![
+        tr(lang, REPORT_TITLE).to_owned(),
+        format!(
+            "{}: {}",
+            tr(lang, "Generated"),
+            chrono::Local::now().naive_local().date().format("%Y-%m-%d")
+        ),
+        "".to_owned(),
+    ];
+    ["Summary", "Rates", "Dividends", "Sales"]
+        .iter()
+        .filter_map(|name| sheets.iter().find(|(sheet, _)| sheet == name))
+        .for_each(|(name, rows)| {
+            lines.push(format!("== {} ==", tr(lang, name)));
+            lines.extend(text_table(&localize_rows(rows, lang)));
+            lines.push("".to_owned());
+        });
+    lines
+}
+
+const HTML_STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+table{border-collapse:collapse;margin-bottom:2em}\
+th,td{border:1px solid #ccc;padding:2px 6px}\
+th{background:#eee;cursor:pointer}\
+td.number{text-align:right}\
+input{margin-bottom:0.5em}";
+
+// Clicking header sorts table by the column (again to reverse), typing into
+// filter input hides rows not containing the text
+const HTML_SCRIPT: &str = "function sortTable(th){\
+const table=th.closest('table');const body=table.tBodies[0];\
+const col=th.cellIndex;const asc=th.dataset.asc!=='true';th.dataset.asc=asc;\
+const value=(row)=>{const text=row.cells[col].textContent;const n=parseFloat(text);\
+return row.cells[col].classList.contains('number')&&!isNaN(n)?n:text;};\
+Array.from(body.rows).sort((a,b)=>{const x=value(a),y=value(b);\
+return (x<y?-1:x>y?1:0)*(asc?1:-1);}).forEach((r)=>body.appendChild(r));}\
+function filterTable(input){const filter=input.value.toLowerCase();\
+const table=document.getElementById(input.dataset.table);\
+Array.from(table.tBodies[0].rows).forEach((r)=>{\
+r.style.display=r.textContent.toLowerCase().includes(filter)?'':'none';});}";
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Standalone HTML report: summary first, then exchange rates used and
+/// transaction tables, each sortable by column and filterable
+fn format_html(sheets: &Vec<(&str, Vec<Vec<CellValue>>)>, lang: Lang) -> String {
+    let title = escape_html(tr(lang, REPORT_TITLE));
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+<title>{}</title>\n<style>{}</style>\n<script>{}</script>\n\
+</head>\n<body>\n<h1>{}</h1>\n",
+        title, HTML_STYLE, HTML_SCRIPT, title
+    );
+    ["Summary", "Rates", "Dividends", "Sales"]
+        .iter()
+        .filter_map(|name| sheets.iter().find(|(sheet, _)| sheet == name))
+        .for_each(|(name, rows)| {
+            let rows = localize_rows(rows, lang);
+            html += &format!("<h2>{}</h2>\n", tr(lang, name));
+            html += &format!(
+                "<input placeholder=\"{}\" data-table=\"{}\" oninput=\"filterTable(this)\">\n",
+                tr(lang, "Filter"),
+                name
+            );
+            html += &format!("<table id=\"{}\">\n<thead>\n<tr>", name);
+            if let Some(header) = rows.first() {
+                header.iter().for_each(|cell| {
+                    html += &format!(
+                        "<th onclick=\"sortTable(this)\">{}</th>",
+                        escape_html(&format_cell(cell))
+                    );
+                });
+            }
+            html += "</tr>\n</thead>\n<tbody>\n";
+            rows.iter().skip(1).for_each(|row| {
+                html += "<tr>";
+                row.iter().for_each(|cell| {
+                    html += &match cell {
+                        CellValue::Number(_) => {
+                            format!("<td class=\"number\">{}</td>", format_cell(cell))
+                        }
+                        CellValue::Text(_) => {
+                            format!("<td>{}</td>", escape_html(&format_cell(cell)))
+                        }
+                    };
+                });
+                html += "</tr>\n";
+            });
+            html += "</tbody>\n</table>\n";
+        });
+    html += "</body>\n</html>\n";
+    html
+}
+
+/// Markdown report: summary first, then exchange rates used and transaction
+/// tables. Numeric columns are right aligned
+fn format_markdown(sheets: &Vec<(&str, Vec<Vec<CellValue>>)>, lang: Lang) -> String {
+    let mut markdown = format!("# {}\n", tr(lang, REPORT_TITLE));
+    ["Summary", "Rates", "Dividends", "Sales"]
+        .iter()
+        .filter_map(|name| sheets.iter().find(|(sheet, _)| sheet == name))
+        .for_each(|(name, rows)| {
+            let rows = localize_rows(rows, lang);
+            markdown += &format!("\n## {}\n\n", tr(lang, name));
+            let row_line = |row: &Vec<CellValue>| {
+                format!(
+                    "| {} |\n",
+                    row.iter()
+                        .map(|cell| format_cell(cell).replace('|', "\\|"))
+                        .collect::<Vec<String>>()
+                        .join(" | ")
+                )
+            };
+            if let Some(header) = rows.first() {
+                markdown += &row_line(header);
+                // Alignment follows values of first data row
+                let alignment: Vec<&str> = (0..header.len())
+                    .map(|c| match rows.get(1).and_then(|row| row.get(c)) {
+                        Some(CellValue::Number(_)) => "---:",
+                        _ => "---",
+                    })
+                    .collect();
+                markdown += &format!("| {} |\n", alignment.join(" | "));
+            }
+            rows.iter()
+                .skip(1)
+                .for_each(|row| markdown += &row_line(row));
+        });
+    markdown
+}
+
+fn format_pdf(lines: Vec<String>) -> Result<Vec<u8>, String> {
+    use printpdf::{BuiltinFont, Mm, PdfDocument};
+    // Landscape A4 with monospaced font keeps table columns aligned
+    const WIDTH: f32 = 297.0;
+    const HEIGHT: f32 = 210.0;
+    const MARGIN: f32 = 10.0;
+    const FONT_SIZE: f32 = 6.0;
+    const LINE_HEIGHT: f32 = 2.6;
+    let lines_per_page = ((HEIGHT - 2.0 * MARGIN) / LINE_HEIGHT) as usize;
+
+    let pdf_error = |e: printpdf::Error| format!("Error creating PDF: {}", e);
+    let (doc, page, layer) =
+        PdfDocument::new("E*TRADE tax return report", Mm(WIDTH), Mm(HEIGHT), "Report");
+    let font = doc
+        .add_builtin_font(BuiltinFont::Courier)
+        .map_err(pdf_error)?;
+    for (i, chunk) in lines.chunks(lines_per_page).enumerate() {
+        let (page, layer) = match i {
+            0 => (page, layer),
+            _ => doc.add_page(Mm(WIDTH), Mm(HEIGHT), "Report"),
+        };
+        let layer = doc.get_page(page).get_layer(layer);
+        chunk.iter().enumerate().for_each(|(l, line)| {
+            layer.use_text(
+                line.as_str(),
+                FONT_SIZE,
+                Mm(MARGIN),
+                Mm(HEIGHT - MARGIN - LINE_HEIGHT * (l as f32 + 1.0)),
+                &font,
+            );
+        });
+    }
+    doc.save_to_bytes().map_err(pdf_error)
+}
+
+/// Processed transactions (CSV), full results (JSON), workbook with sheets of
+/// dividends, sales, exchange rates and summary (XLSX) or report of the same
+/// (PDF, HTML, Markdown, text) in given format
+pub fn format_results(
+    format: &ExportFormat,
+    transactions: &[Transaction],
+    interest_transactions: &[Transaction],
+    sold_transactions: &[SoldTransaction],
+    results: &BTreeMap<i32, TaxYearResult>,
+    rules: &BTreeMap<i32, TaxRules>,
+    lang: Lang,
+) -> Result<Vec<u8>, String> {
+    let sheets = || {
+        build_sheets(
+            transactions,
+            interest_transactions,
+            sold_transactions,
+            results,
+            rules,
+        )
+    };
+    let content: Vec<u8> = match format {
+        ExportFormat::Human => (report_lines(&sheets(), lang).join("\n") + "\n").into_bytes(),
+        ExportFormat::Xlsx => format_xlsx(sheets(), lang)?,
+        ExportFormat::Pdf => format_pdf(report_lines(&sheets(), lang))?,
+        ExportFormat::Html => format_html(&sheets(), lang).into_bytes(),
+        ExportFormat::Markdown => format_markdown(&sheets(), lang).into_bytes(),
+        ExportFormat::KeyValue => format_key_value(results, rules).into_bytes(),
+        ExportFormat::JsonLines => format_json_lines(
+            transactions,
+            interest_transactions,
+            sold_transactions,
+            results,
+        )?
+        .into_bytes(),
+        ExportFormat::Csv => {
+            format_csv(transactions, interest_transactions, sold_transactions).into_bytes()
+        }
+        ExportFormat::Json => serde_json::to_string_pretty(&JsonExport {
+            dividends: transactions,
+            interest: interest_transactions,
+            sold: sold_transactions,
+            results,
+            rules,
+        })
+        .map_err(|e| format!("Error serializing results: {}", e))?
+        .into_bytes(),
+    };
+    Ok(content)
+}
+
+/// Write exported content to file at path or to standard output if path is -
+pub fn write_output(path: &str, content: &[u8]) -> Result<(), String> {
+    if path == "-" {
+        use std::io::Write;
+        return std::io::stdout()
+            .write_all(content)
+            .map_err(|e| format!("Error writing to standard output: {}", e));
+    }
+    write_file(path, content)?;
+    log::info!("Results exported to {}", path);
+    Ok(())
+}
+
+/// Results rendered with user template read from template_path. Template
+/// gets the same data as JSON export: dividends, interest, sold, results
+/// (keyed by tax year) and rules. Rendered text is written to path or to
+/// standard output if path is -
+pub fn render_template(
+    template_path: &str,
+    path: &str,
+    transactions: &Vec<Transaction>,
+    interest_transactions: &Vec<Transaction>,
+    sold_transactions: &Vec<SoldTransaction>,
+    results: &BTreeMap<i32, TaxYearResult>,
+    rules: &BTreeMap<i32, TaxRules>,
+) -> Result<(), String> {
+    let template = std::fs::read_to_string(template_path)
+        .map_err(|e| format!("Error reading template {}: {}", template_path, e))?;
+    // Serialized first so amounts keep their shortest f32 representation
+    let json = serde_json::to_string(&JsonExport {
+        dividends: transactions,
+        interest: interest_transactions,
+        sold: sold_transactions,
+        results,
+        rules,
+    })
+    .map_err(|e| format!("Error serializing results: {}", e))?;
+    let data: serde_json::Value =
+        serde_json::from_str(&json).map_err(|e| format!("Error serializing results: {}", e))?;
+    let content = crate::report::template::render(&template, &data)?;
+    if path == "-" {
+        print!("{}", content);
+        return Ok(());
+    }
+    write_file(path, content.as_bytes())?;
+    log::info!("Template {} rendered to {}", template_path, path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_csv() -> Result<(), String> {
+        let transactions = vec![Transaction {
+            transaction_date: "03/01/23".to_string(),
+            gross_us: 100.0,
+            tax_us: 15.0,
+            symbol: "INTC".to_string(),
+            exchange_rate_date: "2023-02-28".to_string(),
+            exchange_rate: 4.0,
+        }];
+        let sold_transactions = vec![SoldTransaction {
+            settlement_date: "06/03/23".to_string(),
+            acquisition_date: "08/19/21".to_string(),
+            income_us: 200.0,
+            cost_basis: 50.0,
+            fees_us: 1.0,
+            term: HoldingTerm::Long,
+            exchange_rate_settlement_date: "2023-06-02".to_string(),
+            exchange_rate_settlement: 4.0,
+            exchange_rate_acquisition_date: "2021-08-18".to_string(),
+            exchange_rate_acquisition: 3.5,
+        }];
+        let content = format_csv(&transactions, &[], &sold_transactions);
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            "dividend,2023-03-01,INTC,100.00,15.00,2023-02-28,4,400.00,60.00,,,,,"
+        );
+        assert_eq!(
+            lines[2],
+            "sold,2023-06-03,,199.00,0.00,2023-06-02,4,796.00,0.00,2021-08-19,50.00,2021-08-18,3.5,175.00"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_xlsx_sheets() -> Result<(), String> {
+        let transaction = |date: &str, rate: f32| Transaction {
+            transaction_date: date.to_string(),
+            gross_us: 100.0,
+            tax_us: 15.0,
+            symbol: "INTC".to_string(),
+            exchange_rate_date: "N/A".to_string(),
+            exchange_rate: rate,
+        };
+        let results = BTreeMap::from([(2023, TaxYearResult::default())]);
+        let sheets = build_sheets(
+            &[transaction("06/01/23", 4.0), transaction("03/01/23", 4.5)],
+            &[transaction("03/01/23", 4.5)],
+            &[],
+            &results,
+            &BTreeMap::new(),
+        );
+        let names: Vec<&str> = sheets.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, vec!["Dividends", "Sales", "Rates", "Summary"]);
+        // Header and dividends followed by interest
+        assert_eq!(sheets[0].1.len(), 4);
+        assert_eq!(sheets[0].1[3][0], CellValue::Text("interest".to_owned()));
+        // Rates of distinct dates in chronological order
+        assert_eq!(sheets[2].1.len(), 3);
+        assert_eq!(sheets[2].1[1][0], CellValue::Text("2023-03-01".to_owned()));
+        assert_eq!(sheets[2].1[1][2], CellValue::Number(4.5));
+        assert_eq!(sheets[3].1[1][0], CellValue::Number(2023.0));
+
+        let values = sheet_values(
+            &[transaction("03/01/23", 4.5)],
+            &[],
+            &[],
+            &results,
+            &BTreeMap::new(),
+            Lang::Pl,
+        );
+        assert_eq!(values[0].0, "Dywidendy");
+        assert_eq!(values[0].1[1][0], serde_json::Value::from("dywidenda"));
+        assert_eq!(values[3].1[1][0], serde_json::Value::from(2023.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_text_table() -> Result<(), String> {
+        let rows = vec![
+            text_row(&["Date", "Gross", "Exchange rate"]),
+            vec![
+                CellValue::Text("2023-03-01".to_owned()),
+                CellValue::Number(400.5),
+                CellValue::Number(4.0123),
+            ],
+            vec![
+                CellValue::Text("2023-06-01".to_owned()),
+                CellValue::Number(1200.0),
+                CellValue::Number(3.9),
+            ],
+        ];
+        assert_eq!(
+            text_table(&rows),
+            vec![
+                "Date        Gross   Exchange rate",
+                "2023-03-01  400.50         4.0123",
+                "2023-06-01    1200           3.90",
+            ]
+        );
+        let lines = report_lines(&vec![("Sales", vec![]), ("Summary", rows)], Lang::En);
+        assert_eq!(lines[3], "== Summary ==");
+        assert_eq!(lines[8], "== Sales ==");
+        Ok(())
+    }
+
+    #[test]
+    fn test_html_report() -> Result<(), String> {
+        let rows = vec![
+            text_row(&["Symbol", "Gross"]),
+            vec![CellValue::Text("AT&T".to_owned()), CellValue::Number(400.5)],
+        ];
+        let html = format_html(&vec![("Dividends", rows)], Lang::En);
+        assert!(html.contains("<table id=\"Dividends\">"));
+        assert!(html.contains("<th onclick=\"sortTable(this)\">Symbol</th>"));
+        assert!(html.contains("<tr><td>AT&amp;T</td><td class=\"number\">400.50</td></tr>"));
+        assert!(!html.contains("<h2>Sales</h2>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_markdown_report() -> Result<(), String> {
+        let rows = vec![
+            text_row(&["Symbol", "Gross"]),
+            vec![CellValue::Text("A|B".to_owned()), CellValue::Number(400.5)],
+        ];
+        assert_eq!(
+            format_markdown(&vec![("Dividends", rows)], Lang::En),
+            "# E*TRADE tax return report\n\n## Dividends\n\n\
+             | Symbol | Gross |\n| --- | ---: |\n| A\\|B | 400.50 |\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_transaction_tables() -> Result<(), String> {
+        let transaction = |date: &str, gross: f32| Transaction {
+            transaction_date: date.to_string(),
+            gross_us: gross,
+            tax_us: gross / 4.0,
+            symbol: "INTC".to_string(),
+            exchange_rate_date: "2023-02-28".to_string(),
+            exchange_rate: 4.0,
+        };
+        let lines = transaction_tables(
+            &[
+                transaction("03/01/23", 100.0),
+                transaction("06/01/23", 200.0),
+            ],
+            &[],
+            &[],
+            Lang::En,
+        );
+        // Header, separator, 2 transactions, separator, totals and empty line
+        assert_eq!(lines.len(), 7);
+        assert!(lines[1].chars().all(|c| c == '-'));
+        assert!(lines[5].starts_with("TOTAL"));
+        assert!(lines[5].trim_end().ends_with("1200  300"));
+        let lines = transaction_tables(&[transaction("03/01/23", 100.0)], &[], &[], Lang::Pl);
+        assert!(lines[0].starts_with("Rodzaj"));
+        assert!(lines[2].starts_with("dywidenda"));
+        assert!(lines[4].starts_with("RAZEM"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_value() -> Result<(), String> {
+        let results = BTreeMap::from([(
+            2023,
+            TaxYearResult {
+                gross_div: 400.0,
+                tax_div: 60.0,
+                gross_sold: 1000.0,
+                cost_sold: 500.0,
+                ..Default::default()
+            },
+        )]);
+        let rules = BTreeMap::from([(
+            2023,
+            TaxRules {
+                tax_rate: 0.19,
+                treaty_withholding_rate: 0.15,
+                round_to: 1.0,
+                epit_rounding: false,
+                pit38_schema: None,
+            },
+        )]);
+        assert_eq!(
+            format_key_value(&results, &rules),
+            "tax_year=2023\ngross_pln=1400.00\ntax_paid_pln=60.00\ntax_due_pln=111.00\n"
+        );
+        assert_eq!(
+            format_key_value(&results, &BTreeMap::new()),
+            "tax_year=2023\ngross_pln=1400.00\ntax_paid_pln=60.00\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_lines() -> Result<(), String> {
+        let transactions = vec![Transaction {
+            transaction_date: "03/01/23".to_string(),
+            gross_us: 100.0,
+            tax_us: 15.0,
+            symbol: "INTC".to_string(),
+            exchange_rate_date: "2023-02-28".to_string(),
+            exchange_rate: 4.0,
+        }];
+        let results = BTreeMap::from([(2023, TaxYearResult::default())]);
+        let content = format_json_lines(&transactions, &[], &[], &results)?;
+        let lines: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).map_err(|e| e.to_string()))
+            .collect::<Result<_, String>>()?;
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["type"], "dividend");
+        assert_eq!(lines[0]["symbol"], "INTC");
+        assert_eq!(lines[1]["type"], "result");
+        assert_eq!(lines[1]["tax_year"], 2023);
+        Ok(())
+    }
+
+    #[test]
+    fn test_output_path() -> Result<(), String> {
+        assert_eq!(
+            ExportFormat::from_path("out/2023.PDF"),
+            Ok(ExportFormat::Pdf)
+        );
+        assert_eq!(
+            ExportFormat::from_path("report.txt"),
+            Ok(ExportFormat::Human)
+        );
+        assert_eq!(
+            ExportFormat::from_path("tax.env"),
+            Ok(ExportFormat::KeyValue)
+        );
+        assert!(ExportFormat::from_path("report").is_err());
+        assert!(ExportFormat::from_path("report.doc").is_err());
+        assert_eq!(
+            ExportFormat::from_path(&format!("a.{}", ExportFormat::Markdown.extension())),
+            Ok(ExportFormat::Markdown)
+        );
+
+        let dir = std::env::temp_dir().join(format!("etrade_output_{}", std::process::id()));
+        let path = dir.join("2023").join("report.md");
+        write_file(path.to_str().unwrap(), b"# Report\n")?;
+        assert_eq!(
+            std::fs::read_to_string(&path).map_err(|e| e.to_string())?,
+            "# Report\n"
+        );
+        std::fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_export() -> Result<(), String> {
+        let results = BTreeMap::from([(
+            2023,
+            TaxYearResult {
+                gross_div: 400.0,
+                tax_div: 60.0,
+                per_country: BTreeMap::from([("US".to_string(), (400.0, 60.0, 60.0))]),
+                ..Default::default()
+            },
+        )]);
+        let rules = BTreeMap::from([(
+            2023,
+            TaxRules {
+                tax_rate: 0.19,
+                treaty_withholding_rate: 0.15,
+                round_to: 0.01,
+                epit_rounding: false,
+                pit38_schema: None,
+            },
+        )]);
+        let content = serde_json::to_string(&JsonExport {
+            dividends: &[],
+            interest: &[],
+            sold: &[],
+            results: &results,
+            rules: &rules,
+        })
+        .map_err(|e| e.to_string())?;
+        let parsed: serde_json::Value =
+            serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        assert_eq!(parsed["results"]["2023"]["gross_div"], 400.0);
+        assert_eq!(parsed["results"]["2023"]["per_country"]["US"][1], 60.0);
+        assert_eq!(parsed["rules"]["2023"]["treaty_withholding_rate"], 0.15);
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_partial() -> Result<(), String> {
+        let dates = HashMap::from([
+            (
+                "03/06/23".to_owned(),
+                Some(("2023-03-03".to_owned(), 4.4286)),
+            ),
+            // Not requested before interruption
+            ("04/03/23".to_owned(), None),
+        ]);
+        let content = format_partial(
+            &[("03/06/23".to_owned(), 10.0, 1.5, "INTC".to_owned())],
+            &[],
+            &[],
+            &[(
+                "04/03/23".to_owned(),
+                "04/05/23".to_owned(),
+                "01/03/22".to_owned(),
+                100.0,
+                70.0,
+                0.5,
+                HoldingTerm::Long,
+            )],
+            &dates,
+        )?;
+        let parsed: serde_json::Value =
+            serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        assert_eq!(parsed["partial"], true);
+        assert_eq!(parsed["dividends"][0][3], "INTC");
+        assert_eq!(parsed["sold"][0][6], "Long");
+        assert_eq!(
+            parsed["exchange_rates"],
+            serde_json::json!({ "03/06/23": ["2023-03-03", 4.4286_f32] })
+        );
+        Ok(())
+    }
+}
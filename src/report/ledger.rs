@@ -0,0 +1,281 @@
+use std::collections::BTreeMap;
+
+use crate::{compute_tax_due, TaxRules, TaxYearResult};
+
+/// Columns of ledger after account, tax_year and recorded date
+pub(crate) const FIGURES: [&str; 14] = [
+    "gross_div",
+    "tax_div",
+    "gross_interest",
+    "tax_interest",
+    "gross_sold",
+    "cost_sold",
+    "pit8c_income",
+    "pit8c_cost",
+    "crypto_income",
+    "crypto_cost",
+    "crypto_carried_cost",
+    "fx_income",
+    "fx_cost",
+    "tax_due",
+];
+
+fn header() -> String {
+    format!("account,tax_year,recorded,{}\n", FIGURES.join(","))
+}
+
+/// Ledger is versioned by its header. Ledger with other columns (written by
+/// older release) is returned with current ones: values are moved by column
+/// name and the ones missing are left empty. None if no migration is needed.
+/// Ledger with columns unknown to this release is refused
+fn migrate_ledger(path: &str, content: &str) -> Result<Option<String>, String> {
+    let current = header();
+    // Ledger saved by Excel starts with byte order mark
+    let mut lines = content.trim_start_matches('\u{feff}').lines();
+    let columns: Vec<&str> = match lines.next() {
+        Some(line) if line == current.trim_end() => return Ok(None),
+        Some(line) if !line.trim().is_empty() => line.split(',').collect(),
+        _ => return Ok(Some(current)),
+    };
+    let known: Vec<&str> = current.trim_end().split(',').collect();
+    let unknown: Vec<&str> = columns
+        .iter()
+        .filter(|column| !known.contains(column))
+        .cloned()
+        .collect();
+    if !unknown.is_empty() {
+        return Err(format!(
+            "Error: ledger {} has columns unknown to this release of etradeTaxReturnHelper: {}, update it",
+            path,
+            unknown.join(",")
+        ));
+    }
+    let mut migrated = current.clone();
+    for line in lines.filter(|line| !line.trim().is_empty()) {
+        let values: Vec<&str> = line.split(',').collect();
+        if values.len() != columns.len() {
+            return Err(format!("Error: malformed ledger {} line: {}", path, line));
+        }
+        let by_column: std::collections::HashMap<&str, &str> =
+            columns.iter().cloned().zip(values).collect();
+        migrated += &known
+            .iter()
+            .map(|column| by_column.get(column).cloned().unwrap_or(""))
+            .collect::<Vec<&str>>()
+            .join(",");
+        migrated += "\n";
+    }
+    log::info!("Ledger {} migrated to current columns", path);
+    Ok(Some(migrated))
+}
+
+/// Values of FIGURES. Tax due is None for residencies without tax rules
+pub(crate) fn figures(result: &TaxYearResult, rules: Option<&TaxRules>) -> Vec<Option<f32>> {
+    [
+        result.gross_div,
+        result.tax_div,
+        result.gross_interest,
+        result.tax_interest,
+        result.gross_sold,
+        result.cost_sold,
+        result.pit8c_income,
+        result.pit8c_cost,
+        result.crypto_income,
+        result.crypto_cost,
+        result.crypto_carried_cost,
+        result.fx_income,
+        result.fx_cost,
+    ]
+    .into_iter()
+    .map(Some)
+    .chain([rules.map(|rules| compute_tax_due(result, rules))])
+    .collect()
+}
+
+fn ledger_line(
+    account: &str,
+    year: i32,
+    recorded: &str,
+    result: &TaxYearResult,
+    rules: Option<&TaxRules>,
+) -> String {
+    // Figures not known (tax due) are left empty
+    let figures = figures(result, rules)
+        .iter()
+        .map(|amount| amount.map_or("".to_owned(), |amount| format!("{:.2}", amount)))
+        .collect::<Vec<String>>()
+        .join(",");
+    format!("{},{},{},{}\n", account, year, recorded, figures)
+}
+
+/// Append figures of each tax year to CSV ledger kept across runs. Header is
+/// written when ledger is created. Rerun of the same year adds new line,
+/// which supersedes the previous ones when ledger is read. Ledger of older
+/// release is migrated first
+pub fn append_ledger(
+    path: &str,
+    account: &str,
+    results: &BTreeMap<i32, TaxYearResult>,
+    rules: &BTreeMap<i32, TaxRules>,
+) -> Result<(), String> {
+    use std::io::Write;
+    if account.contains(',') {
+        return Err(format!(
+            "Error: ledger account must not contain comma: {}",
+            account
+        ));
+    }
+    // Content of new or migrated ledger, which is written as whole
+    let rewritten = match std::path::Path::new(path).exists() {
+        true => {
+            let existing = std::fs::read_to_string(path)
+                .map_err(|e| format!("Error reading ledger {}: {}", path, e))?;
+            migrate_ledger(path, &existing)?
+        }
+        false => Some(header()),
+    };
+    let rewrite = rewritten.is_some();
+    let mut content = rewritten.unwrap_or_default();
+    let recorded = chrono::Local::now()
+        .naive_local()
+        .date()
+        .format("%Y-%m-%d")
+        .to_string();
+    results.iter().for_each(|(year, result)| {
+        content += &ledger_line(account, *year, &recorded, result, rules.get(year));
+    });
+    if rewrite {
+        // Written next to ledger first, so it is not lost if writing fails
+        let written = format!("{}.new", path);
+        crate::report::export::write_file(&written, content.as_bytes())?;
+        return std::fs::rename(&written, path)
+            .map_err(|e| format!("Error writing ledger {}: {}", path, e));
+    }
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(content.as_bytes()))
+        .map_err(|e| format!("Error appending to ledger {}: {}", path, e))
+}
+
+/// Latest figures of each (account, tax year) recorded in ledger, keyed by
+/// column name e.g. gross_sold. Figures not recorded (empty) are skipped
+pub fn read_ledger(path: &str) -> Result<super::Figures, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Error reading ledger {}: {}", path, e))?;
+    let mut lines = content.trim_start_matches('\u{feff}').lines();
+    let columns: Vec<&str> = lines
+        .next()
+        .ok_or(format!("Error: ledger {} is empty", path))?
+        .split(',')
+        .collect();
+    let mut entries = BTreeMap::new();
+    for line in lines.filter(|line| !line.trim().is_empty()) {
+        let values: Vec<&str> = line.split(',').collect();
+        if values.len() != columns.len() || values.len() < 3 {
+            return Err(format!("Error: malformed ledger {} line: {}", path, line));
+        }
+        let year = values[1]
+            .parse::<i32>()
+            .map_err(|_| format!("Error: malformed ledger {} line: {}", path, line))?;
+        let mut figures = BTreeMap::new();
+        for (column, value) in columns.iter().zip(values.iter()).skip(3) {
+            if !value.is_empty() {
+                let amount = value
+                    .parse::<f32>()
+                    .map_err(|_| format!("Error: malformed ledger {} line: {}", path, line))?;
+                figures.insert(column.to_string(), amount);
+            }
+        }
+        entries.insert((values[0].to_owned(), year), figures);
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ledger() -> Result<(), String> {
+        let path = std::env::temp_dir().join(format!("etrade_ledger_{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        let result = |gross_sold: f32| TaxYearResult {
+            gross_sold,
+            cost_sold: 1000.0,
+            ..Default::default()
+        };
+        let rules = BTreeMap::from([(
+            2023,
+            TaxRules {
+                tax_rate: 0.19,
+                treaty_withholding_rate: 0.15,
+                round_to: 1.0,
+                epit_rounding: false,
+                pit38_schema: None,
+            },
+        )]);
+        append_ledger(
+            path,
+            "etrade",
+            &BTreeMap::from([(2022, result(500.0)), (2023, result(2000.0))]),
+            &rules,
+        )?;
+        // Rerun of 2023 after adding missing statement
+        append_ledger(
+            path,
+            "etrade",
+            &BTreeMap::from([(2023, result(3000.0))]),
+            &rules,
+        )?;
+        let entries = read_ledger(path)?;
+        std::fs::remove_file(path).map_err(|e| e.to_string())?;
+        assert_eq!(entries.len(), 2);
+        let latest = &entries[&("etrade".to_owned(), 2023)];
+        assert_eq!(latest["gross_sold"], 3000.0);
+        assert_eq!(latest["tax_due"], 380.0);
+        // No tax rules for 2022
+        assert_eq!(entries[&("etrade".to_owned(), 2022)].get("tax_due"), None);
+        assert!(append_ledger(path, "a,b", &BTreeMap::new(), &rules).is_err());
+
+        // Ledger of older release without crypto and fx columns
+        std::fs::write(
+            path,
+            "account,tax_year,recorded,gross_div,tax_div,gross_sold,cost_sold,tax_due\n\
+             etrade,2021,2022-04-01,100.00,15.00,500.00,200.00,57.00\n",
+        )
+        .map_err(|e| e.to_string())?;
+        append_ledger(
+            path,
+            "etrade",
+            &BTreeMap::from([(2023, result(3000.0))]),
+            &rules,
+        )?;
+        let content = std::fs::read_to_string(path).map_err(|e| e.to_string());
+        let entries = read_ledger(path);
+        // Ledger of newer release
+        std::fs::write(path, "account,tax_year,recorded,gross_div,new_figure\n")
+            .map_err(|e| e.to_string())?;
+        let newer = append_ledger(path, "etrade", &BTreeMap::new(), &rules);
+        std::fs::remove_file(path).map_err(|e| e.to_string())?;
+        assert!(content?.starts_with(&header()));
+        let entries = entries?;
+        let migrated = &entries[&("etrade".to_owned(), 2021)];
+        assert_eq!(
+            (
+                migrated["gross_div"],
+                migrated["tax_due"],
+                migrated.get("fx_income")
+            ),
+            (100.0, 57.0, None)
+        );
+        assert_eq!(entries[&("etrade".to_owned(), 2023)]["gross_sold"], 3000.0);
+        assert!(newer.is_err());
+        // Ledger saved by Excel on Windows needs no migration
+        assert_eq!(
+            migrate_ledger(path, &format!("\u{feff}{}", header().replace('\n', "\r\n")))?,
+            None
+        );
+        Ok(())
+    }
+}
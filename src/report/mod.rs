@@ -0,0 +1,63 @@
+//! Reports of computed results: exports in various formats, audit trail,
+//! templates, ledger, SQLite database and delivery to Google Sheets or e-mail
+
+pub(crate) mod anonymize;
+pub(crate) mod audit;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod database;
+pub(crate) mod diff;
+pub(crate) mod email;
+pub(crate) mod export;
+pub(crate) mod ledger;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod sheets;
+pub(crate) mod template;
+
+/// Figures of each (account, tax year) keyed by name e.g. gross_sold, as
+/// recorded in ledger or database
+pub(crate) type Figures =
+    std::collections::BTreeMap<(String, i32), std::collections::BTreeMap<String, f32>>;
+
+// SQLite and blocking HTTP requests of Google Sheets are not available in
+// browser, so WASM build reports them as errors
+#[cfg(target_arch = "wasm32")]
+pub(crate) mod database {
+    use crate::{SoldTransaction, TaxRules, TaxYearResult, Transaction};
+    use std::collections::BTreeMap;
+
+    const UNAVAILABLE: &str = "SQLite database is not available in browser";
+
+    pub fn store(
+        _path: &str,
+        _account: &str,
+        _transactions: &[Transaction],
+        _interest_transactions: &[Transaction],
+        _sold_transactions: &[SoldTransaction],
+        _results: &BTreeMap<i32, TaxYearResult>,
+        _rules: &BTreeMap<i32, TaxRules>,
+    ) -> Result<(), String> {
+        Err(UNAVAILABLE.to_owned())
+    }
+
+    pub fn read_database(_path: &str) -> Result<super::Figures, String> {
+        Err(UNAVAILABLE.to_owned())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) mod sheets {
+    use crate::{Lang, SoldTransaction, TaxRules, TaxYearResult, Transaction};
+    use std::collections::BTreeMap;
+
+    pub fn export_google_sheets(
+        _client_file: &str,
+        _transactions: &[Transaction],
+        _interest_transactions: &[Transaction],
+        _sold_transactions: &[SoldTransaction],
+        _results: &BTreeMap<i32, TaxYearResult>,
+        _rules: &BTreeMap<i32, TaxRules>,
+        _lang: Lang,
+    ) -> Result<String, String> {
+        Err("Google Sheets export is not available in browser".to_owned())
+    }
+}
@@ -0,0 +1,197 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+use crate::oauth::{load_cached, store_cached, OAuth2, Token};
+use crate::{Lang, SoldTransaction, TaxRules, TaxYearResult, Transaction};
+
+type ReqwestClient = reqwest::blocking::Client;
+
+const AUTHORIZATION_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const DEVICE_CODE_URL: &str = "https://oauth2.googleapis.com/device/code";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const SHEETS_URL: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+// Keychain service tokens are cached under, per OAuth client id
+const TOKEN_SERVICE: &str = "google-token";
+// Access to spreadsheets created by this program only
+const SCOPE: &str = "https://www.googleapis.com/auth/drive.file";
+
+/// OAuth client of "TVs and Limited Input devices" or "Desktop app" type
+/// created in Google Cloud console. Downloaded JSON has it under "installed"
+/// key, only the latter with redirect URIs
+#[derive(Debug, Deserialize, PartialEq)]
+struct OAuthClient {
+    client_id: String,
+    client_secret: String,
+    #[serde(default)]
+    redirect_uris: Vec<String>,
+}
+
+fn load_client(path: &str) -> Result<OAuthClient, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Error reading OAuth client {}: {}", path, e))?;
+    let value: Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Error parsing OAuth client {}: {}", path, e))?;
+    let client = value.get("installed").cloned().unwrap_or(value);
+    serde_json::from_value(client)
+        .map_err(|e| format!("Error parsing OAuth client {}: {}", path, e))
+}
+
+fn send_json(request: reqwest::blocking::RequestBuilder) -> Result<Value, String> {
+    let response =
+        crate::http::send(request).map_err(|e| format!("Error calling Google API: {}", e))?;
+    let status = response.status();
+    let body: Value = response
+        .json()
+        .map_err(|e| format!("Error reading Google API response: {}", e))?;
+    match status.is_success() {
+        true => Ok(body),
+        false => Err(format!("Error: Google API returned {}: {}", status, body)),
+    }
+}
+
+/// Access token cached in keychain, refreshed once expired, or obtained
+/// with OAuth device flow: user opens verification URL on any device and
+/// enters the code printed. Desktop app client redirects browser opened at
+/// printed URL to local listener instead
+fn authorize(client: &ReqwestClient, oauth: &OAuthClient) -> Result<String, String> {
+    let redirect = !oauth.redirect_uris.is_empty();
+    let oauth = OAuth2 {
+        client_id: oauth.client_id.clone(),
+        client_secret: Some(oauth.client_secret.clone()),
+        authorization_url: AUTHORIZATION_URL.to_owned(),
+        token_url: TOKEN_URL.to_owned(),
+        scope: SCOPE.to_owned(),
+    };
+    let cached: Option<Token> = load_cached(TOKEN_SERVICE, &oauth.client_id);
+    let token = match cached {
+        Some(token) if !token.expired() => return Ok(token.access_token),
+        Some(Token {
+            refresh_token: Some(refresh_token),
+            ..
+        }) => match oauth.refresh(client, &refresh_token) {
+            Ok(token) => Some(token),
+            Err(e) => {
+                log::warn!("{}", e);
+                None
+            }
+        },
+        _ => None,
+    };
+    let token = match token {
+        Some(token) => token,
+        None if redirect => oauth.authorize_redirect(client, &|url| {
+            eprintln!("To allow writing Google Sheet open {}", url);
+            Ok(())
+        })?,
+        None => oauth.authorize_device(client, DEVICE_CODE_URL, &|url, code| {
+            eprintln!(
+                "To allow writing Google Sheet open {} and enter code: {}",
+                url, code
+            )
+        })?,
+    };
+    store_cached(TOKEN_SERVICE, &oauth.client_id, &token);
+    Ok(token.access_token)
+}
+
+/// Body of request creating spreadsheet with given title and sheets
+fn spreadsheet_request(title: &str, sheets: &[(String, Vec<Vec<Value>>)]) -> Value {
+    json!({
+        "properties": {"title": title},
+        "sheets": sheets
+            .iter()
+            .map(|(name, _)| json!({"properties": {"title": name}}))
+            .collect::<Vec<Value>>(),
+    })
+}
+
+/// Body of request writing rows of each sheet starting from its A1 cell
+fn values_request(sheets: &[(String, Vec<Vec<Value>>)]) -> Value {
+    json!({
+        "valueInputOption": "RAW",
+        "data": sheets
+            .iter()
+            .map(|(name, rows)| json!({"range": format!("'{}'!A1", name), "values": rows}))
+            .collect::<Vec<Value>>(),
+    })
+}
+
+/// Create Google Sheet with the same sheets as xlsx export (dividends, sales,
+/// exchange rates and summary). client_file is OAuth client JSON. Returns URL
+/// of created spreadsheet
+pub fn export_google_sheets(
+    client_file: &str,
+    transactions: &[Transaction],
+    interest_transactions: &[Transaction],
+    sold_transactions: &[SoldTransaction],
+    results: &BTreeMap<i32, TaxYearResult>,
+    rules: &BTreeMap<i32, TaxRules>,
+    lang: Lang,
+) -> Result<String, String> {
+    let oauth = load_client(client_file)?;
+    let sheets = crate::report::export::sheet_values(
+        transactions,
+        interest_transactions,
+        sold_transactions,
+        results,
+        rules,
+        lang,
+    );
+    let title = format!(
+        "{} {}",
+        crate::tr(lang, "E*TRADE tax return report"),
+        chrono::Local::now().naive_local().date().format("%Y-%m-%d")
+    );
+    let client = crate::http::shared_client()?;
+    let token = authorize(client, &oauth)?;
+    let spreadsheet = send_json(
+        client
+            .post(SHEETS_URL)
+            .bearer_auth(&token)
+            .json(&spreadsheet_request(&title, &sheets)),
+    )?;
+    let id = spreadsheet
+        .get("spreadsheetId")
+        .and_then(|id| id.as_str())
+        .ok_or("Error: no spreadsheet id in Google API response".to_owned())?;
+    send_json(
+        client
+            .post(format!("{}/{}/values:batchUpdate", SHEETS_URL, id))
+            .bearer_auth(&token)
+            .json(&values_request(&sheets)),
+    )?;
+    Ok(spreadsheet
+        .get("spreadsheetUrl")
+        .and_then(|url| url.as_str())
+        .map(|url| url.to_owned())
+        .unwrap_or(format!("https://docs.google.com/spreadsheets/d/{}", id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sheets_requests() -> Result<(), String> {
+        let sheets = vec![(
+            "Summary".to_owned(),
+            vec![vec![Value::from("Tax year")], vec![Value::from(2023.0)]],
+        )];
+        assert_eq!(
+            spreadsheet_request("Report", &sheets),
+            json!({"properties": {"title": "Report"}, "sheets": [{"properties": {"title": "Summary"}}]})
+        );
+        assert_eq!(
+            values_request(&sheets),
+            json!({"valueInputOption": "RAW",
+                   "data": [{"range": "'Summary'!A1", "values": [["Tax year"], [2023.0]]}]})
+        );
+        let client: OAuthClient = serde_json::from_value(
+            json!({"client_id": "id", "client_secret": "secret", "project_id": "p"}),
+        )
+        .map_err(|e| e.to_string())?;
+        assert_eq!(client.client_id, "id");
+        Ok(())
+    }
+}
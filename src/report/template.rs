@@ -0,0 +1,70 @@
+use handlebars::{Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError};
+use serde_json::Value;
+
+/// {{fixed path}}: number with 2 decimal places, nothing for other values
+fn fixed(
+    h: &Helper,
+    _: &Handlebars,
+    _: &Context,
+    _: &mut RenderContext,
+    out: &mut dyn Output,
+) -> HelperResult {
+    let param = h
+        .param(0)
+        .ok_or_else(|| RenderError::new("Error: {{fixed}} needs a value"))?;
+    if let Some(number) = param.value().as_f64() {
+        out.write(&format!("{:.2}", number))?;
+    }
+    Ok(())
+}
+
+/// Render Handlebars template with given data. Besides built-in helpers
+/// ({{#each}}, {{#if}}, {{@key}}, {{@root}} etc.) there is {{fixed path}}
+/// printing number with 2 decimal places. Values are not escaped
+pub fn render(template: &str, data: &Value) -> Result<String, String> {
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars.register_helper("fixed", Box::new(fixed));
+    handlebars
+        .render_template(template, data)
+        .map_err(|e| format!("Error rendering template: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() -> Result<(), String> {
+        let data: Value = serde_json::from_str(
+            r#"{"results":{"2023":{"gross_div":400.0,"tax_div":60.5}},
+                "dividends":[{"symbol":"INTC"},{"symbol":"AT&T"}],"sold":[]}"#,
+        )
+        .map_err(|e| e.to_string())?;
+        assert_eq!(
+            render(
+                "{{#each results}}{{@key}}: {{fixed gross_div}} {{tax_div}}{{/each}}",
+                &data
+            ),
+            Ok("2023: 400.00 60.5".to_owned())
+        );
+        assert_eq!(
+            render(
+                "{{#each dividends}}{{@index}}={{this.symbol}} {{/each}}{{#if sold}}sold{{else}}none{{/if}}",
+                &data
+            ),
+            Ok("0=INTC 1=AT&T none".to_owned())
+        );
+        assert_eq!(
+            render(
+                "{{#each dividends}}{{@root.results.2023.tax_div}}{{/each}}",
+                &data
+            ),
+            Ok("60.560.5".to_owned())
+        );
+        assert!(render("{{#each dividends}}", &data).is_err());
+        assert!(render("{{/if}}", &data).is_err());
+        assert!(render("{{missing", &data).is_err());
+        Ok(())
+    }
+}
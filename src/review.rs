@@ -0,0 +1,506 @@
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState};
+use ratatui::{Frame, Terminal};
+use std::io::IsTerminal;
+
+use crate::{tr, CollectedTransactions, Error, HoldingTerm, Lang, TransactionBrokers};
+
+/// Parsed transaction listed for review
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    Dividend((String, f32, f32, String)),
+    ShelteredDividend((String, f32, f32, String)),
+    Interest((String, f32, f32, String)),
+    Crypto((String, bool, f32, String)),
+    Sold((String, String, String, f32, f32, f32, HoldingTerm)),
+}
+
+impl Entry {
+    fn kind(&self) -> &'static str {
+        match self {
+            Entry::Dividend(_) => "dywidenda",
+            Entry::ShelteredDividend(_) => "dywidenda, konto chronione",
+            Entry::Interest(_) => "odsetki",
+            Entry::Crypto((_, false, _, _)) => "kryptowaluty, zakup",
+            Entry::Crypto((_, true, _, _)) => "kryptowaluty, zbycie",
+            Entry::Sold(_) => "sprzedaz akcji",
+        }
+    }
+
+    fn date(&self) -> &str {
+        match self {
+            Entry::Dividend((date, ..))
+            | Entry::ShelteredDividend((date, ..))
+            | Entry::Interest((date, ..))
+            | Entry::Crypto((date, ..)) => date,
+            Entry::Sold((_, settlement_date, ..)) => settlement_date,
+        }
+    }
+
+    // Symbol of dividends, currency of virtual currency and acquisition date of sold stock
+    fn description(&self) -> String {
+        match self {
+            Entry::Dividend((_, _, _, symbol)) | Entry::ShelteredDividend((_, _, _, symbol)) => {
+                symbol.clone()
+            }
+            Entry::Interest(_) => "".to_owned(),
+            Entry::Crypto((_, _, _, currency)) => currency.clone(),
+            Entry::Sold((_, _, acquisition_date, ..)) => acquisition_date.clone(),
+        }
+    }
+
+    /// Editable amounts: (gross, tax) of dividends and interest, amount of
+    /// virtual currency and (income, cost basis, fees) of sold stock
+    fn amounts(&self) -> Vec<f32> {
+        match self {
+            Entry::Dividend((_, gross, tax, _))
+            | Entry::ShelteredDividend((_, gross, tax, _))
+            | Entry::Interest((_, gross, tax, _)) => vec![*gross, *tax],
+            Entry::Crypto((_, _, amount, _)) => vec![*amount],
+            Entry::Sold((_, _, _, income, cost, fees, _)) => vec![*income, *cost, *fees],
+        }
+    }
+
+    fn set_amount(&mut self, column: usize, value: f32) {
+        let amount = match (self, column) {
+            (Entry::Dividend((_, gross, ..)), 0)
+            | (Entry::ShelteredDividend((_, gross, ..)), 0)
+            | (Entry::Interest((_, gross, ..)), 0) => gross,
+            (Entry::Dividend((_, _, tax, _)), 1)
+            | (Entry::ShelteredDividend((_, _, tax, _)), 1)
+            | (Entry::Interest((_, _, tax, _)), 1) => tax,
+            (Entry::Crypto((_, _, amount, _)), 0) => amount,
+            (Entry::Sold((_, _, _, income, ..)), 0) => income,
+            (Entry::Sold((_, _, _, _, cost, _, _)), 1) => cost,
+            (Entry::Sold((_, _, _, _, _, fees, _)), 2) => fees,
+            _ => return,
+        };
+        *amount = value;
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Confirmed,
+    Cancelled,
+}
+
+/// State of review screen: (entry, excluded) of every transaction, selected
+/// row and amount, amount being typed and pending confirmation
+struct Review {
+    entries: Vec<(Entry, bool)>,
+    selected: usize,
+    column: usize,
+    input: Option<String>,
+    confirm: bool,
+    edited: usize,
+}
+
+impl Review {
+    fn new(collected: CollectedTransactions) -> Self {
+        let (dividends, sheltered_dividends, interest, crypto, sold) = collected;
+        let entries = dividends
+            .into_iter()
+            .map(Entry::Dividend)
+            .chain(
+                sheltered_dividends
+                    .into_iter()
+                    .map(Entry::ShelteredDividend),
+            )
+            .chain(interest.into_iter().map(Entry::Interest))
+            .chain(crypto.into_iter().map(Entry::Crypto))
+            .chain(sold.into_iter().map(Entry::Sold))
+            .map(|entry| (entry, false))
+            .collect();
+        Review {
+            entries,
+            selected: 0,
+            column: 0,
+            input: None,
+            confirm: false,
+            edited: 0,
+        }
+    }
+
+    fn excluded(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|(_, excluded)| *excluded)
+            .count()
+    }
+
+    fn select(&mut self, row: usize) {
+        self.selected = row.min(self.entries.len().saturating_sub(1));
+        if let Some((entry, _)) = self.entries.get(self.selected) {
+            self.column = self.column.min(entry.amounts().len() - 1);
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Option<Outcome> {
+        // Raw mode turns Ctrl-C into key press
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Some(Outcome::Cancelled);
+        }
+        if let Some(input) = self.input.as_mut() {
+            match key.code {
+                KeyCode::Char(c) if c.is_ascii_digit() || c == '.' || c == ',' || c == '-' => {
+                    input.push(c)
+                }
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                // Invalid amount is left for correction
+                KeyCode::Enter => {
+                    if let Ok(value) = input.replace(',', ".").parse::<f32>() {
+                        let (entry, _) = &mut self.entries[self.selected];
+                        log::info!(
+                            "Review: amount {} of {} transaction of {} changed from {} to {}",
+                            self.column + 1,
+                            entry.kind(),
+                            entry.date(),
+                            entry.amounts()[self.column],
+                            value
+                        );
+                        entry.set_amount(self.column, value);
+                        self.edited += 1;
+                        self.input = None;
+                    }
+                }
+                KeyCode::Esc => self.input = None,
+                _ => (),
+            }
+            return None;
+        }
+        if self.confirm {
+            return match key.code {
+                KeyCode::Char('y') | KeyCode::Char('t') | KeyCode::Enter => {
+                    Some(Outcome::Confirmed)
+                }
+                _ => {
+                    self.confirm = false;
+                    None
+                }
+            };
+        }
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.select(self.selected.saturating_sub(1)),
+            KeyCode::Down | KeyCode::Char('j') => self.select(self.selected + 1),
+            KeyCode::PageUp => self.select(self.selected.saturating_sub(10)),
+            KeyCode::PageDown => self.select(self.selected + 10),
+            KeyCode::Left | KeyCode::Char('h') => self.column = self.column.saturating_sub(1),
+            KeyCode::Right | KeyCode::Char('l') => {
+                if let Some((entry, _)) = self.entries.get(self.selected) {
+                    self.column = (self.column + 1).min(entry.amounts().len() - 1);
+                }
+            }
+            KeyCode::Char(' ') | KeyCode::Char('x') => {
+                if let Some((entry, excluded)) = self.entries.get_mut(self.selected) {
+                    *excluded = !*excluded;
+                    log::info!(
+                        "Review: {} transaction of {} {}",
+                        entry.kind(),
+                        entry.date(),
+                        match excluded {
+                            true => "excluded",
+                            false => "included",
+                        }
+                    );
+                }
+            }
+            KeyCode::Char('e') | KeyCode::Enter => {
+                if let Some((entry, _)) = self.entries.get(self.selected) {
+                    self.input = Some(format!("{:.2}", entry.amounts()[self.column]));
+                }
+            }
+            KeyCode::Char('c') => self.confirm = true,
+            KeyCode::Char('q') | KeyCode::Esc => return Some(Outcome::Cancelled),
+            _ => (),
+        }
+        None
+    }
+
+    /// Brokers of dividends, interest, sold stock and virtual currency left
+    /// after review
+    fn kept_brokers(&self, brokers: TransactionBrokers) -> TransactionBrokers {
+        let (dividends, interest, sold, crypto) = brokers;
+        let (mut dividends, mut interest, mut sold, mut crypto) = (
+            dividends.into_iter(),
+            interest.into_iter(),
+            sold.into_iter(),
+            crypto.into_iter(),
+        );
+        let mut kept: TransactionBrokers = (vec![], vec![], vec![], vec![]);
+        self.entries.iter().for_each(|(entry, excluded)| {
+            let (broker, brokers) = match entry {
+                Entry::Dividend(_) => (dividends.next(), &mut kept.0),
+                Entry::Interest(_) => (interest.next(), &mut kept.1),
+                Entry::Sold(_) => (sold.next(), &mut kept.2),
+                Entry::Crypto(_) => (crypto.next(), &mut kept.3),
+                Entry::ShelteredDividend(_) => return,
+            };
+            if let (Some(broker), false) = (broker, *excluded) {
+                brokers.push(broker);
+            }
+        });
+        kept
+    }
+
+    /// Transactions left after review
+    fn into_transactions(self) -> CollectedTransactions {
+        let mut collected: CollectedTransactions = (vec![], vec![], vec![], vec![], vec![]);
+        self.entries
+            .into_iter()
+            .filter(|(_, excluded)| !*excluded)
+            .for_each(|(entry, _)| match entry {
+                Entry::Dividend(t) => collected.0.push(t),
+                Entry::ShelteredDividend(t) => collected.1.push(t),
+                Entry::Interest(t) => collected.2.push(t),
+                Entry::Crypto(t) => collected.3.push(t),
+                Entry::Sold(t) => collected.4.push(t),
+            });
+        collected
+    }
+}
+
+fn render(frame: &mut Frame, review: &Review, lang: Lang) {
+    let areas = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)])
+        .split(frame.size());
+    let header = Row::new(
+        [
+            "",
+            "Rodzaj",
+            "Data",
+            "Opis",
+            "Kwota",
+            "Podatek / koszt",
+            "Prowizje",
+        ]
+        .iter()
+        .map(|title| Cell::from(tr(lang, title).to_owned())),
+    )
+    .style(Style::default().add_modifier(Modifier::BOLD));
+    let rows = review
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(row, (entry, excluded))| {
+            let mut cells = vec![
+                Cell::from(if *excluded { "[ ]" } else { "[x]" }),
+                Cell::from(tr(lang, entry.kind()).to_owned()),
+                Cell::from(entry.date().to_owned()),
+                Cell::from(entry.description()),
+            ];
+            cells.extend(entry.amounts().iter().enumerate().map(|(column, amount)| {
+                let cell = Cell::from(format!("{:.2}", amount));
+                match row == review.selected && column == review.column {
+                    true => cell.style(Style::default().add_modifier(Modifier::REVERSED)),
+                    false => cell,
+                }
+            }));
+            match excluded {
+                true => Row::new(cells).style(
+                    Style::default()
+                        .fg(Color::DarkGray)
+                        .add_modifier(Modifier::CROSSED_OUT),
+                ),
+                false => Row::new(cells),
+            }
+        });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(3),
+            Constraint::Length(30),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(12),
+            Constraint::Length(16),
+            Constraint::Length(10),
+        ],
+    )
+    .header(header)
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(tr(lang, "PRZEGLAD TRANSAKCJI").to_owned()),
+    )
+    .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+    let mut state = TableState::default();
+    state.select(Some(review.selected));
+    frame.render_stateful_widget(table, areas[0], &mut state);
+
+    let status = match (&review.input, review.confirm) {
+        (Some(input), _) => format!("{}: {}_", tr(lang, "Kwota"), input),
+        (None, true) => format!(
+            "{} {} ({} {})? [{}]",
+            tr(lang, "Oblicz podatek z transakcji:"),
+            review.entries.len() - review.excluded(),
+            tr(lang, "wykluczone:"),
+            review.excluded(),
+            tr(lang, "t/n")
+        ),
+        (None, false) => tr(
+            lang,
+            "gora/dol: wybor, lewo/prawo: kwota, spacja: wyklucz, e: edytuj, c: oblicz, q: przerwij",
+        )
+        .to_owned(),
+    };
+    frame.render_widget(
+        Paragraph::new(status).block(Block::default().borders(Borders::ALL)),
+        areas[1],
+    );
+}
+
+fn run(review: &mut Review, lang: Lang) -> std::io::Result<Outcome> {
+    enable_raw_mode()?;
+    crossterm::execute!(std::io::stdout(), EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(std::io::stdout()))?;
+    let outcome = (|| loop {
+        terminal.draw(|frame| render(frame, review, lang))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                if let Some(outcome) = review.handle_key(key) {
+                    return Ok(outcome);
+                }
+            }
+        }
+    })();
+    // Terminal is restored also when drawing or reading keys failed
+    disable_raw_mode()?;
+    crossterm::execute!(std::io::stdout(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    outcome
+}
+
+/// Let user exclude or correct parsed transactions in terminal UI before
+/// exchange rates are fetched and tax is computed
+pub(crate) fn review_transactions(
+    collected: CollectedTransactions,
+    brokers: TransactionBrokers,
+    lang: Lang,
+) -> Result<(CollectedTransactions, TransactionBrokers), Error> {
+    if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+        return Err(Error::Report(
+            "Review of transactions needs interactive terminal".to_owned(),
+        ));
+    }
+    let mut review = Review::new(collected);
+    match run(&mut review, lang)
+        .map_err(|e| Error::Report(format!("Error running review of transactions: {}", e)))?
+    {
+        Outcome::Cancelled => Err(Error::Report(
+            "Taxation cancelled in review of transactions".to_owned(),
+        )),
+        Outcome::Confirmed => {
+            if review.excluded() > 0 || review.edited > 0 {
+                let msg = format!(
+                    "WARNING: {} transactions excluded and {} amounts edited in review",
+                    review.excluded(),
+                    review.edited
+                );
+                eprintln!("{}", crate::warning(&msg));
+                log::warn!("{}", msg);
+            }
+            let brokers = review.kept_brokers(brokers);
+            Ok((review.into_transactions(), brokers))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_review() -> Result<(), String> {
+        let mut review = Review::new((
+            vec![
+                ("03/01/23".to_owned(), 100.0, 15.0, "INTC".to_owned()),
+                ("06/01/23".to_owned(), 200.0, 30.0, "INTC".to_owned()),
+            ],
+            vec![],
+            vec![],
+            vec![],
+            vec![(
+                "04/03/23".to_owned(),
+                "04/05/23".to_owned(),
+                "12/01/21".to_owned(),
+                500.0,
+                300.0,
+                1.5,
+                HoldingTerm::Long,
+            )],
+        ));
+        let mut keys = |codes: &[KeyCode]| {
+            codes
+                .iter()
+                .map(|code| review.handle_key(KeyEvent::from(*code)))
+                .last()
+                .flatten()
+        };
+        // Exclude first dividend, set tax of second one and fees of sold stock
+        assert_eq!(
+            keys(&[
+                KeyCode::Char(' '),
+                KeyCode::Down,
+                KeyCode::Right,
+                KeyCode::Char('e'),
+                KeyCode::Backspace,
+                KeyCode::Backspace,
+                KeyCode::Backspace,
+                KeyCode::Backspace,
+                KeyCode::Backspace,
+                KeyCode::Char('2'),
+                KeyCode::Char('9'),
+                KeyCode::Char(','),
+                KeyCode::Char('5'),
+                KeyCode::Enter,
+                KeyCode::Down,
+                KeyCode::Right,
+                KeyCode::Char('e'),
+                KeyCode::Char('x'),
+                KeyCode::Esc,
+                KeyCode::Char('c'),
+            ]),
+            None
+        );
+        assert_eq!(keys(&[KeyCode::Char('y')]), Some(Outcome::Confirmed));
+        assert_eq!(review.excluded(), 1);
+        assert_eq!(review.edited, 1);
+        assert_eq!(
+            review.kept_brokers((
+                vec!["E*TRADE".to_owned(), "revolut".to_owned()],
+                vec![],
+                vec!["E*TRADE".to_owned()],
+                vec![]
+            )),
+            (
+                vec!["revolut".to_owned()],
+                vec![],
+                vec!["E*TRADE".to_owned()],
+                vec![]
+            )
+        );
+        let (dividends, _, _, _, sold) = review.into_transactions();
+        assert_eq!(
+            dividends,
+            vec![("06/01/23".to_owned(), 200.0, 29.5, "INTC".to_owned())]
+        );
+        assert_eq!(sold[0].5, 1.5);
+
+        let mut review = Review::new((vec![], vec![], vec![], vec![], vec![]));
+        assert_eq!(
+            review.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Outcome::Cancelled)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+//! Versions of files kept across runs: ledger, database and recorded
+//! exchange rate responses. Files of older versions are migrated when loaded,
+//! files written by newer release are refused instead of being misread
+
+/// Error unless file of given format version is supported by this release
+pub(crate) fn check_version(path: &str, version: u32, supported: u32) -> Result<(), String> {
+    match version > supported {
+        true => Err(format!(
+            "Error: {} has format version {} of newer release of etradeTaxReturnHelper (this one supports up to {}), update it",
+            path, version, supported
+        )),
+        false => Ok(()),
+    }
+}
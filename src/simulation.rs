@@ -0,0 +1,229 @@
+use chrono::Datelike;
+
+use crate::{Error, Residency};
+
+/// Compute (income, cost) in target currency of hypothetical sale of given lots.
+/// Lots are (acquisition exchange rate, quantity, cost basis per share in USD)
+fn compute_simulated_sale(
+    lots: &[(f32, f32, f32)],
+    exchange_rate_sale: f32,
+    price: f32,
+) -> (f32, f32) {
+    let income: f32 = lots
+        .iter()
+        .map(|(_, quantity, _)| quantity * price * exchange_rate_sale)
+        .sum();
+    let cost: f32 = lots
+        .iter()
+        .map(|(exchange_rate_acquisition, quantity, cost_per_share)| {
+            quantity * cost_per_share * exchange_rate_acquisition
+        })
+        .sum();
+    (income, cost)
+}
+
+/// Adjust lots acquired before stock split (and not later than sale) to
+/// post-split quantity and cost basis per share. Total cost basis is unchanged.
+/// splits are (split date, ratio) e.g. ratio 10.0 for 10-for-1 split
+fn apply_stock_splits(
+    lots: &[(String, f32, f32)],
+    splits: &[(String, f32)],
+    sale_date: &chrono::NaiveDate,
+) -> Result<Vec<(String, f32, f32)>, Error> {
+    let parse = |date: &str| {
+        chrono::NaiveDate::parse_from_str(date, "%m/%d/%y")
+            .map_err(|_| Error::Arguments(format!("Error parsing date: {}", date)))
+    };
+    let splits = splits
+        .iter()
+        .map(|(split_date, ratio)| Ok((parse(split_date)?, *ratio)))
+        .collect::<Result<Vec<(chrono::NaiveDate, f32)>, Error>>()?;
+    lots.iter()
+        .map(|(acquisition_date, quantity, cost_per_share)| {
+            let acquired = parse(acquisition_date)?;
+            let ratio: f32 = splits
+                .iter()
+                .filter(|(split_date, _)| acquired < *split_date && split_date <= sale_date)
+                .map(|(_, ratio)| ratio)
+                .product();
+            Ok((
+                acquisition_date.clone(),
+                quantity * ratio,
+                cost_per_share / ratio,
+            ))
+        })
+        .collect()
+}
+
+/// Estimate (income, cost) in target currency of selling given holdings.
+/// lots are (acquisition date, quantity, cost basis per share in USD),
+/// splits are (split date, ratio) of stock splits that happened since acquisition,
+/// sale_date is hypothetical date of sale and price is sale price per share in USD.
+/// If sale date is in the future then latest available exchange rate is used
+pub fn simulate_sale(
+    rd: &dyn Residency,
+    lots: &[(String, f32, f32)],
+    splits: &[(String, f32)],
+    sale_date: &str,
+    price: f32,
+) -> Result<(f32, f32), Error> {
+    let today = chrono::Local::now().naive_local().date();
+    let requested_sale_date = chrono::NaiveDate::parse_from_str(sale_date, "%m/%d/%y")
+        .map_err(|_| Error::Arguments(format!("Error parsing sale date: {}", sale_date)))?;
+    let lots = apply_stock_splits(lots, splits, &requested_sale_date)?;
+    let rate_date = match requested_sale_date > today {
+        true => {
+            let msg = format!(
+                "Sale date {} is in the future. Latest available exchange rate is used",
+                requested_sale_date.format("%Y-%m-%d")
+            );
+            eprintln!("{}", crate::warning(&msg));
+            log::warn!("{}", msg);
+            today.format("%m/%d/%y").to_string()
+        }
+        false => sale_date.to_owned(),
+    };
+
+    let mut dates: std::collections::HashMap<String, Option<(String, f32)>> =
+        std::collections::HashMap::new();
+    dates.insert(rate_date.clone(), None);
+    lots.iter().for_each(|(acquisition_date, _, _)| {
+        dates.entry(acquisition_date.clone()).or_insert(None);
+    });
+    rd.get_exchange_rates(&mut dates)?;
+
+    let rate = |date: &String| -> Result<f32, Error> {
+        dates[date]
+            .clone()
+            .map(|(_, exchange_rate)| exchange_rate)
+            .ok_or(Error::Report(format!(
+                "Error: no exchange rate for {}",
+                date
+            )))
+    };
+    let exchange_rate_sale = rate(&rate_date)?;
+    let lots_with_rates = lots
+        .iter()
+        .map(|(acquisition_date, quantity, cost_per_share)| {
+            Ok((rate(acquisition_date)?, *quantity, *cost_per_share))
+        })
+        .collect::<Result<Vec<(f32, f32, f32)>, Error>>()?;
+
+    Ok(compute_simulated_sale(
+        &lots_with_rates,
+        exchange_rate_sale,
+        price,
+    ))
+}
+
+/// Compute (vest date, income in target currency) of given vests
+fn compute_vests_forecast(
+    vests: &[(String, f32)],
+    exchange_rate: f32,
+    price: f32,
+) -> Vec<(String, f32)> {
+    vests
+        .iter()
+        .map(|(vest_date, quantity)| (vest_date.clone(), quantity * price * exchange_rate))
+        .collect()
+}
+
+/// Forecast income in target currency of RSU vests remaining in current year.
+/// vests are (vest date, quantity) and price is expected share price in USD.
+/// Latest available exchange rate is used for all vests.
+/// Returns (vest date, income) of each vest
+pub fn forecast_vests(
+    rd: &dyn Residency,
+    vests: &Vec<(String, f32)>,
+    price: f32,
+) -> Result<Vec<(String, f32)>, Error> {
+    let today = chrono::Local::now().naive_local().date();
+    let mut upcoming_vests: Vec<(chrono::NaiveDate, String, f32)> = vec![];
+    for (vest_date, quantity) in vests {
+        let date = chrono::NaiveDate::parse_from_str(vest_date, "%m/%d/%y")
+            .map_err(|_| Error::Arguments(format!("Error parsing vest date: {}", vest_date)))?;
+        if date < today || date.year() != today.year() {
+            let msg = format!(
+                "WARNING! Skipping vest on {} as it is not in remaining part of current year",
+                date.format("%Y-%m-%d")
+            );
+            eprintln!("{}", crate::warning(&msg));
+            log::warn!("{}", msg);
+            continue;
+        }
+        upcoming_vests.push((date, vest_date.clone(), *quantity));
+    }
+    upcoming_vests.sort_by_key(|(date, _, _)| *date);
+    let upcoming_vests: Vec<(String, f32)> = upcoming_vests
+        .into_iter()
+        .map(|(_, vest_date, quantity)| (vest_date, quantity))
+        .collect();
+
+    let rate_date = today.format("%m/%d/%y").to_string();
+    let mut dates: std::collections::HashMap<String, Option<(String, f32)>> =
+        std::collections::HashMap::new();
+    dates.insert(rate_date.clone(), None);
+    rd.get_exchange_rates(&mut dates)?;
+    let (_, exchange_rate) = dates[&rate_date].clone().ok_or(Error::Report(format!(
+        "Error: no exchange rate for {}",
+        rate_date
+    )))?;
+
+    Ok(compute_vests_forecast(
+        &upcoming_vests,
+        exchange_rate,
+        price,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulated_sale() -> Result<(), String> {
+        let lots: Vec<(f32, f32, f32)> = vec![(4.0, 10.0, 20.0), (3.5, 5.0, 0.0)];
+        assert_eq!(
+            compute_simulated_sale(&lots, 4.5, 30.0),
+            (15.0 * 30.0 * 4.5, 10.0 * 20.0 * 4.0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_stock_splits() -> Result<(), Error> {
+        let lots: Vec<(String, f32, f32)> = vec![
+            ("05/03/21".to_string(), 10.0, 300.0),
+            ("08/19/22".to_string(), 100.0, 30.0),
+        ];
+        let splits: Vec<(String, f32)> = vec![
+            ("06/10/22".to_string(), 10.0),
+            ("06/10/24".to_string(), 2.0),
+        ];
+        let sale_date = chrono::NaiveDate::from_ymd_opt(2023, 5, 10).unwrap();
+        assert_eq!(
+            apply_stock_splits(&lots, &splits, &sale_date)?,
+            vec![
+                ("05/03/21".to_string(), 100.0, 30.0),
+                ("08/19/22".to_string(), 100.0, 30.0),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_vests_forecast() -> Result<(), String> {
+        let vests: Vec<(String, f32)> = vec![
+            ("08/19/23".to_string(), 10.0),
+            ("11/20/23".to_string(), 20.0),
+        ];
+        assert_eq!(
+            compute_vests_forecast(&vests, 4.0, 30.0),
+            vec![
+                ("08/19/23".to_string(), 1200.0),
+                ("11/20/23".to_string(), 2400.0)
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// Exit codes of program, so wrapper scripts can react to kind of failure
+pub const EXIT_OK: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_PARTIAL_PARSE: i32 = 2;
+pub const EXIT_NETWORK: i32 = 3;
+pub const EXIT_VALIDATION: i32 = 4;
+// As of shell for process ended by SIGINT
+pub const EXIT_INTERRUPTED: i32 = 130;
+
+// Set when some of documents could not be parsed and were skipped
+static PARSE_FAILED: AtomicBool = AtomicBool::new(false);
+// Set e.g. by SIGINT handler when user asks to stop
+static INTERRUPTED: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+pub(crate) fn set_parse_failed() {
+    PARSE_FAILED.store(true, Ordering::Relaxed);
+}
+
+/// Flag set when run is to be interrupted, e.g. by SIGINT handler. Documents
+/// and exchange rates not processed yet are then skipped, and transactions
+/// processed so far are written out as partial results
+pub fn set_interruption_flag(flag: Arc<AtomicBool>) -> Result<(), String> {
+    INTERRUPTED
+        .set(flag)
+        .map_err(|_| "Interruption flag is already set".to_owned())
+}
+
+pub(crate) fn interrupted() -> bool {
+    INTERRUPTED
+        .get()
+        .is_some_and(|flag| flag.load(Ordering::Relaxed))
+}
+
+/// Exit code of program that completed
+pub fn exit_code() -> i32 {
+    match PARSE_FAILED.load(Ordering::Relaxed) {
+        true => EXIT_PARTIAL_PARSE,
+        false => EXIT_OK,
+    }
+}
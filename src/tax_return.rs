@@ -0,0 +1,225 @@
+//! Tax return of documents built step by step: entry point of library for
+//! programs depending on it (GUI, web page, other tools), kept stable across
+//! minor releases
+
+use std::collections::BTreeMap;
+
+use crate::{
+    pl, run_taxation, DocumentSource, Error, ExportFormat, FileSystem, InMemory, Lang, Residency,
+    TaxYearResult, TaxationOptions,
+};
+
+/// Figures of single tax year and tax to be paid for it
+#[derive(Debug, PartialEq, serde::Serialize)]
+#[non_exhaustive]
+pub struct TaxYear {
+    pub result: TaxYearResult,
+    // Tax to be paid under rules of residency of that year. None for
+    // residency without tax rules (DE, US)
+    pub tax_due: Option<f32>,
+}
+
+/// Tax years of transactions found in documents, in order of years
+#[derive(Debug, Default, PartialEq, serde::Serialize)]
+#[non_exhaustive]
+pub struct TaxReturn {
+    pub years: BTreeMap<i32, TaxYear>,
+}
+
+impl TaxReturn {
+    pub fn year(&self, year: i32) -> Option<&TaxYear> {
+        self.years.get(&year)
+    }
+}
+
+// Contents given to builder, other documents are read from source
+#[derive(Debug)]
+struct Documents {
+    contents: InMemory,
+    source: Box<dyn DocumentSource>,
+}
+
+impl DocumentSource for Documents {
+    fn read(&self, name: &str) -> std::io::Result<crate::Document> {
+        self.contents.read(name).or_else(|_| self.source.read(name))
+    }
+}
+
+/// Builder of tax return of documents. Residency is Polish (PIT-38 with
+/// bundled tax rules) unless other one is given:
+///
+/// ```no_run
+/// use etradeTaxReturnHelper::TaxReturnBuilder;
+///
+/// let tax_return = TaxReturnBuilder::new()
+///     .document("Brokerage Statement - XXXX0848 - 202302.pdf")
+///     .document("G&L_Expanded.xlsx")
+///     .year(2023)
+///     .build()?;
+/// if let Some(year) = tax_return.year(2023) {
+///     println!("{:?}", year.tax_due);
+/// }
+/// # Ok::<(), etradeTaxReturnHelper::Error>(())
+/// ```
+#[derive(Default)]
+pub struct TaxReturnBuilder {
+    residency: Option<Box<dyn Residency>>,
+    names: Vec<String>,
+    contents: InMemory,
+    options: TaxationOptions,
+}
+
+impl TaxReturnBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Residency whose exchange rates and tax rules are used e.g. de::DE
+    pub fn residency(mut self, residency: Box<dyn Residency>) -> Self {
+        self.residency = Some(residency);
+        self
+    }
+
+    /// Path of document: PDF brokerage statement, XLSX gains and losses, CSV
+    /// of virtual currency transactions or JSON statement
+    pub fn document(mut self, path: &str) -> Self {
+        self.names.push(path.to_owned());
+        self
+    }
+
+    /// Document already loaded e.g. uploaded one. Kind of document is known
+    /// by extension of its name, as of path
+    pub fn document_content(mut self, name: &str, content: Vec<u8>) -> Self {
+        self.contents.insert(name, content);
+        self.names.push(name.to_owned());
+        self
+    }
+
+    /// Storage paths of documents are read from, instead of local files
+    pub fn source(mut self, source: Box<dyn DocumentSource>) -> Self {
+        self.options.source = Some(source);
+        self
+    }
+
+    /// Only given tax year is computed
+    pub fn year(mut self, year: i32) -> Self {
+        self.options.year = Some(year);
+        self
+    }
+
+    /// Language of warnings and of default Polish residency
+    pub fn lang(mut self, lang: Lang) -> Self {
+        self.options.lang = lang;
+        self
+    }
+
+    /// Dividends are assigned to tax year and exchange rate by record date
+    /// instead of payment date
+    pub fn use_record_date(mut self, use_record_date: bool) -> Self {
+        self.options.use_record_date = use_record_date;
+        self
+    }
+
+    /// Account number (e.g. 0848) of tax-sheltered account, whose dividends
+    /// are recognized by account number in statement
+    pub fn sheltered_account(mut self, account: &str) -> Self {
+        self.options.sheltered_accounts.push(account.to_owned());
+        self
+    }
+
+    /// Trades of the same day and symbol are netted before lots are matched
+    /// with sales
+    pub fn net_same_day_trades(mut self, net: bool) -> Self {
+        self.options.net_same_day_trades = net;
+        self
+    }
+
+    /// Income and cost of sold stock from PIT-8C of Polish broker
+    pub fn pit8c(mut self, income: f32, cost: f32) -> Self {
+        self.options.pit8c = Some((income, cost));
+        self
+    }
+
+    /// Tax already paid for the year (advance payments, tax withheld)
+    pub fn prepaid_tax(mut self, tax: f32) -> Self {
+        self.options.prepaid_tax = Some(tax);
+        self
+    }
+
+    /// Excess of virtual currency costs carried from preceding years
+    pub fn crypto_carried_cost(mut self, cost: f32) -> Self {
+        self.options.crypto_carried_cost = cost;
+        self
+    }
+
+    /// Cash (in USD) moved out of broker account on date (MM/DD/YY), so
+    /// exchange rate differences are computed
+    pub fn withdrawal(mut self, date: &str, amount: f32) -> Self {
+        self.options.withdrawals.push((date.to_owned(), amount));
+        self
+    }
+
+    /// Commission and fees (in USD) of purchase of stock acquired on date
+    /// (MM/DD/YY) at given cost in USD, added to cost basis of its sold shares
+    pub fn purchase_fee(mut self, date: &str, cost: f32, fees: f32) -> Self {
+        self.options
+            .purchase_fees
+            .push((date.to_owned(), cost, fees));
+        self
+    }
+
+    /// Dividend paid in shares on date (MM/DD/YY) at price per share in USD
+    pub fn stock_dividend(mut self, date: &str, symbol: &str, quantity: f32, price: f32) -> Self {
+        self.options
+            .stock_dividends
+            .push((date.to_owned(), symbol.to_owned(), quantity, price));
+        self
+    }
+
+    /// Country of source (e.g. IE) of security not sourced in US
+    pub fn country(mut self, symbol: &str, country: &str) -> Self {
+        self.options
+            .countries
+            .insert(symbol.to_owned(), country.to_owned());
+        self
+    }
+
+    /// File transactions and results are written to
+    pub fn export(mut self, format: ExportFormat, path: &str) -> Self {
+        self.options.export.push((format, path.to_owned()));
+        self
+    }
+
+    /// Parse documents, get exchange rates and compute tax return
+    pub fn build(self) -> Result<TaxReturn, Error> {
+        let TaxReturnBuilder {
+            residency,
+            names,
+            contents,
+            mut options,
+        } = self;
+        let residency = match residency {
+            Some(residency) => residency,
+            None => Box::new(pl::PL::new(
+                pl::Form::PIT38,
+                crate::load_tax_rules("pl", None)?,
+                options.lang,
+            )),
+        };
+        options.source = Some(Box::new(Documents {
+            contents,
+            source: options.source.take().unwrap_or(Box::new(FileSystem)),
+        }));
+        let results = run_taxation(&*residency, names.iter().map(|n| n.as_str()), &options)?;
+        let years = results
+            .into_iter()
+            .map(|(year, result)| {
+                let tax_due = residency
+                    .rules_for_year(year)
+                    .map(|rules| crate::compute_tax_due(&result, &rules));
+                (year, TaxYear { result, tax_due })
+            })
+            .collect();
+        Ok(TaxReturn { years })
+    }
+}
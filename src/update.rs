@@ -0,0 +1,290 @@
+//! Update of program from GitHub releases. Each release has binary of each
+//! platform e.g. etradeTaxReturnHelper-x86_64-linux, SHA256SUMS file with
+//! checksum of each of them and SHA256SUMS.minisig, minisign signature of
+//! SHA256SUMS, as published by release workflow. Signature is verified with
+//! public key built into program, so tampered release is not installed
+
+use sha2::Digest;
+use std::path::{Path, PathBuf};
+
+const RELEASES_API_URL: &str =
+    "https://api.github.com/repos/jczaja/e-trade-tax-return-pl-helper/releases";
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+const SIGNATURE_ASSET: &str = "SHA256SUMS.minisig";
+// Key SHA256SUMS of releases is signed with (secret key is only known to
+// release workflow)
+const PUBLIC_KEY: &str = include_str!("../minisign.pub");
+
+/// Release newer than running program with binary of this platform
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    pub version: String,
+    // Name of binary of this platform
+    pub asset: String,
+    pub url: String,
+    pub checksums_url: String,
+    pub signature_url: String,
+}
+
+#[derive(serde::Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+#[derive(serde::Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+/// Name of binary of platform program runs on
+fn platform_asset() -> String {
+    format!(
+        "etradeTaxReturnHelper-{}-{}{}",
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        std::env::consts::EXE_SUFFIX
+    )
+}
+
+/// Numbers of version e.g. [0, 2, 11] of v0.2.11. Pre-release suffix is not
+/// taken into account
+fn version_numbers(version: &str) -> Vec<u64> {
+    version
+        .trim_start_matches('v')
+        .split(['.', '-', '+'])
+        .map_while(|number| number.parse().ok())
+        .collect()
+}
+
+fn is_newer(version: &str, current: &str) -> bool {
+    version_numbers(version) > version_numbers(current)
+}
+
+/// GitHub API of releases. Tests may use other server (e.g. local mock)
+/// given with RELEASES_API_URL env var, released program can not be pointed
+/// elsewhere
+fn releases_api_url() -> String {
+    #[cfg(test)]
+    if let Ok(url) = std::env::var("RELEASES_API_URL") {
+        return url;
+    }
+    RELEASES_API_URL.to_owned()
+}
+
+fn get(url: &str) -> Result<reqwest::blocking::Response, String> {
+    let client = crate::http::shared_client()?;
+    let response = crate::http::send(client.get(url).header("Accept", "application/json"))
+        .map_err(|e| format!("Error requesting {}: {}", url, e))?;
+    match response.status().is_success() {
+        true => Ok(response),
+        false => Err(format!("Error requesting {}: {}", url, response.status())),
+    }
+}
+
+/// Latest release when it is newer than running program, or None when
+/// program is up to date. Release without binary of this platform is an
+/// error, so user knows to build it from source
+pub fn check_for_update() -> Result<Option<Release>, String> {
+    let url = format!("{}/latest", releases_api_url().trim_end_matches('/'));
+    let release: GithubRelease = get(&url)?
+        .json()
+        .map_err(|e| format!("Error parsing release of {}: {}", url, e))?;
+    log::info!("Latest release: {}", release.tag_name);
+    if !is_newer(&release.tag_name, env!("CARGO_PKG_VERSION")) {
+        return Ok(None);
+    }
+    let asset = platform_asset();
+    let url_of = |name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .map(|asset| asset.browser_download_url.clone())
+            .ok_or(format!(
+                "Release {} has no {}, install it with: cargo install etradeTaxReturnHelper",
+                release.tag_name, name
+            ))
+    };
+    Ok(Some(Release {
+        version: release.tag_name.trim_start_matches('v').to_owned(),
+        url: url_of(&asset)?,
+        checksums_url: url_of(CHECKSUMS_ASSET)?,
+        signature_url: url_of(SIGNATURE_ASSET)?,
+        asset,
+    }))
+}
+
+/// Checksum of file in SHA256SUMS content (lines of hex digest and file name)
+fn find_checksum(checksums: &str, name: &str) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let (digest, file) = line.split_once(char::is_whitespace)?;
+        // Name of file read in binary mode is preceded with *
+        (file.trim_start().trim_start_matches('*') == name).then(|| digest.to_lowercase())
+    })
+}
+
+/// Verify minisign signature of SHA256SUMS content with given public key
+/// (content of minisign.pub)
+fn verify_signature(checksums: &str, signature: &str, public_key: &str) -> Result<(), String> {
+    let public_key = minisign_verify::PublicKey::decode(public_key)
+        .map_err(|e| format!("Error reading public key of releases: {}", e))?;
+    let signature = minisign_verify::Signature::decode(signature)
+        .map_err(|e| format!("Error reading {}: {}", SIGNATURE_ASSET, e))?;
+    public_key
+        .verify(checksums.as_bytes(), &signature, false)
+        .map_err(|e| {
+            format!(
+                "Signature of {} is not valid ({}). Program was not updated",
+                CHECKSUMS_ASSET, e
+            )
+        })
+}
+
+fn verify_checksum(binary: &[u8], checksums: &str, name: &str) -> Result<(), String> {
+    let expected = find_checksum(checksums, name)
+        .ok_or(format!("No checksum of {} in {}", name, CHECKSUMS_ASSET))?;
+    let actual: String = sha2::Sha256::digest(binary)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    match actual == expected {
+        true => Ok(()),
+        false => Err(format!(
+            "Checksum of downloaded {} is {}, expected {}. Program was not updated",
+            name, actual, expected
+        )),
+    }
+}
+
+/// Replace executable with given binary. New binary is written next to it
+/// and renamed over it, so executable is never left half written. Running
+/// executable can not be replaced on Windows, so it is renamed away first
+fn replace_executable(exe: &Path, binary: &[u8]) -> Result<(), String> {
+    let with_extension = |extension: &str| {
+        let mut path = exe.as_os_str().to_owned();
+        path.push(extension);
+        PathBuf::from(path)
+    };
+    let new = with_extension(".new");
+    std::fs::write(&new, binary).map_err(|e| format!("Error writing {}: {}", new.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&new, std::fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Error setting permissions of {}: {}", new.display(), e))?;
+    }
+    if cfg!(windows) {
+        let old = with_extension(".old");
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(exe, &old)
+            .map_err(|e| format!("Error renaming {}: {}", exe.display(), e))?;
+    }
+    std::fs::rename(&new, exe).map_err(|e| format!("Error replacing {}: {}", exe.display(), e))
+}
+
+/// Download binary of release, verify signature of checksums and checksum of
+/// binary, and replace running executable with it. Path of replaced
+/// executable is returned
+pub fn install_update(release: &Release) -> Result<PathBuf, String> {
+    let checksums = get(&release.checksums_url)?
+        .text()
+        .map_err(|e| format!("Error reading {}: {}", release.checksums_url, e))?;
+    let signature = get(&release.signature_url)?
+        .text()
+        .map_err(|e| format!("Error reading {}: {}", release.signature_url, e))?;
+    verify_signature(&checksums, &signature, PUBLIC_KEY)?;
+    let binary = get(&release.url)?
+        .bytes()
+        .map_err(|e| format!("Error downloading {}: {}", release.url, e))?;
+    verify_checksum(&binary, &checksums, &release.asset)?;
+    let exe = std::env::current_exe()
+        .and_then(std::fs::canonicalize)
+        .map_err(|e| format!("Error finding executable: {}", e))?;
+    replace_executable(&exe, &binary)?;
+    log::info!(
+        "{} replaced with version {}",
+        exe.display(),
+        release.version
+    );
+    Ok(exe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_newer() -> Result<(), String> {
+        assert_eq!(version_numbers("v0.2.11"), vec![0, 2, 11]);
+        assert!(is_newer("v0.2.12", "0.2.11"));
+        assert!(is_newer("0.10.0", "0.2.11"));
+        assert!(!is_newer("v0.2.11", "0.2.11"));
+        assert!(!is_newer("v0.2.9", "0.2.11"));
+        assert!(!is_newer("v0.2.11-rc1", "0.2.11"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_checksum() -> Result<(), String> {
+        let checksums = "\
+2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae  etradeTaxReturnHelper-x86_64-linux
+fcde2b2edba56bf408601fb721fe9b5c338d10ee429ea04fae5511b68fbf8fb9 *etradeTaxReturnHelper-x86_64-windows.exe
+";
+        verify_checksum(b"foo", checksums, "etradeTaxReturnHelper-x86_64-linux")?;
+        verify_checksum(
+            b"bar",
+            checksums,
+            "etradeTaxReturnHelper-x86_64-windows.exe",
+        )?;
+        assert!(verify_checksum(b"bar", checksums, "etradeTaxReturnHelper-x86_64-linux").is_err());
+        assert_eq!(
+            verify_checksum(b"foo", checksums, "etradeTaxReturnHelper-aarch64-macos"),
+            Err("No checksum of etradeTaxReturnHelper-aarch64-macos in SHA256SUMS".to_owned())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_signature() -> Result<(), String> {
+        // SHA256SUMS of test_verify_checksum signed with test key
+        let checksums = "\
+2c26b46b68ffc68ff99b453c1d30413413422d706483bfa0f98a5e886266e7ae  etradeTaxReturnHelper-x86_64-linux
+fcde2b2edba56bf408601fb721fe9b5c338d10ee429ea04fae5511b68fbf8fb9 *etradeTaxReturnHelper-x86_64-windows.exe
+";
+        let public_key = "\
+untrusted comment: minisign public key CCF7BFD49255CBEB
+RWTry1WS1L/3zOBC5q8PBdga43jupRhEJlh52ix1h7fYNEadNEGNVZca
+";
+        let signature = "\
+untrusted comment: signature from minisign secret key
+RUTry1WS1L/3zHSQdktH0hSImhVDQVv6RSkYRFp3zjfdgzDkzzDCi3ThEloSsU7FdPsHnxcv5+Qy1iMfP6HoAtbb0bR1NAWeQQs=
+trusted comment: timestamp:1700000000\tfile:SHA256SUMS
+iY8J39xAltigpF8Q4Pdi8t6Hi1Rn+nu+BB24ZEs3TCscSbWtK5EvmqufjtBpvC+oyl0Hv2wEBcsdMsIIrb/vCg==
+";
+        verify_signature(checksums, signature, public_key)?;
+        // Tampered checksums, signature of other key and no signature
+        let tampered = checksums.replace("2c26", "2c27");
+        assert!(verify_signature(&tampered, signature, public_key).is_err());
+        assert!(verify_signature(checksums, signature, PUBLIC_KEY).is_err());
+        assert!(verify_signature(checksums, "", public_key).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_replace_executable() -> Result<(), String> {
+        let exe = std::env::temp_dir().join(format!("etrade_update_{}", std::process::id()));
+        std::fs::write(&exe, "old").map_err(|e| e.to_string())?;
+        replace_executable(&exe, b"new")?;
+        assert_eq!(std::fs::read(&exe).map_err(|e| e.to_string())?, b"new");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let metadata = std::fs::metadata(&exe).map_err(|e| e.to_string())?;
+            assert_eq!(metadata.permissions().mode() & 0o777, 0o755);
+        }
+        let _ = std::fs::remove_file(&exe);
+        Ok(())
+    }
+}
@@ -1,9 +1,9 @@
 pub struct US {}
-impl etradeTaxReturnHelper::Residency for US {
+impl crate::Residency for US {
     fn get_exchange_rates(
         &self,
         dates: &mut std::collections::HashMap<String, Option<(String, f32)>>,
-    ) -> Result<(), String> {
+    ) -> Result<(), crate::RateError> {
         dates.iter_mut().for_each(|(_date, val)| {
             *val = Some(("N/A".to_owned(), 1.0));
         });
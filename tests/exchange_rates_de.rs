@@ -0,0 +1,25 @@
+//! Exchange rates of German residency taken from response of
+//! exchange-rates.org recorded with --record-http, so they do not depend on
+//! live service
+
+use std::collections::HashMap;
+
+use etradeTaxReturnHelper::{de, Replayer, Residency};
+
+#[test]
+fn test_exchange_rate_de() -> Result<(), String> {
+    let replayer = Replayer::open("data/exchange_rates_de.json")?;
+    etradeTaxReturnHelper::set_http_client(Box::new(replayer))?;
+    let rd: Box<dyn Residency> = Box::new(de::DE {});
+
+    let mut dates: HashMap<String, Option<(String, f32)>> = HashMap::new();
+    dates.insert("02/21/23".to_owned(), None);
+    rd.get_exchange_rates(&mut dates)
+        .map_err(|e| e.to_string())?;
+
+    assert_eq!(
+        dates.remove("02/21/23"),
+        Some(Some(("2023-02-20".to_owned(), 0.93561)))
+    );
+    Ok(())
+}
@@ -0,0 +1,260 @@
+//! Exchange rates and whole taxation run against local mock of NBP API, so
+//! they do not depend on live service: tables missing on weekends and
+//! holidays (404), rate limiting (429) and tables corrected after publication
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use etradeTaxReturnHelper::{
+    pl, ExportFormat, InMemory, Lang, Residency, TaxReturnBuilder, TaxationOptions,
+};
+
+#[derive(Default)]
+struct Tables {
+    // (number of table, USD rate) keyed by date of table (YYYY-MM-DD)
+    published: HashMap<String, (String, f32)>,
+    // Number of following requests of date refused due to rate limit
+    throttled: HashMap<String, u32>,
+    // Number of requests of each date
+    requests: HashMap<String, u32>,
+}
+
+/// NBP API of table A USD rates served at 127.0.0.1, answering as NBP does
+struct MockNbp {
+    url: String,
+    tables: Arc<Mutex<Tables>>,
+}
+
+fn respond(tables: &Mutex<Tables>, url: &str) -> (u16, String) {
+    let mut tables = tables.lock().unwrap();
+    let date = match url
+        .strip_prefix("/api/exchangerates/rates/a/usd/")
+        .and_then(|rest| rest.strip_suffix("/?format=json"))
+    {
+        Some(date) => date.to_owned(),
+        None => return (400, "400 BadRequest - Błędne zapytanie".to_owned()),
+    };
+    *tables.requests.entry(date.clone()).or_default() += 1;
+    if let Some(throttled) = tables.throttled.get_mut(&date).filter(|t| **t > 0) {
+        *throttled -= 1;
+        return (429, "429 TooManyRequests".to_owned());
+    }
+    match tables.published.get(&date) {
+        Some((no, mid)) => (
+            200,
+            format!(
+                r#"{{"table":"A","currency":"dolar amerykański","code":"USD","rates":[{{"no":"{}","effectiveDate":"{}","mid":{}}}]}}"#,
+                no, date, mid
+            ),
+        ),
+        None => (404, "404 NotFound - Not Found - Brak danych".to_owned()),
+    }
+}
+
+impl MockNbp {
+    fn start() -> Self {
+        let server = tiny_http::Server::http("127.0.0.1:0").expect("Error starting mock NBP API");
+        let url = format!("http://{}/api", server.server_addr());
+        let tables = Arc::new(Mutex::new(Tables::default()));
+        let served = tables.clone();
+        std::thread::spawn(move || {
+            for request in server.incoming_requests() {
+                let (status, body) = respond(&served, request.url());
+                let response = tiny_http::Response::from_string(body).with_status_code(status);
+                let _ = request.respond(response);
+            }
+        });
+        MockNbp { url, tables }
+    }
+
+    fn publish(&self, date: &str, no: &str, mid: f32) {
+        let mut tables = self.tables.lock().unwrap();
+        tables
+            .published
+            .insert(date.to_owned(), (no.to_owned(), mid));
+    }
+
+    fn throttle(&self, date: &str, times: u32) {
+        let mut tables = self.tables.lock().unwrap();
+        tables.throttled.insert(date.to_owned(), times);
+    }
+
+    fn requests(&self, date: &str) -> u32 {
+        let tables = self.tables.lock().unwrap();
+        tables.requests.get(date).cloned().unwrap_or(0)
+    }
+}
+
+// Tests share the mock, as NBP URL and HTTP client are set once per process.
+// Each test uses dates of its own
+fn mock() -> &'static MockNbp {
+    static MOCK: OnceLock<MockNbp> = OnceLock::new();
+    MOCK.get_or_init(|| {
+        let mock = MockNbp::start();
+        std::env::set_var("NBP_API_URL", &mock.url);
+        // Proxies of environment are not to be used for local server
+        let client = reqwest::blocking::Client::builder()
+            .no_proxy()
+            .build()
+            .expect("Error creating HTTP client");
+        etradeTaxReturnHelper::set_http_client(Box::new(client))
+            .expect("Error setting HTTP client");
+        mock
+    })
+}
+
+fn residency() -> Result<Box<dyn Residency>, String> {
+    Ok(Box::new(pl::PL::new(
+        pl::Form::PIT38,
+        etradeTaxReturnHelper::load_tax_rules("pl", None)?,
+        Lang::Pl,
+    )))
+}
+
+fn exchange_rates(dates: &[&str]) -> Result<HashMap<String, Option<(String, f32)>>, String> {
+    let mut dates = dates.iter().map(|d| (d.to_string(), None)).collect();
+    residency()?
+        .get_exchange_rates(&mut dates)
+        .map_err(|e| e.to_string())?;
+    Ok(dates)
+}
+
+#[test]
+fn test_exchange_rate_weekend_and_holiday() -> Result<(), String> {
+    let mock = mock();
+    mock.publish("2021-02-26", "039/A/NBP/2021", 3.7247);
+    mock.publish("2023-01-05", "003/A/NBP/2023", 4.3821);
+    // Mondays, so rates of preceding Friday are taken. Friday 01/06/23 is
+    // holiday (Epiphany), so rate of Thursday is taken instead
+    let dates = exchange_rates(&["03/01/21", "01/09/23"])?;
+    assert_eq!(dates["03/01/21"], Some(("2021-02-26".to_owned(), 3.7247)));
+    assert_eq!(dates["01/09/23"], Some(("2023-01-05".to_owned(), 4.3821)));
+    assert_eq!(mock.requests("2021-02-27"), 1);
+    assert_eq!(mock.requests("2023-01-06"), 1);
+    Ok(())
+}
+
+#[test]
+fn test_exchange_rate_rate_limit() -> Result<(), String> {
+    let mock = mock();
+    mock.publish("2022-06-01", "105/A/NBP/2022", 4.2836);
+    mock.throttle("2022-06-01", 2);
+    // Refused request is retried, not taken as day without table
+    let dates = exchange_rates(&["06/02/22"])?;
+    assert_eq!(dates["06/02/22"], Some(("2022-06-01".to_owned(), 4.2836)));
+    assert_eq!(mock.requests("2022-06-01"), 3);
+    assert_eq!(mock.requests("2022-05-31"), 0);
+
+    mock.publish("2022-06-02", "106/A/NBP/2022", 4.2658);
+    mock.throttle("2022-06-02", 10);
+    let error = exchange_rates(&["06/03/22"]).unwrap_err();
+    assert!(error.contains("status 429"), "{}", error);
+    assert_eq!(mock.requests("2022-06-02"), 4);
+    Ok(())
+}
+
+#[test]
+fn test_exchange_rate_correction() -> Result<(), String> {
+    let mock = mock();
+    mock.publish("2022-09-14", "178/A/NBP/2022", 4.7042);
+    let dates = exchange_rates(&["09/15/22"])?;
+    assert_eq!(dates["09/15/22"], Some(("2022-09-14".to_owned(), 4.7042)));
+    // Rates are not kept between runs, so corrected table is taken by next one
+    mock.publish("2022-09-14", "178/A/NBP/2022", 4.7024);
+    let dates = exchange_rates(&["09/15/22"])?;
+    assert_eq!(dates["09/15/22"], Some(("2022-09-14".to_owned(), 4.7024)));
+    Ok(())
+}
+
+#[test]
+fn test_exchange_rate_missing_tables() -> Result<(), String> {
+    let mock = mock();
+    // No table at all, e.g. date before NBP API began, ends with error
+    // instead of requesting all preceding days
+    let error = exchange_rates(&["07/20/20"]).unwrap_err();
+    assert!(error.contains("no table"), "{}", error);
+    assert_eq!(mock.requests("2020-07-06"), 1);
+    assert_eq!(mock.requests("2020-07-05"), 0);
+    Ok(())
+}
+
+#[test]
+fn test_taxation() -> Result<(), String> {
+    let mock = mock();
+    mock.publish("2023-03-03", "044/A/NBP/2023", 4.4286);
+    mock.publish("2023-04-27", "082/A/NBP/2023", 4.1729);
+    mock.throttle("2023-04-27", 1);
+    let mut documents = InMemory::default();
+    documents.insert(
+        "crypto.csv",
+        b"Date,Type,Amount,Currency\n2023-03-05,sell,200,USD\n".to_vec(),
+    );
+    let options = TaxationOptions {
+        source: Some(Box::new(documents)),
+        // 10 shares worth $10 each paid on Friday
+        stock_dividends: vec![("04/28/23".to_owned(), "INTC".to_owned(), 10.0, 10.0)],
+        ..Default::default()
+    };
+    let results = etradeTaxReturnHelper::run_taxation(&*residency()?, ["crypto.csv"], &options)
+        .map_err(|e| e.to_string())?;
+    let result = &results[&2023];
+    assert!(
+        (result.gross_div - 417.29).abs() < 0.01,
+        "{}",
+        result.gross_div
+    );
+    assert!(
+        (result.crypto_income - 885.72).abs() < 0.01,
+        "{}",
+        result.crypto_income
+    );
+    Ok(())
+}
+
+#[test]
+fn test_tax_return_builder() -> Result<(), String> {
+    let mock = mock();
+    mock.publish("2024-05-09", "090/A/NBP/2024", 4.0125);
+    let tax_return = TaxReturnBuilder::new()
+        .document_content(
+            "crypto.csv",
+            b"Date,Type,Amount,Currency\n2024-05-10,sell,200,USD\n".to_vec(),
+        )
+        .year(2024)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let year = tax_return.year(2024).ok_or("no tax year 2024")?;
+    assert!((year.result.crypto_income - 802.5).abs() < 0.01);
+    // Polish residency with bundled tax rules: 19% of income
+    let tax_due = year.tax_due.ok_or("no tax due")?;
+    assert!((tax_due - 152.0).abs() <= 1.0, "{}", tax_due);
+    assert!(tax_return.year(2023).is_none());
+    Ok(())
+}
+
+#[test]
+fn test_dry_run() -> Result<(), String> {
+    let mock = mock();
+    mock.publish("2023-03-03", "044/A/NBP/2023", 4.4286);
+    let mut documents = InMemory::default();
+    documents.insert(
+        "crypto.csv",
+        b"Date,Type,Amount,Currency\n2023-03-05,sell,200,USD\n".to_vec(),
+    );
+    let dir = std::env::temp_dir().join(format!("etradeTaxHelper-dry-run-{}", std::process::id()));
+    let path = |name: &str| dir.join(name).to_string_lossy().into_owned();
+    let options = TaxationOptions {
+        source: Some(Box::new(documents)),
+        export: vec![(ExportFormat::Json, path("results.json"))],
+        ledger: Some((path("ledger.csv"), "default".to_owned())),
+        audit: Some(path("audit.txt")),
+        dry_run: true,
+        ..Default::default()
+    };
+    let results = etradeTaxReturnHelper::run_taxation(&*residency()?, ["crypto.csv"], &options)
+        .map_err(|e| e.to_string())?;
+    // Rates are taken and tax computed, but nothing is written
+    assert!((results[&2023].crypto_income - 885.72).abs() < 0.01);
+    assert!(!dir.exists());
+    Ok(())
+}
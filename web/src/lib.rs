@@ -0,0 +1,89 @@
+//! Bindings of web page: documents given by user are parsed and taxed in
+//! browser, only exchange rates are requested from NBP
+
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+use web_sys::XmlHttpRequest;
+
+use etradeTaxReturnHelper::{
+    HttpClient, InMemory, Lang, RateError, TaxReturnBuilder, TaxYearResult,
+};
+
+// Requests are synchronous as taxation is not async. Browsers allow them,
+// with warning when sent from main thread
+struct BrowserClient;
+
+impl HttpClient for BrowserClient {
+    fn get(&self, url: &str) -> Result<(u16, String), RateError> {
+        let request_error = |e: JsValue| RateError::Request {
+            url: url.to_owned(),
+            reason: format!("{:?}", e),
+        };
+        let request = XmlHttpRequest::new().map_err(request_error)?;
+        request
+            .open_with_async("GET", url, false)
+            .map_err(request_error)?;
+        request.send().map_err(request_error)?;
+        let status = request.status().map_err(request_error)?;
+        let body = request.response_text().map_err(request_error)?;
+        Ok((status, body.unwrap_or_default()))
+    }
+}
+
+/// Documents added by web page, taxed together with Polish residency
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct Session {
+    documents: InMemory,
+    names: Vec<String>,
+}
+
+#[wasm_bindgen]
+impl Session {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Session {
+        // Client of previous session is kept
+        let _ = etradeTaxReturnHelper::set_http_client(Box::new(BrowserClient));
+        Session::default()
+    }
+
+    /// Add content of document file chosen by user, named as the file
+    pub fn add_document(&mut self, name: &str, content: Vec<u8>) {
+        self.documents.insert(name, content);
+        if self.names.iter().any(|n| n == name) == false {
+            self.names.push(name.to_owned());
+        }
+    }
+
+    /// Figures of each tax year and tax to be paid as JSON:
+    /// {"results": {"2023": {...}}, "tax_due": {"2023": 123.0}}
+    pub fn compute(&self, year: Option<i32>, lang: &str) -> Result<String, JsValue> {
+        let js_error = |e: String| JsValue::from_str(&e);
+        let lang = Lang::from_name(lang).map_err(js_error)?;
+        let mut builder = TaxReturnBuilder::new()
+            .lang(lang)
+            .source(Box::new(self.documents.clone()));
+        if let Some(year) = year {
+            builder = builder.year(year);
+        }
+        for name in &self.names {
+            builder = builder.document(name);
+        }
+        let tax_return = builder.build().map_err(|e| js_error(e.to_string()))?;
+        let results: BTreeMap<i32, &TaxYearResult> = tax_return
+            .years
+            .iter()
+            .map(|(year, tax_year)| (*year, &tax_year.result))
+            .collect();
+        let tax_due: BTreeMap<i32, f32> = tax_return
+            .years
+            .iter()
+            .filter_map(|(year, tax_year)| tax_year.tax_due.map(|tax_due| (*year, tax_due)))
+            .collect();
+        serde_json::to_string(&serde_json::json!({
+            "results": results,
+            "tax_due": tax_due,
+        }))
+        .map_err(|e| js_error(e.to_string()))
+    }
+}